@@ -0,0 +1,54 @@
+// src/animation.rs
+use bevy::prelude::*;
+use std::collections::HashMap;
+use crate::objects::DOOR_ANIM_FRAMES;
+
+pub struct FrameEventsPlugin;
+
+impl Plugin for FrameEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameEvents>().add_event::<AnimationEvent>();
+    }
+}
+
+// Animations that can say when they reach a frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnimClip {
+    Walk,
+    DoorOpen,
+    DoorClose,
+}
+
+// `entity` has just shown `frame` of `clip`
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub clip: AnimClip,
+    pub frame: usize,
+}
+
+// Which frames of each clip send an AnimationEvent as they come up. Set in code for now.
+#[derive(Resource)]
+pub struct FrameEvents(pub HashMap<AnimClip, Vec<usize>>);
+
+impl Default for FrameEvents {
+    fn default() -> Self {
+        let last_door_frame = DOOR_ANIM_FRAMES - 1;
+        Self(HashMap::from([
+            // Contact frames, where a foot comes down
+            (AnimClip::Walk, vec![1, 3]),
+            // Starting to swing, and hitting the stop
+            (AnimClip::DoorOpen, vec![0, last_door_frame]),
+            (AnimClip::DoorClose, vec![last_door_frame]),
+        ]))
+    }
+}
+
+impl FrameEvents {
+    // Sends an event for `frame` if it's one of `clip`'s
+    pub fn entered(&self, events: &mut EventWriter<AnimationEvent>, entity: Entity, clip: AnimClip, frame: usize) {
+        if self.0.get(&clip).is_some_and(|frames| frames.contains(&frame)) {
+            events.write(AnimationEvent { entity, clip, frame });
+        }
+    }
+}
@@ -0,0 +1,951 @@
+// src/audio.rs
+use bevy::prelude::*;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::audio::Volume;
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::GameSet;
+use crate::animation::{AnimClip, AnimationEvent};
+use crate::flags::{FlagChangedEvent, GameFlags};
+use crate::input::{Action, InputMap};
+use crate::objects::{DOOR_ANIM_FRAMES, NPC};
+use crate::player::Player;
+use crate::rng::{GameRng, RngDraw};
+use crate::settings::Settings;
+use crate::state::GameState;
+use crate::ui::UiState;
+
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicChannel>()
+            .init_resource::<RoomAudio>()
+            .init_resource::<NarrationVoice>()
+            .add_event::<PlayMusicEvent>()
+            .add_event::<StopMusicEvent>()
+            .add_event::<PlaySfxEvent>()
+            .add_systems(Startup, (load_music_tracks, load_sfx_library))
+            .add_systems(OnEnter(GameState::Playing), play_room_music)
+            .add_systems(OnEnter(GameState::MainMenu), |mut stops: EventWriter<StopMusicEvent>| {
+                for layer in [MusicLayer::Music, MusicLayer::Ambience] {
+                    stops.write(StopMusicEvent { fade_secs: ROOM_MUSIC_FADE_SECS, layer });
+                }
+            })
+            // Not gated on GameState: fades carry on through the pause menu and onto the title screen
+            .add_systems(Update, (
+                play_room_music.run_if(
+                    in_state(GameState::Playing).and(resource_changed::<RoomAudio>.or(on_event::<FlagChangedEvent>)),
+                ),
+                change_music,
+                duck_music_for_dialog,
+                fade_music,
+            ).chain())
+            // After game logic, so sounds asked for this frame start this frame
+            .add_systems(Update, (play_animation_sounds, speak_dialog_blips.in_set(RngDraw::Blips), play_sfx, update_ambient_loops).chain().after(GameSet::Process))
+            .add_systems(Update, (toggle_mute, reapply_volume_settings).chain());
+    }
+}
+
+// Music and ambience beds by name, from assets/audio/music/<name>.ogg. Only files that exist are
+// loaded, so a build without them (or a room naming one that isn't there) is simply quiet.
+#[derive(Resource, Default)]
+pub struct MusicTracks(pub HashMap<String, Handle<AudioSource>>);
+
+const MUSIC_DIR: &str = "audio/music";
+
+fn load_music_tracks(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let dir = FileAssetReader::get_base_path().join("assets").join(MUSIC_DIR);
+    let tracks = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ogg"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            let file = path.file_name()?.to_str()?.to_string();
+            Some((name, asset_server.load(format!("{}/{}", MUSIC_DIR, file))))
+        })
+        .collect();
+    commands.insert_resource(MusicTracks(tracks));
+}
+
+// The room's music plays over its ambience bed; each crossfades on its own
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MusicLayer {
+    #[default]
+    Music,
+    Ambience,
+}
+
+// Crossfade from whatever is playing on `layer` to `track` over `fade_secs`. Asking for the track
+// that's already playing leaves it be.
+#[derive(Event, Clone, Debug)]
+pub struct PlayMusicEvent {
+    pub track: Handle<AudioSource>,
+    pub fade_secs: f32,
+    pub looping: bool,
+    pub layer: MusicLayer,
+}
+
+// Fade `layer` out over `fade_secs`
+#[derive(Event, Clone, Copy, Debug)]
+pub struct StopMusicEvent {
+    pub fade_secs: f32,
+    pub layer: MusicLayer,
+}
+
+// The track playing (or fading in) on each layer, if any. Tracks fading out are on their own
+// until they're gone.
+#[derive(Resource)]
+pub struct MusicChannel {
+    music: Option<(Entity, Handle<AudioSource>)>,
+    ambience: Option<(Entity, Handle<AudioSource>)>,
+    duck: f32, // Scales every track, crossfades included: 1.0, or MUSIC_DUCK_LEVEL during dialog
+}
+
+impl Default for MusicChannel {
+    fn default() -> Self {
+        Self { music: None, ambience: None, duck: 1.0 }
+    }
+}
+
+// Music drops to this while a dialog box is up, so the text has the room to itself
+const MUSIC_DUCK_LEVEL: f32 = 0.4;
+const MUSIC_DUCK_SECS: f32 = 0.3; // From full to ducked, or back
+
+impl MusicChannel {
+    pub fn track(&self, layer: MusicLayer) -> Option<&Handle<AudioSource>> {
+        let current = match layer {
+            MusicLayer::Music => &self.music,
+            MusicLayer::Ambience => &self.ambience,
+        };
+        current.as_ref().map(|(_, track)| track)
+    }
+
+    fn current_mut(&mut self, layer: MusicLayer) -> &mut Option<(Entity, Handle<AudioSource>)> {
+        match layer {
+            MusicLayer::Music => &mut self.music,
+            MusicLayer::Ambience => &mut self.ambience,
+        }
+    }
+
+    // Moves the duck toward ducked or full at a fixed rate, so turning around partway through
+    // carries on from where it got to instead of jumping
+    fn ease_duck(&mut self, ducked: bool, secs: f32) {
+        let target = if ducked { MUSIC_DUCK_LEVEL } else { 1.0 };
+        let step = (1.0 - MUSIC_DUCK_LEVEL) / MUSIC_DUCK_SECS * secs;
+        self.duck = if self.duck < target { (self.duck + step).min(target) } else { (self.duck - step).max(target) };
+    }
+}
+
+// One music sink and where its volume is heading, 0.0 to 1.0 before settings apply
+#[derive(Component, Debug)]
+struct MusicTrack {
+    gain: f32,
+    from: f32,
+    to: f32,
+    elapsed: f32,
+    secs: f32,
+}
+
+impl MusicTrack {
+    fn fading_in(secs: f32) -> Self {
+        let mut track = Self { gain: 0.0, from: 0.0, to: 0.0, elapsed: 0.0, secs: 0.0 };
+        track.fade_to(1.0, secs);
+        track
+    }
+
+    // Ramps from wherever it is now, so a fade cut short turns around without a jump
+    fn fade_to(&mut self, to: f32, secs: f32) {
+        self.from = self.gain;
+        self.to = to;
+        self.elapsed = 0.0;
+        self.secs = secs.max(0.0);
+    }
+
+    fn tick(&mut self, secs: f32) {
+        self.elapsed += secs;
+        let t = if self.secs > 0.0 { (self.elapsed / self.secs).min(1.0) } else { 1.0 };
+        self.gain = self.from + (self.to - self.from) * t;
+    }
+
+    fn faded_out(&self) -> bool {
+        self.to == 0.0 && self.gain <= 0.0
+    }
+}
+
+// Seconds to crossfade between rooms' music
+const ROOM_MUSIC_FADE_SECS: f32 = 1.5;
+
+// What the loaded room sounds like, from the `audio` section of its definition: track names in
+// MusicTracks. Each override whose flag is set replaces the parts it names, later ones winning.
+#[derive(Resource, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct RoomAudio {
+    pub music: Option<String>,
+    pub ambience: Option<String>,
+    pub overrides: Vec<RoomAudioOverride>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RoomAudioOverride {
+    pub flag: String,
+    #[serde(default)]
+    pub music: Option<String>,
+    #[serde(default)]
+    pub ambience: Option<String>,
+}
+
+impl RoomAudio {
+    // The music and ambience to play given the flags so far
+    pub fn resolve(&self, flags: &GameFlags) -> (Option<&str>, Option<&str>) {
+        let mut music = self.music.as_deref();
+        let mut ambience = self.ambience.as_deref();
+        for over in self.overrides.iter().filter(|over| flags.is_set(&over.flag)) {
+            music = over.music.as_deref().or(music);
+            ambience = over.ambience.as_deref().or(ambience);
+        }
+        (music, ambience)
+    }
+}
+
+// On entering a room (RoomAudio is inserted with it) and whenever a flag changes. A track already
+// playing carries on, so walking back into a room doesn't start its music over.
+fn play_room_music(
+    audio: Res<RoomAudio>,
+    flags: Res<GameFlags>,
+    tracks: Res<MusicTracks>,
+    mut plays: EventWriter<PlayMusicEvent>,
+    mut stops: EventWriter<StopMusicEvent>,
+) {
+    let (music, ambience) = audio.resolve(&flags);
+    for (layer, name) in [(MusicLayer::Music, music), (MusicLayer::Ambience, ambience)] {
+        match name.and_then(|name| tracks.0.get(name)) {
+            Some(track) => {
+                plays.write(PlayMusicEvent { track: track.clone(), fade_secs: ROOM_MUSIC_FADE_SECS, looping: true, layer });
+            }
+            None => {
+                stops.write(StopMusicEvent { fade_secs: ROOM_MUSIC_FADE_SECS, layer });
+            }
+        }
+    }
+}
+
+// Lets the track on `layer` go, fading it out over `secs`
+fn release_current(
+    commands: &mut Commands,
+    channel: &mut MusicChannel,
+    tracks: &mut Query<&mut MusicTrack>,
+    layer: MusicLayer,
+    secs: f32,
+) {
+    let Some((entity, _)) = channel.current_mut(layer).take() else { return };
+    match tracks.get_mut(entity) {
+        Ok(mut track) => track.fade_to(0.0, secs),
+        // Started this same frame, so nothing's been heard of it yet
+        Err(_) => commands.entity(entity).try_despawn(),
+    }
+}
+
+fn change_music(
+    mut commands: Commands,
+    mut plays: EventReader<PlayMusicEvent>,
+    mut stops: EventReader<StopMusicEvent>,
+    mut channel: ResMut<MusicChannel>,
+    mut tracks: Query<&mut MusicTrack>,
+) {
+    for stop in stops.read() {
+        release_current(&mut commands, &mut channel, &mut tracks, stop.layer, stop.fade_secs);
+    }
+    for play in plays.read() {
+        if channel.track(play.layer) == Some(&play.track) {
+            continue;
+        }
+        release_current(&mut commands, &mut channel, &mut tracks, play.layer, play.fade_secs);
+        let playback = if play.looping { PlaybackSettings::LOOP } else { PlaybackSettings::DESPAWN };
+        let entity = commands
+            .spawn((
+                AudioPlayer::new(play.track.clone()),
+                playback.with_volume(Volume::Linear(0.0)),
+                MusicTrack::fading_in(play.fade_secs),
+            ))
+            .id();
+        *channel.current_mut(play.layer) = Some((entity, play.track.clone()));
+    }
+}
+
+fn duck_music_for_dialog(time: Res<Time<Real>>, ui_state: Res<UiState>, mut channel: ResMut<MusicChannel>) {
+    channel.ease_duck(ui_state.dialog_open, time.delta_secs());
+}
+
+// Real time, so the pause menu and hit-stops don't hold a fade up
+fn fade_music(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    mut channel: ResMut<MusicChannel>,
+    mut tracks: Query<(Entity, &mut MusicTrack, Option<&mut AudioSink>)>,
+) {
+    // A track that doesn't loop despawns itself when it ends
+    for layer in [MusicLayer::Music, MusicLayer::Ambience] {
+        let current = channel.current_mut(layer);
+        if current.as_ref().is_some_and(|(entity, _)| !tracks.contains(*entity)) {
+            *current = None;
+        }
+    }
+    for (entity, mut track, sink) in tracks.iter_mut() {
+        track.tick(time.delta_secs());
+        if track.faded_out() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // No sink until the track has loaded and started; one that never loads stays silent
+        if let Some(mut sink) = sink {
+            sink.set_volume(Volume::Linear(settings.music_gain(track.gain * channel.duck)));
+        }
+    }
+}
+
+// Every sound effect, from assets/audio/sfx/<file_name>.ogg. Like music, only files that exist
+// are loaded and the rest are silent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Reflect)]
+pub enum SfxId {
+    DoorOpen,  // The creak as it starts to swing
+    DoorThunk, // Swung all the way open
+    DoorClose,
+    Pickup,
+    MenuMove,
+    MenuSelect,
+    MenuCancel,
+    InventoryOpen,
+    InventoryClose,
+    DialogOpen,
+    Toast,
+    Error, // Something the player tried can't be done: locked, full
+    TextBlip,  // Narration typing out
+    VoiceBlip, // Someone talking; pitched per speaker
+    GeneratorStart,
+    GeneratorHum, // Looped
+    Drip,         // Looped
+    Footstep,     // Played from the floor's own samples (player.rs FootstepSounds)
+}
+
+impl SfxId {
+    pub const ALL: [Self; 18] = [
+        Self::DoorOpen,
+        Self::DoorThunk,
+        Self::DoorClose,
+        Self::Pickup,
+        Self::MenuMove,
+        Self::MenuSelect,
+        Self::MenuCancel,
+        Self::InventoryOpen,
+        Self::InventoryClose,
+        Self::DialogOpen,
+        Self::Toast,
+        Self::Error,
+        Self::TextBlip,
+        Self::VoiceBlip,
+        Self::GeneratorStart,
+        Self::GeneratorHum,
+        Self::Drip,
+        Self::Footstep,
+    ];
+
+    fn file_name(self) -> &'static str {
+        match self {
+            Self::DoorOpen => "door_open",
+            Self::DoorThunk => "door_thunk",
+            Self::DoorClose => "door_close",
+            Self::Pickup => "pickup",
+            Self::MenuMove => "menu_move",
+            Self::MenuSelect => "menu_select",
+            Self::MenuCancel => "menu_cancel",
+            Self::InventoryOpen => "inventory_open",
+            Self::InventoryClose => "inventory_close",
+            Self::DialogOpen => "dialog_open",
+            Self::Toast => "toast",
+            Self::Error => "error",
+            Self::TextBlip => "text_blip",
+            Self::VoiceBlip => "voice_blip",
+            Self::GeneratorStart => "generator_start",
+            Self::GeneratorHum => "generator_hum",
+            Self::Drip => "drip",
+            Self::Footstep => "footstep",
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SfxLibrary(pub HashMap<SfxId, Handle<AudioSource>>);
+
+impl SfxLibrary {
+    // Interface sounds, most significant first. When several are asked for on one frame (a menu
+    // closing as the dialog box opens) only the first of them plays; anything not listed always does.
+    pub const UI_PRIORITY: [SfxId; 8] = [
+        SfxId::Error,
+        SfxId::DialogOpen,
+        SfxId::InventoryOpen,
+        SfxId::InventoryClose,
+        SfxId::Toast,
+        SfxId::MenuSelect,
+        SfxId::MenuCancel,
+        SfxId::MenuMove,
+    ];
+
+    pub fn ui_priority(sound: SfxId) -> Option<usize> {
+        Self::UI_PRIORITY.iter().position(|&listed| listed == sound)
+    }
+}
+
+const SFX_DIR: &str = "audio/sfx";
+
+fn load_sfx_library(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let base = FileAssetReader::get_base_path().join("assets");
+    let sounds = SfxId::ALL
+        .into_iter()
+        .map(|sound| (sound, format!("{}/{}.ogg", SFX_DIR, sound.file_name())))
+        .filter(|(_, path)| base.join(path).exists())
+        .map(|(sound, path)| (sound, asset_server.load(path)))
+        .collect();
+    commands.insert_resource(SfxLibrary(sounds));
+}
+
+// Play a one-shot sound. With a position it's quieter the further it is from the player.
+#[derive(Event, Clone, Debug)]
+pub struct PlaySfxEvent {
+    pub sound: SfxId,
+    pub position: Option<Vec2>,
+    pub volume: f32, // 0.0 to 1.0, before distance and settings
+    pub speed: f32,  // Playback speed, which is also pitch: 2.0 is an octave up
+    pub sample: Option<Handle<AudioSource>>, // Played in place of the sound's own file
+}
+
+impl PlaySfxEvent {
+    // Heard the same wherever the player is: menus, pickups
+    pub fn ui(sound: SfxId) -> Self {
+        Self { sound, position: None, volume: 1.0, speed: 1.0, sample: None }
+    }
+
+    pub fn at(sound: SfxId, position: Vec2) -> Self {
+        Self { sound, position: Some(position), volume: 1.0, speed: 1.0, sample: None }
+    }
+
+    pub fn with_speed(self, speed: f32) -> Self {
+        Self { speed, ..self }
+    }
+
+    pub fn with_volume(self, volume: f32) -> Self {
+        Self { volume, ..self }
+    }
+
+    pub fn with_sample(self, sample: Handle<AudioSource>) -> Self {
+        Self { sample: Some(sample), ..self }
+    }
+}
+
+// Positional sounds are at full volume this close to the player and fade out linearly to nothing here
+const SFX_FULL_WITHIN: f32 = 60.0;
+const SFX_SILENT_BEYOND: f32 = 300.0;
+
+// At most this many of one sound at once; more are dropped rather than stacking up into a blare
+const MAX_SFX_INSTANCES: usize = 3;
+
+pub fn sfx_attenuation(distance: f32) -> f32 {
+    (1.0 - (distance - SFX_FULL_WITHIN) / (SFX_SILENT_BEYOND - SFX_FULL_WITHIN)).clamp(0.0, 1.0)
+}
+
+// A one-shot sound's volume before the master and sound settings, so a change to them can be
+// applied while it plays. Music and ambient loops set theirs every frame and don't need it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct BaseVolume(pub f32);
+
+impl BaseVolume {
+    pub fn effective(&self, settings: &Settings) -> f32 {
+        settings.sfx_gain(self.0)
+    }
+
+    // Settings to start a sink with at this volume
+    pub fn playback(&self, playback: PlaybackSettings, settings: &Settings) -> PlaybackSettings {
+        playback.with_volume(Volume::Linear(self.effective(settings)))
+    }
+}
+
+fn reapply_volume_settings(settings: Res<Settings>, mut sinks: Query<(&BaseVolume, &mut AudioSink)>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (base, mut sink) in sinks.iter_mut() {
+        sink.set_volume(Volume::Linear(base.effective(&settings)));
+    }
+}
+
+// Works anywhere, the title screen and pause menu included
+fn toggle_mute(keyboard: Res<ButtonInput<KeyCode>>, input: Res<InputMap>, mut settings: ResMut<Settings>) {
+    if input.just_pressed(Action::Mute, &keyboard) {
+        settings.muted = !settings.muted;
+        info!("Sound {}", if settings.muted { "muted" } else { "unmuted" });
+    }
+}
+
+// The sound each animation frame event makes, where the animated entity is. Footfalls (the walk
+// clip's events) are left to play_footsteps, which picks a sample for the floor underfoot.
+const ANIMATION_SOUNDS: &[(AnimClip, usize, SfxId)] = &[
+    (AnimClip::DoorOpen, 0, SfxId::DoorOpen),
+    (AnimClip::DoorOpen, DOOR_ANIM_FRAMES - 1, SfxId::DoorThunk),
+    (AnimClip::DoorClose, DOOR_ANIM_FRAMES - 1, SfxId::DoorClose),
+];
+
+fn play_animation_sounds(
+    mut events: EventReader<AnimationEvent>,
+    transforms: Query<&Transform>,
+    mut sfx: EventWriter<PlaySfxEvent>,
+) {
+    for event in events.read() {
+        let sound = ANIMATION_SOUNDS
+            .iter()
+            .find(|(clip, frame, _)| *clip == event.clip && *frame == event.frame)
+            .map(|(_, _, sound)| *sound);
+        let (Some(sound), Ok(tf)) = (sound, transforms.get(event.entity)) else { continue };
+        sfx.write(PlaySfxEvent::at(sound, tf.translation.truncate()));
+    }
+}
+
+// A one-shot sound still playing; it despawns itself when done
+#[derive(Component)]
+struct SfxInstance(SfxId);
+
+fn play_sfx(
+    mut commands: Commands,
+    mut events: EventReader<PlaySfxEvent>,
+    library: Option<Res<SfxLibrary>>,
+    loaded: Option<Res<Assets<AudioSource>>>,
+    settings: Res<Settings>,
+    player_query: Query<&Transform, With<Player>>,
+    playing: Query<&SfxInstance>,
+) {
+    let listener = player_query.single().ok().map(|tf| tf.translation.truncate());
+    let mut counts: HashMap<SfxId, usize> = HashMap::new();
+    for instance in playing.iter() {
+        *counts.entry(instance.0).or_default() += 1;
+    }
+    let events: Vec<PlaySfxEvent> = events.read().cloned().collect();
+    // The one interface sound this frame gets to make, if any asked
+    let mut ui_turn = events.iter().filter_map(|event| SfxLibrary::ui_priority(event.sound)).min();
+    for event in &events {
+        if let Some(priority) = SfxLibrary::ui_priority(event.sound) {
+            if ui_turn != Some(priority) {
+                continue;
+            }
+            ui_turn = None;
+        }
+        let distance = event.position.zip(listener).map(|(pos, listener)| pos.distance(listener));
+        let volume = event.volume * distance.map_or(1.0, sfx_attenuation);
+        let count = counts.entry(event.sound).or_default();
+        if volume <= 0.0 || *count >= MAX_SFX_INSTANCES {
+            continue;
+        }
+        let sample = event.sample.as_ref().or_else(|| library.as_ref().and_then(|l| l.0.get(&event.sound)));
+        let Some(sample) = sample else { continue };
+        if !loaded.as_ref().is_some_and(|assets| assets.contains(sample)) {
+            continue;
+        }
+        *count += 1;
+        let base = BaseVolume(volume);
+        commands.spawn((
+            AudioPlayer::new(sample.clone()),
+            base.playback(PlaybackSettings::DESPAWN, &settings).with_speed(event.speed),
+            base,
+            SfxInstance(event.sound),
+        ));
+    }
+}
+
+// How someone's lines sound as they type out: `sfx` every few letters, each time at a pitch
+// picked from base_pitch ± pitch_jitter. The spread is what makes a voice sound like someone.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Reflect)]
+pub struct Voice {
+    pub sfx: SfxId,
+    pub base_pitch: f32,
+    pub pitch_jitter: f32,
+}
+
+impl Voice {
+    // The narrator: a plain, even tick
+    pub const NARRATION: Self = Self { sfx: SfxId::TextBlip, base_pitch: 1.0, pitch_jitter: 0.0 };
+    // Anyone not given a voice of their own
+    pub const SPEAKER: Self = Self { sfx: SfxId::VoiceBlip, base_pitch: 1.0, pitch_jitter: 0.1 };
+
+    pub fn pitch(&self, rng: &mut GameRng) -> f32 {
+        self.base_pitch + self.pitch_jitter * rng.range_f32(-1.0..1.0)
+    }
+}
+
+// The voice for lines nobody in particular says
+#[derive(Resource)]
+pub struct NarrationVoice(pub Voice);
+
+impl Default for NarrationVoice {
+    fn default() -> Self {
+        Self(Voice::NARRATION)
+    }
+}
+
+// A blip on the first letter of every pair typed out; spaces and punctuation don't count
+const BLIP_EVERY_LETTERS: usize = 2;
+
+// In the voice of the line's speaker, or the narrator's if there's none (or they've gone)
+fn speak_dialog_blips(
+    ui_state: Res<UiState>,
+    narration: Res<NarrationVoice>,
+    speakers: Query<&NPC>,
+    mut rng: ResMut<GameRng>,
+    mut sfx: EventWriter<PlaySfxEvent>,
+    mut heard: Local<Option<(usize, usize)>>, // The line being typed and its letters so far
+) {
+    let line = ui_state.dialog_queue.get(ui_state.dialog_index).filter(|_| ui_state.dialog_open);
+    let Some(line) = line else {
+        *heard = None;
+        return;
+    };
+    let letters = line
+        .text
+        .chars()
+        .take(ui_state.dialog_revealed as usize)
+        .filter(|c| c.is_alphanumeric())
+        .count();
+    let before = match *heard {
+        Some((index, before)) if index == ui_state.dialog_index => before,
+        _ => 0,
+    };
+    *heard = Some((ui_state.dialog_index, letters));
+    // At most one a frame, so a line skipped to the end doesn't come out as a burst
+    if letters.div_ceil(BLIP_EVERY_LETTERS) <= before.div_ceil(BLIP_EVERY_LETTERS) {
+        return;
+    }
+    let voice = line.speaker.and_then(|speaker| speakers.get(speaker).ok()).map_or(narration.0, |npc| npc.voice);
+    sfx.write(PlaySfxEvent::ui(voice.sfx).with_speed(voice.pitch(&mut rng)));
+}
+
+// A sound looping where this entity stands while `active`, louder the closer the player is and
+// silent from `radius` out. Its sink is a child entity, so it goes when the entity (or its room) does.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct AmbientLoop {
+    pub sound: SfxId,
+    pub base_volume: f32, // 0.0 to 1.0, heard standing on top of it
+    pub radius: f32,
+    pub active: bool,
+    #[reflect(ignore)]
+    sink: Option<Entity>, // Started again on load
+}
+
+impl AmbientLoop {
+    pub fn new(sound: SfxId, base_volume: f32, radius: f32, active: bool) -> Self {
+        Self { sound, base_volume, radius, active, sink: None }
+    }
+
+    pub fn volume_at(&self, distance: f32) -> f32 {
+        if self.radius <= 0.0 {
+            return 0.0;
+        }
+        self.base_volume * (1.0 - distance / self.radius).clamp(0.0, 1.0)
+    }
+}
+
+fn update_ambient_loops(
+    mut commands: Commands,
+    library: Option<Res<SfxLibrary>>,
+    loaded: Option<Res<Assets<AudioSource>>>,
+    settings: Res<Settings>,
+    player_query: Query<&Transform, With<Player>>,
+    mut loops: Query<(Entity, &mut AmbientLoop, &Transform)>,
+    mut sinks: Query<&mut AudioSink>,
+) {
+    let listener = player_query.single().ok().map(|tf| tf.translation.truncate());
+    for (entity, mut ambient, tf) in loops.iter_mut() {
+        let volume = listener.map_or(0.0, |l| ambient.volume_at(tf.translation.truncate().distance(l)));
+        match (ambient.active, ambient.sink) {
+            (true, Some(sink)) => {
+                // No sink component until the sound starts
+                if let Ok(mut sink) = sinks.get_mut(sink) {
+                    sink.set_volume(Volume::Linear(settings.sfx_gain(volume)));
+                }
+            }
+            (true, None) => {
+                let Some(sample) = library.as_ref().and_then(|l| l.0.get(&ambient.sound)) else { continue };
+                if !loaded.as_ref().is_some_and(|assets| assets.contains(sample)) {
+                    continue;
+                }
+                let sink = commands
+                    .spawn((
+                        AudioPlayer::new(sample.clone()),
+                        PlaybackSettings::LOOP.with_volume(Volume::Linear(settings.sfx_gain(volume))),
+                    ))
+                    .id();
+                commands.entity(entity).add_child(sink);
+                ambient.sink = Some(sink);
+            }
+            (false, Some(sink)) => {
+                commands.entity(sink).try_despawn();
+                ambient.sink = None;
+            }
+            (false, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::weak_handle;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+    use crate::ui::DialogLine;
+
+    fn frame(world: &mut World, secs: f32) {
+        world.resource_mut::<Time<Real>>().advance_by(Duration::from_secs_f32(secs));
+        world.run_system_once(change_music).unwrap();
+        world.run_system_once(fade_music).unwrap();
+        world.resource_mut::<Events<PlayMusicEvent>>().clear();
+        world.resource_mut::<Events<StopMusicEvent>>().clear();
+    }
+
+    fn gains(world: &mut World) -> Vec<(Handle<AudioSource>, f32)> {
+        let mut query = world.query::<(&AudioPlayer, &MusicTrack)>();
+        let mut gains: Vec<_> = query.iter(world).map(|(player, track)| (player.0.clone(), track.gain)).collect();
+        gains.sort_by(|a, b| a.1.total_cmp(&b.1));
+        gains
+    }
+
+    #[test]
+    fn crossfades_between_tracks_and_fades_out() {
+        let mut world = World::new();
+        world.init_resource::<Time<Real>>();
+        world.init_resource::<Settings>();
+        world.init_resource::<MusicChannel>();
+        world.init_resource::<Events<PlayMusicEvent>>();
+        world.init_resource::<Events<StopMusicEvent>>();
+        let first: Handle<AudioSource> = weak_handle!("5d0c7b1a-2f4e-4c1b-9a51-0d7e3f6a1b01");
+        let second: Handle<AudioSource> = weak_handle!("5d0c7b1a-2f4e-4c1b-9a51-0d7e3f6a1b02");
+        let play = |track: &Handle<AudioSource>| PlayMusicEvent {
+            track: track.clone(),
+            fade_secs: 1.0,
+            looping: true,
+            layer: MusicLayer::Music,
+        };
+
+        world.send_event(play(&first));
+        frame(&mut world, 0.0);
+        frame(&mut world, 1.0);
+        assert_eq!(gains(&mut world), [(first.clone(), 1.0)]);
+
+        // Partway across, both can be heard
+        world.send_event(play(&second));
+        frame(&mut world, 0.0);
+        frame(&mut world, 0.25);
+        assert_eq!(gains(&mut world), [(second.clone(), 0.25), (first.clone(), 0.75)]);
+        // Asking again doesn't restart it
+        world.send_event(play(&second));
+        frame(&mut world, 0.75);
+        assert_eq!(gains(&mut world), [(second.clone(), 1.0)]);
+        assert_eq!(world.resource::<MusicChannel>().track(MusicLayer::Music), Some(&second));
+
+        world.send_event(StopMusicEvent { fade_secs: 1.0, layer: MusicLayer::Music });
+        frame(&mut world, 0.0);
+        frame(&mut world, 1.0);
+        assert!(gains(&mut world).is_empty());
+        assert_eq!(world.resource::<MusicChannel>().track(MusicLayer::Music), None);
+    }
+
+    #[test]
+    fn room_music_follows_flags_without_restarting() {
+        let mut world = World::new();
+        world.init_resource::<Time<Real>>();
+        world.init_resource::<Settings>();
+        world.init_resource::<MusicChannel>();
+        world.init_resource::<Events<PlayMusicEvent>>();
+        world.init_resource::<Events<StopMusicEvent>>();
+        world.init_resource::<GameFlags>();
+        let ambient: Handle<AudioSource> = weak_handle!("5d0c7b1a-2f4e-4c1b-9a51-0d7e3f6a1b03");
+        let powered: Handle<AudioSource> = weak_handle!("5d0c7b1a-2f4e-4c1b-9a51-0d7e3f6a1b04");
+        let tracks = [("ambient", &ambient), ("ambient_powered", &powered)];
+        world.insert_resource(MusicTracks(tracks.map(|(name, track)| (name.to_string(), track.clone())).into()));
+        world.insert_resource(crate::objects::start_room_audio());
+        let enter_room = |world: &mut World| {
+            world.run_system_once(play_room_music).unwrap();
+            frame(world, 0.5);
+            world.resource::<MusicChannel>().music.clone().unwrap()
+        };
+
+        let (first, track) = enter_room(&mut world);
+        assert_eq!(track, ambient);
+        // No ambience file, so that layer stays quiet
+        assert_eq!(world.resource::<MusicChannel>().track(MusicLayer::Ambience), None);
+        // Coming back in carries on with the same sink
+        assert_eq!(enter_room(&mut world).0, first);
+
+        world.resource_mut::<GameFlags>().set(crate::objects::GENERATOR_RUNNING, true);
+        let (second, track) = enter_room(&mut world);
+        assert_eq!(track, powered);
+        assert_ne!(second, first);
+        world.resource_mut::<GameFlags>().set(crate::objects::GENERATOR_RUNNING, false);
+        assert_eq!(enter_room(&mut world).1, ambient);
+    }
+
+    #[test]
+    fn dialog_ducks_the_music_smoothly() {
+        let mut channel = MusicChannel::default();
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+        channel.ease_duck(true, MUSIC_DUCK_SECS / 2.0);
+        assert!(close(channel.duck, 0.7));
+        // Closed again partway down: it turns around from there
+        channel.ease_duck(false, MUSIC_DUCK_SECS / 6.0);
+        assert!(close(channel.duck, 0.8));
+        channel.ease_duck(true, 1.0);
+        assert_eq!(channel.duck, MUSIC_DUCK_LEVEL);
+        channel.ease_duck(false, 1.0);
+        assert_eq!(channel.duck, 1.0);
+    }
+
+    #[test]
+    fn positional_sounds_fade_with_distance() {
+        assert_eq!(sfx_attenuation(0.0), 1.0);
+        assert_eq!(sfx_attenuation(SFX_FULL_WITHIN), 1.0);
+        assert_eq!(sfx_attenuation(180.0), 0.5);
+        assert_eq!(sfx_attenuation(SFX_SILENT_BEYOND), 0.0);
+        assert_eq!(sfx_attenuation(1000.0), 0.0);
+    }
+
+    #[test]
+    fn one_sound_plays_at_most_three_times_at_once() {
+        let mut world = World::new();
+        world.init_resource::<Settings>();
+        world.init_resource::<Events<PlaySfxEvent>>();
+        let mut assets = Assets::<AudioSource>::default();
+        let slam = assets.add(AudioSource { bytes: Vec::new().into() });
+        world.insert_resource(assets);
+        world.insert_resource(SfxLibrary(HashMap::from([(SfxId::DoorClose, slam)])));
+        world.spawn((Player::default(), Transform::default()));
+        let playing = |world: &mut World| world.query::<(&SfxInstance, &PlaybackSettings)>().iter(world).count();
+
+        // Out of earshot
+        world.send_event(PlaySfxEvent::at(SfxId::DoorClose, Vec2::new(SFX_SILENT_BEYOND, 0.0)));
+        world.run_system_once(play_sfx).unwrap();
+        assert_eq!(playing(&mut world), 0);
+        world.resource_mut::<Events<PlaySfxEvent>>().clear();
+
+        for _ in 0..5 {
+            world.send_event(PlaySfxEvent::at(SfxId::DoorClose, Vec2::new(100.0, 0.0)));
+        }
+        world.run_system_once(play_sfx).unwrap();
+        assert_eq!(playing(&mut world), MAX_SFX_INSTANCES);
+        let mut volumes = world.query::<&PlaybackSettings>();
+        assert!(volumes.iter(&world).all(|p| p.volume == Volume::Linear(sfx_attenuation(100.0))));
+        // Still going, so nothing more gets in
+        world.resource_mut::<Events<PlaySfxEvent>>().clear();
+        world.send_event(PlaySfxEvent::ui(SfxId::DoorClose));
+        world.run_system_once(play_sfx).unwrap();
+        assert_eq!(playing(&mut world), MAX_SFX_INSTANCES);
+    }
+
+    #[test]
+    fn only_the_most_significant_ui_sound_plays_each_frame() {
+        let mut world = World::new();
+        world.init_resource::<Settings>();
+        world.init_resource::<Events<PlaySfxEvent>>();
+        let mut assets = Assets::<AudioSource>::default();
+        let sounds = [SfxId::MenuCancel, SfxId::DialogOpen, SfxId::DoorOpen];
+        let library = sounds.map(|sound| (sound, assets.add(AudioSource { bytes: Vec::new().into() })));
+        world.insert_resource(assets);
+        world.insert_resource(SfxLibrary(library.into()));
+
+        // The menu closes as the dialog box opens, twice over, with a door going too
+        for sound in [SfxId::MenuCancel, SfxId::DialogOpen, SfxId::DialogOpen, SfxId::DoorOpen] {
+            world.send_event(PlaySfxEvent::ui(sound));
+        }
+        world.run_system_once(play_sfx).unwrap();
+        let mut playing: Vec<SfxId> = world.query::<&SfxInstance>().iter(&world).map(|instance| instance.0).collect();
+        playing.sort_by_key(|sound| SfxId::ALL.iter().position(|s| s == sound));
+        assert_eq!(playing, [SfxId::DoorOpen, SfxId::DialogOpen]);
+    }
+
+    #[test]
+    fn dialog_blips_in_the_speakers_voice_with_repeatable_pitches() {
+        let wobbly = Voice { sfx: SfxId::VoiceBlip, base_pitch: 0.7, pitch_jitter: 0.15 };
+        let blips = |seed: u64| {
+            let mut world = World::new();
+            world.init_resource::<Events<PlaySfxEvent>>();
+            world.init_resource::<NarrationVoice>();
+            world.insert_resource(GameRng::seeded(seed));
+            let figure = world.spawn(NPC { name: "Figure".to_string(), dialogue: Vec::new(), voice: wobbly }).id();
+            let gone = world.spawn_empty().id();
+            world.despawn(gone);
+            let line = |text: &str, speaker| DialogLine { text: text.to_string(), speaker };
+            world.insert_resource(UiState {
+                dialog_open: true,
+                dialog_queue: vec![line("* Hello, you.", Some(figure)), line("* Hm.", None), line("* Hm.", Some(gone))],
+                ..default()
+            });
+            // Registered rather than run once, so it remembers what it's already blipped
+            let speak = world.register_system(speak_dialog_blips);
+            let mut heard = Vec::new();
+            for index in 0..3 {
+                world.resource_mut::<UiState>().dialog_index = index;
+                for revealed in 0..=12 {
+                    world.resource_mut::<UiState>().dialog_revealed = revealed as f32;
+                    world.run_system(speak).unwrap();
+                }
+                heard.extend(world.resource_mut::<Events<PlaySfxEvent>>().drain().map(|e| (e.sound, e.speed)));
+            }
+            heard
+        };
+
+        let heard = blips(7);
+        // "Hello, you" has 8 letters: a blip on the first of each pair. Then one for "Hm" each,
+        // in the narrator's voice for nobody and for a speaker who's no longer there.
+        let sounds: Vec<SfxId> = heard.iter().map(|(sound, _)| *sound).collect();
+        assert_eq!(sounds, [[SfxId::VoiceBlip; 4].as_slice(), &[SfxId::TextBlip; 2]].concat());
+        assert!(heard[..4].iter().all(|(_, speed)| (0.55..=0.85).contains(speed)));
+        assert!(heard[..4].windows(2).any(|pair| pair[0].1 != pair[1].1));
+        assert!(heard[4..].iter().all(|(_, speed)| *speed == 1.0));
+        assert_eq!(blips(7), heard);
+    }
+
+    #[test]
+    fn ambient_loops_follow_their_switch_and_their_owner() {
+        let mut world = World::new();
+        world.init_resource::<Settings>();
+        let mut assets = Assets::<AudioSource>::default();
+        let hum = assets.add(AudioSource { bytes: Vec::new().into() });
+        world.insert_resource(assets);
+        world.insert_resource(SfxLibrary(HashMap::from([(SfxId::GeneratorHum, hum)])));
+        let generator = world
+            .spawn((AmbientLoop::new(SfxId::GeneratorHum, 0.6, 200.0, false), Transform::from_xyz(100.0, 0.0, 0.0)))
+            .id();
+        let sinks = |world: &mut World| world.query::<(&AudioPlayer, &ChildOf)>().iter(world).count();
+
+        world.run_system_once(update_ambient_loops).unwrap();
+        assert_eq!(sinks(&mut world), 0);
+        world.get_mut::<AmbientLoop>(generator).unwrap().active = true;
+        world.run_system_once(update_ambient_loops).unwrap();
+        world.run_system_once(update_ambient_loops).unwrap();
+        assert_eq!(sinks(&mut world), 1);
+        world.get_mut::<AmbientLoop>(generator).unwrap().active = false;
+        world.run_system_once(update_ambient_loops).unwrap();
+        assert_eq!(sinks(&mut world), 0);
+
+        // Unloading the room takes the sound with it
+        world.get_mut::<AmbientLoop>(generator).unwrap().active = true;
+        world.run_system_once(update_ambient_loops).unwrap();
+        world.entity_mut(generator).despawn();
+        assert_eq!(sinks(&mut world), 0);
+
+        let ambient = AmbientLoop::new(SfxId::Drip, 0.5, 200.0, true);
+        assert_eq!(ambient.volume_at(0.0), 0.5);
+        assert_eq!(ambient.volume_at(100.0), 0.25);
+        assert_eq!(ambient.volume_at(250.0), 0.0);
+    }
+}
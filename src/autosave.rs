@@ -0,0 +1,154 @@
+// src/autosave.rs
+use bevy::prelude::*;
+use bevy::tasks::{block_on, poll_once, IoTaskPool, Task};
+use crate::GameSet;
+use crate::rooms::LoadRoomEvent;
+use crate::save::{write_save, SaveDir, SaveMeta, SaveSources, AUTOSAVE_SLOT, SAVE_SLOTS};
+use crate::state::GameState;
+use crate::ui::ToastEvent;
+
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autosave>()
+            .add_event::<AutosaveFinishedEvent>()
+            .add_systems(Update, (
+                // Ahead of the gameplay sets: a room loaded last frame has its player placed by now
+                start_autosave.before(GameSet::Detect).run_if(in_state(GameState::Playing)),
+                finish_autosave,
+                announce_autosaves.after(finish_autosave),
+            ));
+    }
+}
+
+// Room changes closer together than this share one autosave
+pub const AUTOSAVE_MIN_GAP_SECS: f32 = 30.0;
+// Autosave this often even without changing rooms
+pub const AUTOSAVE_INTERVAL_SECS: f32 = 5.0 * 60.0;
+
+#[derive(Resource, Default)]
+pub struct Autosave {
+    since_last: f32, // Seconds played since the last autosave (or since the game started)
+    task: Option<Task<Result<(), String>>>,
+}
+
+impl Autosave {
+    // Whether to autosave now, given whether a room was just entered. Never while the last write
+    // is still going.
+    pub fn due(&self, entered_room: bool) -> bool {
+        if self.task.is_some() {
+            return false;
+        }
+        self.since_last >= AUTOSAVE_INTERVAL_SECS || (entered_room && self.since_last >= AUTOSAVE_MIN_GAP_SECS)
+    }
+
+    // Counts from zero again, for a game just loaded or started
+    pub fn restart_clock(&mut self) {
+        self.since_last = 0.0;
+    }
+}
+
+// An autosave finished writing; Err holds why it didn't
+#[derive(Event, Clone, Debug)]
+pub struct AutosaveFinishedEvent(pub Result<(), String>);
+
+// Whether the autosave is worth offering back on the title screen: it's newer than every file
+pub fn autosave_is_newest(autosave: Option<&SaveMeta>, files: &[SaveMeta]) -> bool {
+    autosave.is_some_and(|auto| files.iter().all(|file| file.saved_at < auto.saved_at))
+}
+
+// The same check against the files in `saves`
+pub fn autosave_newer_than_files(saves: &SaveDir) -> bool {
+    let files: Vec<_> = (0..SAVE_SLOTS).filter_map(|slot| saves.slot_meta(slot)).collect();
+    autosave_is_newest(saves.slot_meta(AUTOSAVE_SLOT).as_ref(), &files)
+}
+
+// Snapshots the game on the main thread, then hands the file write to the IO pool. Only walking
+// into a room counts; a save being loaded places the player without a spawn point.
+fn start_autosave(
+    time: Res<Time<Virtual>>,
+    mut loads: EventReader<LoadRoomEvent>,
+    mut autosave: ResMut<Autosave>,
+    sources: SaveSources,
+    saves: Res<SaveDir>,
+) {
+    autosave.since_last += time.delta_secs();
+    let entered_room = loads.read().any(|load| load.spawn_point.is_some());
+    if !autosave.due(entered_room) {
+        return;
+    }
+    let Some(data) = sources.snapshot() else { return };
+    let path = saves.slot_path(AUTOSAVE_SLOT);
+    autosave.since_last = 0.0;
+    autosave.task = Some(IoTaskPool::get().spawn(async move { write_save(&path, &data) }));
+}
+
+fn finish_autosave(mut autosave: ResMut<Autosave>, mut finished: EventWriter<AutosaveFinishedEvent>) {
+    let Some(task) = autosave.task.as_mut() else { return };
+    let Some(result) = block_on(poll_once(task)) else { return };
+    autosave.task = None;
+    finished.write(AutosaveFinishedEvent(result));
+}
+
+fn announce_autosaves(
+    mut finished: EventReader<AutosaveFinishedEvent>,
+    mut toasts: EventWriter<ToastEvent>,
+    saves: Res<SaveDir>,
+) {
+    for AutosaveFinishedEvent(result) in finished.read() {
+        let path = saves.slot_path(AUTOSAVE_SLOT);
+        match result {
+            Ok(()) => {
+                info!("Autosaved to {}", path.display());
+                toasts.write(ToastEvent("Autosaved".to_string()));
+            }
+            Err(e) => {
+                warn!("Couldn't autosave to {}: {}", path.display(), e);
+                toasts.write(ToastEvent("Autosave failed".to_string()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(saved_at: u64) -> SaveMeta {
+        SaveMeta { room: "start".to_string(), playtime: 0.0, saved_at, completed: false }
+    }
+
+    #[test]
+    fn room_changes_are_throttled() {
+        let mut autosave = Autosave::default();
+        assert!(!autosave.due(true));
+        autosave.since_last = AUTOSAVE_MIN_GAP_SECS;
+        assert!(autosave.due(true));
+        assert!(!autosave.due(false));
+        autosave.since_last = AUTOSAVE_INTERVAL_SECS;
+        assert!(autosave.due(false));
+
+        // One write at a time
+        autosave.task = Some(IoTaskPool::get_or_init(Default::default).spawn(async { Ok(()) }));
+        assert!(!autosave.due(true));
+    }
+
+    #[test]
+    fn a_new_game_starts_the_clock_over() {
+        let mut app = crate::headless_app();
+        app.update();
+        app.world_mut().resource_mut::<Autosave>().since_last = AUTOSAVE_INTERVAL_SECS - 1.0;
+        app.world_mut().send_event(crate::save::NewGameEvent { carry_from: None });
+        app.update();
+        assert_eq!(app.world().resource::<Autosave>().since_last, 0.0);
+    }
+
+    #[test]
+    fn autosave_is_offered_only_when_newest() {
+        assert!(!autosave_is_newest(None, &[meta(10)]));
+        assert!(autosave_is_newest(Some(&meta(20)), &[]));
+        assert!(autosave_is_newest(Some(&meta(20)), &[meta(10), meta(19)]));
+        assert!(!autosave_is_newest(Some(&meta(20)), &[meta(10), meta(20)]));
+    }
+}
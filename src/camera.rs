@@ -0,0 +1,721 @@
+// src/camera.rs
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::render::camera::{ScalingMode, Viewport};
+use bevy::window::{MonitorSelection, PrimaryWindow, WindowMode};
+use crate::input::{Action, InputMap};
+use crate::objects::RoomBounds;
+use crate::player::{MovementLocks, Player, TeleportPlayerEvent};
+use crate::GameSet;
+use crate::settings::Settings;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraFocus>()
+            .init_resource::<CameraSettings>()
+            .init_resource::<CameraPan>()
+            .add_event::<CameraPanEvent>()
+            .add_event::<RoomSlideEvent>()
+            .add_systems(Startup, setup_camera)
+            .add_systems(Update, (zoom_camera.in_set(GameSet::Input), apply_zoom).chain())
+            // On every screen, title included
+            .add_systems(Update, (toggle_fullscreen, letterbox))
+            .add_systems(Update, (toggle_deadzone_gizmo.in_set(GameSet::Input), draw_deadzone_gizmo).chain())
+            .add_systems(Update, (start_camera_pans, update_camera_pan, lock_movement_during_pan)
+                .chain()
+                .in_set(GameSet::Process))
+            .add_systems(Update, (advance_room_slide, lock_movement_during_slide).chain().in_set(GameSet::Process))
+            // After everything in Update has moved the player, so the view never trails a frame
+            .add_systems(PostUpdate, (ensure_single_camera_target, camera_follow)
+                .chain()
+                .before(TransformSystem::TransformPropagate));
+    }
+}
+
+// Eases the camera toward the player. `position` is the unrounded spot; the Transform gets it
+// snapped to whole pixels so nearest-filtered sprites don't shimmer.
+#[derive(Component)]
+pub struct CameraFollow {
+    pub smoothing: f32, // Higher is snappier; roughly 1/seconds to close most of the gap
+    pub position: Vec2,
+    pub cut: bool, // Jump straight to the target next frame instead of easing
+}
+
+pub const CAMERA_SMOOTHING: f32 = 8.0;
+
+// How loosely the camera tracks its target
+#[derive(Resource)]
+pub struct CameraSettings {
+    pub deadzone: Vec2,      // Box around the camera center the target can wander in without it moving
+    pub show_deadzone: bool, // Debug: outline the deadzone (F7)
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self { deadzone: Vec2::new(48.0, 32.0), show_deadzone: false }
+    }
+}
+
+impl CameraSettings {
+    // Where the camera at `camera` needs to be to bring `subject` back inside the deadzone;
+    // `camera` itself while it's already there
+    pub fn deadzone_target(&self, camera: Vec2, subject: Vec2) -> Vec2 {
+        let half = self.deadzone / 2.0;
+        // However far the subject has stepped past the box's edge, the camera follows by as much
+        camera + subject - subject.clamp(camera - half, camera + half)
+    }
+}
+
+impl CameraFollow {
+    // Moves `position` part of the way to `target`, the same distance per second at any frame rate
+    pub fn ease_toward(&mut self, target: Vec2, dt: f32) {
+        let t = 1.0 - (-self.smoothing * dt).exp();
+        self.position = self.position.lerp(target, t);
+    }
+
+    // Rounds to whole screen pixels, `pixel` world units apiece (the projection scale)
+    pub fn snapped(&self, pixel: f32) -> Vec2 {
+        (self.position / pixel).round() * pixel
+    }
+}
+
+// The entity the camera follows: the player unless a cutscene hands it to someone else. Exactly
+// one entity should have it; move it with CameraCommandsExt rather than inserting it by hand.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct CameraTarget;
+
+pub trait CameraCommandsExt {
+    // Follows `entity` from now on, taking the marker off whatever had it
+    fn set_camera_target(&mut self, entity: Entity);
+    // Back to following the player
+    fn reset_camera_target(&mut self);
+}
+
+impl CameraCommandsExt for Commands<'_, '_> {
+    fn set_camera_target(&mut self, entity: Entity) {
+        self.queue(move |world: &mut World| retarget_camera(world, Some(entity)));
+    }
+
+    fn reset_camera_target(&mut self) {
+        self.queue(|world: &mut World| retarget_camera(world, None));
+    }
+}
+
+// Moves the marker to `entity`, or to the player if that's None or no longer exists
+fn retarget_camera(world: &mut World, entity: Option<Entity>) {
+    let holders: Vec<Entity> = world.query_filtered::<Entity, With<CameraTarget>>().iter(world).collect();
+    for holder in holders {
+        world.entity_mut(holder).remove::<CameraTarget>();
+    }
+    let target = entity
+        .filter(|&e| world.get_entity(e).is_ok())
+        .or_else(|| world.query_filtered::<Entity, With<Player>>().iter(world).next());
+    if let Some(target) = target {
+        world.entity_mut(target).insert(CameraTarget);
+    }
+}
+
+// Catches a target despawned mid-cutscene (or two claimed at once) and hands the camera back to
+// the player, so it never freezes on nothing
+fn ensure_single_camera_target(
+    mut commands: Commands,
+    targets: Query<Entity, With<CameraTarget>>,
+    player_query: Query<Entity, With<Player>>,
+) {
+    let count = targets.iter().count();
+    if count == 1 || player_query.is_empty() {
+        return;
+    }
+    warn!("{} camera targets; following the player", count);
+    commands.reset_camera_target();
+}
+
+// What the camera eases toward. Player means the CameraTarget, normally the player; anything
+// else locks movement until it's handed back.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub enum CameraFocus {
+    #[default]
+    Player,
+    Entity(Entity),
+    Point(Vec2),
+}
+
+// Looks at `target` for `hold_secs` (counted from the event, so include the trip over), then
+// hands the camera back to the player: easing over if `return_smoothly`, otherwise cutting.
+#[derive(Event, Clone, Copy)]
+pub struct CameraPanEvent {
+    pub target: CameraFocus,
+    pub hold_secs: f32,
+    pub return_smoothly: bool,
+}
+
+// The pan in progress, if any
+#[derive(Resource, Default)]
+pub struct CameraPan(Option<PanState>);
+
+pub struct PanState {
+    remaining: f32,
+    return_smoothly: bool,
+}
+
+// Sent alongside the teleport when the player walks through a sliding RoomTransition. `exit` is
+// where they left the old room; it lines up with the spawn point they arrive at in the new one.
+#[derive(Event, Clone, Copy)]
+pub struct RoomSlideEvent {
+    pub exit: Vec2,
+}
+
+pub const ROOM_SLIDE_SECS: f32 = 0.4;
+// How far the player is carried on into the new room while the camera slides
+pub const ROOM_SLIDE_NUDGE: f32 = 6.0;
+
+// A slide between adjoining rooms in progress. Movement is locked and the follow system defers
+// to it until `t` reaches 1.
+#[derive(Resource)]
+pub struct CameraTransition {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub t: f32,
+    pub player_from: Vec2,
+    pub nudge: Vec2,
+}
+
+impl CameraTransition {
+    // Where the old room's view sits in the new room's coordinates, taking the doorway as fixed:
+    // the camera keeps its offset from the player across the swap
+    pub fn start(camera: Vec2, exit: Vec2, arrival: Vec2, to: Vec2) -> Self {
+        let from = camera - exit + arrival;
+        Self {
+            from,
+            to,
+            t: 0.0,
+            player_from: arrival,
+            nudge: (to - from).normalize_or_zero() * ROOM_SLIDE_NUDGE,
+        }
+    }
+
+    fn eased(&self) -> f32 {
+        self.t * self.t * (3.0 - 2.0 * self.t)
+    }
+
+    pub fn camera(&self) -> Vec2 {
+        self.from.lerp(self.to, self.eased())
+    }
+
+    pub fn player(&self) -> Vec2 {
+        self.player_from + self.nudge * self.eased()
+    }
+}
+
+// Projection scales the zoom keys step through, zoomed out to zoomed in. Each maps a world pixel
+// to a whole number of screen pixels (or exactly two world pixels to one), so sprites stay crisp.
+pub const ZOOM_STEPS: [f32; 4] = [2.0, 1.0, 0.5, 0.25];
+
+// The step `steps` places along from `current` (positive zooms in), stopping at either end
+pub fn zoom_step(current: f32, steps: i32) -> f32 {
+    let index = ZOOM_STEPS.iter().position(|&s| s == current).unwrap_or(1) as i32;
+    ZOOM_STEPS[(index + steps).clamp(0, ZOOM_STEPS.len() as i32 - 1) as usize]
+}
+
+// The game's own resolution, in world pixels at zoom 1. The window shows the largest whole-number
+// multiple of it that fits, with bars around the rest.
+pub const GAME_SIZE: Vec2 = Vec2::new(640.0, 480.0);
+
+// The largest whole-number scale of the game that fits in `window` (physical pixels), and the
+// centered viewport rectangle (position, size) it occupies
+pub fn letterbox_viewport(window: UVec2) -> (u32, UVec2, UVec2) {
+    let game = GAME_SIZE.as_uvec2();
+    let scale = (window.x / game.x).min(window.y / game.y).max(1);
+    let size = game * scale;
+    (scale, window.saturating_sub(size) / 2, size)
+}
+
+// Keeps a view `half_view` wide either side of `pos` inside `bounds`. On an axis where the room is
+// narrower than the view, centers on the room instead.
+pub fn clamp_to_bounds(pos: Vec2, half_view: Vec2, bounds: &RoomBounds) -> Vec2 {
+    let clamp_axis = |p: f32, half: f32, min: f32, max: f32| {
+        if max - min <= half * 2.0 { (min + max) / 2.0 } else { p.clamp(min + half, max - half) }
+    };
+    Vec2::new(
+        clamp_axis(pos.x, half_view.x, bounds.min.x, bounds.max.x),
+        clamp_axis(pos.y, half_view.y, bounds.min.y, bounds.max.y),
+    )
+}
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2d,
+        // Always GAME_SIZE across (times the zoom) however big the viewport is scaled up
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed { width: GAME_SIZE.x, height: GAME_SIZE.y },
+            ..OrthographicProjection::default_2d()
+        }),
+        CameraFollow { smoothing: CAMERA_SMOOTHING, position: Vec2::ZERO, cut: false },
+    ));
+    // Zoom is the projection's scale, set from Settings by apply_zoom
+}
+
+// Remembered in the settings file, so the next launch opens the same way
+fn toggle_fullscreen(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut settings: ResMut<Settings>,
+) {
+    if !input.just_pressed(Action::Fullscreen, &keyboard) {
+        return;
+    }
+    let Ok(mut window) = windows.single_mut() else { return };
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+        _ => WindowMode::Windowed,
+    };
+    settings.fullscreen = window.mode != WindowMode::Windowed;
+    settings.save();
+}
+
+// Fits the camera's viewport to the window at a whole-number scale and scales the UI to match,
+// so menus and the dialog box stay inside the game area rather than stretching into the bars
+fn letterbox(
+    windows: Query<Ref<Window>, With<PrimaryWindow>>,
+    mut cameras: Query<&mut Camera, With<CameraFollow>>,
+    mut ui_scale: ResMut<UiScale>,
+    settings: Res<Settings>,
+) {
+    let Ok(window) = windows.single() else { return };
+    if !window.is_changed() && !settings.is_changed() {
+        return;
+    }
+    let (scale, position, size) = letterbox_viewport(window.physical_size());
+    for mut camera in cameras.iter_mut() {
+        let current = camera.viewport.as_ref().map(|v| (v.physical_position, v.physical_size));
+        if current != Some((position, size)) {
+            camera.viewport = Some(Viewport { physical_position: position, physical_size: size, ..default() });
+        }
+    }
+    // UI Val::Px are logical pixels; make one of them one game pixel (times the player's UI scale)
+    let ui = scale as f32 / window.scale_factor() * settings.ui_scale;
+    if ui_scale.0 != ui {
+        ui_scale.0 = ui;
+    }
+}
+
+fn zoom_camera(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    mut settings: ResMut<Settings>,
+) {
+    let steps = input.just_pressed(Action::ZoomIn, &keyboard) as i32
+        - input.just_pressed(Action::ZoomOut, &keyboard) as i32;
+    if steps != 0 {
+        let zoom = zoom_step(settings.zoom, steps);
+        if zoom != settings.zoom {
+            settings.zoom = zoom;
+            settings.save();
+        }
+    }
+}
+
+// Only the world camera scales; UI is laid out in screen space and stays put
+fn apply_zoom(settings: Res<Settings>, mut camera_query: Query<&mut Projection, With<CameraFollow>>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut projection in camera_query.iter_mut() {
+        if let Projection::Orthographic(ortho) = projection.as_mut()
+            && ortho.scale != settings.zoom
+        {
+            ortho.scale = settings.zoom;
+        }
+    }
+}
+
+fn start_camera_pans(
+    mut events: EventReader<CameraPanEvent>,
+    mut focus: ResMut<CameraFocus>,
+    mut pan: ResMut<CameraPan>,
+) {
+    // A newer pan replaces one still running
+    let Some(event) = events.read().last() else { return };
+    *focus = event.target;
+    pan.0 = Some(PanState { remaining: event.hold_secs, return_smoothly: event.return_smoothly });
+}
+
+// Counts down the hold and gives the camera back to the player, early if the entity it was
+// watching has been despawned. Like the rest of the camera it runs on real time, so a pan still
+// plays out while the dialog that came with it has the world stopped.
+fn update_camera_pan(
+    time: Res<Time<Real>>,
+    mut focus: ResMut<CameraFocus>,
+    mut pan: ResMut<CameraPan>,
+    entities: Query<(), With<Transform>>,
+    mut camera_query: Query<&mut CameraFollow>,
+) {
+    let gone = matches!(*focus, CameraFocus::Entity(e) if entities.get(e).is_err());
+    let Some(state) = pan.0.as_mut() else {
+        if gone {
+            *focus = CameraFocus::Player;
+        }
+        return;
+    };
+    state.remaining -= time.delta_secs();
+    if state.remaining > 0.0 && !gone {
+        return;
+    }
+    if !state.return_smoothly {
+        for mut follow in camera_query.iter_mut() {
+            follow.cut = true;
+        }
+    }
+    pan.0 = None;
+    *focus = CameraFocus::Player;
+}
+
+fn lock_movement_during_pan(focus: Res<CameraFocus>, mut locked: Local<bool>, mut locks: ResMut<MovementLocks>) {
+    let panning = *focus != CameraFocus::Player;
+    if panning == *locked {
+        return;
+    }
+    *locked = panning;
+    if panning {
+        locks.lock();
+    } else {
+        locks.unlock();
+    }
+}
+
+fn advance_room_slide(
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+    transition: Option<ResMut<CameraTransition>>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+) {
+    let Some(mut transition) = transition else { return };
+    transition.t = (transition.t + time.delta_secs() / ROOM_SLIDE_SECS).min(1.0);
+    if let Ok(mut player_tf) = player_query.single_mut() {
+        let pos = transition.player();
+        player_tf.translation.x = pos.x;
+        player_tf.translation.y = pos.y;
+    }
+    if transition.t >= 1.0 {
+        commands.remove_resource::<CameraTransition>();
+    }
+}
+
+fn lock_movement_during_slide(
+    transition: Option<Res<CameraTransition>>,
+    mut locked: Local<bool>,
+    mut locks: ResMut<MovementLocks>,
+) {
+    let sliding = transition.is_some();
+    if sliding == *locked {
+        return;
+    }
+    *locked = sliding;
+    if sliding {
+        locks.lock();
+    } else {
+        locks.unlock();
+    }
+}
+
+// Debug: F7 outlines the camera deadzone
+fn toggle_deadzone_gizmo(keyboard: Res<ButtonInput<KeyCode>>, mut settings: ResMut<CameraSettings>) {
+    if keyboard.just_pressed(KeyCode::F7) {
+        settings.show_deadzone = !settings.show_deadzone;
+    }
+}
+
+fn draw_deadzone_gizmo(
+    settings: Res<CameraSettings>,
+    mut gizmos: Gizmos,
+    camera_query: Query<&CameraFollow>,
+) {
+    if !settings.show_deadzone {
+        return;
+    }
+    for follow in camera_query.iter() {
+        gizmos.rect_2d(Isometry2d::from_translation(follow.position), settings.deadzone, Color::srgb(0.2, 1.0, 0.4));
+    }
+}
+
+// What the camera looks at and how: the focus, the room it's kept inside, and the entities it
+// might be on
+#[derive(SystemParam)]
+struct CameraAim<'w, 's> {
+    focus: Res<'w, CameraFocus>,
+    settings: Res<'w, CameraSettings>,
+    bounds: Option<Res<'w, RoomBounds>>,
+    player_query: Query<'w, 's, &'static Transform, (With<Player>, Without<CameraFollow>)>,
+    target_query: Query<'w, 's, (&'static Transform, Ref<'static, CameraTarget>), Without<CameraFollow>>,
+    focus_query: Query<'w, 's, &'static GlobalTransform, Without<CameraFollow>>,
+}
+
+fn camera_follow(
+    time: Res<Time<Real>>,
+    locks: Res<MovementLocks>,
+    aim: CameraAim,
+    (mut teleports, mut slides): (EventReader<TeleportPlayerEvent>, EventReader<RoomSlideEvent>),
+    transition: Option<Res<CameraTransition>>,
+    mut commands: Commands,
+    mut camera_query: Query<(&mut CameraFollow, &mut Transform, &Projection)>,
+) {
+    let CameraAim { focus, settings, bounds, player_query, target_query, focus_query } = aim;
+    let Ok(player_tf) = player_query.single() else { return };
+    let Ok((mut follow, mut camera_tf, projection)) = camera_query.single_mut() else { return };
+    // Until ensure_single_camera_target has sorted out a missing or doubled marker, the player
+    let (followed, retargeted) = target_query
+        .single()
+        .map_or((player_tf.translation.truncate(), false), |(tf, marker)| (tf.translation.truncate(), marker.is_added()));
+    let subject = match *focus {
+        CameraFocus::Player => followed,
+        // A despawned entity leaves the camera on its target until update_camera_pan notices
+        CameraFocus::Entity(e) => focus_query.get(e).map_or(followed, |tf| tf.translation().truncate()),
+        CameraFocus::Point(point) => point,
+    };
+    // What the camera shows grows with the projection's scale as it zooms out
+    let scale = match projection {
+        Projection::Orthographic(ortho) => ortho.scale,
+        _ => 1.0,
+    };
+    // Room edges beat everything else: the view never shows past them
+    let clamp = |pos: Vec2| bounds.as_ref().map_or(pos, |b| clamp_to_bounds(pos, GAME_SIZE / 2.0 * scale, b));
+    let target = clamp(subject);
+
+    let teleported = teleports.read().count() > 0;
+    if let Some(slide) = slides.read().last() {
+        // The player has just been placed in the new room; start from the old view, carried over
+        let start = CameraTransition::start(follow.position, slide.exit, player_tf.translation.truncate(), target);
+        follow.position = start.from;
+        commands.insert_resource(start);
+    } else if let Some(transition) = transition {
+        follow.position = transition.camera();
+    } else if teleported || follow.cut || retargeted {
+        // Cut straight to a new room, spawn point or subject rather than sweeping across. This runs
+        // after teleport_player (Update) and before transforms propagate, so the very frame that
+        // moved the player already draws from the new spot, including the game's first.
+        follow.position = target;
+        follow.cut = false;
+    } else if locks.is_locked() && *focus == CameraFocus::Player {
+        // Hold still behind menus and dialogs, but not while the lock is a pan's own
+        return;
+    } else {
+        // Following, the target gets some slack; pans go straight to their point
+        let goal = match *focus {
+            CameraFocus::Player => clamp(settings.deadzone_target(follow.position, subject)),
+            _ => target,
+        };
+        follow.ease_toward(goal, time.delta_secs());
+    }
+    let snapped = follow.snapped(scale);
+    camera_tf.translation.x = snapped.x;
+    camera_tf.translation.y = snapped.y;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Just enough of the game for camera_follow to run headless
+    fn follow_app(player_pos: Vec2) -> (App, Entity, Entity) {
+        let mut app = App::new();
+        app.init_resource::<Time<Real>>()
+            .init_resource::<MovementLocks>()
+            .init_resource::<CameraFocus>()
+            .init_resource::<CameraSettings>()
+            .insert_resource(RoomBounds { min: Vec2::new(-500.0, -240.0), max: Vec2::new(500.0, 240.0) })
+            .add_event::<TeleportPlayerEvent>()
+            .add_event::<RoomSlideEvent>()
+            .add_systems(PostUpdate, camera_follow);
+        let camera = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Projection::Orthographic(OrthographicProjection::default_2d()),
+                CameraFollow { smoothing: CAMERA_SMOOTHING, position: Vec2::ZERO, cut: false },
+            ))
+            .id();
+        let player = app
+            .world_mut()
+            .spawn((Player::default(), CameraTarget, Transform::from_translation(player_pos.extend(10.0))))
+            .id();
+        (app, camera, player)
+    }
+
+    #[test]
+    fn easing_is_frame_rate_independent_and_snaps_to_pixels() {
+        let target = Vec2::new(100.0, -40.0);
+        let mut fast = CameraFollow { smoothing: CAMERA_SMOOTHING, position: Vec2::ZERO, cut: false };
+        let mut slow = CameraFollow { smoothing: CAMERA_SMOOTHING, position: Vec2::ZERO, cut: false };
+        for _ in 0..12 {
+            fast.ease_toward(target, 1.0 / 120.0);
+        }
+        for _ in 0..3 {
+            slow.ease_toward(target, 1.0 / 30.0);
+        }
+        assert!(fast.position.distance(slow.position) < 1e-3);
+        assert!(fast.position.x > 0.0 && fast.position.x < target.x);
+
+        let snapped = fast.snapped(1.0);
+        assert_eq!(snapped, snapped.round());
+        for _ in 0..120 {
+            fast.ease_toward(target, 1.0 / 60.0);
+        }
+        assert_eq!(fast.snapped(1.0), target);
+    }
+
+    #[test]
+    fn letterbox_uses_whole_multiples_and_centers() {
+        // Exactly the game size: no bars
+        assert_eq!(letterbox_viewport(UVec2::new(640, 480)), (1, UVec2::ZERO, UVec2::new(640, 480)));
+        // 1080p fits 2x (1280x960), barred on all sides
+        assert_eq!(
+            letterbox_viewport(UVec2::new(1920, 1080)),
+            (2, UVec2::new(320, 60), UVec2::new(1280, 960))
+        );
+        // Tall and narrow: the width decides
+        assert_eq!(
+            letterbox_viewport(UVec2::new(1300, 2000)),
+            (2, UVec2::new(10, 520), UVec2::new(1280, 960))
+        );
+        // Never below 1x, even if the window is somehow smaller
+        assert_eq!(letterbox_viewport(UVec2::new(600, 400)).0, 1);
+    }
+
+    #[test]
+    fn room_slide_carries_the_view_through_the_doorway() {
+        // Left the hallway (camera centered on it) by the exit at its bottom, arriving at the top
+        // of the start room
+        let mut slide = CameraTransition::start(Vec2::ZERO, Vec2::new(0.0, -215.0), Vec2::new(0.0, 160.0), Vec2::ZERO);
+        assert_eq!(slide.camera(), Vec2::new(0.0, 375.0));
+        assert_eq!(slide.player(), Vec2::new(0.0, 160.0));
+
+        slide.t = 0.5;
+        assert_eq!(slide.camera(), Vec2::new(0.0, 187.5));
+        slide.t = 1.0;
+        assert_eq!(slide.camera(), Vec2::ZERO);
+        // Carried a few pixels on down into the room, the way the view moved
+        assert_eq!(slide.player(), Vec2::new(0.0, 160.0 - ROOM_SLIDE_NUDGE));
+    }
+
+    #[test]
+    fn camera_target_moves_and_falls_back_to_the_player() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        let player = world.spawn((Player::default(), CameraTarget)).id();
+        let npc = world.spawn(Transform::default()).id();
+
+        world.commands().set_camera_target(npc);
+        world.flush();
+        assert!(world.get::<CameraTarget>(npc).is_some());
+        assert!(world.get::<CameraTarget>(player).is_none());
+
+        // The NPC walks off and is despawned with the marker still on it
+        world.despawn(npc);
+        world.run_system_once(ensure_single_camera_target).unwrap();
+        world.flush();
+        assert!(world.get::<CameraTarget>(player).is_some());
+
+        // Two at once is just as wrong
+        let other = world.spawn(CameraTarget).id();
+        world.run_system_once(ensure_single_camera_target).unwrap();
+        world.flush();
+        assert!(world.get::<CameraTarget>(other).is_none());
+        assert!(world.get::<CameraTarget>(player).is_some());
+    }
+
+    #[test]
+    fn deadzone_only_moves_the_camera_once_the_subject_leaves_it() {
+        let settings = CameraSettings::default(); // 48x32
+        let camera = Vec2::new(100.0, 50.0);
+        // Anywhere inside the box: stay put
+        assert_eq!(settings.deadzone_target(camera, Vec2::new(120.0, 40.0)), camera);
+        // 6px past the right edge and 4px under the bottom: catch up by exactly that
+        assert_eq!(settings.deadzone_target(camera, Vec2::new(130.0, 30.0)), Vec2::new(106.0, 46.0));
+
+        // Clamped to a room afterwards, so its edges still win
+        let room = RoomBounds { min: Vec2::new(-330.0, -240.0), max: Vec2::new(330.0, 240.0) };
+        let goal = settings.deadzone_target(Vec2::new(8.0, 0.0), Vec2::new(60.0, 0.0));
+        assert_eq!(goal.x, 36.0);
+        assert_eq!(clamp_to_bounds(goal, GAME_SIZE / 2.0, &room).x, 10.0);
+    }
+
+    #[test]
+    fn zoom_steps_stop_at_the_ends() {
+        assert_eq!(zoom_step(1.0, 1), 0.5);
+        assert_eq!(zoom_step(0.5, 1), 0.25);
+        assert_eq!(zoom_step(0.25, 1), 0.25);
+        assert_eq!(zoom_step(1.0, -1), 2.0);
+        assert_eq!(zoom_step(2.0, -1), 2.0);
+        // Anything off the list starts from 1x
+        assert_eq!(zoom_step(0.7, 1), 0.5);
+
+        // Zoomed out, a screen pixel covers two world pixels; zoomed in, a quarter of one
+        let follow = CameraFollow { smoothing: CAMERA_SMOOTHING, position: Vec2::new(3.3, -1.1), cut: false };
+        assert_eq!(follow.snapped(2.0), Vec2::new(4.0, -2.0));
+        assert_eq!(follow.snapped(0.25), Vec2::new(3.25, -1.0));
+    }
+
+    #[test]
+    fn pan_returns_to_the_player_when_its_entity_despawns() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<Time<Real>>();
+        let watched = world.spawn(Transform::default()).id();
+        world.insert_resource(CameraFocus::Entity(watched));
+        world.insert_resource(CameraPan(Some(PanState { remaining: 5.0, return_smoothly: false })));
+        let camera = world
+            .spawn(CameraFollow { smoothing: CAMERA_SMOOTHING, position: Vec2::ZERO, cut: false })
+            .id();
+
+        // Still alive and still holding
+        world.run_system_once(update_camera_pan).unwrap();
+        assert_eq!(*world.resource::<CameraFocus>(), CameraFocus::Entity(watched));
+
+        world.despawn(watched);
+        world.run_system_once(update_camera_pan).unwrap();
+        assert_eq!(*world.resource::<CameraFocus>(), CameraFocus::Player);
+        assert!(world.resource::<CameraPan>().0.is_none());
+        assert!(world.get::<CameraFollow>(camera).unwrap().cut);
+    }
+
+    #[test]
+    fn clamping_keeps_the_view_inside_the_room() {
+        let room = RoomBounds { min: Vec2::new(-400.0, -100.0), max: Vec2::new(400.0, 300.0) };
+        let half_view = Vec2::new(320.0, 240.0);
+
+        // Wide enough to scroll sideways, but stopping short of the walls
+        assert_eq!(clamp_to_bounds(Vec2::new(0.0, 100.0), half_view, &room).x, 0.0);
+        assert_eq!(clamp_to_bounds(Vec2::new(390.0, 100.0), half_view, &room).x, 80.0);
+        assert_eq!(clamp_to_bounds(Vec2::new(-390.0, 100.0), half_view, &room).x, -80.0);
+        // Shorter than the view: always centered vertically
+        assert_eq!(clamp_to_bounds(Vec2::new(0.0, 250.0), half_view, &room).y, 100.0);
+
+        // Zoomed in 2x the view halves, so the same room now scrolls vertically too
+        let zoomed = clamp_to_bounds(Vec2::new(0.0, 250.0), half_view * 0.5, &room);
+        assert_eq!(zoomed.y, 180.0);
+    }
+
+    #[test]
+    fn first_frame_and_teleports_place_the_camera_immediately() {
+        // Far to the right: the room's edge stops the view at x = 500 - 320
+        let (mut app, camera, player) = follow_app(Vec2::new(400.0, 0.0));
+        app.update();
+        let camera_pos = |app: &App| app.world().get::<Transform>(camera).unwrap().translation.truncate();
+        assert_eq!(camera_pos(&app), Vec2::new(180.0, 0.0));
+
+        // Teleported back across the room: no easing over from the old spot
+        app.world_mut().get_mut::<Transform>(player).unwrap().translation.x = -400.0;
+        app.world_mut().send_event(TeleportPlayerEvent {
+            to: crate::player::SpawnTarget::Position(Vec2::new(-400.0, 0.0)),
+            fade: false,
+        });
+        app.update();
+        assert_eq!(camera_pos(&app), Vec2::new(-180.0, 0.0));
+    }
+}
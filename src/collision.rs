@@ -0,0 +1,230 @@
+// src/collision.rs
+use bevy::prelude::*;
+
+// Box collision for everything that walks into things: the player, the crates it shoves and
+// critters. Plain functions on boxes, so the maths can be tested without an App; callers turn
+// their Transforms and Colliders into Aabbs (Collider::aabb) and the results back.
+
+// An axis-aligned box: its middle and half its size
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub center: Vec2,
+    pub half: Vec2,
+}
+
+impl Aabb {
+    pub fn new(center: Vec2, half: Vec2) -> Self {
+        Self { center, half }
+    }
+
+    // The same box with its middle at `center`
+    pub fn at(self, center: Vec2) -> Self {
+        Self { center, ..self }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        (point - self.center).abs().cmple(self.half).all()
+    }
+}
+
+// Whether the boxes overlap; boxes that only share an edge don't
+pub fn overlaps(a: Aabb, b: Aabb) -> bool {
+    let d = (a.center - b.center).abs();
+    d.x < a.half.x + b.half.x && d.y < a.half.y + b.half.y
+}
+
+// The shortest move taking `a` out of `b`, along whichever axis they overlap less. None if they
+// don't overlap.
+pub fn penetration(a: Aabb, b: Aabb) -> Option<Vec2> {
+    if !overlaps(a, b) {
+        return None;
+    }
+    let d = a.center - b.center;
+    let depth = a.half + b.half - d.abs();
+    Some(if depth.x < depth.y { Vec2::new(depth.x * d.x.signum(), 0.0) } else { Vec2::new(0.0, depth.y * d.y.signum()) })
+}
+
+// Where the middle of `moving`, having just moved by `delta` (along one axis) into `solid`, goes:
+// back out, flush against the side it came in from. None if they don't overlap.
+pub fn resolve_axis(moving: Aabb, solid: Aabb, delta: Vec2) -> Option<Vec2> {
+    if !overlaps(moving, solid) {
+        return None;
+    }
+    let (s, mut center) = (solid, moving.center);
+    if delta.x > 0.0 {
+        center.x = s.center.x - s.half.x - moving.half.x;
+    } else if delta.x < 0.0 {
+        center.x = s.center.x + s.half.x + moving.half.x;
+    } else if delta.y > 0.0 {
+        center.y = s.center.y - s.half.y - moving.half.y;
+    } else if delta.y < 0.0 {
+        center.y = s.center.y + s.half.y + moving.half.y;
+    }
+    Some(center)
+}
+
+// Where the middle of `moving`, having just moved by `delta`, ends up once pushed back out of
+// each of `solids` in turn
+pub fn push_out(moving: Aabb, delta: Vec2, solids: impl IntoIterator<Item = Aabb>) -> Vec2 {
+    solids
+        .into_iter()
+        .fold(moving.center, |center, solid| resolve_axis(moving.at(center), solid, delta).unwrap_or(center))
+}
+
+pub fn fits(moving: Aabb, solids: &[Aabb]) -> bool {
+    !solids.iter().any(|&solid| overlaps(moving, solid))
+}
+
+// How far a blocked box may be nudged sideways to slip past a corner it only just clipped
+pub const CORNER_NUDGE: f32 = 4.0;
+
+// Where the middle of `moving` ends up after moving by `delta` (along one axis) among `solids`.
+// If the only thing in the way is a corner overlapping the box's edge by at most CORNER_NUDGE,
+// the blocked movement is spent sliding around it instead, so walls built from several segments
+// don't snag.
+pub fn slide_axis(moving: Aabb, delta: Vec2, solids: &[Aabb]) -> Vec2 {
+    let unblocked = moving.center + delta;
+    let pushed = push_out(moving.at(unblocked), delta, solids.iter().copied());
+    let lost = (unblocked - pushed).length();
+    if lost <= 0.0 {
+        return pushed;
+    }
+
+    let side = if delta.x != 0.0 { Vec2::Y } else { Vec2::X };
+    // Sideways distance needed to clear everything the unblocked move ran into, per direction
+    let clearance = |dir: f32| {
+        solids
+            .iter()
+            .filter(|&&solid| overlaps(moving.at(unblocked), solid))
+            .map(|solid| {
+                let reach = (solid.center + solid.half * dir * side) - (unblocked - moving.half * dir * side);
+                reach.dot(side) * dir
+            })
+            .fold(0.0, f32::max)
+    };
+    let (up, down) = (clearance(1.0), clearance(-1.0));
+    let (dir, needed) = if up <= down { (1.0, up) } else { (-1.0, down) };
+    if needed > CORNER_NUDGE {
+        return pushed;
+    }
+    let slid = if needed <= lost {
+        unblocked + side * dir * needed // Past the corner, keeping the forward movement
+    } else {
+        pushed + side * dir * lost
+    };
+    if fits(moving.at(slid), solids) { slid } else { pushed }
+}
+
+// Longest single collision step: under half the thinnest wall (10px gates), so even a long
+// frame at high speed is resolved in steps that can't skip clean over a wall
+pub const MAX_SUBSTEP: f32 = 4.0;
+
+// Where the middle of `moving` ends up after moving by `delta`, X then Y in small steps, sliding
+// along `solids` and shoving `pushables` (moved in place) out of the way. A pushable wedged
+// against a solid blocks like one.
+pub fn move_and_collide(moving: Aabb, delta: Vec2, solids: &[Aabb], pushables: &mut [Aabb]) -> Vec2 {
+    let steps = (delta.abs().max_element() / MAX_SUBSTEP).ceil().max(1.0);
+    let step = delta / steps;
+    let mut center = moving.center;
+    for _ in 0..steps as u32 {
+        for axis in [Vec2::X, Vec2::Y] {
+            // Not moving this way; a rounding-error overlap mustn't shove crates sideways
+            if step.dot(axis) == 0.0 {
+                continue;
+            }
+            center = slide_axis(moving.at(center), step * axis, solids);
+            center = shove(moving.at(center), axis, step.dot(axis) > 0.0, solids, pushables);
+        }
+    }
+    center
+}
+
+// Pushes each pushable `moving` now overlaps along `axis`, or backs `moving` off one that can't go
+fn shove(moving: Aabb, axis: Vec2, forward: bool, solids: &[Aabb], pushables: &mut [Aabb]) -> Vec2 {
+    let mut center = moving.center;
+    for pushable in pushables.iter_mut() {
+        if !overlaps(moving.at(center), *pushable) {
+            continue;
+        }
+        let (half, c_half) = (moving.half, pushable.half);
+        let overlap = if forward {
+            (center + half) - (pushable.center - c_half)
+        } else {
+            (center - half) - (pushable.center + c_half)
+        };
+        let shove = axis * overlap.dot(axis);
+        let shoved = pushable.at(pushable.center + shove);
+        if solids.iter().any(|&solid| overlaps(shoved, solid)) {
+            center -= shove;
+        } else {
+            *pushable = shoved;
+        }
+    }
+    center
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: Vec2 = Vec2::new(8.0, 10.0);
+
+    fn wall(x: f32, y: f32, w: f32, h: f32) -> Aabb {
+        Aabb::new(Vec2::new(x, y), Vec2::new(w, h) / 2.0)
+    }
+
+    #[test]
+    fn overlap_penetration_and_resolving() {
+        let block = wall(0.0, 0.0, 20.0, 20.0);
+        let player = Aabb::new(Vec2::new(-15.0, 2.0), PLAYER);
+        assert!(overlaps(player, block));
+        // Sharing an edge isn't overlapping
+        assert!(!overlaps(player.at(Vec2::new(-18.0, 0.0)), block));
+        // 3px in from the left but 18 deep vertically: out to the left
+        assert_eq!(penetration(player, block), Some(Vec2::new(-3.0, 0.0)));
+        assert_eq!(penetration(player.at(Vec2::new(-40.0, 0.0)), block), None);
+
+        for (delta, expected) in [
+            (Vec2::X, Vec2::new(-18.0, 2.0)),
+            (Vec2::NEG_X, Vec2::new(18.0, 2.0)),
+            (Vec2::Y, Vec2::new(-15.0, -20.0)),
+            (Vec2::NEG_Y, Vec2::new(-15.0, 20.0)),
+        ] {
+            assert_eq!(resolve_axis(player, block, delta), Some(expected), "moving {delta}");
+        }
+        assert_eq!(resolve_axis(player.at(Vec2::new(-40.0, 0.0)), block, Vec2::X), None);
+    }
+
+    // The start room's alcove: its wall and the barred gate beside it
+    fn alcove() -> [Aabb; 2] {
+        [wall(220.0, 185.0, 10.0, 110.0), wall(270.0, 130.0, 100.0, 10.0)]
+    }
+
+    #[test]
+    fn walking_the_start_rooms_alcove() {
+        let solids = alcove();
+        let player = Aabb::new(Vec2::new(150.0, 200.0), PLAYER);
+        // Into the alcove wall from the left: flush against it
+        assert_eq!(move_and_collide(player, Vec2::new(80.0, 0.0), &solids, &mut []), Vec2::new(207.0, 200.0));
+        // Up into the gate from below
+        let below = player.at(Vec2::new(270.0, 60.0));
+        assert_eq!(move_and_collide(below, Vec2::new(0.0, 80.0), &solids, &mut []), Vec2::new(270.0, 115.0));
+        // Clipping the wall's bottom corner by a pixel slides past it
+        let clipping = player.at(Vec2::new(208.0, 100.0));
+        let end = move_and_collide(clipping, Vec2::new(0.0, 60.0), &solids, &mut []);
+        assert_eq!(end, Vec2::new(207.0, 160.0));
+        // A long frame still can't step over the 10px gate
+        assert_eq!(move_and_collide(below, Vec2::new(0.0, 400.0), &solids, &mut []).y, 115.0);
+    }
+
+    #[test]
+    fn pushables_move_until_wedged() {
+        let solids = alcove();
+        let player = Aabb::new(Vec2::new(150.0, 200.0), PLAYER);
+        let mut crates = [Aabb::new(Vec2::new(175.0, 200.0), Vec2::splat(8.0))];
+        // Shoved along 4px a step until the next shove would put it in the wall, then blocking
+        let end = move_and_collide(player, Vec2::new(80.0, 0.0), &solids, &mut crates);
+        assert_eq!(crates[0].center, Vec2::new(206.0, 200.0));
+        assert_eq!(end, Vec2::new(190.0, 200.0));
+    }
+}
@@ -0,0 +1,387 @@
+// src/cutscene.rs
+use bevy::prelude::*;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::ecs::system::SystemParam;
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::GameSet;
+use crate::camera::{CameraFocus, CameraPanEvent};
+use crate::flags::GameFlags;
+use crate::journal::JournalEvent;
+use crate::objects::ObjectId;
+use crate::player::{AnimationState, Direction, MovementLocks, Player};
+use crate::rooms::CurrentRoom;
+use crate::timescale::SetTimeScaleEvent;
+use crate::ui::{LogEvent, UiState};
+
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartCutsceneEvent>()
+            .init_resource::<ActiveCutscene>()
+            .insert_resource(Cutscenes(load_cutscenes()))
+            .add_systems(Update, (
+                play_room_cutscenes.in_set(GameSet::Detect),
+                run_cutscenes.in_set(GameSet::Process),
+            ));
+    }
+}
+
+// Who a step acts on: the player, or a room object by its ObjectId
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum CutsceneTarget {
+    Player,
+    Object(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum CutsceneStep {
+    LockInput,
+    MoveEntity(CutsceneTarget, (f32, f32), f32), // Walk to a point at a speed (pixels/sec)
+    Face(CutsceneTarget, Direction),              // Only the player has a facing
+    Say(Vec<String>),                             // Waits for the dialog to be closed
+    Pan(CutsceneTarget, f32),                     // Looks at the target for this many seconds
+    Wait(f32),
+    SetFlag(String),
+    Journal(String), // Writes an entry in the journal
+    UnlockInput,
+}
+
+// A scripted sequence, stored as assets/cutscenes/<id>.ron
+#[derive(Clone, Debug, Deserialize)]
+pub struct Cutscene {
+    #[serde(default)]
+    pub on_enter: Option<String>, // Plays the first time the player enters this room
+    pub steps: Vec<CutsceneStep>,
+}
+
+// Every cutscene by id, read once at startup
+#[derive(Resource, Default)]
+pub struct Cutscenes(pub HashMap<String, Cutscene>);
+
+#[derive(Event)]
+pub struct StartCutsceneEvent(pub String);
+
+// The cutscene playing, if any
+#[derive(Resource, Default)]
+pub struct ActiveCutscene(Option<CutsceneRun>);
+
+impl ActiveCutscene {
+    pub fn is_playing(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+struct CutsceneRun {
+    id: String,
+    steps: Vec<CutsceneStep>,
+    index: usize,
+    elapsed: f32,      // Seconds on the current step
+    started: bool,     // The current step has sent its events
+    dialog_seen: bool, // A Say step's dialog has opened
+    locks: u32,        // MovementLocks taken and not yet given back
+}
+
+// Real seconds the game stands still as a cutscene opens
+const CUTSCENE_FREEZE_SECS: f32 = 0.25;
+
+// Flag set when a cutscene starts, so room cutscenes only ever play once
+pub fn seen_flag(id: &str) -> String {
+    format!("seen_cutscene_{}", id)
+}
+
+fn load_cutscenes() -> HashMap<String, Cutscene> {
+    let dir = FileAssetReader::get_base_path().join("assets/cutscenes");
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Couldn't read {}: {}", dir.display(), e);
+            return HashMap::new();
+        }
+    };
+    let mut cutscenes = HashMap::new();
+    for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+        if path.extension().is_none_or(|ext| ext != "ron") {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else { continue };
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str::<Cutscene>(&text).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(cutscene) => {
+                cutscenes.insert(id.to_string(), cutscene);
+            }
+            Err(e) => warn!("Couldn't load {}: {}", path.display(), e),
+        }
+    }
+    cutscenes
+}
+
+// Starts the cutscenes tied to a room the first time the player walks in
+fn play_room_cutscenes(
+    current_room: Res<CurrentRoom>,
+    cutscenes: Res<Cutscenes>,
+    flags: Res<GameFlags>,
+    mut starts: EventWriter<StartCutsceneEvent>,
+) {
+    if !current_room.is_changed() {
+        return;
+    }
+    for (id, cutscene) in cutscenes.0.iter() {
+        if cutscene.on_enter.as_deref() == Some(current_room.0.as_str()) && !flags.is_set(&seen_flag(id)) {
+            starts.write(StartCutsceneEvent(id.clone()));
+        }
+    }
+}
+
+// What a cutscene's steps act on: the player's input, the story flags, the dialog box and
+// everything a step can move or turn
+#[derive(SystemParam)]
+pub struct CutsceneStage<'w, 's> {
+    locks: ResMut<'w, MovementLocks>,
+    flags: ResMut<'w, GameFlags>,
+    ui_state: Res<'w, UiState>,
+    movers: Query<'w, 's, (Entity, &'static mut Transform, Option<&'static ObjectId>, Has<Player>)>,
+    player_query: Query<'w, 's, (&'static mut Player, &'static mut AnimationState)>,
+}
+
+// Plays the active cutscene's steps in order. Instant steps all run in the same frame; the rest
+// (Wait, MoveEntity, Say, Pan) hold the cutscene on them until they're done.
+pub fn run_cutscenes(
+    time: Res<Time>,
+    mut starts: EventReader<StartCutsceneEvent>,
+    cutscenes: Res<Cutscenes>,
+    mut active: ResMut<ActiveCutscene>,
+    stage: CutsceneStage,
+    (mut log_writer, mut pan_events, mut journal, mut time_scale): (
+        EventWriter<LogEvent>,
+        EventWriter<CameraPanEvent>,
+        EventWriter<JournalEvent>,
+        EventWriter<SetTimeScaleEvent>,
+    ),
+) {
+    let CutsceneStage { mut locks, mut flags, ui_state, mut movers, mut player_query } = stage;
+    for StartCutsceneEvent(id) in starts.read() {
+        if let Some(run) = &active.0 {
+            warn!("Not starting cutscene {} while {} is playing", id, run.id);
+            continue;
+        }
+        let Some(cutscene) = cutscenes.0.get(id) else {
+            warn!("Unknown cutscene: {}", id);
+            continue;
+        };
+        info!("Cutscene {} started", id);
+        flags.set(&seen_flag(id), true);
+        // The world holds its breath for a moment before the scene plays out
+        time_scale.write(SetTimeScaleEvent { scale: 0.0, duration: Some(CUTSCENE_FREEZE_SECS) });
+        active.0 = Some(CutsceneRun {
+            id: id.clone(),
+            steps: cutscene.steps.clone(),
+            index: 0,
+            elapsed: 0.0,
+            started: false,
+            dialog_seen: false,
+            locks: 0,
+        });
+    }
+
+    let Some(run) = active.0.as_mut() else { return };
+    let dt = time.delta_secs();
+    run.elapsed += dt;
+
+    while let Some(step) = run.steps.get(run.index) {
+        let done = match step {
+            CutsceneStep::LockInput => {
+                locks.lock();
+                run.locks += 1;
+                true
+            }
+            CutsceneStep::UnlockInput => {
+                if run.locks > 0 {
+                    locks.unlock();
+                    run.locks -= 1;
+                }
+                true
+            }
+            CutsceneStep::MoveEntity(target, (x, y), speed) => match find_target(&mut movers, target) {
+                Some((_, mut tf, _)) => {
+                    let to = Vec2::new(*x, *y);
+                    let pos = tf.translation.truncate();
+                    let next = pos + (to - pos).clamp_length_max(speed * dt);
+                    tf.translation.x = next.x;
+                    tf.translation.y = next.y;
+                    next.distance(to) < 0.01
+                }
+                None => {
+                    warn!("Cutscene {}: nothing to move for {:?}", run.id, target);
+                    true
+                }
+            },
+            CutsceneStep::Face(target, direction) => {
+                if *target == CutsceneTarget::Player {
+                    if let Ok((mut player, mut anim)) = player_query.single_mut() {
+                        player.facing = *direction;
+                        anim.facing = *direction;
+                    }
+                } else {
+                    warn!("Cutscene {}: only the player can face a direction", run.id);
+                }
+                true
+            }
+            CutsceneStep::Say(lines) => {
+                if !run.started {
+                    for l in lines {
+                        log_writer.write(LogEvent::new(l.clone()));
+                    }
+                    run.started = true;
+                }
+                // The dialog opens later this frame; wait for it to open, then to close
+                run.dialog_seen |= ui_state.dialog_open;
+                lines.is_empty() || (run.dialog_seen && !ui_state.dialog_open)
+            }
+            CutsceneStep::Pan(target, secs) => {
+                if !run.started {
+                    let focus = match find_target(&mut movers, target) {
+                        Some((_, _, true)) => Some(CameraFocus::Player),
+                        Some((entity, _, false)) => Some(CameraFocus::Entity(entity)),
+                        None => None,
+                    };
+                    match focus {
+                        Some(target) => {
+                            pan_events.write(CameraPanEvent { target, hold_secs: *secs, return_smoothly: true });
+                        }
+                        None => warn!("Cutscene {}: nothing to look at for {:?}", run.id, target),
+                    }
+                    run.started = true;
+                }
+                run.elapsed >= *secs
+            }
+            CutsceneStep::Wait(secs) => run.elapsed >= *secs,
+            CutsceneStep::SetFlag(name) => {
+                flags.set(name, true);
+                true
+            }
+            CutsceneStep::Journal(text) => {
+                journal.write(JournalEvent(text.clone()));
+                true
+            }
+        };
+        if !done {
+            return;
+        }
+        run.index += 1;
+        run.elapsed = 0.0;
+        run.started = false;
+        run.dialog_seen = false;
+    }
+
+    // Finished: give back any locks the script forgot to
+    for _ in 0..run.locks {
+        locks.unlock();
+    }
+    info!("Cutscene {} finished", run.id);
+    active.0 = None;
+}
+
+// The entity a step acts on, its transform, and whether it's the player
+fn find_target<'a>(
+    movers: &'a mut Query<(Entity, &mut Transform, Option<&ObjectId>, Has<Player>)>,
+    target: &CutsceneTarget,
+) -> Option<(Entity, Mut<'a, Transform>, bool)> {
+    movers
+        .iter_mut()
+        .find(|(_, _, id, is_player)| match target {
+            CutsceneTarget::Player => *is_player,
+            CutsceneTarget::Object(object) => id.is_some_and(|id| &id.0 == object),
+        })
+        .map(|(entity, tf, _, is_player)| (entity, tf, is_player))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    fn cutscene_world(steps: Vec<CutsceneStep>) -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<ActiveCutscene>();
+        world.init_resource::<MovementLocks>();
+        world.init_resource::<GameFlags>();
+        world.init_resource::<UiState>();
+        world.init_resource::<Events<StartCutsceneEvent>>();
+        world.init_resource::<Events<LogEvent>>();
+        world.init_resource::<Events<CameraPanEvent>>();
+        world.init_resource::<Events<JournalEvent>>();
+        world.init_resource::<Events<SetTimeScaleEvent>>();
+        world.insert_resource(Cutscenes(HashMap::from([(
+            "test".to_string(),
+            Cutscene { on_enter: None, steps },
+        )])));
+        world
+    }
+
+    // One frame of `secs`
+    fn frame(world: &mut World, secs: f32) {
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(secs));
+        world.run_system_once(run_cutscenes).unwrap();
+    }
+
+    #[test]
+    fn runner_waits_moves_and_sets_flags_in_order() {
+        let mut world = cutscene_world(vec![
+            CutsceneStep::LockInput,
+            CutsceneStep::Wait(0.5),
+            CutsceneStep::MoveEntity(CutsceneTarget::Object("test/figure".to_string()), (0.0, -10.0), 20.0),
+            CutsceneStep::SetFlag("figure_arrived".to_string()),
+            CutsceneStep::UnlockInput,
+        ]);
+        let figure = world.spawn((Transform::default(), ObjectId("test/figure".to_string()))).id();
+        let y = |world: &World| world.get::<Transform>(figure).unwrap().translation.y;
+
+        world.send_event(StartCutsceneEvent("test".to_string()));
+        frame(&mut world, 0.25);
+        assert!(world.resource::<ActiveCutscene>().is_playing());
+        assert!(world.resource::<MovementLocks>().is_locked());
+        assert!(world.resource::<GameFlags>().is_set(&seen_flag("test")));
+
+        // Waiting: the figure hasn't moved yet
+        frame(&mut world, 0.25);
+        assert_eq!(y(&world), 0.0);
+        // The wait ends and the walk starts the same frame
+        frame(&mut world, 0.25);
+        assert_eq!(y(&world), -5.0);
+        assert!(!world.resource::<GameFlags>().is_set("figure_arrived"));
+
+        frame(&mut world, 0.25);
+        assert_eq!(y(&world), -10.0);
+        assert!(world.resource::<GameFlags>().is_set("figure_arrived"));
+        assert!(!world.resource::<MovementLocks>().is_locked());
+        assert!(!world.resource::<ActiveCutscene>().is_playing());
+    }
+
+    #[test]
+    fn say_waits_for_the_dialog_to_close_and_leftover_locks_are_released() {
+        let mut world = cutscene_world(vec![
+            CutsceneStep::LockInput,
+            CutsceneStep::Say(vec!["* ...".to_string()]),
+        ]);
+        world.send_event(StartCutsceneEvent("test".to_string()));
+        frame(&mut world, 0.1);
+        assert_eq!(world.resource_mut::<Events<LogEvent>>().drain().count(), 1);
+
+        world.resource_mut::<UiState>().dialog_open = true;
+        frame(&mut world, 0.1);
+        assert!(world.resource::<ActiveCutscene>().is_playing());
+        world.resource_mut::<UiState>().dialog_open = false;
+        frame(&mut world, 0.1);
+        assert!(!world.resource::<ActiveCutscene>().is_playing());
+        // No UnlockInput in the script, but the lock doesn't outlive it
+        assert!(!world.resource::<MovementLocks>().is_locked());
+        // Lines are only sent once
+        assert_eq!(world.resource_mut::<Events<LogEvent>>().drain().count(), 0);
+    }
+}
@@ -0,0 +1,101 @@
+// src/depth.rs
+use bevy::prelude::*;
+use crate::objects::GlowTexture;
+use crate::player::Collider;
+
+pub struct DepthPlugin;
+
+impl Plugin for DepthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, attach_shadows)
+            // After everything has moved this frame, before it's drawn
+            .add_systems(PostUpdate, y_sort.before(TransformSystem::TransformPropagate));
+    }
+}
+
+// Draws the entity in front of things whose feet are higher up the screen and behind things
+// whose feet are lower, by deriving its z from its feet. For the player and anything they can
+// walk around; floors, walls and overlays keep the z they were spawned with.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct YSort;
+
+// Feet within 500px of the origin land in 9.5..10.5: over floor decals (z 0.5-1) and under
+// the darkness (z 40)
+pub const Y_SORT_BASE: f32 = 10.0;
+pub const Y_SORT_EPSILON: f32 = 0.001;
+
+pub fn y_sort_z(feet_y: f32) -> f32 {
+    Y_SORT_BASE - feet_y * Y_SORT_EPSILON
+}
+
+fn feet_y(pos: Vec2, sprite: &Sprite, collider: Option<&Collider>) -> f32 {
+    Collider::of(sprite, collider).feet(pos).y
+}
+
+fn y_sort(mut query: Query<(&mut Transform, &Sprite, Option<&Collider>), With<YSort>>) {
+    for (mut tf, sprite, collider) in query.iter_mut() {
+        let z = y_sort_z(feet_y(tf.translation.truncate(), sprite, collider));
+        if tf.translation.z != z {
+            tf.translation.z = z;
+        }
+    }
+}
+
+// Gets a soft oval shadow at its feet
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct CastsShadow;
+
+// The shadow child itself; its alpha is what lighting would dim or deepen
+#[derive(Component)]
+pub struct Shadow;
+
+const SHADOW_ALPHA: f32 = 0.35;
+const SHADOW_HEIGHT: f32 = 6.0;
+
+fn attach_shadows(
+    mut commands: Commands,
+    glow: Res<GlowTexture>,
+    casters: Query<(Entity, &Transform, &Sprite, Option<&Collider>), Added<CastsShadow>>,
+) {
+    for (entity, tf, sprite, collider) in casters.iter() {
+        let body = Collider::of(sprite, collider);
+        let feet = feet_y(tf.translation.truncate(), sprite, collider) - tf.translation.y;
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                // The light glow's soft disc, darkened and squashed
+                Sprite {
+                    image: glow.0.clone(),
+                    color: Color::BLACK.with_alpha(SHADOW_ALPHA),
+                    custom_size: Some(Vec2::new(body.half_extents.x * 2.5, SHADOW_HEIGHT)),
+                    ..default()
+                },
+                // Just behind its owner, so nothing y-sorted can come between them
+                Transform::from_xyz(body.offset.x, feet, -Y_SORT_EPSILON / 2.0),
+                Shadow,
+            ));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lower_feet_draw_in_front() {
+        // A 20x40 door at the origin; its feet are at y = -20
+        let door = Sprite::from_color(Color::WHITE, Vec2::new(20.0, 40.0));
+        let door_z = y_sort_z(feet_y(Vec2::ZERO, &door, None));
+        let player = Collider::new(Vec2::new(8.0, 10.0));
+        let player_sprite = Sprite::default();
+        let player_z = |y: f32| y_sort_z(feet_y(Vec2::new(0.0, y), &player_sprite, Some(&player)));
+
+        // Standing just below the door's base: in front of it
+        assert!(player_z(-12.0) > door_z);
+        // Walked round behind it, overlapping its top half: hidden behind it
+        assert!(player_z(20.0) < door_z);
+        assert!(y_sort_z(-500.0) < 40.0 && y_sort_z(500.0) > 1.0);
+    }
+}
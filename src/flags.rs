@@ -0,0 +1,197 @@
+// src/flags.rs
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::GameSet;
+
+pub struct FlagsPlugin;
+
+impl Plugin for FlagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameFlags>()
+            .add_event::<FlagChangedEvent>()
+            // After the gameplay sets, so a flag set by an interaction is announced the same frame.
+            // Not gated on GameState: loading a save happens from the title screen.
+            .add_systems(Update, emit_flag_changes.after(GameSet::Process));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+pub enum FlagValue {
+    Bool(bool),
+    Int(i32),
+    String(String),
+}
+
+impl From<bool> for FlagValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for FlagValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<&str> for FlagValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for FlagValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+// A flag took a new value; `old` is None the first time it's set
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct FlagChangedEvent {
+    pub name: String,
+    pub old: Option<FlagValue>,
+    pub new: FlagValue,
+}
+
+// Story/progress flags set by interactions (e.g. "flirted_with_figure", "times_called_home")
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+pub struct GameFlags {
+    flags: HashMap<String, FlagValue>,
+    // Changes not yet sent as FlagChangedEvents; never saved
+    #[serde(skip)]
+    #[reflect(ignore)]
+    changes: Vec<FlagChangedEvent>,
+}
+
+// Pending notifications don't make two sets of flags different
+impl PartialEq for GameFlags {
+    fn eq(&self, other: &Self) -> bool {
+        self.flags == other.flags
+    }
+}
+
+impl GameFlags {
+    // Setting a flag to the value it already has is not a change
+    pub fn set(&mut self, name: &str, value: impl Into<FlagValue>) {
+        let new = value.into();
+        let old = self.flags.insert(name.to_string(), new.clone());
+        if old.as_ref() != Some(&new) {
+            self.changes.push(FlagChangedEvent { name: name.to_string(), old, new });
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FlagValue> {
+        self.flags.get(name)
+    }
+
+    // False unless the flag holds `Bool(true)`
+    pub fn get_bool(&self, name: &str) -> bool {
+        matches!(self.get(name), Some(FlagValue::Bool(true)))
+    }
+
+    // Zero unless the flag holds an Int
+    pub fn get_int(&self, name: &str) -> i32 {
+        match self.get(name) {
+            Some(FlagValue::Int(n)) => *n,
+            _ => 0,
+        }
+    }
+
+    // Adds one to a counter, starting from zero, and returns the new count
+    pub fn increment(&mut self, name: &str) -> i32 {
+        let count = self.get_int(name) + 1;
+        self.set(name, count);
+        count
+    }
+
+    // Whether the flag has been set to anything but `false`
+    pub fn is_set(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|value| *value != FlagValue::Bool(false))
+    }
+
+    // A fresh set holding just the named flags, with their values; nothing to announce
+    pub fn only(&self, names: &[String]) -> Self {
+        let flags = names
+            .iter()
+            .filter_map(|name| Some((name.clone(), self.get(name)?.clone())))
+            .collect();
+        Self { flags, changes: Vec::new() }
+    }
+}
+
+fn emit_flag_changes(mut flags: ResMut<GameFlags>, mut events: EventWriter<FlagChangedEvent>) {
+    // Only touch the resource (and trip change detection) when there's something to send
+    if flags.changes.is_empty() {
+        return;
+    }
+    for change in flags.changes.drain(..) {
+        info!("Flag {} = {:?}", change.name, change.new);
+        events.write(change);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_accessors() {
+        let mut flags = GameFlags::default();
+        assert!(!flags.is_set("read_crumpled_note"));
+        assert!(!flags.get_bool("read_crumpled_note"));
+
+        flags.set("read_crumpled_note", true);
+        assert!(flags.is_set("read_crumpled_note"));
+        assert!(flags.get_bool("read_crumpled_note"));
+        flags.set("read_crumpled_note", false);
+        assert!(!flags.is_set("read_crumpled_note"));
+
+        assert_eq!(flags.get_int("times_called_home"), 0);
+        assert_eq!(flags.increment("times_called_home"), 1);
+        assert_eq!(flags.increment("times_called_home"), 2);
+        assert!(flags.is_set("times_called_home"));
+        // A counter isn't a bool
+        assert!(!flags.get_bool("times_called_home"));
+
+        flags.set("figure_name", "Mara");
+        assert_eq!(flags.get("figure_name"), Some(&FlagValue::String("Mara".to_string())));
+        assert_eq!(flags.get_int("figure_name"), 0);
+    }
+
+    #[test]
+    fn changes_are_sent_as_events() {
+        let mut app = App::new();
+        app.add_plugins(FlagsPlugin);
+        {
+            let mut flags = app.world_mut().resource_mut::<GameFlags>();
+            flags.set("first_door_opened", true);
+            // Same value again: no second event
+            flags.set("first_door_opened", true);
+            flags.increment("doors_opened");
+            flags.increment("doors_opened");
+        }
+        app.update();
+
+        let events: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Events<FlagChangedEvent>>()
+            .drain()
+            .collect();
+        assert_eq!(events, vec![
+            FlagChangedEvent { name: "first_door_opened".to_string(), old: None, new: FlagValue::Bool(true) },
+            FlagChangedEvent { name: "doors_opened".to_string(), old: None, new: FlagValue::Int(1) },
+            FlagChangedEvent {
+                name: "doors_opened".to_string(),
+                old: Some(FlagValue::Int(1)),
+                new: FlagValue::Int(2),
+            },
+        ]);
+
+        // Nothing new, nothing sent
+        app.update();
+        assert!(app.world().resource::<Events<FlagChangedEvent>>().is_empty());
+    }
+}
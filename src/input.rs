@@ -0,0 +1,560 @@
+// src/input.rs
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Things the player can do, independent of which keys do them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Run,
+    Sneak,
+    Dash,
+    Interact, // Open the action menu for whatever is in reach
+    Confirm,  // Pick a menu option, advance dialog
+    Cancel,
+    Inventory,
+    QuestLog,
+    NextTab, // Between the quest log's tabs
+    Menu, // Pause; shares Escape with Cancel, which wins while a menu or dialog is open
+    ZoomIn,
+    ZoomOut,
+    Fullscreen,
+    Mute,
+    Screenshot,
+}
+
+impl Action {
+    // In the order the Controls page lists them
+    pub const ALL: [Self; 19] = [
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Run,
+        Self::Sneak,
+        Self::Dash,
+        Self::Interact,
+        Self::Confirm,
+        Self::Cancel,
+        Self::Inventory,
+        Self::QuestLog,
+        Self::NextTab,
+        Self::Menu,
+        Self::ZoomIn,
+        Self::ZoomOut,
+        Self::Fullscreen,
+        Self::Mute,
+        Self::Screenshot,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::MoveUp => "Up",
+            Self::MoveDown => "Down",
+            Self::MoveLeft => "Left",
+            Self::MoveRight => "Right",
+            Self::Run => "Run",
+            Self::Sneak => "Sneak",
+            Self::Dash => "Dash",
+            Self::Interact => "Interact",
+            Self::Confirm => "Confirm",
+            Self::Cancel => "Cancel",
+            Self::Inventory => "Inventory",
+            Self::QuestLog => "Quest Log",
+            Self::NextTab => "Next Tab",
+            Self::Menu => "Pause",
+            Self::ZoomIn => "Zoom In",
+            Self::ZoomOut => "Zoom Out",
+            Self::Fullscreen => "Fullscreen",
+            Self::Mute => "Mute",
+            Self::Screenshot => "Screenshot",
+        }
+    }
+
+    // The menus can't be worked without these, so they always keep at least one key
+    pub fn drives_menus(self) -> bool {
+        matches!(self, Self::MoveUp | Self::MoveDown | Self::Confirm | Self::Cancel)
+    }
+
+    // Whether `self` and `other` may share a key: Interact only happens outside menus and Confirm
+    // inside them, and Escape's Menu/Cancel overlap is sorted out by toggle_pause
+    fn shares_keys_with(self, other: Self) -> bool {
+        use Action::*;
+        matches!((self, other), (Interact, Confirm) | (Confirm, Interact) | (Cancel, Menu) | (Menu, Cancel))
+    }
+}
+
+// How a key is written on screen
+pub fn key_label(key: KeyCode) -> String {
+    use KeyCode::*;
+    let name = match key {
+        ArrowUp => "Up",
+        ArrowDown => "Down",
+        ArrowLeft => "Left",
+        ArrowRight => "Right",
+        ShiftLeft => "Shift",
+        ShiftRight => "R Shift",
+        ControlLeft => "Ctrl",
+        ControlRight => "R Ctrl",
+        AltLeft => "Alt",
+        AltRight => "R Alt",
+        Escape => "Esc",
+        PageUp => "PgUp",
+        PageDown => "PgDn",
+        Equal => "=",
+        Minus => "-",
+        NumpadAdd => "Num +",
+        NumpadSubtract => "Num -",
+        // KeyZ is Z and Digit1 is 1; the rest go by their own names
+        other => {
+            let name = format!("{:?}", other);
+            return name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit")).unwrap_or(&name).to_string();
+        }
+    };
+    name.to_string()
+}
+
+// Which keys trigger each action. Serializable so an options screen can save rebinds.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    pub bindings: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use KeyCode::*;
+        let confirm = vec![KeyZ, Space, Enter];
+        Self {
+            bindings: HashMap::from([
+                (Action::MoveUp, vec![KeyW, ArrowUp]),
+                (Action::MoveDown, vec![KeyS, ArrowDown]),
+                (Action::MoveLeft, vec![KeyA, ArrowLeft]),
+                (Action::MoveRight, vec![KeyD, ArrowRight]),
+                (Action::Run, vec![ShiftLeft]),
+                (Action::Sneak, vec![ControlLeft, ControlRight]),
+                (Action::Dash, vec![KeyC]),
+                (Action::Interact, confirm.clone()),
+                (Action::Confirm, confirm),
+                (Action::Cancel, vec![KeyX, Escape]),
+                (Action::Inventory, vec![KeyI]),
+                (Action::QuestLog, vec![KeyJ]),
+                (Action::NextTab, vec![Tab]),
+                (Action::Menu, vec![Escape]),
+                (Action::ZoomIn, vec![PageUp, Equal, NumpadAdd]),
+                (Action::ZoomOut, vec![PageDown, Minus, NumpadSubtract]),
+                (Action::Fullscreen, vec![F11]),
+                (Action::Mute, vec![KeyM]),
+                (Action::Screenshot, vec![F12]),
+            ]),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn pressed(&self, action: Action, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, action: Action, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    // The action (other than one allowed to share) that `key` already triggers, if any
+    pub fn conflict(&self, action: Action, key: KeyCode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&other| other != action && !action.shares_keys_with(other) && self.keys(other).contains(&key))
+    }
+
+    // Adds `key` to `action`'s keys. Check `conflict` first.
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        let keys = self.bindings.entry(action).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.insert(action, Vec::new());
+    }
+
+    // The key hints show for `action`: its first key, or "?" if it has none
+    pub fn key_display_name(&self, action: Action) -> String {
+        self.keys(action).first().map_or_else(|| "?".to_string(), |&key| key_label(key))
+    }
+
+    // `action`'s keys as the Controls page lists them
+    pub fn describe(&self, action: Action) -> String {
+        let keys: Vec<_> = self.keys(action).iter().map(|&key| key_label(key)).collect();
+        if keys.is_empty() { "(none)".to_string() } else { keys.join(", ") }
+    }
+
+    // Trades the first confirm key and the first cancel key (Z and X by default) between
+    // confirming (and interacting) and cancelling; the other keys stay put. Doing it twice undoes it.
+    pub fn swap_confirm_cancel(&mut self) {
+        let (Some(&confirm), Some(&cancel)) = (self.keys(Action::Confirm).first(), self.keys(Action::Cancel).first())
+        else {
+            return;
+        };
+        for action in [Action::Interact, Action::Confirm, Action::Cancel] {
+            for key in self.bindings.entry(action).or_default() {
+                if *key == confirm {
+                    *key = cancel;
+                } else if *key == cancel {
+                    *key = confirm;
+                }
+            }
+        }
+    }
+}
+
+// Whole layouts to start from, picked on the settings page. The confirm/cancel swap goes on top.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ControlPreset {
+    Default,
+    Swapped,    // X confirms and Z cancels, as in a lot of console games
+    WasdOnly,   // WASD moves, E and Q confirm and cancel; the arrow keys are left free
+    ArrowsOnly, // The arrow keys move; WASD is left free
+}
+
+impl ControlPreset {
+    pub const ALL: [Self; 4] = [Self::Default, Self::Swapped, Self::WasdOnly, Self::ArrowsOnly];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => "Default",
+            Self::Swapped => "Swapped",
+            Self::WasdOnly => "WASD Only",
+            Self::ArrowsOnly => "Arrows Only",
+        }
+    }
+
+    pub fn map(self) -> InputMap {
+        use KeyCode::*;
+        let mut map = InputMap::default();
+        match self {
+            Self::Default => {}
+            Self::Swapped => map.swap_confirm_cancel(),
+            Self::WasdOnly => {
+                let confirm = vec![KeyE, Space, Enter];
+                map.bindings.extend([
+                    (Action::MoveUp, vec![KeyW]),
+                    (Action::MoveDown, vec![KeyS]),
+                    (Action::MoveLeft, vec![KeyA]),
+                    (Action::MoveRight, vec![KeyD]),
+                    (Action::Interact, confirm.clone()),
+                    (Action::Confirm, confirm),
+                    (Action::Cancel, vec![KeyQ, Escape]),
+                ]);
+            }
+            Self::ArrowsOnly => {
+                map.bindings.extend([
+                    (Action::MoveUp, vec![ArrowUp]),
+                    (Action::MoveDown, vec![ArrowDown]),
+                    (Action::MoveLeft, vec![ArrowLeft]),
+                    (Action::MoveRight, vec![ArrowRight]),
+                ]);
+            }
+        }
+        map
+    }
+
+    // The preset `map` is, or None if it's been rebound into something else
+    pub fn matching(map: &InputMap) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.map() == *map)
+    }
+
+    // The preset `steps` along from `current`, stopping at either end. A custom map starts
+    // from Default.
+    pub fn step(current: Option<Self>, steps: i32) -> Self {
+        let Some(current) = current else { return Self::Default };
+        let index = Self::ALL.iter().position(|&p| p == current).unwrap_or(0) as i32;
+        Self::ALL[(index + steps).clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+}
+
+// How soon a second press has to follow the first to make a double tap
+pub const DOUBLE_TAP_SECS: f64 = 0.25;
+
+// Tap patterns for one action
+#[derive(Clone, Copy, Default)]
+struct Gesture {
+    held_since: Option<f64>, // When the current press started; None while it's up
+    last_press: Option<f64>, // The latest press that could still start a double tap
+    double_tapped: bool,     // This frame's press was the second of a pair
+}
+
+// Double taps and holds, worked out once a frame from the InputMap so features asking about them
+// agree on the timing. Times are real seconds, so slow motion doesn't stretch a double tap.
+#[derive(Resource, Default)]
+pub struct InputGestures {
+    gestures: HashMap<Action, Gesture>,
+    now: f64,
+    before: f64, // `now` as of the previous frame
+}
+
+impl InputGestures {
+    // Takes in one frame, `now` seconds in, given which actions are held and which were just pressed
+    pub fn observe(&mut self, now: f64, pressed: impl Fn(Action) -> bool, just_pressed: impl Fn(Action) -> bool) {
+        self.before = self.now;
+        self.now = now;
+        for action in Action::ALL {
+            let gesture = self.gestures.entry(action).or_default();
+            gesture.double_tapped = false;
+            if just_pressed(action) {
+                gesture.double_tapped = gesture.last_press.is_some_and(|t| now - t <= DOUBLE_TAP_SECS);
+                // A third quick press starts a new pair rather than making another double tap
+                gesture.last_press = if gesture.double_tapped { None } else { Some(now) };
+                gesture.held_since = Some(now);
+            }
+            if !pressed(action) {
+                gesture.held_since = None;
+            }
+        }
+    }
+
+    // Whether `action` was pressed this frame for the second time in quick succession
+    pub fn double_tapped(&self, action: Action) -> bool {
+        self.gestures.get(&action).is_some_and(|g| g.double_tapped)
+    }
+
+    // How long `action` has been held; 0.0 while it's up
+    pub fn held_for(&self, action: Action) -> f32 {
+        self.held_since(action).map_or(0.0, |t| (self.now - t) as f32)
+    }
+
+    // True on the one frame the current hold of `action` reaches `threshold` seconds
+    pub fn long_pressed(&self, action: Action, threshold: f32) -> bool {
+        // Held long enough now, but not as of the frame before
+        let held = self.held_for(action);
+        let frame = (self.now - self.before) as f32;
+        self.held_since(action).is_some() && held >= threshold && held - frame < threshold
+    }
+
+    fn held_since(&self, action: Action) -> Option<f64> {
+        self.gestures.get(&action).and_then(|g| g.held_since)
+    }
+}
+
+// Actions something has already acted on, held back from everything else until the frame after
+// next: picking a menu option with Z mustn't also skip the dialog it opens or reopen the menu
+#[derive(Resource, Default)]
+pub struct ConsumedActions {
+    frames: HashMap<Action, u32>, // Frames each is still held back for, counting this one
+}
+
+impl ConsumedActions {
+    // Swallows this frame's press of `action`, and any press the next frame brings
+    pub fn consume(&mut self, action: Action, input: &InputMap, keyboard: &mut ButtonInput<KeyCode>) {
+        for &key in input.keys(action) {
+            keyboard.clear_just_pressed(key);
+        }
+        self.frames.insert(action, 2);
+    }
+
+    pub fn is_consumed(&self, action: Action) -> bool {
+        self.frames.contains_key(&action)
+    }
+}
+
+// Straight after the keyboard is read (and anything standing in for it): counts the held-back
+// actions down and swallows fresh presses of those still held back
+pub fn hold_consumed_actions(
+    mut consumed: ResMut<ConsumedActions>,
+    input: Res<InputMap>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+) {
+    consumed.frames.retain(|_, frames| {
+        *frames -= 1;
+        *frames > 0
+    });
+    for &action in consumed.frames.keys() {
+        for &key in input.keys(action) {
+            keyboard.clear_just_pressed(key);
+        }
+    }
+}
+
+// Runs first in GameSet::Input; systems reading the gestures go after it
+pub fn track_gestures(
+    mut gestures: ResMut<InputGestures>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    time: Res<Time<Real>>,
+) {
+    let now = time.elapsed_secs_f64();
+    gestures.observe(now, |action| input.pressed(action, &keyboard), |action| input.just_pressed(action, &keyboard));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_keep_the_original_keys() {
+        let map = InputMap::default();
+        let mut keyboard = ButtonInput::<KeyCode>::default();
+        keyboard.press(KeyCode::Enter);
+        keyboard.press(KeyCode::ArrowLeft);
+        assert!(map.just_pressed(Action::Confirm, &keyboard));
+        assert!(map.just_pressed(Action::Interact, &keyboard));
+        assert!(map.pressed(Action::MoveLeft, &keyboard));
+        assert!(!map.pressed(Action::MoveRight, &keyboard));
+        // Shift runs; it no longer cancels
+        assert_eq!(map.keys(Action::Cancel), [KeyCode::KeyX, KeyCode::Escape]);
+        assert_eq!(map.keys(Action::Run), [KeyCode::ShiftLeft]);
+        assert_eq!(map.keys(Action::Menu), [KeyCode::Escape]);
+
+        keyboard.clear();
+        assert!(!map.just_pressed(Action::Confirm, &keyboard));
+        assert!(map.pressed(Action::MoveLeft, &keyboard));
+    }
+
+    #[test]
+    fn swapping_confirm_and_cancel_trades_z_and_x() {
+        let mut map = InputMap::default();
+        map.swap_confirm_cancel();
+        assert_eq!(map.keys(Action::Confirm), [KeyCode::KeyX, KeyCode::Space, KeyCode::Enter]);
+        assert_eq!(map.keys(Action::Interact), [KeyCode::KeyX, KeyCode::Space, KeyCode::Enter]);
+        assert_eq!(map.keys(Action::Cancel), [KeyCode::KeyZ, KeyCode::Escape]);
+    }
+
+    #[test]
+    fn rebinds_round_trip_through_ron() {
+        let mut map = InputMap::default();
+        map.bindings.insert(Action::Interact, vec![KeyCode::KeyE]);
+        let text = ron::to_string(&map).unwrap();
+        let back: InputMap = ron::from_str(&text).unwrap();
+        assert_eq!(back, map);
+        assert_eq!(back.keys(Action::Interact), [KeyCode::KeyE]);
+    }
+
+    #[test]
+    fn hints_name_the_keys_actually_bound() {
+        let mut map = InputMap::default();
+        assert_eq!(map.key_display_name(Action::Interact), "Z");
+        assert_eq!(map.key_display_name(Action::Menu), "Esc");
+        assert_eq!(map.key_display_name(Action::MoveUp), "W");
+        map.swap_confirm_cancel();
+        assert_eq!(map.key_display_name(Action::Confirm), "X");
+        assert_eq!(map.key_display_name(Action::Cancel), "Z");
+        map.clear(Action::Dash);
+        assert_eq!(map.key_display_name(Action::Dash), "?");
+
+        let wasd = ControlPreset::WasdOnly.map();
+        assert_eq!(wasd.key_display_name(Action::Interact), "E");
+        assert_eq!(ControlPreset::ArrowsOnly.map().key_display_name(Action::MoveUp), "Up");
+    }
+
+    #[test]
+    fn presets_are_recognised_and_swap_on_top() {
+        assert_eq!(ControlPreset::matching(&InputMap::default()), Some(ControlPreset::Default));
+        let mut wasd = ControlPreset::WasdOnly.map();
+        assert_eq!(ControlPreset::matching(&wasd), Some(ControlPreset::WasdOnly));
+        assert_eq!(wasd.keys(Action::MoveLeft), [KeyCode::KeyA]);
+        // The swap trades whatever confirms and cancels, and a second swap undoes it
+        wasd.swap_confirm_cancel();
+        assert_eq!(wasd.keys(Action::Confirm), [KeyCode::KeyQ, KeyCode::Space, KeyCode::Enter]);
+        assert_eq!(wasd.keys(Action::Cancel), [KeyCode::KeyE, KeyCode::Escape]);
+        wasd.swap_confirm_cancel();
+        assert_eq!(wasd, ControlPreset::WasdOnly.map());
+        // No preset leaves two actions fighting over a key
+        for preset in ControlPreset::ALL {
+            let map = preset.map();
+            for action in Action::ALL {
+                assert!(map.keys(action).iter().all(|&key| map.conflict(action, key).is_none()), "{:?}", preset);
+            }
+        }
+
+        let mut custom = InputMap::default();
+        custom.bind(Action::Dash, KeyCode::KeyV);
+        assert_eq!(ControlPreset::matching(&custom), None);
+        assert_eq!(ControlPreset::step(None, 1), ControlPreset::Default);
+        assert_eq!(ControlPreset::step(Some(ControlPreset::Default), 2), ControlPreset::WasdOnly);
+        assert_eq!(ControlPreset::step(Some(ControlPreset::ArrowsOnly), 1), ControlPreset::ArrowsOnly);
+    }
+
+    #[test]
+    fn rebinding_refuses_keys_other_actions_use() {
+        let mut map = InputMap::default();
+        assert_eq!(map.conflict(Action::Run, KeyCode::KeyZ), Some(Action::Interact));
+        assert_eq!(map.conflict(Action::Run, KeyCode::F12), Some(Action::Screenshot));
+        // Pairs that share keys by design can keep sharing them
+        assert_eq!(map.conflict(Action::Interact, KeyCode::KeyZ), None);
+        assert_eq!(map.conflict(Action::Menu, KeyCode::KeyX), None);
+        assert_eq!(map.conflict(Action::Run, KeyCode::KeyE), None);
+
+        map.bind(Action::Run, KeyCode::KeyE);
+        map.bind(Action::Run, KeyCode::KeyE);
+        assert_eq!(map.keys(Action::Run), [KeyCode::ShiftLeft, KeyCode::KeyE]);
+        assert_eq!(map.describe(Action::Run), "Shift, E");
+        map.clear(Action::Run);
+        assert_eq!(map.describe(Action::Run), "(none)");
+        assert_eq!(map.conflict(Action::Dash, KeyCode::ShiftLeft), None);
+        assert_eq!(map.describe(Action::ZoomIn), "PgUp, =, Num +");
+    }
+
+    // Feeds `gestures` a frame at `now` with `held` down, of which `fresh` were just pressed
+    fn frame(gestures: &mut InputGestures, now: f64, held: &[Action], fresh: &[Action]) {
+        gestures.observe(now, |a| held.contains(&a), |a| fresh.contains(&a));
+    }
+
+    #[test]
+    fn double_taps_need_the_second_press_inside_the_window() {
+        let mut gestures = InputGestures::default();
+        let right = Action::MoveRight;
+        frame(&mut gestures, 1.0, &[right], &[right]);
+        assert!(!gestures.double_tapped(right));
+        frame(&mut gestures, 1.1, &[], &[]);
+        frame(&mut gestures, 1.2, &[right], &[right]);
+        assert!(gestures.double_tapped(right));
+        // Only on the frame of the second press
+        frame(&mut gestures, 1.25, &[right], &[]);
+        assert!(!gestures.double_tapped(right));
+        // A third press straight after starts a new pair
+        frame(&mut gestures, 1.3, &[], &[]);
+        frame(&mut gestures, 1.35, &[right], &[right]);
+        assert!(!gestures.double_tapped(right));
+
+        // Too slow
+        frame(&mut gestures, 3.0, &[], &[Action::Dash]);
+        frame(&mut gestures, 3.0 + DOUBLE_TAP_SECS + 0.01, &[], &[Action::Dash]);
+        assert!(!gestures.double_tapped(Action::Dash));
+        // Each action keeps its own taps
+        frame(&mut gestures, 5.0, &[], &[Action::MoveLeft]);
+        frame(&mut gestures, 5.1, &[], &[right]);
+        assert!(!gestures.double_tapped(right) && !gestures.double_tapped(Action::MoveLeft));
+    }
+
+    #[test]
+    fn long_presses_fire_once_when_the_hold_gets_there() {
+        let mut gestures = InputGestures::default();
+        let z = Action::Interact;
+        frame(&mut gestures, 2.0, &[z], &[z]);
+        assert_eq!(gestures.held_for(z), 0.0);
+        assert!(!gestures.long_pressed(z, 0.5));
+        frame(&mut gestures, 2.3, &[z], &[]);
+        assert!((gestures.held_for(z) - 0.3).abs() < 1e-6);
+        assert!(!gestures.long_pressed(z, 0.5));
+        frame(&mut gestures, 2.6, &[z], &[]);
+        assert!(gestures.long_pressed(z, 0.5));
+        frame(&mut gestures, 2.9, &[z], &[]);
+        assert!(!gestures.long_pressed(z, 0.5));
+        assert!(gestures.long_pressed(z, 0.8));
+
+        // Letting go resets the hold
+        frame(&mut gestures, 3.0, &[], &[]);
+        assert_eq!(gestures.held_for(z), 0.0);
+        frame(&mut gestures, 3.1, &[z], &[z]);
+        frame(&mut gestures, 3.4, &[z], &[]);
+        assert!(!gestures.long_pressed(z, 0.5));
+    }
+}
@@ -1,19 +1,45 @@
 // src/interaction.rs
 use bevy::prelude::*;
-use crate::player::{Player, InteractionIndicator};
+use bevy::ecs::query::QueryData;
+use bevy::ecs::system::SystemParam;
+use serde::Deserialize;
+use bevy::input::touch::Touches;
+use bevy::window::PrimaryWindow;
+use crate::audio::{PlaySfxEvent, SfxId};
+use crate::camera::{CameraFocus, CameraPanEvent};
+use crate::cutscene::ActiveCutscene;
+use crate::player::{Collider, Hidden, MoveTarget, MovementLocks, Player};
+use crate::journal::JournalEvent;
 use crate::ui::{ContextMenuEvent, UiState, LogEvent};
-use crate::GameSet;
-use crate::inventory::{Inventory, InventoryItem};
+use crate::{GameSet, PendingDespawn};
+use crate::flags::GameFlags;
+use crate::ngplus::NgPlus;
+use crate::quests::{Quests, POWER_GENERATOR};
+use crate::input::{Action, ConsumedActions, InputMap};
+use crate::inventory::{is_key_item, item_by_id, Inventory, Wallet};
+use crate::save::{slot_label, SaveDir, WriteSaveEvent, SAVE_SLOTS};
+use crate::rooms::RoomMember;
+use crate::spatial::{update_spatial_grid, SpatialGrid};
+use crate::touch::TouchControls;
+use crate::objects::{
+    close_door, doorway_blocker, open_door, phone_number, Container, Door, DoorwayBlocker, Generator,
+    Hideable, Item, KnownNumbers, Light, LockCheck, Lockable, ObjectId, PendingRespawn, PendingRespawns,
+    Pushable, Respawns, RoomFade, SavePoint, SaveRequestedEvent, Shop, Switch,
+    SwitchToggledEvent, Telephone, WorldState, FUEL_CAN_AMOUNT, NPC,
+};
 
 pub struct InteractionPlugin;
 
 impl Plugin for InteractionPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<InteractionEvent>()
+            .add_systems(Startup, spawn_interaction_indicator)
             .add_systems(Update, (
-                check_nearby_interactables.in_set(GameSet::Detect),
+                check_nearby_interactables.in_set(GameSet::Detect).after(update_spatial_grid),
+                click_to_move.in_set(GameSet::Input).before(handle_interaction_input),
                 handle_interaction_input.in_set(GameSet::Input),
                 process_interactions.in_set(GameSet::Process),
+                move_interaction_indicator.in_set(GameSet::Process),
             ));
     }
 }
@@ -24,7 +50,7 @@ pub struct InteractionEvent {
     pub action: InteractionAction,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Reflect)]
 pub enum InteractionAction {
     Examine,
     Take,
@@ -35,6 +61,14 @@ pub enum InteractionAction {
     Talk,
     Open,
     Close,
+    Peek, // Look past a closed door
+    Deposit(String),  // Item id to put into a container
+    Withdraw(String), // Item id to take out of a container
+    Discard(String),  // Item id to destroy (trash cans), after confirming
+    Buy(String),      // Item id to purchase from a shop
+    Dial(String),     // Phone number to call; unknown numbers just click
+    SaveToSlot(usize),    // Save file to write at a save point; asks first if it's taken
+    OverwriteSlot(usize), // Save file to write, already confirmed
     Custom(String),
 }
 
@@ -50,16 +84,47 @@ impl InteractionAction {
             Self::Talk => "* Talk".to_string(),
             Self::Open => "* Open".to_string(),
             Self::Close => "* Close".to_string(),
+            Self::Peek => "* Peek".to_string(),
+            Self::Deposit(_) => "* Put In".to_string(),
+            Self::Withdraw(_) => "* Take Out".to_string(),
+            Self::Discard(_) => "* Toss".to_string(),
+            Self::Buy(_) => "* Buy".to_string(),
+            Self::Dial(number) => format!("* Dial {}", number),
+            Self::SaveToSlot(slot) => format!("* File {}", slot + 1),
+            Self::OverwriteSlot(_) => "* Yes, overwrite it".to_string(),
             Self::Custom(s) => format!("* {}", s),
         }
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Interactable {
     pub name: String,
     pub actions: Vec<InteractionAction>,
     pub interaction_radius: Option<f32>, // Optional custom radius
+    pub cooldown: Option<f32>, // Seconds before the object reacts to another action
+    pub is_proper_noun: bool, // Named like a person or place: no article, capitals kept
+    pub description: Option<String>, // Said in place of the generic "It appears to be..." line
+}
+
+// How close the player has to be to an object without its own interaction_radius
+pub const DEFAULT_INTERACTION_RADIUS: f32 = 40.0;
+
+impl Interactable {
+    pub fn reach(&self) -> f32 {
+        self.interaction_radius.unwrap_or(DEFAULT_INTERACTION_RADIUS)
+    }
+
+    // "the old lamp", or "Mr. Pickles" for a proper noun
+    pub fn the_name(&self) -> String {
+        the_name(&self.name, self.is_proper_noun)
+    }
+
+    // "an old lamp", "a rusty key", or "Mr. Pickles" for a proper noun
+    pub fn a_name(&self) -> String {
+        a_name(&self.name, self.is_proper_noun)
+    }
 }
 
 impl Default for Interactable {
@@ -68,52 +133,315 @@ impl Default for Interactable {
             name: "Object".to_string(),
             actions: vec![InteractionAction::Examine],
             interaction_radius: None, // Use default radius
+            cooldown: None,
+            is_proper_noun: false,
+            description: None,
         }
     }
 }
 
-#[derive(Component)]
+// Names are written for labels ("Old Lamp"); mid-sentence they're lowercased, keeping
+// initialisms like "TV", unless they're proper nouns, which also go without an article
+pub fn the_name(name: &str, proper: bool) -> String {
+    if proper { name.to_string() } else { format!("the {}", lowercased(name)) }
+}
+
+pub fn a_name(name: &str, proper: bool) -> String {
+    if proper { name.to_string() } else { format!("{} {}", article(name), lowercased(name)) }
+}
+
+// For a name that starts a sentence: "The old lamp is locked."
+pub fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_initialism(word: &str) -> bool {
+    word.len() > 1 && word.chars().all(|c| c.is_ascii_uppercase())
+}
+
+fn lowercased(name: &str) -> String {
+    let words: Vec<String> =
+        name.split(' ').map(|w| if is_initialism(w) { w.to_string() } else { w.to_lowercase() }).collect();
+    words.join(" ")
+}
+
+// Words whose spelling and sound disagree about starting with a vowel
+const SILENT_H: &[&str] = &["hour", "honest", "honor", "honour", "heir"];
+const VOWEL_SAID_YOU: &[&str] = &["eu", "ewe", "once", "one", "unic", "unif", "union", "unit", "univ", "use", "usu", "uten"];
+
+// "a" or "an", by how the name is said rather than spelled
+fn article(name: &str) -> &'static str {
+    let first = name.split([' ', '-']).next().unwrap_or_default();
+    // Initialisms and lone letters are said letter by letter: "an FBI file", "a UFO", "an X-ray"
+    if !first.is_empty() && first.chars().all(|c| c.is_ascii_uppercase()) {
+        return if first.starts_with(|c| "AEFHILMNORSX".contains(c)) { "an" } else { "a" };
+    }
+    let word = first.to_lowercase();
+    if SILENT_H.iter().any(|p| word.starts_with(p)) {
+        "an"
+    } else if VOWEL_SAID_YOU.iter().any(|p| word.starts_with(p)) {
+        "a"
+    } else if word.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        "an"
+    } else {
+        "a"
+    }
+}
+
+// A predicate an action must satisfy to be offered (and executed)
+#[derive(Clone, Debug, Deserialize, Reflect)]
+pub enum ActionCondition {
+    HasItem { id: String, count: usize },
+}
+
+impl ActionCondition {
+    pub fn is_met(&self, inventory: &Inventory) -> bool {
+        match self {
+            Self::HasItem { id, count } => inventory.count_item_id(id) >= *count,
+        }
+    }
+
+    // Extra text appended to the menu label, e.g. "(x2 held)"
+    pub fn label_suffix(&self, inventory: &Inventory) -> Option<String> {
+        match self {
+            Self::HasItem { id, .. } => Some(format!("(x{} held)", inventory.count_item_id(id))),
+        }
+    }
+}
+
+// A fact about the story so far, checked against flags, the inventory, WorldState and the New
+// Game+ cycle. Object ids are ObjectId strings, e.g. "start/old_lamp".
+#[derive(Clone, Debug, Deserialize, Reflect)]
+pub enum Condition {
+    HasFlag(String),
+    HasItem(String),
+    LightOn(String),
+    DoorOpen(String),
+    GeneratorRunning(String),
+    NgPlusCycle(u32), // On this New Game+ cycle or a later one
+}
+
+impl Condition {
+    pub fn is_met(&self, inventory: &Inventory, (flags, ng_plus): (&GameFlags, &NgPlus), world: &WorldState) -> bool {
+        let state = |id: &str| world.objects.get(id);
+        match self {
+            Self::HasFlag(flag) => flags.is_set(flag),
+            Self::NgPlusCycle(cycle) => ng_plus.cycle >= *cycle,
+            Self::HasItem(id) => inventory.count_item_id(id) > 0,
+            Self::LightOn(id) => state(id).and_then(|s| s.light_on).unwrap_or(false),
+            Self::DoorOpen(id) => state(id).and_then(|s| s.door_open).unwrap_or(false),
+            Self::GeneratorRunning(id) => state(id).and_then(|s| s.generator_running).unwrap_or(false),
+        }
+    }
+}
+
+// Examine text that reacts to progress: the first case whose condition holds wins
+#[derive(Component, Clone, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct ConditionalExamine {
+    pub cases: Vec<(Condition, Vec<String>)>,
+    pub default: Vec<String>,
+}
+
+impl ConditionalExamine {
+    pub fn lines(&self, inventory: &Inventory, story: (&GameFlags, &NgPlus), world: &WorldState) -> &[String] {
+        self.cases
+            .iter()
+            .find(|(condition, _)| condition.is_met(inventory, story, world))
+            .map_or(&self.default, |(_, lines)| lines)
+    }
+}
+
+// Per-action requirements; actions without an entry are always available
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct ActionRequirements {
+    pub conditions: Vec<(InteractionAction, ActionCondition)>,
+}
+
+impl ActionRequirements {
+    pub fn condition_for(&self, action: &InteractionAction) -> Option<&ActionCondition> {
+        self.conditions
+            .iter()
+            .find(|(a, _)| a == action)
+            .map(|(_, condition)| condition)
+    }
+
+    pub fn allows(&self, action: &InteractionAction, inventory: &Inventory) -> bool {
+        self.condition_for(action)
+            .is_none_or(|condition| condition.is_met(inventory))
+    }
+}
+
+// Response text for one Custom action label
+#[derive(Clone, Deserialize, Reflect)]
+pub struct CustomResponse {
+    pub label: String,
+    pub line_sets: Vec<Vec<String>>, // Alternate responses, cycled on repeat use
+    #[serde(default)]
+    pub sets_flag: Option<String>,
+    #[serde(default)]
+    pub uses: usize,
+}
+
+impl CustomResponse {
+    pub fn new(label: &str, line_sets: Vec<Vec<&str>>) -> Self {
+        Self {
+            label: label.to_string(),
+            line_sets: line_sets
+                .into_iter()
+                .map(|set| set.into_iter().map(str::to_string).collect())
+                .collect(),
+            sets_flag: None,
+            uses: 0,
+        }
+    }
+
+    pub fn with_flag(mut self, flag: &str) -> Self {
+        self.sets_flag = Some(flag.to_string());
+        self
+    }
+
+    // Returns the lines for this use and advances to the next set
+    pub fn next_lines(&mut self) -> Vec<String> {
+        if self.line_sets.is_empty() {
+            return Vec::new();
+        }
+        let lines = self.line_sets[self.uses % self.line_sets.len()].clone();
+        self.uses += 1;
+        lines
+    }
+}
+
+// What the player sees when peeking past a closed door
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct PeekText {
+    pub lines: Vec<String>,
+}
+
+// Peeking also glances the camera this far past the door, for this long
+const PEEK_PAN_DISTANCE: f32 = 48.0;
+const PEEK_PAN_SECS: f32 = 1.2;
+
+// Per-object flavor text for Custom actions; unlisted labels use the generic sentence
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct CustomActionText {
+    pub responses: Vec<CustomResponse>,
+}
+
+impl CustomActionText {
+    pub fn response_mut(&mut self, label: &str) -> Option<&mut CustomResponse> {
+        self.responses.iter_mut().find(|r| r.label == label)
+    }
+}
+
+// Elapsed time of the last executed action, for objects with a cooldown
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct LastInteracted(pub f64);
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct NearbyInteractable;
 
+// Marker floating over whatever the player would interact with
+#[derive(Component)]
+pub struct InteractionIndicator;
+
+// On the player after clicking an Interactable: open it once in reach, as if interact was pressed
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct PendingInteraction(#[entities] pub Entity);
+
+const INDICATOR_SIZE: f32 = 8.0;
+const INDICATOR_GAP: f32 = 6.0; // Space between the target's top edge and the marker
+const INDICATOR_BOB: f32 = 2.0; // Pixels up and down
+const INDICATOR_BOB_SPEED: f32 = 4.0; // Radians per second
+const INDICATOR_Z: f32 = 50.0; // Above every object and the player
+
+fn spawn_interaction_indicator(mut commands: Commands) {
+    commands.spawn((
+        Sprite::from_color(Color::srgb(1.0, 0.0, 0.0), Vec2::splat(INDICATOR_SIZE)), // Red for visibility
+        Transform::from_xyz(0.0, 0.0, INDICATOR_Z),
+        Visibility::Hidden,
+        InteractionIndicator,
+        Name::new("Interaction Indicator"),
+    ));
+}
+
+// Where the marker sits over a target at `target_pos`, `elapsed` seconds into its bob
+fn indicator_position(target_pos: Vec2, target_sprite: &Sprite, elapsed: f32) -> Vec3 {
+    let top = target_sprite.custom_size.unwrap_or(Vec2::splat(16.0)).y / 2.0;
+    let bob = (elapsed * INDICATOR_BOB_SPEED).sin() * INDICATOR_BOB;
+    Vec3::new(target_pos.x, target_pos.y + top + INDICATOR_GAP + INDICATOR_SIZE / 2.0 + bob, INDICATOR_Z)
+}
+
+type IndicatorQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Transform, &'static mut Visibility),
+    (With<InteractionIndicator>, Without<NearbyInteractable>),
+>;
+
+// Real time, so the bob keeps its pace in slow motion
+fn move_interaction_indicator(
+    time: Res<Time<Real>>,
+    ui_state: Res<UiState>,
+    targets: Query<(&Transform, &Sprite), With<NearbyInteractable>>,
+    mut indicator_query: IndicatorQuery,
+) {
+    let Ok((mut indicator_tf, mut visibility)) = indicator_query.single_mut() else { return };
+    let target = targets.iter().next().filter(|_| !ui_state.menu_open && !ui_state.dialog_open);
+    let Some((target_tf, sprite)) = target else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    indicator_tf.translation = indicator_position(target_tf.translation.truncate(), sprite, time.elapsed_secs());
+    *visibility = Visibility::Visible;
+}
+
 fn check_nearby_interactables(
-    player_query: Query<(&Player, &Transform, &Children)>,
-    interactables: Query<(Entity, &Interactable, &Transform), Without<NearbyInteractable>>,
-    mut indicator_query: Query<&mut Visibility, With<InteractionIndicator>>,
+    player_query: Query<(&Player, &Transform, Option<&Hidden>)>,
+    interactables: Query<(Entity, &Interactable, &Transform, Has<PendingDespawn>), Without<NearbyInteractable>>,
     mut commands: Commands,
     existing_nearby: Query<Entity, With<NearbyInteractable>>,
+    grid: Res<SpatialGrid>,
 ) {
     // Clear all existing nearby markers
     for entity in existing_nearby.iter() {
         commands.entity(entity).remove::<NearbyInteractable>();
     }
 
-    for (_player, player_transform, children) in player_query.iter() {
+    for (_player, player_transform, hidden) in player_query.iter() {
         let mut closest_interactable: Option<Entity> = None;
         let mut closest_distance = f32::MAX;
 
-        for (entity, interactable, transform) in interactables.iter() {
+        let nearby = grid.interactables_near(player_transform.translation.truncate(), grid.reach());
+        for (entity, interactable, transform, doomed) in nearby.into_iter().filter_map(|e| interactables.get(e).ok()) {
+            if doomed {
+                continue;
+            }
+            // While hidden, the only thing within reach is the hiding spot
+            if hidden.is_some_and(|h| h.inside != entity) {
+                continue;
+            }
             let distance = player_transform.translation.truncate()
                 .distance(transform.translation.truncate());
             
-            // Use the object's custom interaction radius, or default to 40
-            let radius = interactable.interaction_radius.unwrap_or(40.0);
+            // Use the object's custom interaction radius, or the default
+            let radius = interactable.reach();
             if distance <= radius && distance < closest_distance {
                 closest_distance = distance;
                 closest_interactable = Some(entity);
             }
         }
 
-        // Update indicator visibility
-        for &child in children {
-            if let Ok(mut visibility) = indicator_query.get_mut(child) {
-                *visibility = if closest_interactable.is_some() {
-                    Visibility::Visible
-                } else {
-                    Visibility::Hidden
-                };
-            }
-        }
-
         // Mark the closest as nearby
         if let Some(entity) = closest_interactable {
             commands.entity(entity).insert(NearbyInteractable);
@@ -121,147 +449,1435 @@ fn check_nearby_interactables(
     }
 }
 
+// A click or tap and what it could land on: the window, the camera looking into the world, the
+// UI over it and the touch buttons
+#[derive(SystemParam)]
+struct PointerInput<'w, 's> {
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    touches: Res<'w, Touches>,
+    touch_controls: Res<'w, TouchControls>,
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    ui_nodes: Query<'w, 's, &'static Interaction>,
+}
+
+type ClickablesQuery<'w, 's> =
+    Query<'w, 's, (Entity, &'static Transform, &'static Sprite), With<Interactable>>;
+
+// Left-clicking the world walks the player there; clicking an object also queues opening it
+fn click_to_move(
+    pointer: PointerInput,
+    ui_state: Res<UiState>,
+    locks: Res<MovementLocks>,
+    player_query: Query<Entity, (With<Player>, Without<Hidden>)>,
+    interactables: ClickablesQuery,
+    grid: Res<SpatialGrid>,
+    mut commands: Commands,
+) {
+    if ui_state.menu_open || ui_state.dialog_open || locks.is_locked() {
+        return;
+    }
+    let PointerInput { mouse, touches, touch_controls, windows, cameras, ui_nodes } = pointer;
+    let Ok(window) = windows.single() else { return };
+    // A tap works like a click, unless it landed on one of the touch buttons
+    let tap = touches
+        .iter_just_pressed()
+        .find(|touch| !touch_controls.on_buttons.contains(&touch.id()))
+        .map(|touch| touch.position());
+    let click = mouse.just_pressed(MouseButton::Left).then(|| window.cursor_position()).flatten();
+    let Some(pointer) = click.or(tap) else { return };
+    // Clicks on UI belong to the UI
+    if ui_nodes.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+    let Ok(player) = player_query.single() else { return };
+    let Ok((camera, camera_tf)) = cameras.single() else { return };
+    // Clicks on the letterbox bars don't count
+    let Some(point) = Some(pointer)
+        .filter(|&c| camera.logical_viewport_rect().is_some_and(|rect| rect.contains(c)))
+        .and_then(|c| camera.viewport_to_world_2d(camera_tf, c).ok())
+    else {
+        return;
+    };
+
+    // The topmost object drawn under the cursor
+    let clicked = grid
+        .interactables_near(point, 0.0)
+        .into_iter()
+        .filter_map(|entity| interactables.get(entity).ok())
+        .filter(|(_, tf, sprite)| Collider::of(sprite, None).contains(tf.translation.truncate(), point))
+        .max_by(|(_, a, _), (_, b, _)| a.translation.z.total_cmp(&b.translation.z));
+
+    let mut player = commands.entity(player);
+    match clicked {
+        Some((entity, tf, _)) => {
+            player.insert((MoveTarget(tf.translation.truncate()), PendingInteraction(entity)));
+        }
+        None => {
+            player.insert(MoveTarget(point)).remove::<PendingInteraction>();
+        }
+    }
+}
+
+// The interact key as bound, and whether this frame's press has already been spent
+#[derive(SystemParam)]
+struct InteractKey<'w> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    input: Res<'w, InputMap>,
+    consumed: Res<'w, ConsumedActions>,
+}
+
+// What holds off interacting: a menu or dialog up, a room change, a cutscene with the stage
+#[derive(SystemParam)]
+struct InteractionGate<'w> {
+    ui_state: Res<'w, UiState>,
+    room_fade: Res<'w, RoomFade>,
+    cutscene: Res<'w, ActiveCutscene>,
+}
+
+impl InteractionGate<'_> {
+    fn is_closed(&self) -> bool {
+        self.ui_state.menu_open || self.ui_state.dialog_open || self.room_fade.is_active() || self.cutscene.is_playing()
+    }
+}
+
+type InteractorQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Transform, Option<&'static Hidden>, Option<&'static PendingInteraction>, Has<MoveTarget>),
+    With<Player>,
+>;
+
+type MenuSourcesQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Interactable,
+        &'static Transform,
+        Option<&'static ActionRequirements>,
+        Option<&'static Light>,
+        Option<&'static Door>,
+        Option<&'static Item>,
+    ),
+>;
+
+// The player and what's around them to act on
+#[derive(SystemParam)]
+struct Reach<'w, 's> {
+    player: InteractorQuery<'w, 's>,
+    interactables: MenuSourcesQuery<'w, 's>,
+    grid: Res<'w, SpatialGrid>,
+}
+
 fn handle_interaction_input(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    player_query: Query<&Transform, With<Player>>,
-    interactables_query: Query<(Entity, &Interactable, &Transform)>,
+    mut commands: Commands,
+    key: InteractKey,
+    reach: Reach,
+    inventory: Res<Inventory>,
     mut menu_events: EventWriter<ContextMenuEvent>,
-    mut interaction_events: EventWriter<InteractionEvent>,
-    ui_state: Res<UiState>,
+    mut interaction_events: ResMut<Events<InteractionEvent>>,
+    gate: InteractionGate,
 ) {
-    // Don't process interaction if menu is already open
-    if ui_state.menu_open || ui_state.dialog_open {
+    if gate.is_closed() {
         return;
     }
+    let InteractKey { keyboard, input, consumed } = key;
+    let Reach { player: player_query, interactables: interactables_query, grid } = reach;
+    // Something was acted on this frame or last (a menu pick, say), and the press that did it
+    // is spent
+    if !interaction_events.is_empty() || consumed.is_consumed(Action::Confirm) {
+        return;
+    }
+    let Ok((player, player_tf, hidden, pending, walking)) = player_query.single() else { return };
+    let player_pos = player_tf.translation.truncate();
+    let in_reach = |interactable: &Interactable, tf: &Transform| {
+        player_pos.distance(tf.translation.truncate()) <= interactable.reach()
+    };
 
     // Check for interaction key
-    let interact_pressed = keyboard.just_pressed(KeyCode::KeyZ) 
-        || keyboard.just_pressed(KeyCode::Space)
-        || keyboard.just_pressed(KeyCode::Enter);
+    let interact_pressed = input.just_pressed(Action::Interact, &keyboard);
+
+    // A clicked object opens as soon as the player walks into its reach
+    let arrived = pending
+        .filter(|p| interactables_query.get(p.0).is_ok_and(|(_, i, tf, ..)| in_reach(i, tf)))
+        .map(|p| p.0);
+    if pending.is_some() && (arrived.is_some() || !walking || interact_pressed || hidden.is_some()) {
+        commands.entity(player).remove::<PendingInteraction>();
+        if arrived.is_some() {
+            commands.entity(player).remove::<MoveTarget>();
+        }
+    }
 
-    if interact_pressed {
+    // Pressing interact while hidden always climbs back out
+    if let Some(hidden) = hidden {
+        if interact_pressed {
+            interaction_events.send(InteractionEvent {
+                entity: hidden.inside,
+                action: InteractionAction::Custom(Hideable::ACTION.to_string()),
+            });
+        }
+        return;
+    }
+
+    let target = if interact_pressed {
         // Find nearest interactable in range from the player
-    if let Ok(player_tf) = player_query.single() {
-            let mut best: Option<(Entity, &Interactable)> = None;
-            let mut best_dist = f32::MAX;
-            for (entity, interactable, tf) in interactables_query.iter() {
-                let d = player_tf.translation.truncate().distance(tf.translation.truncate());
-                let radius = interactable.interaction_radius.unwrap_or(40.0);
-                if d <= radius && d < best_dist {
-                    best_dist = d;
-                    best = Some((entity, interactable));
-                }
+        let mut best = None;
+        let mut best_dist = f32::MAX;
+        for nearby in grid.interactables_near(player_pos, grid.reach()) {
+            let Ok((entity, interactable, tf, ..)) = interactables_query.get(nearby) else { continue };
+            let d = player_pos.distance(tf.translation.truncate());
+            if in_reach(interactable, tf) && d < best_dist {
+                best_dist = d;
+                best = Some(entity);
             }
+        }
+        best
+    } else {
+        arrived
+    };
 
-            if let Some((entity, interactable)) = best {
-                info!("Interacting with: {} ({} actions)", interactable.name, interactable.actions.len());
-                if interactable.actions.len() == 1 {
-                    interaction_events.write(InteractionEvent { entity, action: interactable.actions[0].clone() });
-                } else {
-                    menu_events.write(ContextMenuEvent {
-                        entity,
-                        actions: interactable.actions.clone(),
-                        object_name: interactable.name.clone(),
-                    });
-                }
-            }
+    let Some(Ok((entity, interactable, _, requirements, light, door, item))) = target.map(|e| interactables_query.get(e)) else {
+        return;
+    };
+    let (mut actions, labels, disabled) = menu_entries(interactable, requirements, (light, door, item), &inventory);
+
+    info!("Interacting with: {} ({} actions)", interactable.name, actions.len());
+    if actions.len() == 1 {
+        interaction_events.send(InteractionEvent { entity, action: actions.remove(0) });
+    } else if !actions.is_empty() {
+        menu_events.write(ContextMenuEvent {
+            entity,
+            actions,
+            labels,
+            object_name: interactable.name.clone(),
+            disabled,
+            suffixes: Vec::new(),
+        });
+    }
+}
+
+// The actions an object's menu offers, their labels and which are greyed out. Actions whose
+// conditions aren't met are dropped.
+fn menu_entries(
+    interactable: &Interactable,
+    requirements: Option<&ActionRequirements>,
+    (light, door, item): (Option<&Light>, Option<&Door>, Option<&Item>),
+    inventory: &Inventory,
+) -> (Vec<InteractionAction>, Vec<String>, Vec<bool>) {
+    let mut actions = Vec::new();
+    let mut labels = Vec::new();
+    let mut disabled = Vec::new();
+    for action in &interactable.actions {
+        // Lights and doors only offer the state they aren't in
+        let redundant = match action {
+            InteractionAction::TurnOn => light.is_some_and(|l| l.is_on),
+            InteractionAction::TurnOff => light.is_some_and(|l| !l.is_on),
+            InteractionAction::Open | InteractionAction::Peek => door.is_some_and(|d| d.is_open),
+            InteractionAction::Close => door.is_some_and(|d| !d.is_open),
+            _ => false,
+        };
+        if redundant {
+            continue;
+        }
+        let condition = requirements.and_then(|r| r.condition_for(action));
+        if condition.is_some_and(|c| !c.is_met(inventory)) {
+            continue;
         }
+        let label = match condition.and_then(|c| c.label_suffix(inventory)) {
+            Some(suffix) => format!("{} {}", action.to_string(), suffix),
+            None => action.to_string(),
+        };
+        // Offered greyed out when it can't be carried off, so choosing it explains why
+        disabled.push(*action == InteractionAction::Take && !item.is_some_and(|i| i.can_pickup));
+        actions.push(action.clone());
+        labels.push(label);
     }
+    (actions, labels, disabled)
 }
 
+// "the red key", "the red key and the blue key", "the red key, the blue key and the small key"
+fn list_with_the(names: &[String]) -> String {
+    let items: Vec<String> = names.iter().map(|n| the_name(n, false)).collect();
+    match items.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+// An interactable and every part an action might touch. New kinds of object add a field here.
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct InteractableParts {
+    interactable: &'static Interactable,
+    requirements: Option<&'static ActionRequirements>,
+    item: Option<&'static Item>,
+    respawns: Option<(&'static Respawns, &'static RoomMember, &'static Transform)>,
+    custom_text: Option<&'static mut CustomActionText>,
+    examine: Option<&'static ConditionalExamine>,
+    peek: Option<&'static PeekText>,
+    last: Option<&'static LastInteracted>,
+    generator: Option<&'static mut Generator>,
+    light: Option<&'static mut Light>,
+    door: Option<(&'static mut Door, &'static Transform, &'static Sprite)>,
+    switch: Option<&'static mut Switch>,
+    is_save_point: Has<SavePoint>,
+    hideable: Option<(&'static Hideable, &'static Transform)>,
+    container: Option<&'static Container>,
+    lockable: Option<&'static mut Lockable>,
+    object_id: Option<&'static ObjectId>,
+    shop: Option<&'static mut Shop>,
+    is_telephone: Has<Telephone>,
+    npc: Option<&'static NPC>,
+    transform: Option<&'static Transform>, // Where sounds it makes come from
+}
+
+type InteractablesQuery<'w, 's> = Query<'w, 's, InteractableParts, (Without<Player>, Without<PendingDespawn>)>;
+
+type PlayerBodyQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static mut Transform, &'static mut Visibility, Has<Hidden>, &'static Collider),
+    With<Player>,
+>;
+
+type CratesQuery<'w, 's> =
+    Query<'w, 's, (&'static Transform, &'static Sprite, Option<&'static Collider>), (With<Pushable>, Without<Player>)>;
+
+// What acting on an object can change: the world, what the player carries, the story so far
+#[derive(SystemParam)]
+struct InteractionState<'w> {
+    world: ResMut<'w, WorldState>,
+    inventory: ResMut<'w, Inventory>,
+    wallet: ResMut<'w, Wallet>,
+    pending_respawns: ResMut<'w, PendingRespawns>,
+    flags: ResMut<'w, GameFlags>,
+    quests: ResMut<'w, Quests>,
+    ng_plus: Res<'w, NgPlus>,
+    known_numbers: Res<'w, KnownNumbers>,
+    time: Res<'w, Time>,
+    saves: Res<'w, SaveDir>,
+}
+
+// Everything acting on an object sends on: lines to show, sounds, and work for other plugins
+#[derive(SystemParam)]
+struct InteractionWriters<'w> {
+    log_writer: EventWriter<'w, LogEvent>,
+    journal: EventWriter<'w, JournalEvent>,
+    sfx: EventWriter<'w, PlaySfxEvent>,
+    switch_events: EventWriter<'w, SwitchToggledEvent>,
+    save_events: EventWriter<'w, SaveRequestedEvent>,
+    write_events: EventWriter<'w, WriteSaveEvent>,
+    menu_events: EventWriter<'w, ContextMenuEvent>,
+    pan_events: EventWriter<'w, CameraPanEvent>,
+}
+
+// Custom action that burns a bundle of firewood in whatever offers it
+const ADD_WOOD: &str = "Add Wood";
+
 fn process_interactions(
     mut events: EventReader<InteractionEvent>,
     mut commands: Commands,
-    interactables: Query<&Interactable>,
-    mut inventory: ResMut<Inventory>,
-    mut log_writer: EventWriter<LogEvent>,
+    mut interactables: InteractablesQuery,
+    mut player_query: PlayerBodyQuery,
+    crates: CratesQuery,
+    state: InteractionState,
+    writers: InteractionWriters,
 ) {
+    let InteractionState {
+        mut world,
+        mut inventory,
+        mut wallet,
+        mut pending_respawns,
+        mut flags,
+        mut quests,
+        ng_plus,
+        known_numbers,
+        time,
+        saves,
+    } = state;
+    let InteractionWriters {
+        mut log_writer,
+        mut journal,
+        mut sfx,
+        mut switch_events,
+        mut save_events,
+        mut write_events,
+        mut menu_events,
+        mut pan_events,
+    } = writers;
+    // Taken this frame; PendingDespawn only lands once this system is done
+    let mut taken: Vec<Entity> = Vec::new();
     for event in events.read() {
         info!("Processing interaction: {:?}", event.action);
-        
-        if let Ok(interactable) = interactables.get(event.entity) {
+        if taken.contains(&event.entity) {
+            continue;
+        }
+
+        if let Ok(parts) = interactables.get_mut(event.entity) {
+            let InteractablePartsItem {
+                interactable,
+                requirements,
+                item,
+                respawns,
+                mut custom_text,
+                examine,
+                peek,
+                last,
+                mut generator,
+                mut light,
+                mut door,
+                mut switch,
+                is_save_point,
+                hideable,
+                container,
+                mut lockable,
+                object_id,
+                mut shop,
+                is_telephone,
+                npc,
+                transform,
+            } = parts;
+            // Swallow repeats within the cooldown; menus still open, only execution is gated
+            let now = time.elapsed().as_secs_f64();
+            if let Some(cooldown) = interactable.cooldown
+                && last.is_some_and(|l| now - l.0 < cooldown as f64)
+            {
+                info!("{} is on cooldown, ignoring {:?}", interactable.name, event.action);
+                continue;
+            }
+
+            // Inventory may have changed since the menu was opened
+            if requirements.is_some_and(|r| !r.allows(&event.action, &inventory)) {
+                let l = "* You don't have what you need anymore.".to_string();
+                info!("{}", l);
+                log_writer.write(LogEvent::new(l));
+                continue;
+            }
+
+            // Doing anything with the generator is what starts the quest to power it; a fuel can
+            // picked up beforehand already counts
+            if generator.is_some() && quests.activate(POWER_GENERATOR) && inventory.has_item_id("fuel_can") {
+                quests.complete_objective(POWER_GENERATOR, 0);
+            }
+
+            // Doors and containers share one lock check before Open does anything else
+            if let (InteractionAction::Open, Some(lock)) = (&event.action, lockable.as_deref_mut()) {
+                let lines = match lock.try_open(&mut inventory) {
+                    LockCheck::Unlocked => Vec::new(),
+                    LockCheck::UnlockedWith { key_names, consumed } => {
+                        let keys = list_with_the(&key_names);
+                        let mut lines = vec![format!("* You unlock {} with {}.", interactable.the_name(), keys)];
+                        if consumed {
+                            lines.push(format!("* You leave {} in the lock.", keys));
+                        }
+                        lines
+                    }
+                    LockCheck::MissingKey => vec![format!("* {} is locked.", capitalized(&interactable.the_name()))],
+                    LockCheck::MissingKeys(names) => vec![
+                        format!("* {} is locked.", capitalized(&interactable.the_name())),
+                        format!("* You need {}.", list_with_the(&names)),
+                    ],
+                    LockCheck::Sealed => vec![format!("* {} won't budge.", capitalized(&interactable.the_name()))],
+                };
+                if let Some(id) = object_id {
+                    world.object_mut(id).locked = Some(lock.locked);
+                }
+                let still_locked = lock.locked;
+                for l in lines {
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
+                }
+                if still_locked {
+                    sfx.write(PlaySfxEvent::ui(SfxId::Error));
+                    continue;
+                }
+            }
+
+            // Only an action that gets past the checks above starts the cooldown
+            if interactable.cooldown.is_some() {
+                commands.entity(event.entity).insert(LastInteracted(now));
+            }
+
             match &event.action {
+                InteractionAction::Examine if examine.is_some() => {
+                    let Some(examine) = examine else { continue };
+                    let mut lines = examine.lines(&inventory, (&flags, &ng_plus), &world).to_vec();
+                    if let Some(g) = generator.as_deref() {
+                        lines.push(format!("* The fuel gauge reads {}.", g.gauge_text()));
+                    }
+                    for l in lines {
+                        info!("{}", l);
+                        log_writer.write(LogEvent::new(l));
+                    }
+                }
                 InteractionAction::Examine => {
-                    let l1 = format!("* You examine the {}.", interactable.name);
-                    let l2 = format!("* It appears to be a regular {}.", interactable.name);
+                    let l1 = format!("* You examine {}.", interactable.the_name());
+                    let l2 = match generator.as_deref() {
+                        Some(g) => format!(
+                            "* It's {}. The fuel gauge reads {}.",
+                            if g.is_running { "running" } else { "silent" },
+                            g.gauge_text()
+                        ),
+                        None => match &interactable.description {
+                            Some(description) => description.clone(),
+                            None => format!("* It appears to be {}.", interactable.a_name()),
+                        },
+                    };
                     info!("{}", l1);
                     info!("{}", l2);
-                    log_writer.write(LogEvent(l1));
-                    log_writer.write(LogEvent(l2));
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                }
+                // Only items that can be picked up come away; anything else stays put
+                InteractionAction::Take if !item.is_some_and(|i| i.can_pickup) => {
+                    let l = "* It won't budge.".to_string();
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
+                    sfx.write(PlaySfxEvent::ui(SfxId::Error));
                 }
                 InteractionAction::Take => {
-                    let added = inventory.add_item(InventoryItem {
-                        name: interactable.name.clone(),
-                        description: format!("A {} that you picked up.", interactable.name),
-                        icon_color: Color::WHITE,
-                    });
-                    
+                    let Some(item) = item else { continue };
+                    let id = item.id.clone();
+                    let added = inventory.add_item(item_by_id(&id));
+
                     if added {
-                        let l = format!("* You obtained the {}!", interactable.name);
+                        sfx.write(PlaySfxEvent::ui(SfxId::Pickup));
+                        journal.write(JournalEvent(format!("Found {}", interactable.the_name())));
+                        if id == "fuel_can" {
+                            quests.complete_objective(POWER_GENERATOR, 0);
+                        }
+                        let l = format!("* You obtained {}!", interactable.the_name());
                         info!("{}", l);
-                        log_writer.write(LogEvent(l));
-                        // Despawn the entity completely (recursive by default in 0.16)
-                        commands.entity(event.entity).despawn();
+                        log_writer.write(LogEvent::new(l));
+                        // Despawned (with its children) in Cleanup
+                        commands.entity(event.entity).insert(PendingDespawn);
+                        taken.push(event.entity);
+                        if let Some(id) = object_id {
+                            world.object_mut(id).taken = true;
+                        }
+                        if let Some((respawns, member, tf)) = respawns {
+                            pending_respawns.entries.push(PendingRespawn {
+                                room: member.0.clone(),
+                                def: respawns.def.clone(),
+                                position: tf.translation.truncate(),
+                                timer: respawns.after,
+                            });
+                        }
                     } else {
                         let l = "* Your inventory is full!".to_string();
                         info!("{}", l);
-                        log_writer.write(LogEvent(l));
+                        log_writer.write(LogEvent::new(l));
+                        sfx.write(PlaySfxEvent::ui(SfxId::Error));
+                    }
+                }
+                InteractionAction::Use if generator.is_some() => {
+                    let Some(g) = generator.as_deref_mut() else { continue };
+                    let lines = if g.is_running {
+                        g.is_running = false;
+                        vec![
+                            format!("* You switch off {}.", interactable.the_name()),
+                            "* It rattles to a stop.".to_string(),
+                        ]
+                    } else if g.fuel_level <= 0.0 {
+                        vec![
+                            "* You pull the starter cord.".to_string(),
+                            "* It sputters. No fuel.".to_string(),
+                        ]
+                    } else {
+                        g.is_running = true;
+                        if let Some(tf) = transform {
+                            sfx.write(PlaySfxEvent::at(SfxId::GeneratorStart, tf.translation.truncate()));
+                        }
+                        flags.set("first_generator_started", true);
+                        quests.complete_objective(POWER_GENERATOR, 2);
+                        journal.write(JournalEvent(format!("Started {}", interactable.the_name())));
+                        vec![
+                            "* You pull the starter cord.".to_string(),
+                            format!("* {} roars to life.", capitalized(&interactable.the_name())),
+                        ]
+                    };
+                    for l in lines {
+                        info!("{}", l);
+                        log_writer.write(LogEvent::new(l));
                     }
                 }
+                InteractionAction::Use if shop.is_some() => {
+                    let Some(shop) = shop.as_deref() else { continue };
+
+                    // One entry per item still in stock, greyed with a red price if unaffordable
+                    let mut actions = Vec::new();
+                    let mut labels = Vec::new();
+                    let mut disabled = Vec::new();
+                    let mut suffixes = Vec::new();
+                    for (id, price) in &shop.stock {
+                        if actions.contains(&InteractionAction::Buy(id.clone())) {
+                            continue;
+                        }
+                        let affordable = wallet.gold >= *price;
+                        labels.push(format!("* {}", item_by_id(id).name));
+                        actions.push(InteractionAction::Buy(id.clone()));
+                        disabled.push(!affordable);
+                        suffixes.push(Some((
+                            format!(" - {}G", price),
+                            if affordable { Color::WHITE } else { Color::srgb(1.0, 0.2, 0.2) },
+                        )));
+                    }
+
+                    if actions.is_empty() {
+                        let l = format!("* {} is sold out.", capitalized(&interactable.the_name()));
+                        info!("{}", l);
+                        log_writer.write(LogEvent::new(l));
+                    } else {
+                        menu_events.write(ContextMenuEvent {
+                            entity: event.entity,
+                            actions,
+                            labels,
+                            object_name: format!("{} ({}G)", interactable.name, wallet.gold),
+                            disabled,
+                            suffixes,
+                        });
+                    }
+                }
+                InteractionAction::Buy(id) if shop.is_some() => {
+                    let Some(shop) = shop.as_deref_mut() else { continue };
+                    let item = item_by_id(id);
+                    let l = match shop.stock.iter().position(|(stocked, _)| stocked == id) {
+                        None => "* That's sold out.".to_string(),
+                        Some(index) => {
+                            let price = shop.stock[index].1;
+                            let name = item.name.clone();
+                            if wallet.gold < price {
+                                format!("* You don't have enough money. ({}G)", wallet.gold)
+                            } else if !inventory.add_item(item) {
+                                sfx.write(PlaySfxEvent::ui(SfxId::Error));
+                                "* Your inventory is full!".to_string()
+                            } else {
+                                wallet.spend(price);
+                                shop.stock.remove(index);
+                                if let Some(object_id) = object_id {
+                                    world.object_mut(object_id).shop_stock = Some(shop.stock.clone());
+                                }
+                                format!("* You bought {} for {}G.", the_name(&name, false), price)
+                            }
+                        }
+                    };
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
+                }
                 InteractionAction::Use => {
-                    let l1 = format!("* You use the {}.", interactable.name);
+                    let l1 = format!("* You use {}.", interactable.the_name());
                     let l2 = "* Nothing happens.".to_string();
                     info!("{}", l1);
                     info!("{}", l2);
-                    log_writer.write(LogEvent(l1));
-                    log_writer.write(LogEvent(l2));
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                }
+                // Someone with something to say says it, in their own voice
+                InteractionAction::Talk if npc.is_some_and(|npc| !npc.dialogue.is_empty()) => {
+                    let Some(npc) = npc else { continue };
+                    for l in &npc.dialogue {
+                        info!("{}: {}", npc.name, l);
+                        log_writer.write(LogEvent::said(event.entity, l.clone()));
+                    }
                 }
                 InteractionAction::Talk => {
-                    let l1 = format!("* You speak to the {}.", interactable.name);
+                    let l1 = format!("* You speak to {}.", interactable.the_name());
                     let l2 = "* ...".to_string();
                     let l3 = "* It doesn't respond.".to_string();
                     info!("{}", l1);
                     info!("{}", l2);
                     info!("{}", l3);
-                    log_writer.write(LogEvent(l1));
-                    log_writer.write(LogEvent(l2));
-                    log_writer.write(LogEvent(l3));
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                    log_writer.write(LogEvent::new(l3));
+                }
+                InteractionAction::Open if door.is_some() => {
+                    // Its creak and thunk come from the swing, see AnimClip::DoorOpen
+                    let Some((d, _, _)) = door.as_mut() else { continue };
+                    open_door(&mut commands, event.entity, d);
+                    if let Some(id) = object_id {
+                        world.object_mut(id).door_open = Some(true);
+                    }
+                    flags.set("first_door_opened", true);
+                    flags.increment("doors_opened");
+                    journal.write(JournalEvent(format!("Opened {}", interactable.the_name())));
+                    let l = format!("* You pull {} open.", interactable.the_name());
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
+                }
+                InteractionAction::Close if door.is_some() => {
+                    let Some((d, door_tf, door_sprite)) = door.as_mut() else { continue };
+                    let player = player_query.single().ok().map(|(_, tf, _, _, c)| (tf.translation.truncate(), c));
+                    let l = match doorway_blocker(door_tf, door_sprite, player, crates.iter()) {
+                        Some(DoorwayBlocker::Player) => "* You're standing in the way.".to_string(),
+                        Some(DoorwayBlocker::Object) => "* Something is blocking it.".to_string(),
+                        None => {
+                            close_door(&mut commands, event.entity, d);
+                            if let Some(id) = object_id {
+                                world.object_mut(id).door_open = Some(false);
+                            }
+                            format!("* You push {} shut.", interactable.the_name())
+                        }
+                    };
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
+                }
+                InteractionAction::Peek if door.is_some() => {
+                    let Some((d, _, _)) = door.as_ref() else { continue };
+                    let lines = match peek {
+                        _ if d.is_open => vec![format!("* {} is already open.", capitalized(&interactable.the_name()))],
+                        Some(peek) => peek.lines.clone(),
+                        None => vec![
+                            format!("* You crouch and peer under {}.", interactable.the_name()),
+                            "* It's too dark to make anything out.".to_string(),
+                        ],
+                    };
+                    if !d.is_open {
+                        flags.set(&format!("peeked_{}", interactable.name.to_lowercase().replace(' ', "_")), true);
+                        // Lean the view a little past the door, away from where the player stands
+                        if let (Some((_, door_tf, _)), Ok((_, player_tf, _, _, _))) = (door.as_ref(), player_query.single()) {
+                            let door_pos = door_tf.translation.truncate();
+                            let away = (door_pos - player_tf.translation.truncate()).normalize_or_zero();
+                            pan_events.write(CameraPanEvent {
+                                target: CameraFocus::Point(door_pos + away * PEEK_PAN_DISTANCE),
+                                hold_secs: PEEK_PAN_SECS,
+                                return_smoothly: true,
+                            });
+                        }
+                    }
+                    for l in lines {
+                        info!("{}", l);
+                        log_writer.write(LogEvent::new(l));
+                    }
+                }
+                InteractionAction::Open if container.is_some() => {
+                    let Some(container) = container else { continue };
+                    let stored = world.contents_mut(container);
+
+                    // One menu: stored items to take out, then held items to put in
+                    let mut actions = Vec::new();
+                    let mut labels = Vec::new();
+                    if !container.destroys_items {
+                        for id in stored {
+                            labels.push(format!("* Take out {}", item_by_id(id).name));
+                            actions.push(InteractionAction::Withdraw(id.clone()));
+                        }
+                    }
+                    for held in &inventory.items {
+                        let verb = if container.destroys_items { "Throw away" } else { "Put in" };
+                        labels.push(format!("* {} {}", verb, held.name));
+                        actions.push(InteractionAction::Deposit(held.id.clone()));
+                    }
+
+                    if actions.is_empty() {
+                        let l1 = format!("* You open {}.", interactable.the_name());
+                        let l2 = "* It's empty inside.".to_string();
+                        info!("{}", l1);
+                        info!("{}", l2);
+                        log_writer.write(LogEvent::new(l1));
+                        log_writer.write(LogEvent::new(l2));
+                    } else {
+                        menu_events.write(ContextMenuEvent {
+                            entity: event.entity,
+                            actions,
+                            labels,
+                            object_name: interactable.name.clone(),
+                            disabled: Vec::new(),
+                            suffixes: Vec::new(),
+                        });
+                    }
+                }
+                InteractionAction::Deposit(id) if container.is_some_and(|c| c.destroys_items) => {
+                    let name = item_by_id(id).name;
+                    if is_key_item(id) {
+                        let l = format!("* You can't throw away {}. You might need it.", the_name(&name, false));
+                        info!("{}", l);
+                        log_writer.write(LogEvent::new(l));
+                    } else {
+                        // Confirm before anything is destroyed
+                        menu_events.write(ContextMenuEvent {
+                            entity: event.entity,
+                            actions: vec![InteractionAction::Discard(id.clone())],
+                            labels: vec!["* Yes, toss it".to_string()],
+                            object_name: format!("Throw away {}?", the_name(&name, false)),
+                            disabled: Vec::new(),
+                            suffixes: Vec::new(),
+                        });
+                    }
+                }
+                InteractionAction::Deposit(id) if container.is_some() => {
+                    let Some(container) = container else { continue };
+                    let l = if world.contents_mut(container).len() >= container.capacity {
+                        sfx.write(PlaySfxEvent::ui(SfxId::Error));
+                        format!("* {} is full.", capitalized(&interactable.the_name()))
+                    } else if let Some(held) = inventory.remove_item_id(id) {
+                        world.contents_mut(container).push(held.id);
+                        format!("* You put {} in {}.", the_name(&held.name, false), interactable.the_name())
+                    } else {
+                        "* You don't have that anymore.".to_string()
+                    };
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
+                }
+                InteractionAction::Withdraw(id) if container.is_some() => {
+                    let Some(container) = container else { continue };
+                    let stored = world.contents_mut(container);
+                    let l = match stored.iter().position(|s| s == id) {
+                        None => "* It's not in there anymore.".to_string(),
+                        Some(index) => {
+                            let item = item_by_id(id);
+                            let name = item.name.clone();
+                            if inventory.add_item(item) {
+                                stored.remove(index);
+                                format!("* You take {} out of {}.", the_name(&name, false), interactable.the_name())
+                            } else {
+                                sfx.write(PlaySfxEvent::ui(SfxId::Error));
+                                "* Your inventory is full!".to_string()
+                            }
+                        }
+                    };
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
+                }
+                InteractionAction::Discard(id) => {
+                    let l = match inventory.remove_item_id(id) {
+                        Some(held) => format!("* You toss {}. It's gone.", the_name(&held.name, false)),
+                        None => "* You don't have that anymore.".to_string(),
+                    };
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
                 }
                 InteractionAction::Open => {
-                    let l1 = format!("* You open the {}.", interactable.name);
+                    let l1 = format!("* You open {}.", interactable.the_name());
                     let l2 = "* It's empty inside.".to_string();
                     info!("{}", l1);
                     info!("{}", l2);
-                    log_writer.write(LogEvent(l1));
-                    log_writer.write(LogEvent(l2));
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                }
+                InteractionAction::TurnOn if light.as_ref().is_some_and(|l| !l.has_power) => {
+                    let l1 = format!("* You flip the switch on {}.", interactable.the_name());
+                    let l2 = "* Nothing happens. There's no power.".to_string();
+                    info!("{}", l1);
+                    info!("{}", l2);
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
                 }
                 InteractionAction::TurnOn => {
-                    let l1 = format!("* You flip the switch on the {}.", interactable.name);
+                    if let Some(l) = light.as_deref_mut() {
+                        l.is_on = true;
+                        if let Some(id) = object_id {
+                            world.object_mut(id).light_on = Some(true);
+                        }
+                    }
+                    let l1 = format!("* You flip the switch on {}.", interactable.the_name());
                     let l2 = "* It hums to life.".to_string();
                     info!("{}", l1);
                     info!("{}", l2);
-                    log_writer.write(LogEvent(l1));
-                    log_writer.write(LogEvent(l2));
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                }
+                InteractionAction::TurnOff => {
+                    if let Some(l) = light.as_deref_mut() {
+                        l.is_on = false;
+                        if let Some(id) = object_id {
+                            world.object_mut(id).light_on = Some(false);
+                        }
+                    }
+                    let l1 = format!("* You flip the switch on {}.", interactable.the_name());
+                    let l2 = "* It clicks off.".to_string();
+                    info!("{}", l1);
+                    info!("{}", l2);
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                }
+                InteractionAction::Refuel if generator.is_some() => {
+                    let Some(g) = generator.as_deref_mut() else { continue };
+                    let lines = if g.is_full() {
+                        vec!["* The tank is already full.".to_string()]
+                    } else if inventory.remove_item_id("fuel_can").is_some() {
+                        g.add_fuel(FUEL_CAN_AMOUNT);
+                        quests.complete_objective(POWER_GENERATOR, 1);
+                        vec![
+                            format!("* You pour the fuel can into {}.", interactable.the_name()),
+                            format!("* The gauge now reads {}.", g.gauge_text()),
+                        ]
+                    } else {
+                        vec![
+                            format!("* You search for fuel to add to {}.", interactable.the_name()),
+                            "* You don't have any fuel.".to_string(),
+                        ]
+                    };
+                    for l in lines {
+                        info!("{}", l);
+                        log_writer.write(LogEvent::new(l));
+                    }
                 }
                 InteractionAction::Refuel => {
-                    let l1 = format!("* You search for fuel to add to the {}.", interactable.name);
+                    let l1 = format!("* You search for fuel to add to {}.", interactable.the_name());
                     let l2 = "* You don't have any fuel.".to_string();
                     info!("{}", l1);
                     info!("{}", l2);
-                    log_writer.write(LogEvent(l1));
-                    log_writer.write(LogEvent(l2));
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                }
+                InteractionAction::Custom(label) if hideable.is_some() && label == Hideable::ACTION => {
+                    let (Some((spot, spot_tf)), Ok((player, mut player_tf, mut visibility, is_hidden, _))) =
+                        (hideable, player_query.single_mut())
+                    else {
+                        continue;
+                    };
+                    let l = if is_hidden {
+                        let exit = spot_tf.translation.truncate() + spot.exit_offset;
+                        player_tf.translation.x = exit.x;
+                        player_tf.translation.y = exit.y;
+                        *visibility = Visibility::Inherited;
+                        commands.entity(player).remove::<Hidden>();
+                        format!("* You climb out of {}.", interactable.the_name())
+                    } else {
+                        // Park the player on the spot so it stays the nearest interactable
+                        player_tf.translation.x = spot_tf.translation.x;
+                        player_tf.translation.y = spot_tf.translation.y;
+                        *visibility = Visibility::Hidden;
+                        commands.entity(player).insert(Hidden { inside: event.entity });
+                        "* You squeeze inside.".to_string()
+                    };
+                    info!("{}", l);
+                    log_writer.write(LogEvent::new(l));
+                }
+                InteractionAction::Custom(label) if is_save_point && label == SavePoint::ACTION => {
+                    let slots: Vec<usize> = (0..SAVE_SLOTS).collect();
+                    menu_events.write(ContextMenuEvent {
+                        entity: event.entity,
+                        actions: slots.iter().map(|&slot| InteractionAction::SaveToSlot(slot)).collect(),
+                        labels: slots.iter().map(|&slot| format!("* {}", slot_label(slot, saves.slot_meta(slot).as_ref()))).collect(),
+                        object_name: "Save to which file?".to_string(),
+                        disabled: Vec::new(),
+                        suffixes: Vec::new(),
+                    });
+                }
+                InteractionAction::SaveToSlot(slot) if saves.slot_path(*slot).exists() => {
+                    menu_events.write(ContextMenuEvent {
+                        entity: event.entity,
+                        actions: vec![InteractionAction::OverwriteSlot(*slot)],
+                        labels: vec!["* Yes, overwrite it".to_string()],
+                        object_name: format!("File {} already has a save. Overwrite it?", slot + 1),
+                        disabled: Vec::new(),
+                        suffixes: Vec::new(),
+                    });
+                }
+                InteractionAction::SaveToSlot(slot) | InteractionAction::OverwriteSlot(slot) => {
+                    let l1 = "* The quiet hum of the generator fills you with determination.".to_string();
+                    let l2 = format!("* HP fully restored. Progress saved to File {}.", slot + 1);
+                    info!("{}", l1);
+                    info!("{}", l2);
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                    // Nothing's checkpointed until a slot is picked, so backing out of the picker gains nothing
+                    save_events.write(SaveRequestedEvent);
+                    write_events.write(WriteSaveEvent { slot: *slot });
+                }
+                InteractionAction::Custom(label) if is_telephone && label == Telephone::ACTION => {
+                    // Second-level menu: every number the player knows, plus a blind guess
+                    let mut actions = Vec::new();
+                    let mut labels = Vec::new();
+                    for number in &known_numbers.numbers {
+                        let contact = phone_number(number).map_or("???", |entry| entry.contact);
+                        labels.push(format!("* {} ({})", number, contact));
+                        actions.push(InteractionAction::Dial(number.clone()));
+                    }
+                    labels.push("* Dial at random".to_string());
+                    actions.push(InteractionAction::Dial(String::new()));
+                    menu_events.write(ContextMenuEvent {
+                        entity: event.entity,
+                        actions,
+                        labels,
+                        object_name: "Who do you call?".to_string(),
+                        disabled: Vec::new(),
+                        suffixes: Vec::new(),
+                    });
+                }
+                InteractionAction::Dial(number) => {
+                    let lines: Vec<String> = match phone_number(number) {
+                        Some(entry) => entry.lines.iter().map(|l| l.to_string()).collect(),
+                        None => vec!["* You dial a number.".to_string(), "* ...click.".to_string()],
+                    };
+                    for l in lines {
+                        info!("{}", l);
+                        log_writer.write(LogEvent::new(l));
+                    }
+                }
+                InteractionAction::Custom(label) if switch.as_deref().is_some_and(|sw| sw.action == *label) => {
+                    let Some(sw) = switch.as_deref_mut() else { continue };
+                    sw.is_on = !sw.is_on;
+                    if let Some(id) = object_id {
+                        world.object_mut(id).switch_on = Some(sw.is_on);
+                    }
+                    switch_events.write(SwitchToggledEvent {
+                        target_id: sw.target_id.clone(),
+                        is_on: sw.is_on,
+                    });
+                    // In its own words: a lever's pulled, a button's pressed
+                    let l1 = format!("* You {} {}.", label.to_lowercase(), interactable.the_name());
+                    let l2 = "* Something clanks in the distance.".to_string();
+                    info!("{}", l1);
+                    info!("{}", l2);
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
+                }
+                InteractionAction::Custom(label) if label == ADD_WOOD => {
+                    // The requirement checked there's a bundle to burn
+                    if inventory.remove_item_id("firewood").is_none() {
+                        continue;
+                    }
+                    let l1 = format!("* You feed the firewood to {}.", interactable.the_name());
+                    let l2 = "* The flames crackle and climb.".to_string();
+                    info!("{}", l1);
+                    info!("{}", l2);
+                    log_writer.write(LogEvent::new(l1));
+                    log_writer.write(LogEvent::new(l2));
                 }
                 _ => {
+                    // Objects can supply their own text for custom actions
+                    let response = match (&event.action, custom_text.as_deref_mut()) {
+                        (InteractionAction::Custom(label), Some(text)) => text.response_mut(label),
+                        _ => None,
+                    };
+                    if let Some(response) = response {
+                        for l in response.next_lines() {
+                            info!("{}", l);
+                            log_writer.write(LogEvent::new(l));
+                        }
+                        if let Some(flag) = &response.sets_flag {
+                            flags.set(flag, true);
+                        }
+                        continue;
+                    }
+
                     let action_str = event
                         .action
                         .to_string()
                         .replace("* ", "")
                         .to_lowercase();
-                    let l = format!("* You {} the {}.", action_str, interactable.name);
+                    let l = format!("* You {} {}.", action_str, interactable.the_name());
                     info!("{}", l);
-                    log_writer.write(LogEvent(l));
+                    log_writer.write(LogEvent::new(l));
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::item_by_id;
+
+    // A world where the lamp is on, the door was opened then shut, and the key is held
+    fn synthetic_world() -> (Inventory, GameFlags, WorldState) {
+        let mut inventory = Inventory::new(8);
+        inventory.add_item(item_by_id("rusty_key"));
+        let mut flags = GameFlags::default();
+        flags.set("read_crumpled_note", true);
+        let mut world = WorldState::default();
+        world.object_mut(&ObjectId("start/old_lamp".to_string())).light_on = Some(true);
+        world.object_mut(&ObjectId("start/metal_door".to_string())).door_open = Some(false);
+        (inventory, flags, world)
+    }
+
+    #[test]
+    fn conditions_read_flags_items_and_world_state() {
+        let (inventory, flags, world) = synthetic_world();
+        let ng_plus = NgPlus::default();
+        let met = |c: Condition| c.is_met(&inventory, (&flags, &ng_plus), &world);
+
+        assert!(met(Condition::HasFlag("read_crumpled_note".to_string())));
+        assert!(!met(Condition::HasFlag("flirted_with_figure".to_string())));
+        assert!(met(Condition::HasItem("rusty_key".to_string())));
+        assert!(!met(Condition::HasItem("fuel_can".to_string())));
+        assert!(met(Condition::LightOn("start/old_lamp".to_string())));
+        assert!(!met(Condition::DoorOpen("start/metal_door".to_string())));
+        // Objects with nothing recorded are in their initial (off/closed) state
+        assert!(!met(Condition::DoorOpen("hallway/nowhere".to_string())));
+        assert!(!met(Condition::GeneratorRunning("start/generator".to_string())));
+        assert!(met(Condition::NgPlusCycle(0)));
+        assert!(!met(Condition::NgPlusCycle(1)));
+
+        // A repeat playthrough
+        let second = NgPlus { cycle: 1, carried_flags: Vec::new() };
+        assert!(Condition::NgPlusCycle(1).is_met(&inventory, (&flags, &second), &world));
+    }
+
+    #[test]
+    fn conditional_examine_uses_first_matching_case() {
+        let (mut inventory, flags, world) = synthetic_world();
+        let story = (&flags, &NgPlus::default());
+        let examine = ConditionalExamine {
+            cases: vec![
+                (Condition::HasItem("rusty_key".to_string()), vec!["key".to_string()]),
+                (Condition::LightOn("start/old_lamp".to_string()), vec!["lamp".to_string()]),
+            ],
+            default: vec!["default".to_string()],
+        };
+        assert_eq!(examine.lines(&inventory, story, &world), ["key"]);
+
+        inventory.remove_item_id("rusty_key");
+        assert_eq!(examine.lines(&inventory, story, &world), ["lamp"]);
+
+        let dark = WorldState::default();
+        assert_eq!(examine.lines(&inventory, story, &dark), ["default"]);
+    }
+
+    #[test]
+    fn names_read_right_mid_sentence() {
+        for (name, a, the) in [
+            ("Old Lamp", "an old lamp", "the old lamp"),
+            ("Rusty Key", "a rusty key", "the rusty key"),
+            ("Anvil", "an anvil", "the anvil"),
+            ("Hourglass", "an hourglass", "the hourglass"),
+            ("Hearth Rug", "a hearth rug", "the hearth rug"),
+            ("Umbrella Stand", "an umbrella stand", "the umbrella stand"),
+            ("Unicorn Plush", "a unicorn plush", "the unicorn plush"),
+            ("Uninvited Guest", "an uninvited guest", "the uninvited guest"),
+            ("One-Way Mirror", "a one-way mirror", "the one-way mirror"),
+            ("Euphonium", "a euphonium", "the euphonium"),
+            ("X-Ray", "an x-ray", "the x-ray"),
+            ("TV Stand", "a TV stand", "the TV stand"),
+            ("FBI File", "an FBI file", "the FBI file"),
+        ] {
+            assert_eq!(a_name(name, false), a, "{name}");
+            assert_eq!(the_name(name, false), the, "{name}");
+        }
+        // Proper nouns stand alone, as written
+        assert_eq!(a_name("Mr. Pickles", true), "Mr. Pickles");
+        assert_eq!(the_name("Mr. Pickles", true), "Mr. Pickles");
+        assert_eq!(capitalized(&the_name("Old Lamp", false)), "The old lamp");
+    }
+
+    #[test]
+    fn indicator_floats_above_target_top_edge() {
+        let tall = Sprite::from_color(Color::WHITE, Vec2::new(24.0, 36.0));
+        let pos = indicator_position(Vec2::new(-260.0, 180.0), &tall, 0.0);
+        assert_eq!(pos, Vec3::new(-260.0, 180.0 + 18.0 + INDICATOR_GAP + INDICATOR_SIZE / 2.0, INDICATOR_Z));
+
+        // Bobs within INDICATOR_BOB of its rest height
+        let rest = pos.y;
+        for t in [0.1, 0.4, 0.9, 1.6] {
+            let y = indicator_position(Vec2::new(-260.0, 180.0), &tall, t).y;
+            assert!((y - rest).abs() <= INDICATOR_BOB + 1e-4);
+        }
+    }
+
+    // Everything process_interactions reads, and a player at the origin
+    fn interaction_world() -> World {
+        use crate::objects::{KnownNumbers, PendingRespawns, SaveRequestedEvent};
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(Inventory::new(8));
+        world.init_resource::<Wallet>();
+        world.init_resource::<WorldState>();
+        world.init_resource::<KnownNumbers>();
+        world.init_resource::<PendingRespawns>();
+        world.init_resource::<GameFlags>();
+        world.init_resource::<Quests>();
+        world.init_resource::<NgPlus>();
+        world.insert_resource(SaveDir::scratch());
+        world.init_resource::<Events<InteractionEvent>>();
+        world.init_resource::<Events<SwitchToggledEvent>>();
+        world.init_resource::<Events<SaveRequestedEvent>>();
+        world.init_resource::<Events<WriteSaveEvent>>();
+        world.init_resource::<Events<ContextMenuEvent>>();
+        world.init_resource::<Events<CameraPanEvent>>();
+        world.init_resource::<Events<LogEvent>>();
+        world.init_resource::<Events<JournalEvent>>();
+        world.init_resource::<Events<PlaySfxEvent>>();
+        let collider = Collider { half_extents: Vec2::splat(8.0), offset: Vec2::ZERO };
+        world.spawn((Player::default(), Transform::default(), Visibility::default(), collider));
+        world
+    }
+
+    #[test]
+    fn taking_an_item_twice_in_one_frame_takes_it_once() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = interaction_world();
+        let can = world
+            .spawn((
+                Interactable { name: "Fuel Can".to_string(), ..default() },
+                Item { id: "fuel_can".to_string(), name: "Fuel Can".to_string(), can_pickup: true },
+                Transform::from_xyz(10.0, 0.0, 0.0),
+            ))
+            .id();
+
+        // Examined, then taken from the menu and by a queued click, all in the same frame
+        for action in [InteractionAction::Examine, InteractionAction::Take, InteractionAction::Take] {
+            world.send_event(InteractionEvent { entity: can, action });
+        }
+        world.run_system_once(process_interactions).unwrap();
+        assert_eq!(world.resource::<Inventory>().items.len(), 1);
+        assert_eq!(world.resource_mut::<Events<PlaySfxEvent>>().drain().count(), 1);
+
+        // Still around until Cleanup, but nothing picks it up as within reach
+        assert!(world.get::<PendingDespawn>(can).is_some());
+        world.init_resource::<SpatialGrid>();
+        world.run_system_once(update_spatial_grid).unwrap();
+        world.run_system_once(check_nearby_interactables).unwrap();
+        assert!(world.get::<NearbyInteractable>(can).is_none());
+
+        world.run_system_once(crate::despawn_pending).unwrap();
+        assert!(world.get_entity(can).is_err());
+    }
+
+    #[test]
+    fn only_items_that_can_be_picked_up_are_taken() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = interaction_world();
+        let take = || Interactable {
+            name: "Anvil".to_string(),
+            actions: vec![InteractionAction::Examine, InteractionAction::Take],
+            ..default()
+        };
+        let item = |can_pickup| Item { id: "anvil".to_string(), name: "Anvil".to_string(), can_pickup };
+        let loose = world.spawn((take(), item(true), Transform::default())).id();
+        let bolted = world.spawn((take(), item(false), Transform::default())).id();
+        let scenery = world.spawn((take(), Transform::default())).id();
+
+        // Take is on every menu, greyed out where it can't work
+        let inventory = Inventory::new(8);
+        for (entity, greyed) in [(loose, false), (bolted, true), (scenery, true)] {
+            let interactable = world.get::<Interactable>(entity).unwrap();
+            let (actions, _, disabled) =
+                menu_entries(interactable, None, (None, None, world.get::<Item>(entity)), &inventory);
+            assert_eq!(actions, [InteractionAction::Examine, InteractionAction::Take]);
+            assert_eq!(disabled, [false, greyed]);
+        }
+
+        for (entity, taken) in [(loose, true), (bolted, false), (scenery, false)] {
+            world.send_event(InteractionEvent { entity, action: InteractionAction::Take });
+            world.run_system_once(process_interactions).unwrap();
+            world.resource_mut::<Events<InteractionEvent>>().clear();
+            let lines: Vec<String> = world.resource_mut::<Events<LogEvent>>().drain().map(|e| e.text).collect();
+            assert_eq!(world.get::<PendingDespawn>(entity).is_some(), taken);
+            if taken {
+                assert_eq!(lines, ["* You obtained the anvil!"]);
+            } else {
+                assert_eq!(lines, ["* It won't budge."]);
+            }
+        }
+        // Only the loose one made it into the inventory
+        assert_eq!(world.resource::<Inventory>().items.len(), 1);
+    }
+
+    // Runs one action on `entity` and returns the lines it logged
+    fn act(world: &mut World, entity: Entity, action: InteractionAction) -> Vec<String> {
+        use bevy::ecs::system::RunSystemOnce;
+
+        world.send_event(InteractionEvent { entity, action });
+        world.run_system_once(process_interactions).unwrap();
+        world.resource_mut::<Events<InteractionEvent>>().clear();
+        world.resource_mut::<Events<LogEvent>>().drain().map(|e| e.text).collect()
+    }
+
+    #[test]
+    fn a_refused_action_doesnt_start_the_cooldown() {
+        use crate::objects::KeyRequirement;
+
+        let mut world = interaction_world();
+        let locker = world
+            .spawn((
+                Interactable { name: "Locker".to_string(), cooldown: Some(5.0), ..default() },
+                Lockable {
+                    requires: KeyRequirement::AnyOf(vec!["rusty_key".to_string()]),
+                    locked: true,
+                    consumes_key: false,
+                },
+                Transform::default(),
+            ))
+            .id();
+
+        assert_eq!(act(&mut world, locker, InteractionAction::Open), ["* The locker is locked."]);
+        assert!(world.get::<LastInteracted>(locker).is_none());
+
+        // Coming back with the key straight away still gets it open, and that starts the clock
+        world.resource_mut::<Inventory>().add_item(item_by_id("rusty_key"));
+        let lines = act(&mut world, locker, InteractionAction::Open);
+        assert!(lines[0].starts_with("* You unlock the locker"), "{lines:?}");
+        assert!(world.get::<LastInteracted>(locker).is_some());
+        assert!(act(&mut world, locker, InteractionAction::Open).is_empty());
+    }
+
+    #[test]
+    fn custom_actions_only_do_what_theyre_labelled() {
+        let mut world = interaction_world();
+        let custom = |label: &str| InteractionAction::Custom(label.to_string());
+        let lever = world
+            .spawn((
+                Interactable {
+                    name: "Lever".to_string(),
+                    actions: vec![InteractionAction::Examine, custom("Pull"), custom("Kick")],
+                    ..default()
+                },
+                Switch { target_id: "gate".to_string(), is_on: false, action: "Pull".to_string() },
+                CustomActionText { responses: vec![CustomResponse::new("Kick", vec![vec!["* Ow."]])] },
+                Transform::default(),
+            ))
+            .id();
+
+        // A second custom action is the object's own, and leaves the switch be
+        assert_eq!(act(&mut world, lever, custom("Kick")), ["* Ow."]);
+        assert!(!world.get::<Switch>(lever).unwrap().is_on);
+        assert_eq!(act(&mut world, lever, custom("Pull")), ["* You pull the lever.", "* Something clanks in the distance."]);
+        assert!(world.get::<Switch>(lever).unwrap().is_on);
+
+        let button = world
+            .spawn((
+                Interactable { name: "Call Button".to_string(), actions: vec![custom("Press")], ..default() },
+                Switch { target_id: "lift".to_string(), is_on: false, action: "Press".to_string() },
+                Transform::default(),
+            ))
+            .id();
+        assert_eq!(act(&mut world, button, custom("Press"))[0], "* You press the call button.");
+
+        // Only Hide climbs into a locker
+        let locker = world
+            .spawn((
+                Interactable { name: "Locker".to_string(), actions: vec![custom("Hide"), custom("Knock")], ..default() },
+                Hideable { exit_offset: Vec2::new(0.0, -20.0) },
+                CustomActionText { responses: vec![CustomResponse::new("Knock", vec![vec!["* It sounds hollow."]])] },
+                Transform::default(),
+            ))
+            .id();
+        assert_eq!(act(&mut world, locker, custom("Knock")), ["* It sounds hollow."]);
+        assert!(world.query_filtered::<(), With<Hidden>>().iter(&world).next().is_none());
+        assert_eq!(act(&mut world, locker, custom("Hide")), ["* You squeeze inside."]);
+        assert!(world.query_filtered::<(), With<Hidden>>().iter(&world).next().is_some());
+
+        // Only Save asks for a file
+        let star = world
+            .spawn((
+                Interactable { name: "Save Point".to_string(), actions: vec![custom("Save"), custom("Touch")], ..default() },
+                SavePoint,
+                CustomActionText { responses: vec![CustomResponse::new("Touch", vec![vec!["* It's warm."]])] },
+                Transform::default(),
+            ))
+            .id();
+        assert_eq!(act(&mut world, star, custom("Touch")), ["* It's warm."]);
+        assert_eq!(world.resource_mut::<Events<ContextMenuEvent>>().drain().count(), 0);
+        act(&mut world, star, custom("Save"));
+        let menu = world.resource_mut::<Events<ContextMenuEvent>>().drain().next().unwrap();
+        assert_eq!(menu.object_name, "Save to which file?");
+
+        // Only Call brings up the numbers
+        let phone = world
+            .spawn((
+                Interactable { name: "Telephone".to_string(), actions: vec![custom("Call"), custom("Listen")], ..default() },
+                Telephone,
+                CustomActionText { responses: vec![CustomResponse::new("Listen", vec![vec!["* A dial tone."]])] },
+                Transform::default(),
+            ))
+            .id();
+        assert_eq!(act(&mut world, phone, custom("Listen")), ["* A dial tone."]);
+        assert_eq!(world.resource_mut::<Events<ContextMenuEvent>>().drain().count(), 0);
+        act(&mut world, phone, custom("Call"));
+        let menu = world.resource_mut::<Events<ContextMenuEvent>>().drain().next().unwrap();
+        assert_eq!(menu.object_name, "Who do you call?");
+    }
+
+    #[test]
+    fn adding_wood_burns_a_bundle_of_firewood() {
+        let mut world = interaction_world();
+        for _ in 0..2 {
+            world.resource_mut::<Inventory>().add_item(item_by_id("firewood"));
+        }
+        let add_wood = InteractionAction::Custom(ADD_WOOD.to_string());
+        let fireplace = world
+            .spawn((
+                Interactable { name: "Fireplace".to_string(), actions: vec![add_wood.clone()], ..default() },
+                Transform::default(),
+            ))
+            .id();
+
+        assert_eq!(
+            act(&mut world, fireplace, add_wood),
+            ["* You feed the firewood to the fireplace.", "* The flames crackle and climb."]
+        );
+        assert_eq!(world.resource::<Inventory>().count_item_id("firewood"), 1);
+    }
+
+    // Every InteractionEvent the game acts on, in order
+    #[derive(Resource, Default)]
+    struct Acted(Vec<InteractionAction>);
+
+    fn record_interactions(mut events: EventReader<InteractionEvent>, mut acted: ResMut<Acted>) {
+        acted.0.extend(events.read().map(|event| event.action.clone()));
+    }
+
+    #[test]
+    fn picking_from_the_menu_with_z_acts_once() {
+        use bevy::input::keyboard::{Key, KeyboardInput};
+        use bevy::input::ButtonState;
+        use bevy::time::TimeUpdateStrategy;
+        use crate::save::NewGameEvent;
+        use crate::state::GameState;
+        use std::time::Duration;
+
+        let mut app = crate::headless_app();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(1.0 / 60.0)))
+            .init_resource::<Acted>()
+            .add_systems(Update, record_interactions.after(GameSet::Cleanup));
+        // Startup first, then into a new game as if from the title screen
+        app.update();
+        crate::wait_for_room_files(&mut app);
+        app.world_mut().send_event(NewGameEvent { carry_from: None });
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.update();
+        app.update();
+        // Just below the generator, which has more than one thing to do with it
+        let world = app.world_mut();
+        let mut player = world.query_filtered::<&mut Transform, With<Player>>();
+        player.single_mut(world).unwrap().translation = Vec3::new(0.0, -80.0, 0.0);
+
+        // As the keyboard reports it: Z goes down or comes up, then `frames` frames pass
+        let z = |app: &mut App, state: ButtonState, frames: usize| {
+            app.world_mut().send_event(KeyboardInput {
+                key_code: KeyCode::KeyZ,
+                logical_key: Key::Character("z".into()),
+                state,
+                text: None,
+                repeat: false,
+                window: Entity::PLACEHOLDER,
+            });
+            for _ in 0..frames {
+                app.update();
+            }
+        };
+        z(&mut app, ButtonState::Pressed, 1);
+        z(&mut app, ButtonState::Released, 10);
+        let ui_state = app.world().resource::<UiState>();
+        assert!(ui_state.menu_open);
+        assert_eq!(ui_state.current_actions[0], InteractionAction::Examine);
+
+        // Picked with Z: the press is spent, so nothing read later that frame or the next sees it
+        z(&mut app, ButtonState::Pressed, 1);
+        let input = app.world().resource::<InputMap>();
+        let keyboard = app.world().resource::<ButtonInput<KeyCode>>();
+        assert!(!input.just_pressed(Action::Confirm, keyboard));
+        assert!(app.world().resource::<ConsumedActions>().is_consumed(Action::Confirm));
+        // ...and held on to for a while
+        for _ in 0..20 {
+            app.update();
+        }
+        z(&mut app, ButtonState::Released, 10);
+        assert_eq!(app.world().resource::<Acted>().0, [InteractionAction::Examine]);
+        let ui_state = app.world().resource::<UiState>();
+        assert!(!ui_state.menu_open);
+        // Still on the examine text's first line
+        assert!(ui_state.dialog_open);
+        assert_eq!(ui_state.dialog_index, 0);
+        assert_eq!(ui_state.dialog_queue[0].text, "* A squat old generator.");
+    }
+}
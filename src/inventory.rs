@@ -1,16 +1,21 @@
 use bevy::prelude::*;
 use crate::GameSet;
+use crate::audio::{PlaySfxEvent, SfxId};
+use crate::input::{Action, InputMap};
+use crate::interaction::{a_name, capitalized};
 
 pub struct InventoryPlugin;
 
 impl Plugin for InventoryPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Inventory::new(8))
+            .insert_resource(Wallet { gold: STARTING_GOLD })
             .add_systems(Update, toggle_inventory_display.in_set(GameSet::Input));
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
 pub struct Inventory {
     pub items: Vec<InventoryItem>,
     pub max_size: usize,
@@ -48,22 +53,92 @@ impl Inventory {
             None
         }
     }
+
+    pub fn count_item_id(&self, id: &str) -> usize {
+        self.items.iter().filter(|item| item.id == id).count()
+    }
+
+    pub fn has_item_id(&self, id: &str) -> bool {
+        self.count_item_id(id) > 0
+    }
+
+    // Removes the first item with the given id, if any
+    pub fn remove_item_id(&mut self, id: &str) -> Option<InventoryItem> {
+        let index = self.items.iter().position(|item| item.id == id)?;
+        self.remove_item(index)
+    }
+}
+
+// Money on hand, in G
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Wallet {
+    pub gold: u32,
+}
+
+pub const STARTING_GOLD: u32 = 20;
+
+impl Wallet {
+    // Deducts `amount` if there's enough; otherwise leaves the wallet untouched
+    pub fn spend(&mut self, amount: u32) -> bool {
+        if self.gold >= amount {
+            self.gold -= amount;
+            true
+        } else {
+            false
+        }
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Reflect)]
 pub struct InventoryItem {
+    pub id: String, // Stable id used by conditions, keys, etc.
     pub name: String,
     pub description: String,
     pub icon_color: Color,
 }
 
+// Known item definitions, so items stored only by id (containers, saves) can be rebuilt
+pub fn item_by_id(id: &str) -> InventoryItem {
+    let (name, description) = match id {
+        "rusty_key" => ("Rusty Key", "An old key, flaked with rust."),
+        "firewood" => ("Firewood", "A bundle of dry split logs."),
+        "fuel_can" => ("Fuel Can", "A red jerry can. It sloshes."),
+        "scrap" => ("Scrap Metal", "A twisted strip of sheet metal. Someone might want it."),
+        "battery" => ("Battery", "A 9-volt. Licking it confirms it still has charge."),
+        "small_key" => ("Small Key", "A tiny brass key. Too small for any door."),
+        "master_key" => ("Master Key", "The janitor's ring. One of these opens everything."),
+        "closet_key" => ("Closet Key", "Stamped SUPPLY. Someone lost it a long time ago."),
+        _ => {
+            // Unknown ids fall back to a name built from the id: "old_rag" -> "Old Rag"
+            let name = id.split('_').map(capitalized).collect::<Vec<_>>().join(" ");
+            let description = format!("{} that you picked up.", capitalized(&a_name(&name, false)));
+            return InventoryItem { id: id.to_string(), name, description, icon_color: Color::WHITE };
+        }
+    };
+    InventoryItem {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        icon_color: Color::WHITE,
+    }
+}
+
+// Key items can't be thrown away
+pub fn is_key_item(id: &str) -> bool {
+    id.ends_with("_key")
+}
+
 fn toggle_inventory_display(
     keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
     mut inventory: ResMut<Inventory>,
+    mut sfx: EventWriter<PlaySfxEvent>,
 ) {
     // Toggle with I key
-    if keyboard.just_pressed(KeyCode::KeyI) {
+    if input.just_pressed(Action::Inventory, &keyboard) {
         inventory.is_open = !inventory.is_open;
+        sfx.write(PlaySfxEvent::ui(if inventory.is_open { SfxId::InventoryOpen } else { SfxId::InventoryClose }));
         if inventory.is_open {
             info!("=== INVENTORY ===");
             if inventory.items.is_empty() {
@@ -0,0 +1,98 @@
+// src/journal.rs
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::GameSet;
+use crate::quests::{QuestEvent, Quests};
+use crate::save::Playtime;
+
+pub struct JournalPlugin;
+
+impl Plugin for JournalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Journal>()
+            .add_event::<JournalEvent>()
+            // After the gameplay sets, so an interaction's entry lands the same frame.
+            // Not gated on GameState, like the flags.
+            .add_systems(Update, (journal_quest_progress, record_journal_entries).chain().after(GameSet::Process));
+    }
+}
+
+// Something worth remembering happened, e.g. "Opened the metal door"
+#[derive(Event, Clone, Debug)]
+pub struct JournalEvent(pub String);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct JournalEntry {
+    pub text: String,
+    pub playtime: f32, // When it happened, in Playtime seconds
+}
+
+// The notable moments of a playthrough, oldest first. Unlike the message log it's kept in saves.
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    // Adds an entry unless the same thing was already written down; returns whether it was added
+    pub fn record(&mut self, text: &str, playtime: f32) -> bool {
+        if self.entries.iter().any(|entry| entry.text == text) {
+            return false;
+        }
+        self.entries.push(JournalEntry { text: text.to_string(), playtime });
+        true
+    }
+
+    pub fn newest_first(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter().rev()
+    }
+}
+
+// Starting and finishing quests are journal moments; single objectives aren't
+fn journal_quest_progress(
+    mut quest_events: EventReader<QuestEvent>,
+    quests: Res<Quests>,
+    mut journal_events: EventWriter<JournalEvent>,
+) {
+    for event in quest_events.read() {
+        let (prefix, id) = match event {
+            QuestEvent::Activated(id) => ("Took on", id),
+            QuestEvent::Completed(id) => ("Finished", id),
+            QuestEvent::ObjectiveCompleted { .. } => continue,
+        };
+        if let Some(quest) = quests.get(id) {
+            journal_events.write(JournalEvent(format!("{}: {}", prefix, quest.title)));
+        }
+    }
+}
+
+fn record_journal_entries(
+    mut events: EventReader<JournalEvent>,
+    mut journal: ResMut<Journal>,
+    playtime: Res<Playtime>,
+) {
+    for JournalEvent(text) in events.read() {
+        if journal.record(text, playtime.0) {
+            info!("Journal: {}", text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_are_written_down_once() {
+        let mut journal = Journal::default();
+        assert!(journal.record("Examined the Generator", 12.0));
+        for _ in 0..50 {
+            assert!(!journal.record("Examined the Generator", 30.0));
+        }
+        assert!(journal.record("Started the Generator", 40.0));
+
+        let texts: Vec<_> = journal.newest_first().map(|entry| (entry.text.as_str(), entry.playtime)).collect();
+        assert_eq!(texts, [("Started the Generator", 40.0), ("Examined the Generator", 12.0)]);
+    }
+}
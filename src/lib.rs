@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use bevy::input::InputSystem;
+
+pub mod player;
+pub mod animation;
+pub mod audio;
+pub mod autosave;
+pub mod camera;
+pub mod collision;
+pub mod cutscene;
+pub mod depth;
+pub mod flags;
+pub mod input;
+pub mod interaction;
+pub mod inventory;
+pub mod journal;
+pub mod ngplus;
+pub mod objects;
+pub mod quests;
+pub mod replay;
+pub mod rng;
+pub mod rooms;
+pub mod save;
+pub mod scenes;
+pub mod screenshot;
+pub mod settings;
+pub mod spatial;
+pub mod state;
+pub mod timescale;
+pub mod touch;
+pub mod ui;
+
+use player::PlayerPlugin;
+use animation::FrameEventsPlugin;
+use audio::AudioPlugin;
+use autosave::AutosavePlugin;
+use camera::CameraPlugin;
+use cutscene::CutscenePlugin;
+use depth::DepthPlugin;
+use flags::FlagsPlugin;
+use input::{hold_consumed_actions, track_gestures, ConsumedActions, InputGestures};
+use interaction::InteractionPlugin;
+use inventory::InventoryPlugin;
+use journal::JournalPlugin;
+use ngplus::NgPlusPlugin;
+use objects::ObjectsPlugin;
+use quests::QuestsPlugin;
+use rng::RngPlugin;
+use rooms::RoomsPlugin;
+use save::SavePlugin;
+use scenes::ScenesPlugin;
+use screenshot::ScreenshotPlugin;
+use settings::Settings;
+use spatial::SpatialPlugin;
+use state::{GameState, StatePlugin};
+use timescale::TimeScalePlugin;
+use touch::TouchPlugin;
+use ui::UiPlugin;
+
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub enum GameSet {
+    Detect,   // proximity detection, markers
+    Input,    // read inputs, emit UI events
+    Ui,       // show/hide menus, handle UI navigation
+    Process,  // apply game logic, update logs
+    Cleanup,  // despawn what Process marked PendingDespawn
+}
+
+// An entity on its way out: Process marks it, Cleanup despawns it. Until then it still exists,
+// so anything that looks entities up (nearby markers, the open menu) should skip it.
+#[derive(Component)]
+pub struct PendingDespawn;
+
+pub fn despawn_pending(mut commands: Commands, doomed: Query<Entity, With<PendingDespawn>>) {
+    for entity in doomed.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// The game itself, on top of whatever Bevy plugins the app runs with (a window, or none for tests).
+// Settings and the InputMap are the caller's to insert.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // Ensure systems across plugins run in a deterministic, single-frame order, and only
+            // while actually playing (not on the title screen or paused). Gameplay entities are
+            // removed by marking them PendingDespawn in Process, not despawned on the spot: commands
+            // from Process are applied as Cleanup starts and Cleanup's despawns as it ends, so a
+            // marked entity is gone by the next frame's Detect and, until then, skippable.
+            .configure_sets(Update, (
+                GameSet::Detect,
+                GameSet::Input,
+                GameSet::Ui,
+                GameSet::Process,
+                GameSet::Cleanup,
+            ).chain().run_if(in_state(GameState::Playing)))
+            .add_systems(Update, (ApplyDeferred, despawn_pending, ApplyDeferred).chain().in_set(GameSet::Cleanup))
+            // Whatever asks about double taps or holds runs after this
+            .init_resource::<InputGestures>()
+            .add_systems(Update, track_gestures.in_set(GameSet::Input))
+            .init_resource::<ConsumedActions>()
+            .add_systems(PreUpdate, hold_consumed_actions.after(InputSystem))
+            .add_plugins((
+                StatePlugin,
+                PlayerPlugin,
+                CameraPlugin,
+                CutscenePlugin,
+                // Where things are: drawing order, and what's near what
+                (DepthPlugin, SpatialPlugin),
+                InteractionPlugin,
+                InventoryPlugin,
+                ObjectsPlugin,
+                RoomsPlugin,
+                // Game speed, and chance
+                (TimeScalePlugin, RngPlugin),
+                // The on-screen buttons are UI too
+                (UiPlugin, TouchPlugin),
+                ScenesPlugin,
+                FrameEventsPlugin,
+                // Sound, and capturing the screen
+                (AudioPlugin, ScreenshotPlugin),
+                // Progress, and keeping it
+                (FlagsPlugin, JournalPlugin, QuestsPlugin, SavePlugin, AutosavePlugin, NgPlusPlugin),
+            ));
+    }
+}
+
+// The whole game with no window, renderer or sound: just the pieces of DefaultPlugins its
+// systems read from. It starts on the title screen with default settings and controls, and saves
+// to a scratch folder of its own.
+// Sprites, text and UI nodes are only components until a renderer draws them, so every plugin
+// runs as it does in the game; the asset types they hold handles to just need registering.
+// Used by the unit tests and the scenarios under tests/.
+pub fn headless_app() -> App {
+    use bevy::audio::AudioSource;
+    use bevy::gizmos::GizmoPlugin;
+    use bevy::input::InputPlugin;
+    use bevy::render::render_resource::Shader;
+    use bevy::state::app::StatesPlugin;
+    use bevy::window::ExitCondition;
+
+    let mut app = App::new();
+    let windowless = WindowPlugin { primary_window: None, exit_condition: ExitCondition::DontExit, ..default() };
+    app.add_plugins((MinimalPlugins, StatesPlugin, AssetPlugin::default(), InputPlugin, windowless))
+        .init_asset::<Shader>()
+        .add_plugins(GizmoPlugin)
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlasLayout>()
+        .init_asset::<AudioSource>()
+        .init_resource::<UiScale>()
+        .insert_resource(Settings::default())
+        .insert_resource(input::InputMap::default())
+        .insert_resource(save::SaveDir::scratch())
+        .add_plugins(GamePlugin);
+    app
+}
+
+// Runs frames until every room file asked for is in, so a room loaded after this appears on the
+// frame it's asked for; straight after startup, that's all of them. For tests, which would
+// otherwise race the AssetServer.
+pub fn wait_for_room_files(app: &mut App) {
+    for _ in 0..600 {
+        if !app.world().resource::<rooms::RoomDefs>().is_loading() {
+            return;
+        }
+        app.update();
+    }
+    panic!("room files still loading after 600 frames");
+}
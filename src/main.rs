@@ -1,62 +1,52 @@
 use bevy::prelude::*;
-use bevy::window::WindowResolution;
-
-mod player;
-mod interaction;
-mod inventory;
-mod objects;
-mod ui;
-
-use player::PlayerPlugin;
-use interaction::InteractionPlugin;
-use inventory::InventoryPlugin;
-use objects::ObjectsPlugin;
-use ui::UiPlugin;
-
-#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
-pub enum GameSet {
-    Detect,   // proximity detection, markers
-    Input,    // read inputs, emit UI events
-    Ui,       // show/hide menus, handle UI navigation
-    Process,  // apply game logic, update logs
-}
+use bevy::window::{MonitorSelection, WindowMode, WindowResizeConstraints, WindowResolution};
+use dissonance::camera;
+use dissonance::replay::ReplayPlugin;
+use dissonance::rng::GameRng;
+use dissonance::settings::Settings;
+use dissonance::GamePlugin;
 
 fn main() {
+    // --record/--replay; both play with the default settings, so a replay runs the same anywhere
+    let replay = ReplayPlugin::from_args(std::env::args().skip(1));
+    // Read before the window exists, so it opens fullscreen straight away if that was the choice
+    let settings = if replay.is_active() { Settings::default() } else { Settings::load_or_default() };
+    // Rebound keys too, before any system reads them
+    let input_map = settings.input_map();
+    // --seed N, to play a reported run's dice again; a recording keeps it
+    let rng = GameRng::from_args(std::env::args().skip(1));
+
     App::new()
         .add_plugins(DefaultPlugins
             .set(WindowPlugin {
                 primary_window: Some(Window {
                     title: "DISSONANCE".to_string(),
-                    resolution: WindowResolution::new(640.0, 480.0),
-                    resizable: false,
+                    resolution: WindowResolution::new(camera::GAME_SIZE.x, camera::GAME_SIZE.y),
+                    mode: if settings.fullscreen {
+                        WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+                    } else {
+                        WindowMode::Windowed
+                    },
+                    // Never smaller than one whole copy of the game; bigger windows get letterboxed
+                    resize_constraints: WindowResizeConstraints {
+                        min_width: camera::GAME_SIZE.x,
+                        min_height: camera::GAME_SIZE.y,
+                        ..default()
+                    },
                     ..default()
                 }),
+                // The close button asks first while a game is going; see state.rs
+                close_when_requested: false,
                 ..default()
             })
             .set(ImagePlugin::default_nearest()) // Pixel-perfect rendering
+            // Edited room files reload while the game runs (rooms.rs), but only in debug builds
+            .set(AssetPlugin { watch_for_changes_override: Some(cfg!(debug_assertions)), ..default() })
         )
-        // Ensure systems across plugins run in a deterministic, single-frame order
-        .configure_sets(Update, (
-            GameSet::Detect,
-            GameSet::Input,
-            GameSet::Ui,
-            GameSet::Process,
-        ).chain())
         .insert_resource(ClearColor(Color::srgb(0.05, 0.05, 0.05)))
-        .add_plugins((
-            PlayerPlugin,
-            InteractionPlugin,
-            InventoryPlugin,
-            ObjectsPlugin,
-            UiPlugin,
-        ))
-        .add_systems(Startup, setup_camera)
+        .insert_resource(settings)
+        .insert_resource(input_map)
+        .insert_resource(rng)
+        .add_plugins((GamePlugin, replay))
         .run();
 }
-
-fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2d);
-    // To zoom: Query for OrthographicProjection component and modify its scale field
-    // Smaller scale = zoomed in, Larger scale = zoomed out  
-    // Example: projection.scale = 0.5; // 2x zoom in
-}
\ No newline at end of file
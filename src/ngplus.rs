@@ -0,0 +1,83 @@
+// src/ngplus.rs
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::GameSet;
+use crate::flags::GameFlags;
+use crate::save::{SaveDir, SAVE_SLOTS};
+
+pub struct NgPlusPlugin;
+
+impl Plugin for NgPlusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NgPlus>()
+            .add_systems(Update, debug_complete_game.in_set(GameSet::Input));
+    }
+}
+
+// Set by the ending. A file saved with it set unlocks New Game+ on the title screen.
+pub const GAME_COMPLETED: &str = "game_completed";
+
+// The only flags that survive into the next cycle, so a repeat playthrough can remember the last
+pub const CARRIED_FLAGS: &[&str] = &["met_figure_in_hallway", "flirted_with_figure"];
+
+// Which playthrough this is (0 the first time) and the flags brought along into it
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+pub struct NgPlus {
+    pub cycle: u32,
+    pub carried_flags: Vec<String>,
+}
+
+impl NgPlus {
+    // The cycle after this one, given the flags it finished with, and the flags the next one
+    // starts with: just the carried ones that were set
+    pub fn next_cycle(&self, flags: &GameFlags) -> (Self, GameFlags) {
+        let carried: Vec<String> = CARRIED_FLAGS
+            .iter()
+            .filter(|name| flags.is_set(name))
+            .map(|name| name.to_string())
+            .collect();
+        let next_flags = flags.only(&carried);
+        (Self { cycle: self.cycle + 1, carried_flags: carried }, next_flags)
+    }
+}
+
+// The most recently saved file in `saves` that finished the game, if any
+pub fn newest_completed_slot(saves: &SaveDir) -> Option<usize> {
+    (0..SAVE_SLOTS)
+        .filter_map(|slot| Some((slot, saves.slot_meta(slot)?)))
+        .filter(|(_, meta)| meta.completed)
+        .max_by_key(|(_, meta)| meta.saved_at)
+        .map(|(slot, _)| slot)
+}
+
+// Debug: F10 marks this playthrough finished, until there's an ending to do it. Saving afterwards
+// unlocks New Game+.
+fn debug_complete_game(keyboard: Res<ButtonInput<KeyCode>>, mut flags: ResMut<GameFlags>) {
+    if keyboard.just_pressed(KeyCode::F10) {
+        flags.set(GAME_COMPLETED, true);
+        info!("Marked the game completed; save to unlock New Game+");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_cycle_keeps_only_carried_flags() {
+        let mut finished = GameFlags::default();
+        finished.set("peeked_metal_door", true);
+        finished.set("flirted_with_figure", true);
+        finished.set(GAME_COMPLETED, true);
+        let ng_plus = NgPlus { cycle: 1, carried_flags: Vec::new() };
+
+        let (ng_plus, flags) = ng_plus.next_cycle(&finished);
+        assert_eq!(ng_plus, NgPlus { cycle: 2, carried_flags: vec!["flirted_with_figure".to_string()] });
+        assert!(flags.is_set("flirted_with_figure"));
+        // Everything else starts over, finishing the game included
+        assert!(!flags.is_set("peeked_metal_door"));
+        assert!(!flags.is_set(GAME_COMPLETED));
+        assert!(!flags.is_set("met_figure_in_hallway"));
+    }
+}
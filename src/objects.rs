@@ -1,158 +1,3324 @@
 // src/objects.rs
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use crate::interaction::{Interactable, InteractionAction};
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Entry, HashMap};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use crate::GameSet;
+use crate::animation::{AnimClip, AnimationEvent, FrameEvents};
+use crate::audio::{AmbientLoop, RoomAudio, RoomAudioOverride, SfxId, Voice};
+use crate::camera::RoomSlideEvent;
+use crate::depth::{CastsShadow, YSort};
+use crate::state::GameState;
+use crate::player::{
+    push_out_of_solids, Collider, DamageEvent, FloorModifier, FloorSurface, PlayerDiedEvent, Player,
+    PlayerRespawnedEvent, SpawnPoint, SpawnTarget, MovementLocks, Sneaking, TeleportPlayerEvent, teleport_player, PLAYER_START, SNEAK_NOTICE_MULTIPLIER,
+};
+use crate::inventory::{item_by_id, Inventory, InventoryItem};
+use crate::ui::LogEvent;
+use crate::flags::GameFlags;
+use crate::rng::{GameRng, RngDraw};
+use crate::rooms::{load_rooms, CurrentRoom, LoadRoomEvent, RoomMember, RoomRegistry, HALLWAY, START_ROOM};
+use crate::interaction::{
+    capitalized, ActionCondition, ActionRequirements, Condition, ConditionalExamine, CustomActionText,
+    CustomResponse, Interactable, InteractionAction, NearbyInteractable, PeekText,
+};
 
 pub struct ObjectsPlugin;
 
 impl Plugin for ObjectsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_example_objects);
+        app.add_event::<SwitchToggledEvent>()
+            .add_event::<SaveRequestedEvent>()
+            .init_resource::<RoomFade>()
+            .init_resource::<WorldState>()
+            .init_resource::<KnownNumbers>()
+            .init_resource::<PendingRespawns>()
+            .init_resource::<RoomRegistry>()
+            .add_systems(Startup, setup_lighting)
+            // Straight after the room loader, so a room never shows a frame of how it was first
+            // laid out (a taken key still lying there); not gated, as Continue loads from the title
+            .add_systems(Update, restore_object_state.after(load_rooms).before(teleport_player))
+            .add_systems(Update, (
+                learn_phone_numbers.in_set(GameSet::Detect),
+                check_hazards.in_set(GameSet::Detect),
+                update_light_power.in_set(GameSet::Detect),
+                attach_light_glows.in_set(GameSet::Detect),
+                attach_fuel_gauges.in_set(GameSet::Detect),
+                check_room_transitions.in_set(GameSet::Detect),
+                check_pressure_plates.in_set(GameSet::Detect),
+                auto_close_doors.in_set(GameSet::Detect),
+                restore_checkpoint_on_key.in_set(GameSet::Input),
+                (animate_doors, wander_critters.in_set(RngDraw::Critters), pulse_sprites).run_if(in_state(GameState::Playing)),
+            ))
+            .add_systems(Update, (
+                sync_light_visuals.in_set(GameSet::Process),
+                update_darkness.in_set(GameSet::Process),
+                burn_generator_fuel.in_set(GameSet::Process),
+                (update_fuel_gauges, show_nearby_fuel_gauges, hum_while_running, flag_generator_running).after(burn_generator_fuel).in_set(GameSet::Process),
+                respawn_pickups.in_set(GameSet::Process),
+                move_elevators.in_set(GameSet::Process),
+                record_power_state.in_set(GameSet::Process),
+                apply_switch_toggles.in_set(GameSet::Process),
+                record_checkpoint.in_set(GameSet::Process),
+                sync_switch_visuals.in_set(GameSet::Process),
+                run_room_fade.in_set(GameSet::Process),
+                lock_movement_during_fade.after(run_room_fade).in_set(GameSet::Process),
+                respawn_on_death.before(run_room_fade).in_set(GameSet::Process),
+            ));
+        // The hand-built layouts, for when a room file is missing
+        let mut registry = app.world_mut().resource_mut::<RoomRegistry>();
+        registry.register(START_ROOM, spawn_start_room);
+        registry.register(HALLWAY, spawn_hallway);
     }
 }
 
-#[allow(dead_code)]
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Item {
+    pub id: String,
     pub name: String,
     pub can_pickup: bool,
 }
 
-#[allow(dead_code)]
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Light {
     pub is_on: bool,
+    #[entities]
+    pub powered_by: Option<Entity>, // Generator feeding this light, if any
+    pub has_power: bool, // Kept up to date by update_light_power
+    pub on_color: Color,
+    pub off_color: Color,
+    pub glow_radius: Option<f32>, // Size of the glow cast when on; None casts none
 }
 
-#[allow(dead_code)]
+// Fixture colors when a room doesn't give its own (the old lamp's)
+pub const LIGHT_ON_COLOR: Color = Color::srgb(1.0, 0.9, 0.55); // Warm yellow
+pub const LIGHT_OFF_COLOR: Color = Color::srgb(0.3, 0.3, 0.3); // Dark gray
+
+// Darkness overlay opacity with every light off / every light on
+pub const MAX_DARKNESS: f32 = 0.6;
+pub const MIN_DARKNESS: f32 = 0.1;
+
+// World-space layers: the darkness sits over the room, glows sit over the darkness
+const DARKNESS_Z: f32 = 40.0;
+const GLOW_Z: f32 = 45.0;
+
+// Soft radial texture shared by all light glows
+#[derive(Resource)]
+pub struct GlowTexture(pub Handle<Image>);
+
+#[derive(Component)]
+pub struct DarknessOverlay;
+
 #[derive(Component)]
+pub struct LightGlow;
+
+// Bar floating over a generator; both the backing and the fill carry this
+#[derive(Component)]
+pub struct FuelGauge;
+
+#[derive(Component)]
+pub struct FuelGaugeFill;
+
+pub const FUEL_GAUGE_SIZE: Vec2 = Vec2::new(32.0, 4.0);
+const FUEL_GAUGE_BACK_COLOR: Color = Color::srgb(0.1, 0.1, 0.1);
+const FUEL_GAUGE_OK_COLOR: Color = Color::srgb(0.3, 0.85, 0.3);
+const FUEL_GAUGE_LOW_COLOR: Color = Color::srgb(0.9, 0.2, 0.2); // Below a quarter tank
+
+// Width of the fill (left-aligned in the bar) and its color for this much fuel
+pub fn fuel_gauge_fill(generator: &Generator) -> (f32, Color) {
+    let fraction = generator.fuel_fraction().clamp(0.0, 1.0);
+    let color = if fraction < 0.25 { FUEL_GAUGE_LOW_COLOR } else { FUEL_GAUGE_OK_COLOR };
+    (FUEL_GAUGE_SIZE.x * fraction, color)
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Generator {
     pub is_running: bool,
     pub fuel_level: f32,
     pub max_fuel: f32,
 }
 
-#[allow(dead_code)]
-#[derive(Component)]
+// Fuel burned per second while running
+pub const GENERATOR_BURN_RATE: f32 = 0.2;
+// Fuel added by pouring in one fuel can
+pub const FUEL_CAN_AMOUNT: f32 = 4.0;
+
+impl Generator {
+    // Burns fuel for `dt` seconds; returns true if the tank ran dry this tick
+    pub fn burn(&mut self, dt: f32) -> bool {
+        if !self.is_running {
+            return false;
+        }
+        self.fuel_level = (self.fuel_level - GENERATOR_BURN_RATE * dt).max(0.0);
+        if self.fuel_level <= 0.0 {
+            self.is_running = false;
+            return true;
+        }
+        false
+    }
+
+    // Adds fuel up to max_fuel; returns how much actually went in
+    pub fn add_fuel(&mut self, amount: f32) -> f32 {
+        let before = self.fuel_level;
+        self.fuel_level = (self.fuel_level + amount).min(self.max_fuel);
+        self.fuel_level - before
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.fuel_level >= self.max_fuel
+    }
+
+    pub fn fuel_fraction(&self) -> f32 {
+        if self.max_fuel > 0.0 { self.fuel_level / self.max_fuel } else { 0.0 }
+    }
+
+    // Rough reading of the fuel gauge for Examine text
+    pub fn gauge_text(&self) -> &'static str {
+        let fraction = self.fuel_fraction();
+        if fraction <= 0.0 {
+            "empty"
+        } else if fraction < 0.15 {
+            "nearly empty"
+        } else if fraction < 0.4 {
+            "about a quarter full"
+        } else if fraction < 0.65 {
+            "about half full"
+        } else if fraction < 0.9 {
+            "about three-quarters full"
+        } else {
+            "full"
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct NPC {
     pub name: String,
     pub dialogue: Vec<String>,
+    pub voice: Voice,
 }
 
 // Marks an entity as blocking for simple 2D collision
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Solid;
 
-fn spawn_example_objects(mut commands: Commands) {
-    // Spawn a pickupable key
-    commands.spawn((
-        Sprite::from_color(
-            Color::srgb(0.8, 0.7, 0.3), // Gold color
-            Vec2::new(12.0, 12.0)
-        ),
-        Transform::from_xyz(-100.0, 0.0, 1.0),
-        Interactable {
-            name: "Rusty Key".to_string(),
-            actions: vec![
-                InteractionAction::Examine,
-                InteractionAction::Take,
-            ],
-            interaction_radius: Some(35.0), // Small object, normal radius
-        },
-        Item {
-            name: "Rusty Key".to_string(),
-            can_pickup: true,
-        },
-        Solid,
-        Name::new("Rusty Key"),
-    ));
+// Flipping this sends a SwitchToggledEvent to every SwitchTarget with the same id
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Switch {
+    pub target_id: String,
+    pub is_on: bool,
+    pub action: String, // The Custom action that flips it; any others are left to CustomActionText
+}
 
-    // Spawn a light/lamp
-    commands.spawn((
-        Sprite::from_color(
-            Color::srgb(0.3, 0.3, 0.3), // Dark gray (off)
-            Vec2::new(20.0, 28.0)
-        ),
-        Transform::from_xyz(100.0, 50.0, 1.0),
-        Interactable {
-            name: "Old Lamp".to_string(),
-            actions: vec![
-                InteractionAction::Examine,
-                InteractionAction::TurnOn,
-            ],
-            interaction_radius: Some(40.0), // Medium object
-        },
-        Light { is_on: false },
-        Solid,
-        Name::new("Old Lamp"),
-    ));
+impl Switch {
+    pub const DEFAULT_ACTION: &str = "Pull";
+}
 
-    // Spawn a generator - LARGER OBJECT
-    commands.spawn((
-        Sprite::from_color(
-            Color::srgb(0.4, 0.4, 0.5), // Blue-gray
-            Vec2::new(48.0, 48.0)  // Large size
-        ),
-        Transform::from_xyz(0.0, -120.0, 1.0),
-        Interactable {
-            name: "Generator".to_string(),
-            actions: vec![
-                InteractionAction::Examine,
-                InteractionAction::Use,
-                InteractionAction::Refuel,
-            ],
-            interaction_radius: Some(60.0), // Large object needs bigger radius
-        },
-        Generator {
-            is_running: false,
-            fuel_level: 2.5,
-            max_fuel: 10.0,
-        },
-        Solid,
-        Name::new("Generator"),
-    ));
+pub const SWITCH_ON_COLOR: Color = Color::srgb(0.3, 0.7, 0.3); // Green
+pub const SWITCH_OFF_COLOR: Color = Color::srgb(0.6, 0.25, 0.2); // Rust red
 
-    // Spawn an NPC
-    commands.spawn((
-        Sprite::from_color(
-            Color::srgb(0.6, 0.3, 0.8), // Purple
-            Vec2::new(16.0, 20.0)
-        ),
-        Transform::from_xyz(60.0, 0.0, 1.0),
-        Interactable {
-            name: "Strange Figure".to_string(),
-            actions: vec![
-                InteractionAction::Talk,
-                InteractionAction::Examine,
-            ],
-            interaction_radius: Some(40.0), // Human-sized
-        },
-        Solid,
-        NPC {
-            name: "Strange Figure".to_string(),
-            dialogue: vec![
-                "* ...".to_string(),
-                "* The figure stares at you silently.".to_string(),
-            ],
-        },
-        Name::new("Strange Figure"),
+// Something a switch controls: gates (Solid + Visibility), remote lights or elevators
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct SwitchTarget(pub String);
+
+// Floor platform that carries the player between waypoints. Its switch picks the end it
+// travels to: on for the last waypoint, off for the first.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Elevator {
+    pub waypoints: Vec<Vec2>,
+    pub speed: f32,
+    pub current: usize, // Waypoint it's at, or left from while moving
+    pub moving: bool,
+}
+
+impl Elevator {
+    pub fn next(&self) -> usize {
+        (self.current + 1) % self.waypoints.len()
+    }
+}
+
+#[derive(Event)]
+pub struct SwitchToggledEvent {
+    pub target_id: String,
+    pub is_on: bool,
+}
+
+// A Solid the player can shove around by walking into it
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Pushable;
+
+// Activates its SwitchTarget while something stands on it
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct PressurePlate {
+    pub target_id: String,
+    pub requires_weight: bool, // Only a crate is heavy enough; the player alone won't do
+    pub is_pressed: bool,
+}
+
+pub const PLATE_UP_COLOR: Color = Color::srgb(0.55, 0.5, 0.35); // Brass
+pub const PLATE_DOWN_COLOR: Color = Color::srgb(0.3, 0.27, 0.18); // Pressed into shadow
+
+// Which key ids open a lock
+#[derive(Clone, Debug, Default, Deserialize, Reflect)]
+pub enum KeyRequirement {
+    AnyOf(Vec<String>), // Any one will do, e.g. the door's own key or a master key
+    AllOf(Vec<String>), // Every key at once
+    #[default]
+    None,               // Can't be unlocked from this side
+}
+
+// Lock shared by doors and containers; Open checks it before anything else
+#[derive(Component, Clone, Debug, Deserialize, Reflect)]
+#[reflect(Component)]
+#[serde(from = "LockableFile")]
+pub struct Lockable {
+    pub requires: KeyRequirement,
+    pub locked: bool,
+    pub consumes_key: bool, // Used keys are taken out of the inventory
+}
+
+// A lock as room files write it. Older ones name their one key with `required_key_id` (None:
+// sealed) instead of `requires`, so those still open with the same key.
+#[derive(Deserialize)]
+struct LockableFile {
+    #[serde(default)]
+    requires: KeyRequirement,
+    #[serde(default)]
+    required_key_id: Option<String>,
+    locked: bool,
+    consumes_key: bool,
+}
+
+impl From<LockableFile> for Lockable {
+    fn from(file: LockableFile) -> Self {
+        let requires = match file.required_key_id {
+            Some(id) => KeyRequirement::AnyOf(vec![id]),
+            None => file.requires,
+        };
+        Self { requires, locked: file.locked, consumes_key: file.consumes_key }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LockCheck {
+    Unlocked,
+    UnlockedWith { key_names: Vec<String>, consumed: bool }, // Was locked; the player had the keys
+    MissingKey,               // AnyOf, and none are held
+    MissingKeys(Vec<String>), // AllOf: names of the keys not held
+    Sealed,
+}
+
+impl Lockable {
+    // Unlocks using the player's keys if they have them
+    pub fn try_open(&mut self, inventory: &mut Inventory) -> LockCheck {
+        if !self.locked {
+            return LockCheck::Unlocked;
+        }
+        let used: Vec<String> = match &self.requires {
+            KeyRequirement::None => return LockCheck::Sealed,
+            KeyRequirement::AnyOf(ids) => match ids.iter().find(|id| inventory.has_item_id(id)) {
+                Some(id) => vec![id.clone()],
+                None => return LockCheck::MissingKey,
+            },
+            KeyRequirement::AllOf(ids) => {
+                let missing: Vec<String> = ids
+                    .iter()
+                    .filter(|id| !inventory.has_item_id(id))
+                    .map(|id| item_by_id(id).name)
+                    .collect();
+                if !missing.is_empty() {
+                    return LockCheck::MissingKeys(missing);
+                }
+                ids.clone()
+            }
+        };
+        self.locked = false;
+        if self.consumes_key {
+            for id in &used {
+                inventory.remove_item_id(id);
+            }
+        }
+        LockCheck::UnlockedWith {
+            key_names: used.iter().map(|id| item_by_id(id).name).collect(),
+            consumed: self.consumes_key,
+        }
+    }
+}
+
+// Two-way storage. Contents live in WorldState by id so they outlive the entity
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Container {
+    pub id: String,
+    pub capacity: usize,
+    pub initial_items: Vec<String>, // Item ids inside before the player first opens it
+    pub destroys_items: bool, // Trash cans: deposits are gone for good
+}
+
+// Stable id for an object whose state should outlive its entity, e.g. "start/rusty_key"
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+pub struct ObjectId(pub String);
+
+// Everything the player has changed in the world, keyed by ObjectId (objects) or
+// Container::id (containers). This, not the entities, is what survives room reloads
+// and goes into save data.
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+pub struct WorldState {
+    pub objects: HashMap<String, ObjectState>,
+    pub containers: HashMap<String, Vec<String>>,
+}
+
+// Facts recorded about one object; None means it hasn't changed since the room data
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct ObjectState {
+    pub taken: bool,
+    pub door_open: Option<bool>,
+    pub locked: Option<bool>,
+    pub light_on: Option<bool>,
+    pub switch_on: Option<bool>,
+    pub generator_running: Option<bool>,
+    pub fuel_level: Option<f32>,
+    pub shop_stock: Option<Vec<(String, u32)>>,
+    pub elevator_stop: Option<usize>,
+}
+
+impl WorldState {
+    pub fn object_mut(&mut self, id: &ObjectId) -> &mut ObjectState {
+        self.objects.entry(id.0.clone()).or_default()
+    }
+
+    // Seeds the container's initial items the first time it's looked at
+    pub fn contents_mut(&mut self, container: &Container) -> &mut Vec<String> {
+        self.containers
+            .entry(container.id.clone())
+            .or_insert_with(|| container.initial_items.clone())
+    }
+}
+
+// Pickup that comes back a while after being taken, rebuilt from the entry it was spawned from
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Respawns {
+    pub after: f32,
+    pub def: RoomEntry,
+}
+
+// Pickups don't pop back in closer than this to the player
+pub const RESPAWN_MIN_DISTANCE: f32 = 160.0;
+
+pub struct PendingRespawn {
+    pub room: String,
+    pub def: RoomEntry,
+    pub position: Vec2,
+    pub timer: f32, // Seconds left
+}
+
+#[derive(Resource, Default)]
+pub struct PendingRespawns {
+    pub entries: Vec<PendingRespawn>,
+}
+
+// Sells items for G. Each entry is one unit (item id, price); repeat an id to stock more.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Shop {
+    pub stock: Vec<(String, u32)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Reflect)]
+pub enum HazardKind {
+    Slippery, // magnitude = speed multiplier while standing in it (as a FloorModifier)
+    Electric, // magnitude = damage per second while live
+}
+
+// Floor region that affects the player while they overlap it
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub magnitude: f32,
+    #[entities]
+    pub powered_by: Option<Entity>, // Electric hazards are only live while this generator runs
+}
+
+// Seconds between damage ticks from an electric hazard
+pub const HAZARD_DAMAGE_INTERVAL: f32 = 1.0;
+// How hard a shock throws the player back from the wires (px/s, see apply_knockback)
+pub const ELECTRIC_KNOCKBACK: f32 = 300.0;
+
+// Wall phone; its Call action lists the KnownNumbers
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Telephone;
+
+impl Telephone {
+    pub const ACTION: &str = "Call";
+}
+
+pub struct PhoneNumber {
+    pub number: &'static str,
+    pub contact: &'static str,
+    pub revealed_by: Option<&'static str>, // Flag that teaches the player this number
+    pub lines: &'static [&'static str],    // The conversation when dialed
+}
+
+pub const PHONE_BOOK: &[PhoneNumber] = &[
+    PhoneNumber {
+        number: "555-0143",
+        contact: "Home",
+        revealed_by: None,
+        lines: &[
+            "* Ring... ring...",
+            "* The line picks up. Nobody speaks.",
+            "* You can hear your own breathing, a second late.",
+        ],
+    },
+    PhoneNumber {
+        number: "555-0199",
+        contact: "Caretaker",
+        revealed_by: Some("read_crumpled_note"),
+        lines: &[
+            "* Ring... ring...",
+            "* \"Who gave you this number?\"",
+            "* \"...Never mind. Keep the generator running.\"",
+            "* \"Whatever you do, don't let the lights go out.\"",
+            "* Click.",
+        ],
+    },
+];
+
+pub fn phone_number(number: &str) -> Option<&'static PhoneNumber> {
+    PHONE_BOOK.iter().find(|entry| entry.number == number)
+}
+
+// Numbers the player can pick from when calling, in the order they were learned
+#[derive(Resource)]
+pub struct KnownNumbers {
+    pub numbers: Vec<String>,
+}
+
+impl Default for KnownNumbers {
+    fn default() -> Self {
+        Self {
+            numbers: PHONE_BOOK
+                .iter()
+                .filter(|entry| entry.revealed_by.is_none())
+                .map(|entry| entry.number.to_string())
+                .collect(),
+        }
+    }
+}
+
+// Furniture the player can climb into via its Hide action
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Hideable {
+    pub exit_offset: Vec2, // Where the player steps out, relative to the furniture
+}
+
+impl Hideable {
+    pub const ACTION: &str = "Hide";
+}
+
+// Undertale-style save star; its Save action records a LastCheckpoint
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct SavePoint;
+
+impl SavePoint {
+    pub const ACTION: &str = "Save";
+}
+
+// Gentle scale throb for glowing things
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Pulse {
+    pub speed: f32,  // Radians per second
+    pub amount: f32, // Fraction of base scale
+}
+
+// Small animal that ambles around `home`, never straying past `wander_radius`. It freezes
+// while the player is within `notice_radius` (shrunk while they sneak).
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Critter {
+    pub home: Vec2,
+    pub wander_radius: f32,
+    pub speed: f32,
+    pub notice_radius: f32,
+    pub target: Vec2,    // Spot it's ambling toward
+    pub next_pick: f32,  // Seconds until it picks a new spot
+}
+
+impl Critter {
+    pub fn new(home: Vec2, wander_radius: f32, speed: f32) -> Self {
+        Self { home, wander_radius, speed, notice_radius: CRITTER_NOTICE_RADIUS, target: home, next_pick: 0.0 }
+    }
+
+    // Whether a player at `player_pos` is close enough to make it hold still
+    pub fn notices(&self, pos: Vec2, player_pos: Vec2, sneaking: bool) -> bool {
+        let radius = if sneaking { self.notice_radius * SNEAK_NOTICE_MULTIPLIER } else { self.notice_radius };
+        pos.distance(player_pos) <= radius
+    }
+}
+
+pub const CRITTER_NOTICE_RADIUS: f32 = 48.0;
+
+#[derive(Event)]
+pub struct SaveRequestedEvent;
+
+// Snapshot taken at the last save point, restored on death/reset
+#[derive(Resource, Clone)]
+pub struct LastCheckpoint {
+    pub room: String,
+    pub player_position: Vec2,
+    pub items: Vec<InventoryItem>,
+    pub world: WorldState,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Door {
+    pub is_open: bool,
+    pub auto_close_after: Option<f32>, // Seconds before swinging shut on its own
+    pub open_for: f32, // Seconds since it was opened
+}
+
+// Seconds for a door to swing fully open or shut
+pub const DOOR_ANIM_SECS: f32 = 0.25;
+// Frames the swing counts through for AnimationEvents: the first as it starts, the last as it ends
+pub const DOOR_ANIM_FRAMES: usize = 4;
+
+// Squashes the door sprite's width toward zero (opening) or back out (closing)
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct DoorAnimation {
+    pub opening: bool,
+    pub progress: f32,        // 0.0 to 1.0
+    pub frame: Option<usize>, // The last frame shown, None before the first
+}
+
+impl DoorAnimation {
+    fn clip(&self) -> AnimClip {
+        if self.opening { AnimClip::DoorOpen } else { AnimClip::DoorClose }
+    }
+
+    fn frame_at(progress: f32) -> usize {
+        (progress * (DOOR_ANIM_FRAMES - 1) as f32) as usize
+    }
+
+    // Moves the swing on by `dt` seconds. Returns every frame passed into since last time, so a
+    // long hitch can't skip the thunk.
+    fn advance(&mut self, dt: f32) -> std::ops::RangeInclusive<usize> {
+        self.progress = (self.progress + dt / DOOR_ANIM_SECS).min(1.0);
+        let frame = Self::frame_at(self.progress);
+        let first = self.frame.map_or(0, |shown| shown + 1);
+        self.frame = Some(frame);
+        first..=frame
+    }
+}
+
+pub fn open_door(commands: &mut Commands, entity: Entity, door: &mut Door) {
+    door.is_open = true;
+    door.open_for = 0.0;
+    commands
+        .entity(entity)
+        .remove::<Solid>()
+        .insert(DoorAnimation { opening: true, progress: 0.0, frame: None });
+}
+
+// Open already, as the room is put back how it was left: no swing and no sound
+fn restore_open_door(commands: &mut Commands, entity: Entity, door: &mut Door) {
+    door.is_open = true;
+    commands
+        .entity(entity)
+        .remove::<Solid>()
+        .insert(DoorAnimation { opening: true, progress: 1.0, frame: Some(DOOR_ANIM_FRAMES - 1) });
+}
+
+pub fn close_door(commands: &mut Commands, entity: Entity, door: &mut Door) {
+    door.is_open = false;
+    commands
+        .entity(entity)
+        .insert((Solid, DoorAnimation { opening: false, progress: 0.0, frame: None }));
+}
+
+// Whether the player is standing where the closed door would be
+pub fn doorway_occupied(door_tf: &Transform, door_sprite: &Sprite, player_pos: Vec2, player: &Collider) -> bool {
+    player.overlaps(player_pos, &Collider::of(door_sprite, None), door_tf.translation.truncate())
+}
+
+// What would be caught in a door if it shut now
+#[derive(Debug, PartialEq)]
+pub enum DoorwayBlocker {
+    Player,
+    Object, // A crate or anything else solid that can be moved into the doorway
+}
+
+pub fn doorway_blocker<'a>(
+    door_tf: &Transform,
+    door_sprite: &Sprite,
+    player: Option<(Vec2, &Collider)>,
+    objects: impl IntoIterator<Item = (&'a Transform, &'a Sprite, Option<&'a Collider>)>,
+) -> Option<DoorwayBlocker> {
+    if player.is_some_and(|(pos, c)| doorway_occupied(door_tf, door_sprite, pos, c)) {
+        return Some(DoorwayBlocker::Player);
+    }
+    let doorway = Collider::of(door_sprite, None);
+    let door_pos = door_tf.translation.truncate();
+    objects
+        .into_iter()
+        .any(|(tf, sprite, c)| Collider::of(sprite, c).overlaps(tf.translation.truncate(), &doorway, door_pos))
+        .then_some(DoorwayBlocker::Object)
+}
+
+// Walking into this entity (once its Door, if any, is open) moves the player to another room
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+pub struct RoomTransition {
+    pub target_room: String,
+    pub spawn_point: String,
+    pub slide: bool, // The rooms adjoin: slide the camera across instead of fading through black
+}
+
+// The area of the current room the camera may show, in world space. Replaced whenever a room spawns.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct RoomBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+// The start room fills the 640x480 view exactly, so the camera never moves there
+pub const START_ROOM_BOUNDS: RoomBounds = RoomBounds { min: Vec2::new(-320.0, -240.0), max: Vec2::new(320.0, 240.0) };
+pub const HALLWAY_BOUNDS: RoomBounds = RoomBounds { min: Vec2::new(-68.0, -230.0), max: Vec2::new(68.0, 230.0) };
+
+// Set while the start room's generator runs; its music picks up once the power's back
+pub const GENERATOR_RUNNING: &str = "generator_running";
+
+// The built-in rooms' audio, as in their files
+pub fn start_room_audio() -> RoomAudio {
+    RoomAudio {
+        music: Some("ambient".to_string()),
+        ambience: Some("room_tone".to_string()),
+        overrides: vec![RoomAudioOverride {
+            flag: GENERATOR_RUNNING.to_string(),
+            music: Some("ambient_powered".to_string()),
+            ambience: None,
+        }],
+    }
+}
+
+pub fn hallway_audio() -> RoomAudio {
+    RoomAudio {
+        music: Some("hallway".to_string()),
+        ambience: Some("hallway_drone".to_string()),
+        overrides: Vec::new(),
+    }
+}
+
+// Seconds for each half of the fade-to-black during a room swap
+pub const ROOM_FADE_SECS: f32 = 0.25;
+
+#[derive(Resource, Default)]
+pub enum RoomFade {
+    #[default]
+    Idle,
+    Out { to: RoomTransition, elapsed: f32 },
+    Respawn { elapsed: f32 }, // Fading out after the player went down
+    Determined { opened: bool }, // Black behind "* Stay determined." until it's read
+    In { elapsed: f32 },
+    Slide { to: RoomTransition, exit: Vec2 }, // Swaps at once; the camera slide does the rest
+}
+
+impl RoomFade {
+    pub fn is_active(&self) -> bool {
+        !matches!(self, Self::Idle)
+    }
+
+    // Opacity of the black overlay, 0.0 (clear) to 1.0 (black)
+    pub fn overlay_alpha(&self) -> f32 {
+        match self {
+            Self::Idle | Self::Slide { .. } => 0.0,
+            Self::Out { elapsed, .. } | Self::Respawn { elapsed } => (elapsed / ROOM_FADE_SECS).min(1.0),
+            Self::Determined { .. } => 1.0,
+            Self::In { elapsed } => 1.0 - (elapsed / ROOM_FADE_SECS).min(1.0),
+        }
+    }
+}
+
+// A room layout as stored in assets/rooms/*.ron, loaded through the AssetServer (see rooms.rs)
+#[derive(Asset, TypePath, Deserialize)]
+pub struct RoomDef {
+    #[serde(default)]
+    pub bounds: Option<((f32, f32), (f32, f32))>, // (min, max); defaults to the box around every entry
+    pub entries: Vec<RoomEntry>,
+    #[serde(default)]
+    pub audio: RoomAudio,
+}
+
+impl RoomDef {
+    pub fn bounds(&self) -> RoomBounds {
+        if let Some(((min_x, min_y), (max_x, max_y))) = self.bounds {
+            return RoomBounds { min: Vec2::new(min_x, min_y), max: Vec2::new(max_x, max_y) };
+        }
+        if self.entries.is_empty() {
+            return RoomBounds { min: Vec2::ZERO, max: Vec2::ZERO };
+        }
+        let mut min = Vec2::splat(f32::INFINITY);
+        let mut max = Vec2::splat(f32::NEG_INFINITY);
+        for entry in &self.entries {
+            let pos = Vec2::new(entry.position.0, entry.position.1);
+            let half = Vec2::new(entry.size.0, entry.size.1) / 2.0;
+            min = min.min(pos - half);
+            max = max.max(pos + half);
+        }
+        RoomBounds { min, max }
+    }
+}
+
+// One placed object. `kind` picks the bundle; fields that don't apply to it are ignored.
+#[derive(Deserialize, Default, Clone, Reflect)]
+#[serde(default)]
+pub struct RoomEntry {
+    pub kind: String,
+    pub name: String,
+    pub id: Option<String>, // ObjectId; defaults to "<room>/<name in snake_case>"
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    pub color: (f32, f32, f32),
+    pub z: Option<f32>,
+    pub solid: Option<bool>, // Overrides the kind's usual solidity
+    pub collider: Option<((f32, f32), (f32, f32))>, // (half extents, offset); defaults to the sprite
+    // Interactable overrides
+    pub radius: Option<f32>,
+    pub cooldown: Option<f32>,
+    pub proper_noun: bool, // Said as written, without "the"
+    pub description: Option<String>, // Replaces the generic examine line
+    pub actions: Option<Vec<InteractionAction>>,
+    pub requirements: Vec<(InteractionAction, ActionCondition)>,
+    pub responses: Vec<CustomResponse>,
+    // Kind-specific
+    pub item_id: Option<String>,          // Item
+    pub dialogue: Vec<String>,            // NPC
+    pub voice: Option<Voice>,             // NPC: how their lines blip; a plain speaking voice otherwise
+    pub fuel: Option<f32>,                // Generator
+    pub max_fuel: Option<f32>,            // Generator
+    pub powered_by: Option<String>,       // Light: name of an earlier Generator entry
+    pub light_radius: Option<f32>,        // Light: glow size, 0 for no glow
+    pub on_color: Option<(f32, f32, f32)>,  // Light
+    pub off_color: Option<(f32, f32, f32)>, // Light
+    pub target_id: Option<String>,        // Lever, PressurePlate, Gate
+    pub requires_weight: bool,            // PressurePlate
+    pub target_room: Option<String>,      // Door, Trigger
+    pub spawn_point: Option<String>,      // Door, Trigger
+    pub slide: bool,                      // Door, Trigger: target_room adjoins this one
+    pub auto_close_after: Option<f32>,    // Door
+    pub peek: Vec<String>,                // Door: what's seen on the other side
+    pub container_id: Option<String>,     // Chest
+    pub capacity: Option<usize>,          // Chest
+    pub items: Vec<String>,               // Chest
+    pub destroys_items: bool,             // Chest
+    pub exit_offset: Option<(f32, f32)>,  // Locker
+    pub stock: Vec<(String, u32)>,        // Shop: (item id, price) per unit
+    pub hazard: Option<HazardKind>,       // Hazard
+    pub magnitude: Option<f32>,           // Hazard
+    pub respawn_after: Option<f32>,       // Item: seconds before it comes back once taken
+    pub examine: Option<ConditionalExamine>,
+    pub lock: Option<Lockable>, // Door, Chest
+    pub waypoints: Vec<(f32, f32)>, // Elevator; it starts at the first
+    pub speed: Option<f32>,         // Elevator, Critter
+    pub wander_radius: Option<f32>, // Critter: how far it strays from where it's placed
+    pub surface: Option<FloorSurface>, // Footstep sounds while standing on it
+    pub speed_mult: Option<f32>,       // Walking speed scale while standing on it
+    pub sound: Option<SfxId>,          // Ambience: looped where it's placed
+    pub volume: Option<f32>,           // Ambience
+    pub hearing_radius: Option<f32>,   // Ambience, Generator: how far its loop carries
+}
+
+impl RoomEntry {
+    pub fn object_id(&self, room: &str) -> ObjectId {
+        ObjectId(self.id.clone().unwrap_or_else(|| {
+            format!("{}/{}", room, self.name.to_lowercase().replace(' ', "_"))
+        }))
+    }
+}
+
+// Where `room`'s file is, under assets/
+pub fn room_def_path(room: &str) -> String {
+    format!("rooms/{}.ron", room)
+}
+
+fn default_actions(kind: &str) -> Vec<InteractionAction> {
+    match kind {
+        "Door" => vec![
+            InteractionAction::Examine,
+            InteractionAction::Peek,
+            InteractionAction::Open,
+            InteractionAction::Close,
+        ],
+        "Item" => vec![InteractionAction::Examine, InteractionAction::Take],
+        "Light" => vec![InteractionAction::Examine, InteractionAction::TurnOn, InteractionAction::TurnOff],
+        "NPC" => vec![InteractionAction::Talk, InteractionAction::Examine],
+        "Generator" => vec![InteractionAction::Examine, InteractionAction::Use, InteractionAction::Refuel],
+        "Chest" => vec![InteractionAction::Open, InteractionAction::Examine],
+        "Lever" => vec![InteractionAction::Examine, InteractionAction::Custom(Switch::DEFAULT_ACTION.to_string())],
+        "Locker" => vec![InteractionAction::Examine, InteractionAction::Custom(Hideable::ACTION.to_string())],
+        "SavePoint" => vec![InteractionAction::Custom(SavePoint::ACTION.to_string())],
+        "Shop" => vec![InteractionAction::Use],
+        "Telephone" => vec![InteractionAction::Examine, InteractionAction::Custom(Telephone::ACTION.to_string())],
+        "Critter" => vec![InteractionAction::Examine, InteractionAction::Custom("Pet".to_string())],
+        _ => vec![InteractionAction::Examine],
+    }
+}
+
+pub fn spawn_room_def(commands: &mut Commands, room: &str, def: &RoomDef) {
+    commands.insert_resource(def.bounds());
+    commands.insert_resource(def.audio.clone());
+
+    // Entities spawned so far by entry name, so later entries can refer back (powered_by)
+    let mut spawned: HashMap<&str, Entity> = HashMap::new();
+
+    for entry in &def.entries {
+        if let Some(entity) = spawn_room_entry(commands, room, entry, &spawned) {
+            spawned.insert(entry.name.as_str(), entity);
+        }
+    }
+}
+
+// Spawns one entry with every component its kind implies. Also used to bring back respawning pickups.
+pub fn spawn_room_entry(
+    commands: &mut Commands,
+    room: &str,
+    entry: &RoomEntry,
+    spawned: &HashMap<&str, Entity>,
+) -> Option<Entity> {
+    // Generators are looked up by entry name (Lights and Hazards)
+    let powered_by = entry.powered_by.as_deref().and_then(|name| {
+        let generator = spawned.get(name).copied();
+        if generator.is_none() {
+            warn!("{} is powered by {}, which isn't spawned before it", entry.name, name);
+        }
+        generator
+    });
+    spawn_entry(commands, room, entry, powered_by)
+}
+
+fn spawn_entry(
+    commands: &mut Commands,
+    room: &str,
+    entry: &RoomEntry,
+    powered_by: Option<Entity>,
+) -> Option<Entity> {
+    // Floors, walls and gates stay flat; everything else depth-sorts against the player
+    let (interactive, solid_by_default, sorted) = match entry.kind.as_str() {
+        "Wall" | "Gate" => (false, true, false),
+        "Trigger" | "PressurePlate" | "Hazard" | "Elevator" | "SpawnPoint" | "Floor" | "Ambience" => (false, false, false),
+        "Door" | "Item" | "Light" | "NPC" | "Generator" | "Chest" | "Lever" | "Crate"
+        | "Locker" | "Shop" | "Telephone" | "Critter" => (true, true, true),
+        "SavePoint" | "Prop" => (true, false, true),
+        other => {
+            warn!("Skipping {} in {}: unknown kind {}", entry.name, room, other);
+            return None;
+        }
+    };
+
+    let (r, g, b) = entry.color;
+    let (x, y) = entry.position;
+    let mut e = commands.spawn((
+        Sprite::from_color(Color::srgb(r, g, b), Vec2::new(entry.size.0, entry.size.1)),
+        Transform::from_xyz(x, y, entry.z.unwrap_or(1.0)),
+        RoomMember::new(room),
+        entry.object_id(room),
+        Name::new(entry.name.clone()),
     ));
 
-    // Spawn a chest/container
-    commands.spawn((
-        Sprite::from_color(
-            Color::srgb(0.5, 0.3, 0.1), // Brown
-            Vec2::new(24.0, 20.0)
-        ),
-        Transform::from_xyz(-50.0, -50.0, 1.0),
-        Interactable {
-            name: "Wooden Chest".to_string(),
-            actions: vec![
-                InteractionAction::Open,
-                InteractionAction::Examine,
-            ],
-            interaction_radius: Some(40.0), // Medium object
+    if interactive {
+        e.insert(Interactable {
+            name: entry.name.clone(),
+            actions: entry.actions.clone().unwrap_or_else(|| default_actions(&entry.kind)),
+            interaction_radius: entry.radius,
+            cooldown: entry.cooldown,
+            is_proper_noun: entry.proper_noun,
+            description: entry.description.clone(),
+        });
+    }
+    if entry.solid.unwrap_or(solid_by_default) {
+        e.insert(Solid);
+    }
+    // An explicit z pins it to a layer
+    if sorted && entry.z.is_none() {
+        e.insert(YSort);
+    }
+    if entry.surface.is_some() || entry.speed_mult.is_some() {
+        e.insert(FloorModifier {
+            speed_mult: entry.speed_mult.unwrap_or(1.0),
+            surface: entry.surface.unwrap_or_default(),
+        });
+    }
+    if let Some(((hx, hy), (ox, oy))) = entry.collider {
+        e.insert(Collider { half_extents: Vec2::new(hx, hy), offset: Vec2::new(ox, oy) });
+    }
+    if let Some(examine) = &entry.examine {
+        e.insert(examine.clone());
+    }
+    if let Some(lock) = &entry.lock {
+        e.insert(lock.clone());
+    }
+    if !entry.requirements.is_empty() {
+        e.insert(ActionRequirements { conditions: entry.requirements.clone() });
+    }
+    if !entry.responses.is_empty() {
+        e.insert(CustomActionText {
+            responses: entry
+                .responses
+                .iter()
+                .map(|r| CustomResponse {
+                    label: r.label.clone(),
+                    line_sets: r.line_sets.clone(),
+                    sets_flag: r.sets_flag.clone(),
+                    uses: 0,
+                })
+                .collect(),
+        });
+    }
+    if let (Some(target_room), Some(spawn_point)) = (&entry.target_room, &entry.spawn_point) {
+        e.insert(RoomTransition {
+            target_room: target_room.clone(),
+            spawn_point: spawn_point.clone(),
+            slide: entry.slide,
+        });
+    }
+
+    match entry.kind.as_str() {
+        "Door" => {
+            e.insert(Door {
+                is_open: false,
+                auto_close_after: entry.auto_close_after,
+                open_for: 0.0,
+            });
+            if !entry.peek.is_empty() {
+                e.insert(PeekText { lines: entry.peek.clone() });
+            }
+        }
+        "Item" => {
+            if let Some(after) = entry.respawn_after {
+                e.insert(Respawns { after, def: entry.clone() });
+            }
+            let id = entry
+                .item_id
+                .clone()
+                .unwrap_or_else(|| entry.name.to_lowercase().replace(' ', "_"));
+            e.insert(Item { id, name: entry.name.clone(), can_pickup: true });
+        }
+        "Light" => {
+            let off_color = entry.off_color.map_or(LIGHT_OFF_COLOR, |(r, g, b)| Color::srgb(r, g, b));
+            e.insert((
+                Light {
+                    is_on: false,
+                    powered_by,
+                    has_power: false,
+                    on_color: entry.on_color.map_or(LIGHT_ON_COLOR, |(r, g, b)| Color::srgb(r, g, b)),
+                    off_color,
+                    glow_radius: Some(entry.light_radius.unwrap_or(90.0)).filter(|&r| r > 0.0),
+                },
+                Sprite::from_color(off_color, Vec2::new(entry.size.0, entry.size.1)),
+            ));
+        }
+        "NPC" => {
+            let voice = entry.voice.unwrap_or(Voice::SPEAKER);
+            e.insert((NPC { name: entry.name.clone(), dialogue: entry.dialogue.clone(), voice }, CastsShadow));
+        }
+        "Generator" => {
+            e.insert((
+                Generator {
+                    is_running: false,
+                    fuel_level: entry.fuel.unwrap_or(0.0),
+                    max_fuel: entry.max_fuel.unwrap_or(10.0),
+                },
+                AmbientLoop::new(
+                    SfxId::GeneratorHum,
+                    GENERATOR_HUM_VOLUME,
+                    entry.hearing_radius.unwrap_or(GENERATOR_HUM_RADIUS),
+                    false,
+                ),
+            ));
+        }
+        "Chest" => {
+            e.insert(Container {
+                id: entry
+                    .container_id
+                    .clone()
+                    .unwrap_or_else(|| format!("{}/{}", room, entry.name)),
+                capacity: entry.capacity.unwrap_or(usize::MAX),
+                initial_items: entry.items.clone(),
+                destroys_items: entry.destroys_items,
+            });
+        }
+        "Gate" => {
+            e.insert(SwitchTarget(entry.target_id.clone().unwrap_or_default()));
+        }
+        "SpawnPoint" => {
+            e.insert((SpawnPoint { name: entry.name.clone() }, Visibility::Hidden));
+        }
+        "Elevator" => {
+            let mut waypoints: Vec<Vec2> = entry.waypoints.iter().map(|&(x, y)| Vec2::new(x, y)).collect();
+            if waypoints.is_empty() {
+                waypoints.push(Vec2::new(x, y));
+            }
+            e.insert((
+                Elevator { waypoints, speed: entry.speed.unwrap_or(60.0), current: 0, moving: false },
+                SwitchTarget(entry.target_id.clone().unwrap_or_default()),
+            ));
+        }
+        "Lever" => {
+            // Its first custom action is the one that flips it
+            let action = entry.actions.iter().flatten().find_map(|action| match action {
+                InteractionAction::Custom(label) => Some(label.clone()),
+                _ => None,
+            });
+            e.insert((
+                Switch {
+                    target_id: entry.target_id.clone().unwrap_or_default(),
+                    is_on: false,
+                    action: action.unwrap_or_else(|| Switch::DEFAULT_ACTION.to_string()),
+                },
+                Sprite::from_color(SWITCH_OFF_COLOR, Vec2::new(entry.size.0, entry.size.1)),
+            ));
+        }
+        "PressurePlate" => {
+            e.insert((
+                PressurePlate {
+                    target_id: entry.target_id.clone().unwrap_or_default(),
+                    requires_weight: entry.requires_weight,
+                    is_pressed: false,
+                },
+                Sprite::from_color(PLATE_UP_COLOR, Vec2::new(entry.size.0, entry.size.1)),
+            ));
+        }
+        "Crate" => {
+            e.insert(Pushable);
+        }
+        "Shop" => {
+            e.insert(Shop { stock: entry.stock.clone() });
+        }
+        "Telephone" => {
+            e.insert(Telephone);
+        }
+        "Critter" => {
+            e.insert(Critter::new(
+                Vec2::new(x, y),
+                entry.wander_radius.unwrap_or(40.0),
+                entry.speed.unwrap_or(30.0),
+            ));
+            e.insert(CastsShadow);
+        }
+        "Hazard" => {
+            let kind = entry.hazard.unwrap_or(HazardKind::Slippery);
+            let magnitude = entry.magnitude.unwrap_or(0.5);
+            e.insert(Hazard { kind, magnitude, powered_by });
+            if kind == HazardKind::Slippery {
+                e.insert(FloorModifier { speed_mult: magnitude, surface: entry.surface.unwrap_or(FloorSurface::Oil) });
+            }
+        }
+        "Locker" => {
+            let (ox, oy) = entry.exit_offset.unwrap_or((0.0, -30.0));
+            e.insert(Hideable { exit_offset: Vec2::new(ox, oy) });
+        }
+        "Ambience" => match entry.sound {
+            Some(sound) => {
+                let radius = entry.hearing_radius.unwrap_or(AMBIENCE_RADIUS);
+                e.insert(AmbientLoop::new(sound, entry.volume.unwrap_or(1.0), radius, true));
+            }
+            None => warn!("{} in {} has no sound to play", entry.name, room),
         },
-        Solid,
-        Name::new("Wooden Chest"),
-    ));
-}
\ No newline at end of file
+        "SavePoint" => {
+            e.insert((
+                SavePoint,
+                Pulse { speed: 3.0, amount: 0.15 },
+                Transform::from_xyz(x, y, entry.z.unwrap_or(1.0))
+                    .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+            ));
+        }
+        _ => {}
+    }
+
+    Some(e.id())
+}
+
+// Fluent wrapper over RoomEntry for placing objects from code. It spawns through the same
+// path as the RON loader, so hand-built rooms get exactly the components a room file would.
+pub struct ObjectBuilder {
+    room: String,
+    entry: RoomEntry,
+    powered_by: Option<Entity>,
+}
+
+impl ObjectBuilder {
+    // Private, so every builder starts from one of the constructors below and its kind is one
+    // spawn_entry knows
+    fn new(kind: &str, name: &str, pos: Vec2) -> Self {
+        Self {
+            room: START_ROOM.to_string(),
+            entry: RoomEntry {
+                kind: kind.to_string(),
+                name: name.to_string(),
+                position: (pos.x, pos.y),
+                size: (16.0, 16.0),
+                color: (0.5, 0.5, 0.5),
+                ..default()
+            },
+            powered_by: None,
+        }
+    }
+
+    pub fn wall(pos: Vec2) -> Self {
+        Self::new("Wall", "Wall", pos)
+    }
+
+    pub fn gate(pos: Vec2, target_id: &str) -> Self {
+        Self::new("Gate", "Gate", pos).target(target_id)
+    }
+
+    pub fn trigger(pos: Vec2, target_room: &str, spawn_point: &str) -> Self {
+        Self::new("Trigger", "Exit", pos).leads_to(target_room, spawn_point)
+    }
+
+    pub fn pressure_plate(pos: Vec2, target_id: &str) -> Self {
+        Self::new("PressurePlate", "Pressure Plate", pos).target(target_id)
+    }
+
+    // Walkable region that changes the player's speed and footsteps; sits under everything
+    pub fn floor(pos: Vec2, surface: FloorSurface) -> Self {
+        let mut builder = Self::new("Floor", "Floor", pos).z(0.5);
+        builder.entry.surface = Some(surface);
+        builder
+    }
+
+    pub fn hazard(pos: Vec2, kind: HazardKind, magnitude: f32) -> Self {
+        let mut builder = Self::new("Hazard", "Hazard", pos);
+        builder.entry.hazard = Some(kind);
+        builder.entry.magnitude = Some(magnitude);
+        builder
+    }
+
+    pub fn door(pos: Vec2) -> Self {
+        Self::new("Door", "Door", pos)
+    }
+
+    // Named after the item it gives
+    pub fn pickup(item_id: &str, pos: Vec2) -> Self {
+        let mut builder = Self::new("Item", &item_by_id(item_id).name, pos);
+        builder.entry.item_id = Some(item_id.to_string());
+        builder
+    }
+
+    pub fn light(pos: Vec2) -> Self {
+        Self::new("Light", "Lamp", pos)
+    }
+
+    pub fn npc(pos: Vec2, name: &str, dialogue: &[&str]) -> Self {
+        let mut builder = Self::new("NPC", name, pos);
+        builder.entry.dialogue = dialogue.iter().map(|l| l.to_string()).collect();
+        builder
+    }
+
+    pub fn generator(pos: Vec2, fuel: f32) -> Self {
+        let mut builder = Self::new("Generator", "Generator", pos);
+        builder.entry.fuel = Some(fuel);
+        builder
+    }
+
+    pub fn chest(pos: Vec2, container_id: &str) -> Self {
+        let mut builder = Self::new("Chest", "Chest", pos);
+        builder.entry.container_id = Some(container_id.to_string());
+        builder
+    }
+
+    pub fn lever(pos: Vec2, target_id: &str) -> Self {
+        Self::new("Lever", "Lever", pos).target(target_id)
+    }
+
+    pub fn pushable(pos: Vec2) -> Self {
+        Self::new("Crate", "Crate", pos)
+    }
+
+    pub fn locker(pos: Vec2) -> Self {
+        Self::new("Locker", "Locker", pos)
+    }
+
+    pub fn shop(pos: Vec2, stock: &[(&str, u32)]) -> Self {
+        let mut builder = Self::new("Shop", "Shop", pos);
+        builder.entry.stock = stock.iter().map(|&(id, price)| (id.to_string(), price)).collect();
+        builder
+    }
+
+    pub fn telephone(pos: Vec2) -> Self {
+        Self::new("Telephone", "Telephone", pos)
+    }
+
+    pub fn save_point(pos: Vec2) -> Self {
+        Self::new("SavePoint", "Save Point", pos)
+    }
+
+    // Named by where the player arrives from, e.g. "from_hallway"
+    pub fn spawn_point(pos: Vec2, name: &str) -> Self {
+        Self::new("SpawnPoint", name, pos).size(16.0, 20.0)
+    }
+
+    pub fn critter(pos: Vec2, name: &str) -> Self {
+        Self::new("Critter", name, pos)
+    }
+
+    pub fn prop(pos: Vec2, name: &str) -> Self {
+        Self::new("Prop", name, pos)
+    }
+
+    // Loops `sound` where it's placed, for as long as the room is loaded
+    pub fn ambience(pos: Vec2, name: &str, sound: SfxId) -> Self {
+        let mut builder = Self::new("Ambience", name, pos);
+        builder.entry.sound = Some(sound);
+        builder
+    }
+
+    // Starts at the first waypoint; its switch sends it between the first and last
+    pub fn elevator(waypoints: &[Vec2], target_id: &str) -> Self {
+        let mut builder = Self::new("Elevator", "Elevator", waypoints[0]).target(target_id);
+        builder.entry.waypoints = waypoints.iter().map(|p| (p.x, p.y)).collect();
+        builder
+    }
+
+    pub fn room(mut self, room: &str) -> Self {
+        self.room = room.to_string();
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.entry.name = name.to_string();
+        self
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.entry.id = Some(id.to_string());
+        self
+    }
+
+    pub fn size(mut self, w: f32, h: f32) -> Self {
+        self.entry.size = (w, h);
+        self
+    }
+
+    pub fn color(mut self, r: f32, g: f32, b: f32) -> Self {
+        self.entry.color = (r, g, b);
+        self
+    }
+
+    pub fn z(mut self, z: f32) -> Self {
+        self.entry.z = Some(z);
+        self
+    }
+
+    pub fn solid(mut self, solid: bool) -> Self {
+        self.entry.solid = Some(solid);
+        self
+    }
+
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.entry.radius = Some(radius);
+        self
+    }
+
+    pub fn cooldown(mut self, seconds: f32) -> Self {
+        self.entry.cooldown = Some(seconds);
+        self
+    }
+
+    pub fn description(mut self, line: &str) -> Self {
+        self.entry.description = Some(line.to_string());
+        self
+    }
+
+    pub fn actions(mut self, actions: Vec<InteractionAction>) -> Self {
+        self.entry.actions = Some(actions);
+        self
+    }
+
+    pub fn requires(mut self, action: InteractionAction, condition: ActionCondition) -> Self {
+        self.entry.requirements.push((action, condition));
+        self
+    }
+
+    pub fn response(mut self, response: CustomResponse) -> Self {
+        self.entry.responses.push(response);
+        self
+    }
+
+    pub fn examine(mut self, examine: ConditionalExamine) -> Self {
+        self.entry.examine = Some(examine);
+        self
+    }
+
+    // Locks it; each call adds another key that opens it on its own
+    pub fn key(mut self, item_id: &str) -> Self {
+        let lock = self.entry.lock.get_or_insert(Lockable {
+            requires: KeyRequirement::AnyOf(Vec::new()),
+            locked: true,
+            consumes_key: false,
+        });
+        if let KeyRequirement::AnyOf(ids) = &mut lock.requires {
+            ids.push(item_id.to_string());
+        }
+        self
+    }
+
+    pub fn consumes_key(mut self) -> Self {
+        if let Some(lock) = &mut self.entry.lock {
+            lock.consumes_key = true;
+        }
+        self
+    }
+
+    pub fn target(mut self, target_id: &str) -> Self {
+        self.entry.target_id = Some(target_id.to_string());
+        self
+    }
+
+    pub fn leads_to(mut self, target_room: &str, spawn_point: &str) -> Self {
+        self.entry.target_room = Some(target_room.to_string());
+        self.entry.spawn_point = Some(spawn_point.to_string());
+        self
+    }
+
+    pub fn slide(mut self) -> Self {
+        self.entry.slide = true;
+        self
+    }
+
+    pub fn auto_close_after(mut self, seconds: f32) -> Self {
+        self.entry.auto_close_after = Some(seconds);
+        self
+    }
+
+    pub fn peek(mut self, lines: &[&str]) -> Self {
+        self.entry.peek = lines.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    pub fn light_colors(mut self, on: (f32, f32, f32), off: (f32, f32, f32)) -> Self {
+        self.entry.on_color = Some(on);
+        self.entry.off_color = Some(off);
+        self
+    }
+
+    pub fn glow_radius(mut self, radius: f32) -> Self {
+        self.entry.light_radius = Some(radius);
+        self
+    }
+
+    pub fn powered_by(mut self, generator: Entity) -> Self {
+        self.powered_by = Some(generator);
+        self
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.entry.capacity = Some(capacity);
+        self
+    }
+
+    pub fn items(mut self, item_ids: &[&str]) -> Self {
+        self.entry.items = item_ids.iter().map(|id| id.to_string()).collect();
+        self
+    }
+
+    pub fn destroys_items(mut self) -> Self {
+        self.entry.destroys_items = true;
+        self
+    }
+
+    pub fn respawn_after(mut self, seconds: f32) -> Self {
+        self.entry.respawn_after = Some(seconds);
+        self
+    }
+
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.entry.speed = Some(speed);
+        self
+    }
+
+    pub fn wander_radius(mut self, radius: f32) -> Self {
+        self.entry.wander_radius = Some(radius);
+        self
+    }
+
+    pub fn surface(mut self, surface: FloorSurface) -> Self {
+        self.entry.surface = Some(surface);
+        self
+    }
+
+    pub fn speed_mult(mut self, speed_mult: f32) -> Self {
+        self.entry.speed_mult = Some(speed_mult);
+        self
+    }
+
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.entry.volume = Some(volume);
+        self
+    }
+
+    pub fn hearing_radius(mut self, radius: f32) -> Self {
+        self.entry.hearing_radius = Some(radius);
+        self
+    }
+
+    pub fn voice(mut self, voice: Voice) -> Self {
+        self.entry.voice = Some(voice);
+        self
+    }
+
+    pub fn collider(mut self, half_extents: Vec2, offset: Vec2) -> Self {
+        self.entry.collider = Some(((half_extents.x, half_extents.y), (offset.x, offset.y)));
+        self
+    }
+
+    pub fn spawn(self, commands: &mut Commands) -> Entity {
+        spawn_entry(commands, &self.room, &self.entry, self.powered_by)
+            .expect("ObjectBuilder only builds known kinds")
+    }
+}
+
+// Low and unsteady, as in the room files
+const FIGURE_VOICE: Voice = Voice { sfx: SfxId::VoiceBlip, base_pitch: 0.7, pitch_jitter: 0.15 };
+
+fn spawn_start_room(commands: &mut Commands) {
+    commands.insert_resource(START_ROOM_BOUNDS);
+    commands.insert_resource(start_room_audio());
+
+    // Where the game begins, and where the hallway door lets out
+    ObjectBuilder::spawn_point(Vec2::ZERO, PLAYER_START).spawn(commands);
+    ObjectBuilder::spawn_point(Vec2::new(0.0, 160.0), "from_hallway").spawn(commands);
+
+    // Spawn a pickupable key
+    ObjectBuilder::pickup("rusty_key", Vec2::new(-100.0, 0.0))
+        .size(12.0, 12.0)
+        .color(0.8, 0.7, 0.3) // Gold color
+        .radius(35.0) // Small object, normal radius
+        .spawn(commands);
+
+    // Spawn a generator - LARGER OBJECT
+    let generator = ObjectBuilder::generator(Vec2::new(0.0, -120.0), 2.5)
+        .size(48.0, 48.0) // Large size
+        .color(0.4, 0.4, 0.5) // Blue-gray
+        .radius(60.0) // Large object needs bigger radius
+        .examine(ConditionalExamine {
+            cases: vec![(
+                Condition::GeneratorRunning("start/generator".to_string()),
+                vec![
+                    "* The generator shudders and roars.".to_string(),
+                    "* Warm, oily air pours off it.".to_string(),
+                ],
+            )],
+            default: vec![
+                "* A squat old generator.".to_string(),
+                "* It's cold to the touch.".to_string(),
+            ],
+        })
+        .spawn(commands);
+
+    // Oil leaking from the generator
+    ObjectBuilder::hazard(Vec2::new(52.0, -150.0), HazardKind::Slippery, 0.5)
+        .name("Oil")
+        .size(44.0, 26.0)
+        .color(0.08, 0.07, 0.1) // Black sheen
+        .z(0.5)
+        .spawn(commands);
+
+    // Rug in front of the fireplace; drags at the player's feet a little
+    ObjectBuilder::floor(Vec2::new(-200.0, 80.0), FloorSurface::Carpet)
+        .name("Hearth Rug")
+        .speed_mult(0.85)
+        .size(80.0, 40.0)
+        .color(0.35, 0.1, 0.12) // Faded red
+        .spawn(commands);
+
+    // Spawn a light/lamp, wired to the generator
+    ObjectBuilder::light(Vec2::new(100.0, 50.0))
+        .name("Old Lamp")
+        .size(20.0, 28.0)
+        .radius(40.0) // Medium object
+        .cooldown(0.5) // Stop Z-mashing from flickering it
+        .description("* Brass, dented, and older than the house.")
+        .powered_by(generator)
+        .spawn(commands);
+
+    // A torch by the door; it needs no power, just a hand to light it
+    ObjectBuilder::light(Vec2::new(40.0, 222.0))
+        .name("Wall Torch")
+        .size(8.0, 14.0)
+        .radius(35.0)
+        .light_colors(
+            (1.0, 0.55, 0.2),  // Warm orange flame
+            (0.25, 0.2, 0.18), // Cold soot
+        )
+        .glow_radius(60.0)
+        .spawn(commands);
+
+    // Spawn a fuel can to keep the generator going, behind the barred gate
+    ObjectBuilder::pickup("fuel_can", Vec2::new(270.0, 190.0))
+        .size(12.0, 14.0)
+        .color(0.7, 0.15, 0.1) // Red jerry can
+        .radius(35.0)
+        .spawn(commands);
+
+    // Alcove wall and the barred gate sealing it off
+    ObjectBuilder::wall(Vec2::new(220.0, 185.0))
+        .name("Alcove Wall")
+        .size(10.0, 110.0)
+        .color(0.2, 0.2, 0.25) // Grimy concrete
+        .spawn(commands);
+    ObjectBuilder::gate(Vec2::new(270.0, 130.0), "alcove_gate")
+        .name("Barred Gate")
+        .size(100.0, 10.0)
+        .color(0.35, 0.35, 0.38) // Iron bars
+        .spawn(commands);
+
+    // Spawn the lever that opens the gate
+    ObjectBuilder::lever(Vec2::new(-280.0, 0.0), "alcove_gate")
+        .size(8.0, 18.0)
+        .radius(35.0)
+        .spawn(commands);
+
+    // Spawn a locker to hide in
+    ObjectBuilder::locker(Vec2::new(-260.0, 180.0))
+        .size(24.0, 36.0)
+        .color(0.3, 0.38, 0.35) // Chipped green paint
+        .radius(40.0)
+        .spawn(commands);
+
+    // Spawn a save point
+    ObjectBuilder::save_point(Vec2::new(-200.0, -150.0))
+        .size(12.0, 12.0)
+        .color(1.0, 0.95, 0.4) // Glowing yellow
+        .radius(35.0)
+        .spawn(commands);
+
+    // Spawn an NPC
+    ObjectBuilder::npc(Vec2::new(60.0, 0.0), "Strange Figure", &[
+        "* ...",
+        "* The figure stares at you silently.",
+    ])
+        .voice(FIGURE_VOICE)
+        .size(16.0, 20.0)
+        .color(0.6, 0.3, 0.8) // Purple
+        .radius(40.0) // Human-sized
+        .actions(vec![
+            InteractionAction::Talk,
+            InteractionAction::Examine,
+            InteractionAction::Custom("Flirt".to_string()),
+            InteractionAction::Custom("Wave".to_string()),
+        ])
+        .response(CustomResponse::new("Flirt", vec![
+            vec!["* You wink at the figure.", "* It tilts its head. Was that a blush?"],
+            vec!["* You try again, with feeling.", "* The figure looks away. Politely."],
+            vec!["* You compliment its silhouette.", "* ...", "* It seems flattered. You think."],
+        ]).with_flag("flirted_with_figure"))
+        .response(CustomResponse::new("Wave", vec![
+            vec!["* You wave at the figure.", "* It slowly raises a hand back."],
+            vec!["* You wave again.", "* It keeps its hand raised. It never lowered it."],
+        ]))
+        // It remembers earlier playthroughs
+        .examine(ConditionalExamine {
+            cases: vec![(
+                Condition::NgPlusCycle(1),
+                vec![
+                    "* The figure is already looking at you.".to_string(),
+                    "* As if it knew you'd be back.".to_string(),
+                ],
+            )],
+            default: vec![
+                "* A tall figure, darker than the room around it.".to_string(),
+                "* It hasn't blinked.".to_string(),
+            ],
+        })
+        .spawn(commands);
+
+    // Spawn a chest/container
+    ObjectBuilder::chest(Vec2::new(-50.0, -50.0), "wooden_chest")
+        .name("Wooden Chest")
+        .size(24.0, 20.0)
+        .color(0.5, 0.3, 0.1) // Brown
+        .radius(40.0) // Medium object
+        .capacity(6)
+        .items(&["firewood", "master_key"])
+        .key("small_key")
+        .consumes_key()
+        .spawn(commands);
+
+    // Spawn a trash can - same code path as the chest, but deposits are destroyed
+    ObjectBuilder::chest(Vec2::new(-120.0, -190.0), "trash_can")
+        .name("Trash Can")
+        .size(16.0, 20.0)
+        .color(0.35, 0.38, 0.35) // Dented tin
+        .radius(35.0)
+        .destroys_items()
+        .spawn(commands);
+
+    // Spawn the metal door out to the hallway
+    ObjectBuilder::door(Vec2::new(0.0, 200.0))
+        .name("Metal Door")
+        .size(32.0, 40.0)
+        .color(0.45, 0.47, 0.5) // Steel gray
+        .radius(45.0)
+        .auto_close_after(6.0)
+        .key("rusty_key")
+        .key("master_key")
+        .peek(&[
+            "* You crouch and peer under the door.",
+            "* You see a long hallway. Something hums at the far end.",
+        ])
+        .leads_to(HALLWAY, "from_start")
+        .slide()
+        .spawn(commands);
+
+    // Spawn a fireplace - "Add Wood" only shows up while holding firewood
+    ObjectBuilder::prop(Vec2::new(-200.0, 120.0), "Fireplace")
+        .size(40.0, 28.0)
+        .color(0.35, 0.2, 0.15) // Soot brown
+        .radius(45.0)
+        .solid(true)
+        .collider(Vec2::new(20.0, 8.0), Vec2::new(0.0, -6.0)) // Just the hearth; walk past the mantel
+        .actions(vec![
+            InteractionAction::Examine,
+            InteractionAction::Custom("Add Wood".to_string()),
+        ])
+        .requires(
+            InteractionAction::Custom("Add Wood".to_string()),
+            ActionCondition::HasItem { id: "firewood".to_string(), count: 1 },
+        )
+        .spawn(commands);
+
+    // Spawn a mirror that notices what you're carrying
+    ObjectBuilder::prop(Vec2::new(-60.0, 215.0), "Cracked Mirror")
+        .size(16.0, 24.0)
+        .color(0.7, 0.78, 0.82) // Silvered glass
+        .radius(35.0)
+        .solid(true)
+        .examine(ConditionalExamine {
+            cases: vec![
+                (
+                    Condition::HasItem("rusty_key".to_string()),
+                    vec![
+                        "* You see yourself in the cracked mirror.".to_string(),
+                        "* Your reflection pats its pocket. The key is still there.".to_string(),
+                    ],
+                ),
+                (
+                    Condition::LightOn("start/old_lamp".to_string()),
+                    vec![
+                        "* The lamplight catches the crack.".to_string(),
+                        "* For a moment there are two of you.".to_string(),
+                    ],
+                ),
+            ],
+            default: vec![
+                "* You see yourself in the cracked mirror.".to_string(),
+                "* Your reflection's hands are empty. So are yours.".to_string(),
+            ],
+        })
+        .spawn(commands);
+
+    // Spawn a wall phone
+    ObjectBuilder::telephone(Vec2::new(-130.0, 215.0))
+        .name("Wall Phone")
+        .size(12.0, 18.0)
+        .color(0.15, 0.15, 0.15) // Black bakelite
+        .radius(35.0)
+        .spawn(commands);
+
+    // A stray that's made itself at home by the generator
+    ObjectBuilder::critter(Vec2::new(140.0, -180.0), "Dog")
+        .size(14.0, 10.0)
+        .color(0.55, 0.42, 0.3) // Scruffy tan
+        .radius(35.0)
+        .wander_radius(50.0)
+        .examine(ConditionalExamine {
+            cases: Vec::new(),
+            default: vec![
+                "* A scruffy little dog.".to_string(),
+                "* It seems to think it lives here.".to_string(),
+            ],
+        })
+        .response(CustomResponse::new("Pet", vec![
+            vec!["* You pat the dog on the head.", "* Its tail gives one cautious wag."],
+            vec!["* You scratch behind its ears.", "* It leans into your hand."],
+            vec!["* You rub its belly.", "* It flops over and refuses to get up. It loves you now."],
+        ]))
+        .spawn(commands);
+
+    // A couple of firewood bundles to feed it
+    for (i, pos) in [Vec2::new(180.0, -60.0), Vec2::new(205.0, -95.0)].into_iter().enumerate() {
+        ObjectBuilder::pickup("firewood", pos)
+            .id(&format!("start/firewood_{}", i + 1))
+            .size(14.0, 8.0)
+            .color(0.45, 0.3, 0.15) // Bark brown
+            .radius(30.0)
+            .spawn(commands);
+    }
+}
+
+fn spawn_hallway(commands: &mut Commands) {
+    commands.insert_resource(HALLWAY_BOUNDS);
+    commands.insert_resource(hallway_audio());
+
+    // Long walls on either side of the corridor
+    for (id, x) in [("hallway/west_wall", -60.0), ("hallway/east_wall", 60.0)] {
+        ObjectBuilder::wall(Vec2::new(x, 0.0))
+            .room(HALLWAY)
+            .name("Hallway Wall")
+            .id(id)
+            .size(16.0, 440.0)
+            .color(0.2, 0.2, 0.25) // Grimy concrete
+            .spawn(commands);
+    }
+
+    // Doorway back to the starting room, and where the player arrives through it
+    ObjectBuilder::spawn_point(Vec2::new(0.0, -150.0), "from_start")
+        .room(HALLWAY)
+        .spawn(commands);
+    ObjectBuilder::trigger(Vec2::new(0.0, -215.0), START_ROOM, "from_hallway")
+        .room(HALLWAY)
+        .name("Hallway Exit")
+        .size(32.0, 16.0)
+        .color(0.12, 0.12, 0.12) // Dark threshold
+        .z(0.5)
+        .slide()
+        .spawn(commands);
+
+    // The figure again, waiting in front of the gate (see assets/cutscenes/hallway_figure.ron)
+    ObjectBuilder::npc(Vec2::new(0.0, 70.0), "Strange Figure", &[
+        "* The figure regards you.",
+        "* It doesn't blink. You're not sure it can.",
+    ])
+        .voice(FIGURE_VOICE)
+        .room(HALLWAY)
+        .size(16.0, 20.0)
+        .color(0.6, 0.3, 0.8) // Purple
+        .radius(40.0)
+        .spawn(commands);
+
+    // Gate across the corridor, held open by the pressure plate below it
+    ObjectBuilder::gate(Vec2::new(0.0, 100.0), "hallway_gate")
+        .room(HALLWAY)
+        .name("Hallway Gate")
+        .size(104.0, 10.0)
+        .color(0.35, 0.35, 0.38) // Iron bars
+        .spawn(commands);
+    ObjectBuilder::pressure_plate(Vec2::new(-28.0, 40.0), "hallway_gate")
+        .room(HALLWAY)
+        .size(24.0, 24.0)
+        .z(0.5)
+        .spawn(commands);
+
+    // A crate to leave on the plate
+    ObjectBuilder::pushable(Vec2::new(20.0, -60.0))
+        .room(HALLWAY)
+        .size(20.0, 20.0)
+        .color(0.55, 0.4, 0.2) // Light wood
+        .radius(35.0)
+        .spawn(commands);
+
+    // A leaky pipe down the west wall, dripping the whole time you're here
+    ObjectBuilder::ambience(Vec2::new(-49.0, -20.0), "Dripping Pipe", SfxId::Drip)
+        .room(HALLWAY)
+        .size(5.0, 36.0)
+        .color(0.3, 0.33, 0.3) // Corroded iron
+        .volume(0.7)
+        .hearing_radius(180.0)
+        .spawn(commands);
+
+    // Something to look at on the way
+    ObjectBuilder::prop(Vec2::new(44.0, 40.0), "Dusty Painting")
+        .room(HALLWAY)
+        .size(12.0, 24.0)
+        .color(0.4, 0.25, 0.3) // Faded frame
+        .radius(35.0)
+        .spawn(commands);
+
+    // Vending machine selling fuel
+    ObjectBuilder::shop(Vec2::new(-38.0, -140.0), &[("fuel_can", 10)])
+        .room(HALLWAY)
+        .name("Vending Machine")
+        .size(24.0, 32.0)
+        .color(0.2, 0.35, 0.6) // Faded blue casing
+        .radius(40.0)
+        .spawn(commands);
+
+    // A note past the gate with a phone number on it
+    ObjectBuilder::prop(Vec2::new(-30.0, 170.0), "Crumpled Note")
+        .room(HALLWAY)
+        .size(10.0, 8.0)
+        .color(0.85, 0.82, 0.7) // Yellowed paper
+        .radius(30.0)
+        .actions(vec![
+            InteractionAction::Examine,
+            InteractionAction::Custom("Read".to_string()),
+        ])
+        .response(CustomResponse::new("Read", vec![
+            vec!["* You smooth out the note.", "* \"If the lights fail, call 555-0199.\""],
+            vec!["* \"555-0199.\" The ink is smudged, but legible."],
+        ]).with_flag("read_crumpled_note"))
+        .spawn(commands);
+
+    // Supply closet at the end of the corridor; its key is long gone, but the master key fits
+    ObjectBuilder::door(Vec2::new(0.0, 214.0))
+        .room(HALLWAY)
+        .name("Supply Closet")
+        .size(32.0, 12.0)
+        .color(0.42, 0.36, 0.3) // Scuffed wood
+        .radius(40.0)
+        .key("closet_key")
+        .key("master_key")
+        .spawn(commands);
+
+    // The key to the wooden chest, past the gate
+    ObjectBuilder::pickup("small_key", Vec2::new(-36.0, 205.0))
+        .room(HALLWAY)
+        .size(8.0, 8.0)
+        .color(0.75, 0.6, 0.25) // Brass
+        .radius(30.0)
+        .spawn(commands);
+
+    // Junk that turns up again if you wait
+    ObjectBuilder::pickup("scrap", Vec2::new(32.0, -180.0))
+        .room(HALLWAY)
+        .size(14.0, 6.0)
+        .color(0.5, 0.5, 0.52) // Dull steel
+        .radius(30.0)
+        .respawn_after(60.0)
+        .spawn(commands);
+    ObjectBuilder::pickup("battery", Vec2::new(28.0, 190.0))
+        .room(HALLWAY)
+        .size(6.0, 10.0)
+        .color(0.2, 0.6, 0.3) // Green label
+        .radius(30.0)
+        .respawn_after(120.0)
+        .spawn(commands);
+
+    // Two-stop lift along the west wall, called with the button beside it
+    ObjectBuilder::elevator(&[Vec2::new(-36.0, -100.0), Vec2::new(-36.0, -10.0)], "hallway_elevator")
+        .room(HALLWAY)
+        .size(24.0, 24.0)
+        .color(0.3, 0.3, 0.34) // Scuffed steel plate
+        .z(0.5)
+        .speed(50.0)
+        .surface(FloorSurface::Metal)
+        .spawn(commands);
+    ObjectBuilder::lever(Vec2::new(-49.0, -55.0), "hallway_elevator")
+        .room(HALLWAY)
+        .name("Call Button")
+        .size(6.0, 10.0)
+        .radius(50.0)
+        .actions(vec![
+            InteractionAction::Examine,
+            InteractionAction::Custom("Press".to_string()),
+        ])
+        .spawn(commands);
+}
+
+// Generators hum while they run, louder the closer you are
+const GENERATOR_HUM_VOLUME: f32 = 0.6;
+const GENERATOR_HUM_RADIUS: f32 = 240.0;
+
+// How far an Ambience entry carries unless it says
+const AMBIENCE_RADIUS: f32 = 200.0;
+
+fn hum_while_running(mut generators: Query<(&Generator, &mut AmbientLoop), Changed<Generator>>) {
+    for (generator, mut hum) in generators.iter_mut() {
+        hum.active = generator.is_running;
+    }
+}
+
+// Only rooms with a generator in them say; elsewhere the flag keeps what it was when the player left
+fn flag_generator_running(
+    changed: Query<(), Changed<Generator>>,
+    generators: Query<&Generator>,
+    mut flags: ResMut<GameFlags>,
+) {
+    let running = generators.iter().any(|generator| generator.is_running);
+    // Burning fuel changes the generator every frame; leave the flags alone unless this is news
+    if !changed.is_empty() && flags.get_bool(GENERATOR_RUNNING) != running {
+        flags.set(GENERATOR_RUNNING, running);
+    }
+}
+
+fn burn_generator_fuel(
+    time: Res<Time>,
+    mut generators: Query<(&Interactable, &mut Generator)>,
+    mut log_writer: EventWriter<LogEvent>,
+) {
+    for (interactable, mut generator) in generators.iter_mut() {
+        if generator.burn(time.delta_secs()) {
+            let l = format!("* {} sputters and dies. It's out of fuel.", capitalized(&interactable.the_name()));
+            info!("{}", l);
+            log_writer.write(LogEvent::new(l));
+        }
+    }
+}
+
+type ElevatorsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Elevator, &'static mut Transform, &'static Sprite, Option<&'static ObjectId>),
+    Without<Player>,
+>;
+
+type RiderQuery<'w, 's> = Query<'w, 's, (&'static mut Transform, &'static Collider), (With<Player>, Without<Elevator>)>;
+
+// What a rider can be pushed into: every solid but the platforms themselves
+type RiderSolidsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Transform, &'static Sprite, Option<&'static Collider>),
+    (With<Solid>, Without<Player>, Without<Elevator>),
+>;
+
+// Slides moving elevators toward their next stop, carrying the player if they're on board.
+// If the rider would be pushed into a wall, the platform waits instead.
+fn move_elevators(
+    time: Res<Time>,
+    mut world: ResMut<WorldState>,
+    mut elevators: ElevatorsQuery,
+    mut player_query: RiderQuery,
+    solids: RiderSolidsQuery,
+) {
+    let mut player = player_query.single_mut().ok();
+    for (mut elevator, mut tf, sprite, object_id) in elevators.iter_mut() {
+        if !elevator.moving {
+            continue;
+        }
+        let pos = tf.translation.truncate();
+        let target = elevator.waypoints[elevator.next()];
+        let step = (target - pos).clamp_length_max(elevator.speed * time.delta_secs());
+
+        let half = sprite.custom_size.unwrap_or(Vec2::splat(16.0)) / 2.0;
+        if let Some((player_tf, player_collider)) = player.as_mut() {
+            let player_pos = player_tf.translation.truncate();
+            let riding = (player_collider.center(player_pos) - pos).abs().cmplt(half).all();
+            if riding {
+                let carried = player_pos + step;
+                let blocked = solids.iter().any(|(s_tf, s_sprite, s_collider)| {
+                    let solid = Collider::of(s_sprite, s_collider);
+                    player_collider.overlaps(carried, &solid, s_tf.translation.truncate())
+                });
+                if blocked {
+                    continue;
+                }
+                player_tf.translation.x = carried.x;
+                player_tf.translation.y = carried.y;
+            }
+        }
+
+        tf.translation.x += step.x;
+        tf.translation.y += step.y;
+        if tf.translation.truncate().distance(target) < 0.01 {
+            elevator.current = elevator.next();
+            elevator.moving = false;
+            if let Some(id) = object_id {
+                world.object_mut(id).elevator_stop = Some(elevator.current);
+            }
+        }
+    }
+}
+
+// Brings taken pickups back once their timer runs out and the player isn't looking
+fn respawn_pickups(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut pending: ResMut<PendingRespawns>,
+    mut world: ResMut<WorldState>,
+    current_room: Res<CurrentRoom>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let player_pos = player_query.single().ok().map(|tf| tf.translation.truncate());
+    let dt = time.delta_secs();
+    pending.entries.retain_mut(|respawn| {
+        respawn.timer -= dt;
+        if respawn.timer > 0.0 {
+            return true;
+        }
+        let id = respawn.def.object_id(&respawn.room);
+        if respawn.room != current_room.0 {
+            // It'll be there the next time the room loads
+            world.object_mut(&id).taken = false;
+            return false;
+        }
+        if player_pos.is_some_and(|p| p.distance(respawn.position) < RESPAWN_MIN_DISTANCE) {
+            return true;
+        }
+        world.object_mut(&id).taken = false;
+        let mut def = respawn.def.clone();
+        def.position = (respawn.position.x, respawn.position.y);
+        spawn_room_entry(&mut commands, &respawn.room, &def, &HashMap::new());
+        info!("{} respawned in {}", def.name, respawn.room);
+        false
+    });
+}
+
+type HazardsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Hazard, &'static Name, &'static Transform, &'static Sprite, Option<&'static Collider>),
+>;
+
+// What a hazard does to the player: hurts them, and says so
+#[derive(SystemParam)]
+struct HazardEffects<'w> {
+    damage_events: EventWriter<'w, DamageEvent>,
+    log_writer: EventWriter<'w, LogEvent>,
+}
+
+// Warns about hazards the player steps into and shocks them while they stand in live ones
+// (slippery ones slow them through their FloorModifier). `inside` maps each hazard the
+// player is in to the seconds left before its next damage tick.
+fn check_hazards(
+    time: Res<Time>,
+    mut inside: Local<HashMap<Entity, f32>>,
+    ui_state: Res<crate::ui::UiState>,
+    player_query: Query<(&Transform, &Collider), With<Player>>,
+    hazards: HazardsQuery,
+    generators: Query<&Generator>,
+    effects: HazardEffects,
+) {
+    let HazardEffects { mut damage_events, mut log_writer } = effects;
+    let Ok((player_tf, player_collider)) = player_query.single() else { return };
+    let player_pos = player_tf.translation.truncate();
+    let paused = ui_state.menu_open || ui_state.dialog_open;
+
+    for (entity, hazard, name, tf, sprite, collider) in hazards.iter() {
+        if !player_collider.overlaps(player_pos, &Collider::of(sprite, collider), tf.translation.truncate()) {
+            inside.remove(&entity);
+            continue;
+        }
+        let live = hazard
+            .powered_by
+            .is_none_or(|g| generators.get(g).is_ok_and(|g| g.is_running));
+
+        if let Entry::Vacant(slot) = inside.entry(entity) {
+            slot.insert(0.0);
+            let l = match hazard.kind {
+                HazardKind::Slippery => format!("* The floor is slick with {}.", name.as_str().to_lowercase()),
+                HazardKind::Electric if live => "* Sparks snap at your feet!".to_string(),
+                HazardKind::Electric => "* Loose wires. They're dead... for now.".to_string(),
+            };
+            info!("{}", l);
+            log_writer.write(LogEvent::new(l));
+        }
+
+        match hazard.kind {
+            HazardKind::Electric if live && !paused => {
+                let Some(next_tick) = inside.get_mut(&entity) else { continue };
+                *next_tick -= time.delta_secs();
+                if *next_tick <= 0.0 {
+                    *next_tick = HAZARD_DAMAGE_INTERVAL;
+                    damage_events.write(DamageEvent {
+                        amount: (hazard.magnitude * HAZARD_DAMAGE_INTERVAL).round() as i32,
+                        source: Some(entity),
+                        knockback: ELECTRIC_KNOCKBACK,
+                    });
+                }
+            }
+            HazardKind::Electric | HazardKind::Slippery => {}
+        }
+    }
+    // Forget hazards that were despawned with their room
+    inside.retain(|entity, _| hazards.contains(*entity));
+}
+
+// Adds a phone number once the flag that reveals it has been set
+fn learn_phone_numbers(flags: Res<GameFlags>, mut known: ResMut<KnownNumbers>) {
+    if !flags.is_changed() {
+        return;
+    }
+    for entry in PHONE_BOOK {
+        let Some(flag) = entry.revealed_by else { continue };
+        if flags.is_set(flag) && !known.numbers.iter().any(|n| n == entry.number) {
+            info!("Learned phone number {} ({})", entry.number, entry.contact);
+            known.numbers.push(entry.number.to_string());
+        }
+    }
+}
+
+// Fuel drains every frame, so generators are recorded here rather than per interaction
+// (as are lights, which go dark on their own when their generator stops)
+fn record_power_state(
+    mut world: ResMut<WorldState>,
+    generators: Query<(&ObjectId, &Generator), Changed<Generator>>,
+    lights: Query<(&ObjectId, &Light), Changed<Light>>,
+) {
+    for (id, generator) in generators.iter() {
+        let state = world.object_mut(id);
+        state.generator_running = Some(generator.is_running);
+        state.fuel_level = Some(generator.fuel_level);
+    }
+    for (id, light) in lights.iter() {
+        world.object_mut(id).light_on = Some(light.is_on);
+    }
+}
+
+// Objects just spawned, with every part WorldState can record
+type RestoredObjectsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static ObjectId,
+        Option<&'static mut Door>,
+        Option<&'static mut Light>,
+        Option<&'static mut Switch>,
+        Option<&'static mut Generator>,
+        Option<&'static mut Shop>,
+        Option<&'static mut Lockable>,
+        Option<(&'static mut Elevator, &'static mut Transform)>,
+    ),
+    Added<ObjectId>,
+>;
+
+// Applies recorded WorldState to objects as their room spawns. Taken ones go at once; nothing
+// has had a chance to look at them yet.
+fn restore_object_state(
+    mut commands: Commands,
+    world: Res<WorldState>,
+    mut objects: RestoredObjectsQuery,
+    mut switch_events: EventWriter<SwitchToggledEvent>,
+) {
+    for (entity, id, door, light, switch, generator, shop, lockable, elevator) in objects.iter_mut() {
+        let Some(state) = world.objects.get(&id.0) else { continue };
+        if state.taken {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if let (Some(mut door), Some(true)) = (door, state.door_open) {
+            restore_open_door(&mut commands, entity, &mut door);
+        }
+        if let (Some(mut light), Some(is_on)) = (light, state.light_on) {
+            light.is_on = is_on;
+        }
+        if let (Some(mut switch), Some(is_on)) = (switch, state.switch_on) {
+            switch.is_on = is_on;
+            switch_events.write(SwitchToggledEvent { target_id: switch.target_id.clone(), is_on });
+        }
+        if let Some(mut generator) = generator {
+            if let Some(is_running) = state.generator_running {
+                generator.is_running = is_running;
+            }
+            if let Some(fuel_level) = state.fuel_level {
+                generator.fuel_level = fuel_level;
+            }
+        }
+        if let (Some(mut shop), Some(stock)) = (shop, &state.shop_stock) {
+            shop.stock = stock.clone();
+        }
+        if let (Some(mut lockable), Some(locked)) = (lockable, state.locked) {
+            lockable.locked = locked;
+        }
+        if let (Some((mut elevator, mut tf)), Some(stop)) = (elevator, state.elevator_stop)
+            && let Some(pos) = elevator.waypoints.get(stop).copied()
+        {
+            elevator.current = stop;
+            tf.translation.x = pos.x;
+            tf.translation.y = pos.y;
+        }
+    }
+}
+
+// Cuts lights whose generator isn't running and keeps sprites in sync with is_on
+fn update_light_power(
+    generators: Query<&Generator>,
+    mut lights: Query<&mut Light>,
+) {
+    for mut light in lights.iter_mut() {
+        let has_power = light
+            .powered_by
+            .is_none_or(|entity| generators.get(entity).is_ok_and(|g| g.is_running));
+        if light.has_power != has_power {
+            light.has_power = has_power;
+        }
+        if !has_power && light.is_on {
+            light.is_on = false;
+        }
+    }
+}
+
+fn setup_lighting(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    // Bake a white disc that fades out toward its edge
+    const SIZE: u32 = 64;
+    let center = (SIZE as f32 - 1.0) / 2.0;
+    let mut data = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let d = Vec2::new(x as f32 - center, y as f32 - center).length() / center;
+            let alpha = (1.0 - d).clamp(0.0, 1.0).powi(2);
+            data.extend_from_slice(&[255, 255, 255, (alpha * 255.0) as u8]);
+        }
+    }
+    let image = Image::new(
+        Extent3d { width: SIZE, height: SIZE, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    commands.insert_resource(GlowTexture(images.add(image)));
+
+    // Big enough to cover the view wherever the camera ends up
+    commands.spawn((
+        Sprite::from_color(Color::BLACK.with_alpha(MAX_DARKNESS), Vec2::splat(4000.0)),
+        Transform::from_xyz(0.0, 0.0, DARKNESS_Z),
+        DarknessOverlay,
+        Name::new("Darkness"),
+    ));
+}
+
+// Gives every new light a glow child, hidden until the light turns on
+fn attach_light_glows(
+    mut commands: Commands,
+    glow: Res<GlowTexture>,
+    lights: Query<(Entity, &Light, &Transform), Added<Light>>,
+) {
+    for (entity, light, tf) in lights.iter() {
+        let Some(radius) = light.glow_radius else { continue };
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                Sprite {
+                    image: glow.0.clone(),
+                    color: light.on_color.with_alpha(0.35),
+                    custom_size: Some(Vec2::splat(radius * 2.0)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, 0.0, GLOW_Z - tf.translation.z),
+                if light.is_on { Visibility::Inherited } else { Visibility::Hidden },
+                LightGlow,
+            ));
+        });
+    }
+}
+
+// Hangs a hidden fuel bar over each new generator
+fn attach_fuel_gauges(
+    mut commands: Commands,
+    generators: Query<(Entity, &Generator, &Sprite), Added<Generator>>,
+) {
+    for (entity, generator, sprite) in generators.iter() {
+        let y = sprite.custom_size.unwrap_or(Vec2::splat(16.0)).y / 2.0 + 8.0;
+        let (width, color) = fuel_gauge_fill(generator);
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                Sprite::from_color(FUEL_GAUGE_BACK_COLOR, FUEL_GAUGE_SIZE),
+                Transform::from_xyz(0.0, y, 0.5),
+                Visibility::Hidden,
+                FuelGauge,
+            ));
+            parent.spawn((
+                Sprite::from_color(color, Vec2::new(width, FUEL_GAUGE_SIZE.y)),
+                Transform::from_xyz((width - FUEL_GAUGE_SIZE.x) / 2.0, y, 0.6),
+                Visibility::Hidden,
+                FuelGauge,
+                FuelGaugeFill,
+            ));
+        });
+    }
+}
+
+fn update_fuel_gauges(
+    generators: Query<(&Generator, &Children), Changed<Generator>>,
+    mut fills: Query<(&mut Sprite, &mut Transform), With<FuelGaugeFill>>,
+) {
+    for (generator, children) in generators.iter() {
+        let (width, color) = fuel_gauge_fill(generator);
+        for child in children {
+            if let Ok((mut sprite, mut tf)) = fills.get_mut(*child) {
+                sprite.custom_size = Some(Vec2::new(width, FUEL_GAUGE_SIZE.y));
+                sprite.color = color;
+                tf.translation.x = (width - FUEL_GAUGE_SIZE.x) / 2.0;
+            }
+        }
+    }
+}
+
+// Only the generator the player is standing at shows its bar
+fn show_nearby_fuel_gauges(
+    generators: Query<(Has<NearbyInteractable>, &Children), With<Generator>>,
+    mut gauges: Query<&mut Visibility, With<FuelGauge>>,
+) {
+    for (nearby, children) in generators.iter() {
+        let visibility = if nearby { Visibility::Inherited } else { Visibility::Hidden };
+        for child in children {
+            if let Ok(mut gauge) = gauges.get_mut(*child) {
+                gauge.set_if_neq(visibility);
+            }
+        }
+    }
+}
+
+fn sync_light_visuals(
+    mut lights: Query<(&Light, &mut Sprite, Option<&Children>), Changed<Light>>,
+    mut glows: Query<&mut Visibility, With<LightGlow>>,
+) {
+    for (light, mut sprite, children) in lights.iter_mut() {
+        sprite.color = if light.is_on { light.on_color } else { light.off_color };
+        for child in children.into_iter().flatten() {
+            if let Ok(mut visibility) = glows.get_mut(*child) {
+                *visibility = if light.is_on { Visibility::Inherited } else { Visibility::Hidden };
+            }
+        }
+    }
+}
+
+// The more of the room's lights are on, the less dark it gets
+fn update_darkness(
+    lights: Query<&Light>,
+    changed: Query<(), Changed<Light>>,
+    mut removed: RemovedComponents<Light>,
+    mut overlay_query: Query<&mut Sprite, With<DarknessOverlay>>,
+) {
+    let any_removed = removed.read().count() > 0;
+    if changed.is_empty() && !any_removed {
+        return;
+    }
+    let total = lights.iter().count();
+    let on = lights.iter().filter(|l| l.is_on).count();
+    let lit = if total > 0 { on as f32 / total as f32 } else { 0.0 };
+    if let Ok(mut sprite) = overlay_query.single_mut() {
+        let alpha = MAX_DARKNESS - (MAX_DARKNESS - MIN_DARKNESS) * lit;
+        sprite.color = Color::BLACK.with_alpha(alpha);
+    }
+}
+
+fn animate_doors(
+    time: Res<Time>,
+    mut commands: Commands,
+    frame_events: Res<FrameEvents>,
+    mut anim_events: EventWriter<AnimationEvent>,
+    mut doors: Query<(Entity, &mut DoorAnimation, &mut Transform)>,
+) {
+    for (entity, mut anim, mut transform) in doors.iter_mut() {
+        for frame in anim.advance(time.delta_secs()) {
+            frame_events.entered(&mut anim_events, entity, anim.clip(), frame);
+        }
+        transform.scale.x = if anim.opening { 1.0 - anim.progress } else { anim.progress };
+        if anim.progress >= 1.0 {
+            commands.entity(entity).remove::<DoorAnimation>();
+        }
+    }
+}
+
+type AutoDoorsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static mut Door, &'static Transform, &'static Sprite, Option<&'static ObjectId>),
+    Without<Player>,
+>;
+
+// Swings doors shut once their timer runs out, waiting while the player is in the way
+fn auto_close_doors(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut world: ResMut<WorldState>,
+    player_query: Query<(&Transform, &Collider), With<Player>>,
+    mut doors: AutoDoorsQuery,
+    crates: Query<(&Transform, &Sprite, Option<&Collider>), With<Pushable>>,
+) {
+    let player = player_query.single().ok().map(|(tf, c)| (tf.translation.truncate(), c));
+    for (entity, mut door, door_tf, sprite, object_id) in doors.iter_mut() {
+        let Some(delay) = door.auto_close_after else { continue };
+        if !door.is_open {
+            continue;
+        }
+        door.open_for += time.delta_secs();
+        if door.open_for < delay {
+            continue;
+        }
+        if doorway_blocker(door_tf, sprite, player, crates.iter()).is_some() {
+            continue; // Try again next frame
+        }
+        close_door(&mut commands, entity, &mut door);
+        if let Some(id) = object_id {
+            world.object_mut(id).door_open = Some(false);
+        }
+        info!("Door {:?} closed on its own", entity);
+    }
+}
+
+type PlateCratesQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Transform, &'static Sprite, Option<&'static Collider>),
+    (With<Pushable>, Without<PressurePlate>),
+>;
+
+// Emits switch events on the edges where a plate goes from empty to weighted and back
+fn check_pressure_plates(
+    player_query: Query<(&Transform, &Collider), With<Player>>,
+    crates: PlateCratesQuery,
+    mut plates: Query<(&mut PressurePlate, &Transform, &mut Sprite), Without<Pushable>>,
+    mut switch_events: EventWriter<SwitchToggledEvent>,
+) {
+    let player = player_query.single().ok();
+
+    for (mut plate, plate_tf, mut sprite) in plates.iter_mut() {
+        let plate_pos = plate_tf.translation.truncate();
+        let plate_box = Collider::of(&sprite, None);
+
+        let player_on = !plate.requires_weight
+            && player.is_some_and(|(tf, c)| c.overlaps(tf.translation.truncate(), &plate_box, plate_pos));
+        let crate_on = crates.iter().any(|(tf, crate_sprite, collider)| {
+            Collider::of(crate_sprite, collider).overlaps(tf.translation.truncate(), &plate_box, plate_pos)
+        });
+
+        let pressed = player_on || crate_on;
+        if pressed != plate.is_pressed {
+            plate.is_pressed = pressed;
+            sprite.color = if pressed { PLATE_DOWN_COLOR } else { PLATE_UP_COLOR };
+            switch_events.write(SwitchToggledEvent {
+                target_id: plate.target_id.clone(),
+                is_on: pressed,
+            });
+        }
+    }
+}
+
+// Opens/closes gates and turns remote lights on/off for each toggled switch id
+fn apply_switch_toggles(
+    mut events: EventReader<SwitchToggledEvent>,
+    mut commands: Commands,
+    mut targets: Query<(Entity, &SwitchTarget, Option<&mut Light>, Option<&mut Elevator>)>,
+) {
+    for event in events.read() {
+        for (entity, target, light, elevator) in targets.iter_mut() {
+            if target.0 != event.target_id {
+                continue;
+            }
+            if let Some(mut elevator) = elevator {
+                // Nowhere to go without any stops
+                let Some(last) = elevator.waypoints.len().checked_sub(1) else { continue };
+                let stop = if event.is_on { last } else { 0 };
+                if !elevator.moving && elevator.current != stop {
+                    elevator.moving = true;
+                }
+                continue;
+            }
+            match light {
+                Some(mut light) => light.is_on = event.is_on && light.has_power,
+                None if event.is_on => {
+                    commands.entity(entity).remove::<Solid>().insert(Visibility::Hidden);
+                }
+                None => {
+                    commands.entity(entity).insert((Solid, Visibility::Inherited));
+                }
+            }
+        }
+    }
+}
+
+fn sync_switch_visuals(mut switches: Query<(&Switch, &mut Sprite), Changed<Switch>>) {
+    for (switch, mut sprite) in switches.iter_mut() {
+        sprite.color = if switch.is_on { SWITCH_ON_COLOR } else { SWITCH_OFF_COLOR };
+    }
+}
+
+// Starts a fade when the player steps into an open doorway
+fn check_room_transitions(
+    mut fade: ResMut<RoomFade>,
+    player_query: Query<(&Transform, &Collider), With<Player>>,
+    transitions: Query<(&Transform, &Sprite, &RoomTransition, Option<&Door>), Without<Player>>,
+) {
+    if fade.is_active() {
+        return;
+    }
+    let Ok((player_tf, player_collider)) = player_query.single() else { return };
+    let player_pos = player_tf.translation.truncate();
+
+    for (tf, sprite, transition, door) in transitions.iter() {
+        if door.is_some_and(|d| !d.is_open) {
+            continue;
+        }
+        if player_collider.overlaps(player_pos, &Collider::of(sprite, None), tf.translation.truncate()) {
+            info!("Leaving for {} ({})", transition.target_room, transition.spawn_point);
+            *fade = if transition.slide {
+                RoomFade::Slide { to: transition.clone(), exit: player_pos }
+            } else {
+                RoomFade::Out { to: transition.clone(), elapsed: 0.0 }
+            };
+            return;
+        }
+    }
+}
+
+// Everything a fade sends out as it moves the player between rooms
+#[derive(SystemParam)]
+struct FadeEvents<'w> {
+    loads: EventWriter<'w, LoadRoomEvent>,
+    teleports: EventWriter<'w, TeleportPlayerEvent>,
+    slides: EventWriter<'w, RoomSlideEvent>,
+    respawns: EventWriter<'w, PlayerRespawnedEvent>,
+    log_writer: EventWriter<'w, LogEvent>,
+}
+
+// What a respawn puts back: the last save point, and the pockets and world it rewinds
+#[derive(SystemParam)]
+struct RespawnState<'w> {
+    checkpoint: Option<Res<'w, LastCheckpoint>>,
+    inventory: ResMut<'w, Inventory>,
+    world: ResMut<'w, WorldState>,
+}
+
+// Advances the fade and swaps rooms while the screen is fully black. Real time: a cutscene's
+// freeze or a hit-stop shouldn't hold the screen dark.
+fn run_room_fade(
+    time: Res<Time<Real>>,
+    mut fade: ResMut<RoomFade>,
+    events: FadeEvents,
+    ui_state: Res<crate::ui::UiState>,
+    respawn: RespawnState,
+) {
+    let FadeEvents { mut loads, mut teleports, mut slides, mut respawns, mut log_writer } = events;
+    let RespawnState { checkpoint, mut inventory, mut world } = respawn;
+    let dt = time.delta_secs();
+    match &mut *fade {
+        RoomFade::Idle => {}
+        RoomFade::Out { to, elapsed } => {
+            *elapsed += dt;
+            if *elapsed < ROOM_FADE_SECS {
+                return;
+            }
+            loads.write(LoadRoomEvent::at(&to.target_room, SpawnTarget::Named(to.spawn_point.clone())));
+            *fade = RoomFade::In { elapsed: 0.0 };
+        }
+        RoomFade::Slide { to, exit } => {
+            loads.write(LoadRoomEvent::at(&to.target_room, SpawnTarget::Named(to.spawn_point.clone())));
+            slides.write(RoomSlideEvent { exit: *exit });
+            *fade = RoomFade::Idle;
+        }
+        RoomFade::Respawn { elapsed } => {
+            *elapsed += dt;
+            if *elapsed < ROOM_FADE_SECS {
+                return;
+            }
+            log_writer.write(LogEvent::new("* Stay determined.".to_string()));
+            *fade = RoomFade::Determined { opened: false };
+        }
+        // The line shows up a frame after it's sent; wait for it to open, then to be dismissed
+        RoomFade::Determined { opened } => {
+            if ui_state.dialog_open {
+                *opened = true;
+                return;
+            }
+            if !*opened {
+                return;
+            }
+            // Back to the last save point, or the very start with empty pockets if there isn't
+            // one. Loaded without a spawn point, so coming back isn't counted as a visit, and
+            // placed by teleport, so the camera snaps and the spot is checked for walls.
+            let (room, to) = match checkpoint.as_deref() {
+                Some(checkpoint) => {
+                    restore_checkpoint(checkpoint, &mut inventory, &mut world);
+                    (checkpoint.room.clone(), SpawnTarget::Position(checkpoint.player_position))
+                }
+                None => {
+                    inventory.items.clear();
+                    *world = WorldState::default();
+                    (START_ROOM.to_string(), SpawnTarget::Named(PLAYER_START.to_string()))
+                }
+            };
+            loads.write(LoadRoomEvent { id: room, spawn_point: None });
+            teleports.write(TeleportPlayerEvent { to, fade: true });
+            respawns.write(PlayerRespawnedEvent);
+            *fade = RoomFade::In { elapsed: 0.0 };
+        }
+        RoomFade::In { elapsed } => {
+            *elapsed += dt;
+            if *elapsed >= ROOM_FADE_SECS {
+                *fade = RoomFade::Idle;
+            }
+        }
+    }
+}
+
+// Going down fades to black and starts over from the last save point
+fn respawn_on_death(mut events: EventReader<PlayerDiedEvent>, mut fade: ResMut<RoomFade>) {
+    if events.read().last().is_some() {
+        info!("Player went down");
+        *fade = RoomFade::Respawn { elapsed: 0.0 };
+    }
+}
+
+// Holds the player still from the moment a fade starts until it has fully cleared
+fn lock_movement_during_fade(fade: Res<RoomFade>, mut locked: Local<bool>, mut locks: ResMut<MovementLocks>) {
+    if fade.is_active() == *locked {
+        return;
+    }
+    *locked = fade.is_active();
+    if *locked {
+        locks.lock();
+    } else {
+        locks.unlock();
+    }
+}
+
+type CrittersQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Critter, &'static mut Transform, &'static Sprite, Option<&'static Collider>),
+    Without<Player>,
+>;
+
+// The player as a critter sees them: where, how big, and whether they're creeping
+type WatchedPlayerQuery<'w, 's> =
+    Query<'w, 's, (&'static Transform, &'static Collider, Has<Sneaking>), (With<Player>, Without<Critter>)>;
+
+type CritterSolidsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Transform, &'static Sprite, Option<&'static Collider>),
+    (With<Solid>, Without<Critter>, Without<Player>),
+>;
+
+// Critters pick a new spot near home every few seconds and walk to it, bumping off solids.
+// They hold still while a menu or dialog is open so they stay in reach mid-conversation.
+fn wander_critters(
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    ui_state: Res<crate::ui::UiState>,
+    mut critters: CrittersQuery,
+    player_query: WatchedPlayerQuery,
+    solids: CritterSolidsQuery,
+) {
+    if ui_state.menu_open || ui_state.dialog_open {
+        return;
+    }
+    let player = player_query.single().ok();
+    for (mut critter, mut tf, sprite, collider) in critters.iter_mut() {
+        critter.next_pick -= time.delta_secs();
+        if critter.next_pick <= 0.0 {
+            let angle = rng.range_f32(0.0..std::f32::consts::TAU);
+            let distance = critter.wander_radius * rng.range_f32(0.0..1.0).sqrt();
+            critter.target = critter.home + Vec2::from_angle(angle) * distance;
+            critter.next_pick = rng.range_f32(2.0..5.0);
+        }
+
+        let pos = tf.translation.truncate();
+        let step = (critter.target - pos).clamp_length_max(critter.speed * time.delta_secs());
+        let body = Collider::of(sprite, collider);
+        // Freeze while the player is close, and never shove into them
+        if step == Vec2::ZERO
+            || player.is_some_and(|(p_tf, p, sneaking)| {
+                let player_pos = p_tf.translation.truncate();
+                critter.notices(pos, player_pos, sneaking) || body.overlaps(pos + step, p, player_pos)
+            })
+        {
+            continue;
+        }
+        tf.translation.x += step.x;
+        push_out_of_solids(&mut tf.translation, &body, Vec2::new(step.x, 0.0), solids.iter());
+        tf.translation.y += step.y;
+        push_out_of_solids(&mut tf.translation, &body, Vec2::new(0.0, step.y), solids.iter());
+    }
+}
+
+fn pulse_sprites(time: Res<Time>, mut query: Query<(&Pulse, &mut Transform)>) {
+    let t = time.elapsed_secs();
+    for (pulse, mut transform) in query.iter_mut() {
+        let scale = 1.0 + (t * pulse.speed).sin() * pulse.amount;
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
+
+// Saving at a save point also remembers where to come back to
+fn record_checkpoint(
+    mut events: EventReader<SaveRequestedEvent>,
+    mut commands: Commands,
+    player_query: Query<&Transform, With<Player>>,
+    inventory: Res<Inventory>,
+    world: Res<WorldState>,
+    current_room: Res<CurrentRoom>,
+) {
+    for _ in events.read() {
+        let Ok(player_tf) = player_query.single() else { continue };
+        commands.insert_resource(LastCheckpoint {
+            room: current_room.0.clone(),
+            player_position: player_tf.translation.truncate(),
+            items: inventory.items.clone(),
+            world: world.clone(),
+        });
+        info!("Checkpoint saved in {}", current_room.0);
+    }
+}
+
+// Puts back the checkpoint's items and world state. The caller reloads its room (always, so
+// taken items and toggled objects come back too) and moves the player.
+fn restore_checkpoint(checkpoint: &LastCheckpoint, inventory: &mut Inventory, world: &mut WorldState) {
+    *world = checkpoint.world.clone();
+    inventory.items = checkpoint.items.clone();
+}
+
+// Debug: F9 rewinds to the last save point
+fn restore_checkpoint_on_key(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    checkpoint: Option<Res<LastCheckpoint>>,
+    mut inventory: ResMut<Inventory>,
+    mut world: ResMut<WorldState>,
+    mut loads: EventWriter<LoadRoomEvent>,
+    mut teleports: EventWriter<TeleportPlayerEvent>,
+) {
+    if !keyboard.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let Some(checkpoint) = checkpoint else {
+        info!("No checkpoint to restore");
+        return;
+    };
+
+    restore_checkpoint(&checkpoint, &mut inventory, &mut world);
+    loads.write(LoadRoomEvent { id: checkpoint.room.clone(), spawn_point: None });
+    teleports.write(TeleportPlayerEvent { to: SpawnTarget::Position(checkpoint.player_position), fade: true });
+    info!("Restored checkpoint in {}", checkpoint.room);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::{wall_at, PLAYER_HALF_EXTENTS};
+
+    fn generator(fuel_level: f32, is_running: bool) -> Generator {
+        Generator { is_running, fuel_level, max_fuel: 10.0 }
+    }
+
+    #[test]
+    fn burn_drains_only_while_running() {
+        let mut idle = generator(5.0, false);
+        assert!(!idle.burn(10.0));
+        assert_eq!(idle.fuel_level, 5.0);
+
+        let mut running = generator(5.0, true);
+        assert!(!running.burn(5.0));
+        assert!((running.fuel_level - 4.0).abs() < 1e-5);
+        assert!(running.is_running);
+    }
+
+    #[test]
+    fn burn_shuts_off_at_zero() {
+        let mut g = generator(0.1, true);
+        assert!(g.burn(1.0));
+        assert_eq!(g.fuel_level, 0.0);
+        assert!(!g.is_running);
+        // Already off; doesn't report running dry again
+        assert!(!g.burn(1.0));
+    }
+
+    #[test]
+    fn add_fuel_clamps_to_max() {
+        let mut g = generator(8.0, false);
+        let added = g.add_fuel(FUEL_CAN_AMOUNT);
+        assert_eq!(added, 2.0);
+        assert_eq!(g.fuel_level, 10.0);
+        assert!(g.is_full());
+        assert_eq!(g.add_fuel(FUEL_CAN_AMOUNT), 0.0);
+    }
+
+    #[test]
+    fn gauge_text_buckets() {
+        assert_eq!(generator(0.0, false).gauge_text(), "empty");
+        assert_eq!(generator(1.0, false).gauge_text(), "nearly empty");
+        assert_eq!(generator(2.5, false).gauge_text(), "about a quarter full");
+        assert_eq!(generator(5.0, false).gauge_text(), "about half full");
+        assert_eq!(generator(7.5, false).gauge_text(), "about three-quarters full");
+        assert_eq!(generator(10.0, false).gauge_text(), "full");
+    }
+
+    #[test]
+    fn fuel_gauge_turns_red_below_a_quarter() {
+        assert_eq!(fuel_gauge_fill(&generator(10.0, true)), (FUEL_GAUGE_SIZE.x, FUEL_GAUGE_OK_COLOR));
+        assert_eq!(fuel_gauge_fill(&generator(2.5, true)), (FUEL_GAUGE_SIZE.x / 4.0, FUEL_GAUGE_OK_COLOR));
+        assert_eq!(fuel_gauge_fill(&generator(2.4, true)).1, FUEL_GAUGE_LOW_COLOR);
+        assert_eq!(fuel_gauge_fill(&generator(0.0, false)), (0.0, FUEL_GAUGE_LOW_COLOR));
+    }
+
+    #[test]
+    fn a_generator_burns_nothing_while_a_dialog_is_up() {
+        use crate::save::NewGameEvent;
+        use crate::state::GameState;
+        use crate::ui::{LogEvent, UiState};
+        use bevy::time::TimeUpdateStrategy;
+        use std::time::Duration;
+
+        let mut app = crate::headless_app();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(1.0 / 60.0)));
+        app.update();
+        crate::wait_for_room_files(&mut app);
+        app.world_mut().send_event(NewGameEvent { carry_from: None });
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.update();
+        app.update();
+        let fuel = |app: &mut App| {
+            let world = app.world_mut();
+            world.query::<&Generator>().single(world).unwrap().fuel_level
+        };
+        {
+            let world = app.world_mut();
+            let mut generator = world.query::<&mut Generator>().single_mut(world).unwrap();
+            generator.is_running = true;
+            generator.fuel_level = 5.0;
+        }
+
+        // A line comes up and stays up for five seconds
+        app.world_mut().send_event(LogEvent::new("* The generator rattles.".to_string()));
+        app.update();
+        assert!(app.world().resource::<UiState>().dialog_open);
+        let before = fuel(&mut app);
+        for _ in 0..300 {
+            app.update();
+        }
+        assert!(app.world().resource::<Time<Virtual>>().is_paused());
+        assert_eq!(fuel(&mut app), before);
+
+        // Once it's dismissed the tank drains again
+        {
+            let mut ui_state = app.world_mut().resource_mut::<UiState>();
+            ui_state.dialog_open = false;
+            ui_state.dialog_queue.clear();
+        }
+        for _ in 0..60 {
+            app.update();
+        }
+        assert!(fuel(&mut app) < before);
+    }
+
+    #[test]
+    fn running_dry_names_the_generator_in_a_sentence() {
+        use bevy::ecs::system::RunSystemOnce;
+        use std::time::Duration;
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Events<LogEvent>>();
+        world.spawn((
+            Interactable { name: "Generator".to_string(), ..default() },
+            generator(0.1, true), // Less than a second's burn
+        ));
+        world.resource_mut::<Time>().advance_by(Duration::from_secs(1));
+        world.run_system_once(burn_generator_fuel).unwrap();
+        let lines: Vec<_> = world.resource_mut::<Events<LogEvent>>().drain().map(|e| e.text).collect();
+        assert_eq!(lines, ["* The generator sputters and dies. It's out of fuel."]);
+    }
+
+    #[test]
+    fn world_state_round_trips_through_ron() {
+        let mut world = WorldState::default();
+        world.object_mut(&ObjectId("start/rusty_key".to_string())).taken = true;
+        world.object_mut(&ObjectId("start/metal_door".to_string())).door_open = Some(true);
+        world.object_mut(&ObjectId("start/old_lamp".to_string())).light_on = Some(false);
+        world.object_mut(&ObjectId("start/lever".to_string())).switch_on = Some(true);
+        let generator = world.object_mut(&ObjectId("start/generator".to_string()));
+        generator.generator_running = Some(true);
+        generator.fuel_level = Some(3.25);
+        world.containers.insert("wooden_chest".to_string(), vec!["firewood".to_string(), "rusty_key".to_string()]);
+
+        let text = ron::to_string(&world).unwrap();
+        let loaded: WorldState = ron::from_str(&text).unwrap();
+        assert_eq!(loaded, world);
+    }
+
+    #[test]
+    fn object_state_fills_missing_fields() {
+        let loaded: WorldState =
+            ron::from_str(r#"(objects: {"start/rusty_key": (taken: true)}, containers: {})"#).unwrap();
+        assert_eq!(
+            loaded.objects["start/rusty_key"],
+            ObjectState { taken: true, ..default() }
+        );
+    }
+
+    #[test]
+    fn a_room_spawns_already_as_it_was_left() {
+        let mut app = crate::headless_app();
+        app.update();
+        crate::wait_for_room_files(&mut app);
+        let mut world_state = app.world_mut().resource_mut::<WorldState>();
+        world_state.object_mut(&ObjectId("start/rusty_key".to_string())).taken = true;
+        world_state.object_mut(&ObjectId("start/metal_door".to_string())).door_open = Some(true);
+        app.world_mut().send_event(LoadRoomEvent { id: START_ROOM.to_string(), spawn_point: None });
+        app.update();
+
+        // The same frame, not the one after
+        let world = app.world_mut();
+        let mut objects = world.query::<(&ObjectId, Option<&Door>)>();
+        assert!(!objects.iter(world).any(|(id, _)| id.0 == "start/rusty_key"));
+        let (_, door) = objects.iter(world).find(|(id, _)| id.0 == "start/metal_door").unwrap();
+        assert!(door.unwrap().is_open);
+    }
+
+    fn any_of(ids: &[&str]) -> KeyRequirement {
+        KeyRequirement::AnyOf(ids.iter().map(|id| id.to_string()).collect())
+    }
+
+    fn all_of(ids: &[&str]) -> KeyRequirement {
+        KeyRequirement::AllOf(ids.iter().map(|id| id.to_string()).collect())
+    }
+
+    fn lock(requires: KeyRequirement, locked: bool, consumes_key: bool) -> Lockable {
+        Lockable { requires, locked, consumes_key }
+    }
+
+    fn holding(ids: &[&str]) -> Inventory {
+        let mut inventory = Inventory::new(8);
+        for id in ids {
+            inventory.add_item(item_by_id(id));
+        }
+        inventory
+    }
+
+    #[test]
+    fn unlocked_lock_opens_without_touching_keys() {
+        for requires in [KeyRequirement::None, any_of(&["small_key"]), all_of(&["small_key"])] {
+            let mut inventory = holding(&["small_key"]);
+            let mut l = lock(requires, false, true);
+            assert_eq!(l.try_open(&mut inventory), LockCheck::Unlocked);
+            assert_eq!(inventory.count_item_id("small_key"), 1);
+        }
+    }
+
+    #[test]
+    fn key_unlocks_and_is_kept_or_consumed() {
+        for consumes_key in [false, true] {
+            let mut inventory = holding(&["small_key"]);
+            let mut l = lock(any_of(&["small_key"]), true, consumes_key);
+            assert_eq!(
+                l.try_open(&mut inventory),
+                LockCheck::UnlockedWith { key_names: vec!["Small Key".to_string()], consumed: consumes_key }
+            );
+            assert!(!l.locked);
+            assert_eq!(inventory.count_item_id("small_key"), if consumes_key { 0 } else { 1 });
+            // Stays unlocked; the key isn't needed again
+            assert_eq!(l.try_open(&mut inventory), LockCheck::Unlocked);
+        }
+    }
+
+    #[test]
+    fn missing_or_wrong_key_stays_locked() {
+        for held in [&[][..], &["rusty_key"][..]] {
+            let mut inventory = holding(held);
+            let mut l = lock(any_of(&["small_key"]), true, true);
+            assert_eq!(l.try_open(&mut inventory), LockCheck::MissingKey);
+            assert!(l.locked);
+            assert_eq!(inventory.items.len(), held.len());
+        }
+    }
+
+    #[test]
+    fn keyless_lock_is_sealed() {
+        let mut inventory = holding(&["small_key", "rusty_key"]);
+        let mut l = lock(KeyRequirement::None, true, false);
+        assert_eq!(l.try_open(&mut inventory), LockCheck::Sealed);
+        assert!(l.locked);
+        assert_eq!(inventory.items.len(), 2);
+    }
+
+    #[test]
+    fn any_of_uses_whichever_key_is_held() {
+        let mut inventory = holding(&["master_key"]);
+        let mut l = lock(any_of(&["closet_key", "master_key"]), true, true);
+        assert_eq!(
+            l.try_open(&mut inventory),
+            LockCheck::UnlockedWith { key_names: vec!["Master Key".to_string()], consumed: true }
+        );
+        assert!(inventory.items.is_empty());
+    }
+
+    #[test]
+    fn all_of_lists_missing_keys_and_needs_every_one() {
+        let mut l = lock(all_of(&["red_key", "blue_key"]), true, true);
+
+        let mut inventory = holding(&[]);
+        assert_eq!(
+            l.try_open(&mut inventory),
+            LockCheck::MissingKeys(vec!["Red Key".to_string(), "Blue Key".to_string()])
+        );
+        let mut inventory = holding(&["blue_key"]);
+        assert_eq!(l.try_open(&mut inventory), LockCheck::MissingKeys(vec!["Red Key".to_string()]));
+        assert_eq!(inventory.count_item_id("blue_key"), 1);
+        assert!(l.locked);
+
+        let mut inventory = holding(&["red_key", "blue_key", "rusty_key"]);
+        assert_eq!(
+            l.try_open(&mut inventory),
+            LockCheck::UnlockedWith {
+                key_names: vec!["Red Key".to_string(), "Blue Key".to_string()],
+                consumed: true,
+            }
+        );
+        assert_eq!(inventory.items.len(), 1);
+    }
+
+    // Spawns the builder into a bare world; returns the entity and the names of its game components
+    fn build(builder: ObjectBuilder) -> (World, Entity, Vec<String>) {
+        let mut world = World::new();
+        let entity = builder.spawn(&mut world.commands());
+        world.flush();
+        let mut names: Vec<String> = world
+            .inspect_entity(entity)
+            .unwrap()
+            .map(|info| info.name().to_string())
+            .filter(|name| name.starts_with(env!("CARGO_CRATE_NAME")))
+            .map(|name| name.rsplit("::").next().unwrap().to_string())
+            .collect();
+        names.sort();
+        assert!(world.get::<Sprite>(entity).is_some());
+        assert!(world.get::<Transform>(entity).is_some());
+        assert!(world.get::<Name>(entity).is_some());
+        (world, entity, names)
+    }
+
+    #[test]
+    fn door_builder_attaches_lock_and_transition() {
+        let (world, door, components) = build(
+            ObjectBuilder::door(Vec2::new(0.0, 200.0))
+                .name("Metal Door")
+                .key("rusty_key")
+                .key("master_key")
+                .size(32.0, 40.0)
+                .peek(&["* You see a long hallway."])
+                .leads_to(HALLWAY, "from_start"),
+        );
+        assert_eq!(
+            components,
+            ["Door", "Interactable", "Lockable", "ObjectId", "PeekText", "RoomMember", "RoomTransition", "Solid", "YSort"]
+        );
+        let lock = world.get::<Lockable>(door).unwrap();
+        assert!(matches!(&lock.requires, KeyRequirement::AnyOf(ids) if ids == &["rusty_key", "master_key"]));
+        assert!(lock.locked && !lock.consumes_key);
+        assert_eq!(world.get::<ObjectId>(door).unwrap().0, "start/metal_door");
+        assert_eq!(world.get::<Sprite>(door).unwrap().custom_size, Some(Vec2::new(32.0, 40.0)));
+    }
+
+    #[test]
+    fn locks_written_with_the_old_single_key_field_still_load() {
+        let old: Lockable = ron::from_str(r#"(required_key_id: Some("small_key"), locked: true, consumes_key: true)"#).unwrap();
+        assert!(matches!(&old.requires, KeyRequirement::AnyOf(ids) if ids == &["small_key"]));
+        assert!(old.locked && old.consumes_key);
+        let sealed: Lockable = ron::from_str("(required_key_id: None, locked: true, consumes_key: false)").unwrap();
+        assert!(matches!(sealed.requires, KeyRequirement::None));
+        let new: Lockable = ron::from_str(r#"(requires: AllOf(["a", "b"]), locked: true, consumes_key: false)"#).unwrap();
+        assert!(matches!(&new.requires, KeyRequirement::AllOf(ids) if ids == &["a", "b"]));
+    }
+
+    #[test]
+    fn pickup_builder_is_named_after_its_item() {
+        let (world, key, components) =
+            build(ObjectBuilder::pickup("rusty_key", Vec2::new(-100.0, 0.0)).room(HALLWAY));
+        assert_eq!(components, ["Interactable", "Item", "ObjectId", "RoomMember", "Solid", "YSort"]);
+        let item = world.get::<Item>(key).unwrap();
+        assert_eq!((item.id.as_str(), item.name.as_str()), ("rusty_key", "Rusty Key"));
+        assert_eq!(world.get::<ObjectId>(key).unwrap().0, "hallway/rusty_key");
+        assert_eq!(world.get::<Interactable>(key).unwrap().actions, default_actions("Item"));
+
+        let (_, _, components) =
+            build(ObjectBuilder::pickup("scrap", Vec2::ZERO).respawn_after(60.0));
+        assert_eq!(components, ["Interactable", "Item", "ObjectId", "Respawns", "RoomMember", "Solid", "YSort"]);
+    }
+
+    #[test]
+    fn npc_builder_carries_dialogue() {
+        let (world, npc, components) =
+            build(ObjectBuilder::npc(Vec2::new(60.0, 0.0), "Strange Figure", &["* ..."]));
+        assert_eq!(components, ["CastsShadow", "Interactable", "NPC", "ObjectId", "RoomMember", "Solid", "YSort"]);
+        assert_eq!(world.get::<NPC>(npc).unwrap().dialogue, ["* ..."]);
+    }
+
+    #[test]
+    fn scenery_builders_skip_interactable() {
+        let (_, _, components) = build(ObjectBuilder::wall(Vec2::ZERO));
+        assert_eq!(components, ["ObjectId", "RoomMember", "Solid"]);
+        let (_, _, components) = build(ObjectBuilder::gate(Vec2::ZERO, "alcove_gate"));
+        assert_eq!(components, ["ObjectId", "RoomMember", "Solid", "SwitchTarget"]);
+        let (_, _, components) = build(ObjectBuilder::hazard(Vec2::ZERO, HazardKind::Slippery, 0.5));
+        assert_eq!(components, ["FloorModifier", "Hazard", "ObjectId", "RoomMember"]);
+        let (world, carpet, components) =
+            build(ObjectBuilder::floor(Vec2::ZERO, FloorSurface::Carpet).speed_mult(0.85));
+        assert_eq!(components, ["FloorModifier", "ObjectId", "RoomMember"]);
+        assert_eq!(world.get::<FloorModifier>(carpet).unwrap().speed_mult, 0.85);
+        let (_, _, components) = build(ObjectBuilder::prop(Vec2::ZERO, "Dusty Painting"));
+        assert_eq!(components, ["Interactable", "ObjectId", "RoomMember", "YSort"]);
+        let (_, _, components) = build(ObjectBuilder::prop(Vec2::ZERO, "Fireplace").solid(true));
+        assert_eq!(components, ["Interactable", "ObjectId", "RoomMember", "Solid", "YSort"]);
+        // A pinned layer opts out of sorting
+        let (_, _, components) = build(ObjectBuilder::prop(Vec2::ZERO, "Rug").z(0.5));
+        assert_eq!(components, ["Interactable", "ObjectId", "RoomMember"]);
+    }
+
+    #[test]
+    fn light_builder_wires_its_generator() {
+        let mut world = World::new();
+        let generator = ObjectBuilder::generator(Vec2::ZERO, 2.5).spawn(&mut world.commands());
+        let lamp = ObjectBuilder::light(Vec2::ZERO).powered_by(generator).spawn(&mut world.commands());
+        world.flush();
+        assert_eq!(world.get::<Generator>(generator).unwrap().fuel_level, 2.5);
+        assert_eq!(world.get::<Light>(lamp).unwrap().powered_by, Some(generator));
+    }
+
+    #[test]
+    fn chest_builder_fills_container() {
+        let (world, chest, components) = build(
+            ObjectBuilder::chest(Vec2::ZERO, "wooden_chest")
+                .capacity(6)
+                .items(&["firewood"])
+                .key("small_key")
+                .consumes_key(),
+        );
+        assert_eq!(components, ["Container", "Interactable", "Lockable", "ObjectId", "RoomMember", "Solid", "YSort"]);
+        let container = world.get::<Container>(chest).unwrap();
+        assert_eq!((container.id.as_str(), container.capacity), ("wooden_chest", 6));
+        assert_eq!(container.initial_items, ["firewood"]);
+        assert!(world.get::<Lockable>(chest).unwrap().consumes_key);
+    }
+
+    #[test]
+    fn solid_collider_overrides_its_sprite() {
+        let (world, wall, components) = build(
+            ObjectBuilder::wall(Vec2::ZERO)
+                .size(32.0, 40.0)
+                .collider(Vec2::new(16.0, 10.0), Vec2::new(0.0, -10.0)),
+        );
+        assert_eq!(components, ["Collider", "ObjectId", "RoomMember", "Solid"]);
+        let collider = world.get::<Collider>(wall).copied();
+        let mut pos = Vec3::new(0.0, 15.0, 0.0);
+        // Clear of the lower half the collider covers, though inside the sprite
+        push_out_of_solids(&mut pos, &Collider::new(PLAYER_HALF_EXTENTS), Vec2::NEG_Y, [(
+            world.get::<Transform>(wall).unwrap(),
+            world.get::<Sprite>(wall).unwrap(),
+            collider.as_ref(),
+        )]);
+        assert_eq!(pos.y, 15.0);
+    }
+
+    #[test]
+    fn spawn_point_builder_is_invisible() {
+        let (world, point, components) = build(ObjectBuilder::spawn_point(Vec2::new(0.0, 160.0), "from_hallway"));
+        assert_eq!(components, ["ObjectId", "RoomMember", "SpawnPoint"]);
+        assert_eq!(world.get::<SpawnPoint>(point).unwrap().name, "from_hallway");
+        assert_eq!(world.get::<Visibility>(point), Some(&Visibility::Hidden));
+    }
+
+    #[test]
+    fn room_fade_holds_its_own_lock() {
+        let mut world = World::new();
+        world.init_resource::<RoomFade>();
+        world.init_resource::<MovementLocks>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(lock_movement_during_fade);
+        let locked = |world: &World| world.resource::<MovementLocks>().is_locked();
+
+        // A dialog is open when the fade starts, and closes before it ends
+        world.resource_mut::<MovementLocks>().lock();
+        *world.resource_mut::<RoomFade>() = RoomFade::In { elapsed: 0.0 };
+        schedule.run(&mut world);
+        schedule.run(&mut world); // Still fading; doesn't lock twice
+        world.resource_mut::<MovementLocks>().unlock();
+        assert!(locked(&world));
+
+        *world.resource_mut::<RoomFade>() = RoomFade::Idle;
+        schedule.run(&mut world);
+        assert!(!locked(&world));
+
+        // Ending the fade first leaves the dialog's lock in place
+        world.resource_mut::<MovementLocks>().lock();
+        *world.resource_mut::<RoomFade>() = RoomFade::In { elapsed: 0.0 };
+        schedule.run(&mut world);
+        *world.resource_mut::<RoomFade>() = RoomFade::Idle;
+        schedule.run(&mut world);
+        assert!(locked(&world));
+    }
+
+    #[test]
+    fn going_down_waits_on_stay_determined_then_restores() {
+        let mut world = World::new();
+        world.init_resource::<Time<Real>>();
+        world.init_resource::<crate::ui::UiState>();
+        world.init_resource::<WorldState>();
+        world.insert_resource(Inventory::new(8));
+        world.insert_resource(RoomFade::Respawn { elapsed: ROOM_FADE_SECS });
+        world.init_resource::<Events<LoadRoomEvent>>();
+        world.init_resource::<Events<TeleportPlayerEvent>>();
+        world.init_resource::<Events<RoomSlideEvent>>();
+        world.init_resource::<Events<PlayerRespawnedEvent>>();
+        world.init_resource::<Events<LogEvent>>();
+        world.resource_mut::<Inventory>().add_item(item_by_id("fuel_can"));
+        let mut schedule = Schedule::default();
+        schedule.add_systems(run_room_fade);
+        let dialog_open = |world: &mut World, open: bool| world.resource_mut::<crate::ui::UiState>().dialog_open = open;
+        let loads = |world: &mut World| world.resource_mut::<Events<LoadRoomEvent>>().drain().collect::<Vec<_>>();
+
+        // Fully black: the line goes out, and nothing is restored until it's been read
+        schedule.run(&mut world);
+        let lines: Vec<_> = world.resource_mut::<Events<LogEvent>>().drain().map(|e| e.text).collect();
+        assert_eq!(lines, ["* Stay determined."]);
+        schedule.run(&mut world);
+        dialog_open(&mut world, true);
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        assert!(loads(&mut world).is_empty());
+        assert_eq!(world.resource::<RoomFade>().overlay_alpha(), 1.0);
+
+        // No save point yet: back to the start with empty pockets, through the usual events
+        dialog_open(&mut world, false);
+        schedule.run(&mut world);
+        let loads = loads(&mut world);
+        assert_eq!((loads.len(), loads[0].id.as_str(), &loads[0].spawn_point), (1, START_ROOM, &None));
+        let teleports: Vec<_> = world.resource_mut::<Events<TeleportPlayerEvent>>().drain().collect();
+        assert_eq!(teleports[0].to, SpawnTarget::Named(PLAYER_START.to_string()));
+        assert_eq!(world.resource_mut::<Events<PlayerRespawnedEvent>>().drain().count(), 1);
+        assert!(world.resource::<Inventory>().items.is_empty());
+        assert!(matches!(*world.resource::<RoomFade>(), RoomFade::In { .. }));
+    }
+
+    #[test]
+    fn door_swings_pass_through_every_frame_once() {
+        let mut swing = DoorAnimation { opening: true, progress: 0.0, frame: None };
+        let frames: Vec<usize> = (0..12).flat_map(|_| swing.advance(DOOR_ANIM_SECS / 10.0)).collect();
+        assert_eq!(frames, (0..DOOR_ANIM_FRAMES).collect::<Vec<_>>());
+        // One long hitch still gets to the last frame, by way of the rest
+        let mut swing = DoorAnimation { opening: false, progress: 0.0, frame: None };
+        assert_eq!(swing.advance(1.0), 0..=DOOR_ANIM_FRAMES - 1);
+        // A door put back open has nothing left to show
+        let mut restored = DoorAnimation { opening: true, progress: 1.0, frame: Some(DOOR_ANIM_FRAMES - 1) };
+        assert!(restored.advance(0.1).is_empty());
+    }
+
+    #[test]
+    fn doors_refuse_to_close_on_whatever_is_in_the_way() {
+        let player = Collider::new(PLAYER_HALF_EXTENTS);
+        let (door_tf, door) = wall_at(0.0, 100.0, 32.0, 40.0);
+        // Shoved halfway into the doorway from the far side
+        let (crate_tf, crate_sprite) = wall_at(0.0, 125.0, 20.0, 20.0);
+        let crates = [(&crate_tf, &crate_sprite, None)];
+
+        let blocker = |player_y: f32, crates: &[(&Transform, &Sprite, Option<&Collider>)]| {
+            doorway_blocker(&door_tf, &door, Some((Vec2::new(0.0, player_y), &player)), crates.iter().copied())
+        };
+        assert_eq!(blocker(90.0, &crates), Some(DoorwayBlocker::Player));
+        assert_eq!(blocker(0.0, &crates), Some(DoorwayBlocker::Object));
+        assert_eq!(blocker(0.0, &[]), None);
+    }
+}
@@ -1,26 +1,345 @@
+use bevy::asset::io::file::FileAssetReader;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use crate::objects::Solid;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::DerefMut;
+use crate::animation::{AnimClip, AnimationEvent, FrameEvents};
+use crate::audio::{PlaySfxEvent, SfxId};
+use crate::objects::{Pushable, RoomFade, Solid};
+use crate::rng::{GameRng, RngDraw};
+use crate::rooms::load_rooms;
+use crate::camera::CameraTarget;
+use crate::collision::{self, move_and_collide, penetration, push_out, Aabb, CORNER_NUDGE};
+use crate::depth::{CastsShadow, YSort};
+use crate::input::{track_gestures, Action, InputGestures, InputMap};
+use crate::state::GameState;
+use crate::GameSet;
+use crate::settings::Settings;
+use crate::spatial::{update_spatial_grid, SpatialGrid};
+use crate::timescale::{SetTimeScaleEvent, HIT_STOP_SCALE, HIT_STOP_SECS};
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_player)
+        app.add_event::<DamageEvent>()
+            .add_event::<HealEvent>()
+            .add_event::<PlayerDiedEvent>()
+            .add_event::<PlayerRespawnedEvent>()
+            .add_event::<TeleportPlayerEvent>()
+            .add_event::<DashedEvent>()
+            .init_resource::<MovementLocks>()
+            .add_systems(Startup, (spawn_player, load_footstep_sounds))
             .add_systems(Update, (
-                player_movement,
+                update_sneaking.before(player_movement),
+                player_movement.after(track_gestures).after(update_spatial_grid),
                 update_player_facing,
+                animate_player.after(player_movement).after(update_player_facing),
+                play_footsteps.after(animate_player).in_set(RngDraw::Footsteps),
+                apply_damage.in_set(GameSet::Process),
+                flash_on_damage.after(apply_damage),
+                debug_teleport.in_set(GameSet::Input),
+            ).run_if(in_state(GameState::Playing)))
+            // Not gameplay, so not gated: Start and Continue place the player from the title screen
+            .add_systems(Update, (
+                // After the room loader, so the spawn points of a room loaded this frame exist
+                teleport_player.after(load_rooms),
+                // Something may have turned solid around the player this frame (a door, a gate)
+                depenetrate_player.after(teleport_player),
             ));
     }
 }
 
-#[derive(Component)]
+// Player AABB (half extents) — approximate sprite size
+pub const PLAYER_HALF_EXTENTS: Vec2 = Vec2::new(8.0, 10.0);
+
+// Box an entity collides with, offset from its position. Entities without one collide with
+// their whole sprite; a smaller box lets e.g. only the feet of a tall sprite bump into walls.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Collider {
+    pub half_extents: Vec2,
+    pub offset: Vec2,
+}
+
+impl Collider {
+    pub fn new(half_extents: Vec2) -> Self {
+        Self { half_extents, offset: Vec2::ZERO }
+    }
+
+    // The entity's Collider, or one covering its sprite
+    pub fn of(sprite: &Sprite, collider: Option<&Collider>) -> Self {
+        collider
+            .copied()
+            .unwrap_or_else(|| Self::new(sprite.custom_size.unwrap_or(Vec2::splat(16.0)) / 2.0))
+    }
+
+    // Middle of the box for an entity at `pos`
+    pub fn center(&self, pos: Vec2) -> Vec2 {
+        pos + self.offset
+    }
+
+    // The box itself, for an entity at `pos`
+    pub fn aabb(&self, pos: Vec2) -> Aabb {
+        Aabb::new(self.center(pos), self.half_extents)
+    }
+
+    pub fn overlaps(&self, pos: Vec2, other: &Collider, other_pos: Vec2) -> bool {
+        collision::overlaps(self.aabb(pos), other.aabb(other_pos))
+    }
+
+    pub fn contains(&self, pos: Vec2, point: Vec2) -> bool {
+        self.aabb(pos).contains(point)
+    }
+
+    // Middle of the bottom edge, where the entity stands
+    pub fn feet(&self, pos: Vec2) -> Vec2 {
+        self.center(pos) - Vec2::new(0.0, self.half_extents.y)
+    }
+}
+
+// A plain w by h solid centred on (x, y), sized by its sprite, for collision tests
+#[cfg(test)]
+pub(crate) fn wall_at(x: f32, y: f32, w: f32, h: f32) -> (Transform, Sprite) {
+    (Transform::from_xyz(x, y, 0.0), Sprite::from_color(Color::WHITE, Vec2::new(w, h)))
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Player {
     pub speed: f32,
+    pub run_multiplier: f32, // Speed (and walk-cycle rate) scale while running
+    pub running: bool,
     pub interact_range: f32,
     pub facing: Direction,
 }
 
-#[derive(Clone, Copy, Debug)]
+// How the player starts out: walking, facing the camera
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            speed: 120.0,
+            run_multiplier: DEFAULT_RUN_MULTIPLIER,
+            running: false,
+            interact_range: 30.0,
+            facing: Direction::Down,
+        }
+    }
+}
+
+impl Player {
+    // Speed (and walk-cycle rate) scale for the current pace
+    pub fn gait(&self, sneaking: bool) -> f32 {
+        if sneaking {
+            SNEAK_SPEED_MULTIPLIER
+        } else if self.running {
+            self.run_multiplier
+        } else {
+            1.0
+        }
+    }
+}
+
+pub const PLAYER_MAX_HP: i32 = 20;
+pub const DEFAULT_RUN_MULTIPLIER: f32 = 1.75;
+
+// On the player while they hold the sneak key. Anything that notices the player should
+// scale its notice radius by SNEAK_NOTICE_MULTIPLIER while this is present.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Sneaking;
+
+// Speed and walk-cycle rate while sneaking; overrides running
+pub const SNEAK_SPEED_MULTIPLIER: f32 = 0.5;
+pub const SNEAK_NOTICE_MULTIPLIER: f32 = 0.5;
+
+// A short burst of speed in the direction the player is walking, then a cooldown
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Dash {
+    pub direction: Vec2,
+    pub remaining: f32, // Seconds of the current dash left
+    pub cooldown: f32,  // Seconds until the next dash is allowed
+}
+
+// Double-tapping any of these dashes, as well as the Dash key
+const MOVE_ACTIONS: [Action; 4] = [Action::MoveUp, Action::MoveDown, Action::MoveLeft, Action::MoveRight];
+
+pub const DASH_SPEED_MULTIPLIER: f32 = 4.0;
+pub const DASH_SECS: f32 = 0.12;
+pub const DASH_COOLDOWN_SECS: f32 = 0.8;
+
+impl Dash {
+    // Starts dashing along `direction` if it's ready; false if it isn't or there's no direction
+    pub fn start(&mut self, direction: Vec2) -> bool {
+        if self.remaining > 0.0 || self.cooldown > 0.0 || direction == Vec2::ZERO {
+            return false;
+        }
+        self.direction = direction.normalize();
+        self.remaining = DASH_SECS;
+        true
+    }
+
+    // Seconds of this frame spent dashing (0 if not dashing). Counts the cooldown down, and
+    // starts it once the dash runs out.
+    pub fn advance(&mut self, dt: f32) -> f32 {
+        if self.remaining > 0.0 {
+            let dashed = self.remaining.min(dt);
+            self.remaining -= dashed;
+            if self.remaining <= 0.0 {
+                self.cooldown = DASH_COOLDOWN_SECS;
+            }
+            return dashed;
+        }
+        self.cooldown = (self.cooldown - dt).max(0.0);
+        0.0
+    }
+
+    // 1 right after dashing, 0 when the next dash is ready
+    pub fn cooldown_fraction(&self) -> f32 {
+        if self.remaining > 0.0 { 1.0 } else { self.cooldown / DASH_COOLDOWN_SECS }
+    }
+}
+
+// The player just dashed along `direction`
+#[derive(Event)]
+pub struct DashedEvent {
+    pub direction: Vec2,
+}
+
+// Floor region that changes how the player walks while their feet are inside it: how fast
+// (e.g. carpet drags, oil is slick) and what their steps sound like
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct FloorModifier {
+    pub speed_mult: f32,
+    pub surface: FloorSurface,
+}
+
+// Overlapping regions multiply together, within these bounds
+pub const MIN_FLOOR_SPEED: f32 = 0.25;
+pub const MAX_FLOOR_SPEED: f32 = 2.0;
+
+// A point the player walks toward in a straight line (set by clicking). Movement keys cancel
+// it, and it's dropped on arrival or once a wall stops the player short.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct MoveTarget(pub Vec2);
+
+// How close counts as arrived
+pub const MOVE_TARGET_REACHED: f32 = 2.0;
+
+// Velocity the player is carried along by on top of (or instead of) input, e.g. knockback.
+// It bleeds off by `decay` per second and is removed once spent.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct ExternalImpulse {
+    pub velocity: Vec2,
+    pub decay: f32,
+}
+
+// e^(-15 * 0.3) leaves about 1% of the starting speed after 0.3s
+pub const KNOCKBACK_DECAY: f32 = 15.0;
+// Below this (px/s) an impulse no longer visibly moves the player
+const IMPULSE_REST_SPEED: f32 = 4.0;
+
+impl ExternalImpulse {
+    // This frame's displacement, decaying the velocity after; None once it has died down
+    pub fn advance(&mut self, dt: f32) -> Option<Vec2> {
+        if self.velocity.length() < IMPULSE_REST_SPEED {
+            return None;
+        }
+        let delta = self.velocity * dt;
+        self.velocity *= (-self.decay * dt).exp();
+        Some(delta)
+    }
+}
+
+// Shoves `entity` along `dir` at `strength` px/s, replacing any impulse it already had
+pub fn apply_knockback(commands: &mut Commands, entity: Entity, dir: Vec2, strength: f32) {
+    commands.entity(entity).insert(ExternalImpulse {
+        velocity: dir.normalize_or_zero() * strength,
+        decay: KNOCKBACK_DECAY,
+    });
+}
+
+// Hit points, plus a short grace period after each hit
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PlayerStats {
+    pub hp: i32,
+    pub max_hp: i32,
+    pub invulnerable_for: f32, // Seconds of i-frames left
+}
+
+// Shorter than HAZARD_DAMAGE_INTERVAL, so standing in a live hazard still hurts every tick
+pub const INVULNERABLE_SECS: f32 = 0.75;
+// After coming back from going down: long enough to step off whatever is at the save point
+pub const RESPAWN_INVULNERABLE_SECS: f32 = 1.0;
+
+impl PlayerStats {
+    pub fn new(max_hp: i32) -> Self {
+        Self { hp: max_hp, max_hp, invulnerable_for: 0.0 }
+    }
+
+    // Lands a hit unless still invulnerable or already down; true if it did
+    pub fn take_damage(&mut self, amount: i32) -> bool {
+        if self.invulnerable_for > 0.0 || self.is_down() {
+            return false;
+        }
+        self.hp = (self.hp - amount).max(0);
+        self.invulnerable_for = INVULNERABLE_SECS;
+        true
+    }
+
+    pub fn heal(&mut self, amount: i32) {
+        self.hp = self.hp.saturating_add(amount.max(0)).min(self.max_hp);
+    }
+
+    // Back on their feet: full HP and a moment nothing can hurt them
+    pub fn revive(&mut self) {
+        self.hp = self.max_hp;
+        self.invulnerable_for = RESPAWN_INVULNERABLE_SECS;
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.invulnerable_for = (self.invulnerable_for - dt).max(0.0);
+    }
+
+    pub fn is_down(&self) -> bool {
+        self.hp == 0
+    }
+}
+
+#[derive(Event)]
+pub struct DamageEvent {
+    pub amount: i32,
+    pub source: Option<Entity>, // What dealt it, for the log
+    pub knockback: f32,         // Speed the player is shoved away from `source` at, if it lands
+}
+
+#[derive(Event)]
+pub struct HealEvent {
+    pub amount: i32,
+}
+
+impl HealEvent {
+    // Tops the player back up to max_hp
+    pub fn full() -> Self {
+        Self { amount: i32::MAX }
+    }
+}
+
+// HP just reached 0
+#[derive(Event)]
+pub struct PlayerDiedEvent;
+
+// The player was put back at the last save point after going down
+#[derive(Event)]
+pub struct PlayerRespawnedEvent;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Reflect)]
 pub enum Direction {
     Up,
     Down,
@@ -28,152 +347,1286 @@ pub enum Direction {
     Right,
 }
 
-fn spawn_player(mut commands: Commands) {
-    // Main player entity
-    commands.spawn((
-        Sprite::from_color(
+impl Direction {
+    // The facing closest to `v`, preferring Left/Right on an exact diagonal
+    pub fn toward(v: Vec2) -> Self {
+        if v.x.abs() >= v.y.abs() {
+            if v.x < 0.0 { Self::Left } else { Self::Right }
+        } else if v.y < 0.0 {
+            Self::Down
+        } else {
+            Self::Up
+        }
+    }
+}
+
+// Walk cycles, one row per facing (down, up, left, right); column 0 is the idle pose
+const PLAYER_SHEET_PATH: &str = "sprites/player.png";
+const PLAYER_FRAME_SIZE: UVec2 = UVec2::new(16, 20);
+const WALK_FRAMES: usize = 4;
+const WALK_FRAME_SECS: f32 = 0.15;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct AnimationState {
+    pub facing: Direction,
+    pub moving: bool, // Set by player_movement from this frame's input
+    pub frame: usize,
+    pub timer: Timer,
+}
+
+impl AnimationState {
+    pub fn atlas_index(&self) -> usize {
+        sheet_index(self.facing, self.frame)
+    }
+}
+
+fn sheet_index(facing: Direction, frame: usize) -> usize {
+    let row = match facing {
+        Direction::Down => 0,
+        Direction::Up => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    };
+    row * WALK_FRAMES + frame
+}
+
+// A run of sheet frames held for `frame_secs` each, looping, for poses outside the walk cycle
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct SpriteClip {
+    pub frames: Vec<usize>,
+    pub frame_secs: f32,
+}
+
+impl SpriteClip {
+    // Glances left, right, left, then back at the viewer
+    pub fn look_around() -> Self {
+        let idle = |facing| sheet_index(facing, 0);
+        Self {
+            frames: vec![idle(Direction::Left), idle(Direction::Right), idle(Direction::Left), idle(Direction::Down)],
+            frame_secs: 0.6,
+        }
+    }
+
+    // Turns to face the viewer; a stand-in until the sheet has a proper startled pose
+    pub fn surprised() -> Self {
+        Self { frames: vec![sheet_index(Direction::Down, 0)], frame_secs: 1.0 }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames.len() as f32 * self.frame_secs
+    }
+
+    pub fn frame_at(&self, elapsed: f32) -> usize {
+        let i = (elapsed / self.frame_secs) as usize % self.frames.len().max(1);
+        self.frames.get(i).copied().unwrap_or(0)
+    }
+}
+
+// Plays `clip` in place of the facing/walk animation until `remaining` runs out. Cutscenes and
+// scripts insert it; animate_player removes it when done.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct AnimationOverride {
+    pub clip: SpriteClip,
+    pub remaining: f32,
+    pub elapsed: f32,
+}
+
+impl AnimationOverride {
+    pub fn new(clip: SpriteClip, secs: f32) -> Self {
+        Self { clip, remaining: secs, elapsed: 0.0 }
+    }
+
+    // Plays the clip through once
+    pub fn once(clip: SpriteClip) -> Self {
+        let secs = clip.duration();
+        Self::new(clip, secs)
+    }
+
+    // The frame to show after another `dt` seconds, or None once it's over
+    pub fn advance(&mut self, dt: f32) -> Option<usize> {
+        if self.remaining <= 0.0 {
+            return None;
+        }
+        let frame = self.clip.frame_at(self.elapsed);
+        self.elapsed += dt;
+        self.remaining -= dt;
+        Some(frame)
+    }
+}
+
+// Seconds since the player last moved or pressed anything; past IDLE_FIDGET_SECS they fidget
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct IdleTimer(pub f32);
+
+pub const IDLE_FIDGET_SECS: f32 = 10.0;
+
+impl IdleTimer {
+    // True when the player has just been idle long enough to fidget; starts counting again after
+    pub fn tick(&mut self, dt: f32, active: bool) -> bool {
+        if active {
+            self.0 = 0.0;
+            return false;
+        }
+        self.0 += dt;
+        if self.0 >= IDLE_FIDGET_SECS {
+            self.0 = 0.0;
+            return true;
+        }
+        false
+    }
+}
+
+// The walk-cycle sheet, or the plain yellow block if it isn't on disk
+fn player_sprite(asset_server: &AssetServer, layouts: &mut Assets<TextureAtlasLayout>) -> Sprite {
+    let path = FileAssetReader::get_base_path().join("assets").join(PLAYER_SHEET_PATH);
+    if !path.exists() {
+        info!("No player sheet at {}, using a plain sprite", path.display());
+        return Sprite::from_color(
             Color::srgb(1.0, 1.0, 0.0), // Yellow like Frisk
             Vec2::new(16.0, 20.0)
-        ),
+        );
+    }
+    let layout = TextureAtlasLayout::from_grid(PLAYER_FRAME_SIZE, WALK_FRAMES as u32, 4, None, None);
+    Sprite {
+        custom_size: Some(PLAYER_FRAME_SIZE.as_vec2()),
+        ..Sprite::from_atlas_image(
+            asset_server.load(PLAYER_SHEET_PATH),
+            TextureAtlas { layout: layouts.add(layout), index: 0 },
+        )
+    }
+}
+
+fn spawn_player(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    // Main player entity; placed by whichever room Start or Continue loads
+    commands.spawn((
+        player_sprite(&asset_server, &mut layouts),
         Transform::from_xyz(0.0, 0.0, 10.0),
-        Player { 
-            speed: 120.0,
-            interact_range: 30.0,
+        Collider::new(PLAYER_HALF_EXTENTS),
+        Footsteps::new(FOOTSTEP_VOLUME),
+        AnimationState {
             facing: Direction::Down,
+            moving: false,
+            frame: 0,
+            timer: Timer::from_seconds(WALK_FRAME_SECS, TimerMode::Repeating),
         },
+        Player::default(),
+        PlayerStats::new(PLAYER_MAX_HP),
+        Dash::default(),
+        IdleTimer::default(),
+        YSort,
+        CastsShadow,
+        CameraTarget,
         Name::new("Player"),
-    ))
-    .with_children(|parent| {
-        // Interaction indicator (shows when near interactable)
-        parent.spawn((
-            Sprite::from_color(
-                Color::srgb(1.0, 0.0, 0.0), // Red for visibility
-                Vec2::new(16.0, 16.0) // Bigger size
-            ),
-            Transform::from_xyz(0.0, 20.0, 1.0), // Higher above player
-            Visibility::Hidden,
-            InteractionIndicator,
-        ));
-    });
+    ));
 }
 
-#[derive(Component)]
-pub struct InteractionIndicator;
+// Spawn point the game begins at
+pub const PLAYER_START: &str = "player_start";
 
-fn player_movement(
-    time: Res<Time>,
+// Player is tucked inside a Hideable; movement is locked until they climb out
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Hidden {
+    #[entities]
+    pub inside: Entity,
+}
+
+// How many things are currently holding the player still (open menu, dialog, room fade...).
+// Each takes one lock and releases it when done, so overlapping reasons nest: the player
+// only moves again once the last of them ends.
+#[derive(Resource, Default, Debug)]
+pub struct MovementLocks(u32);
+
+impl MovementLocks {
+    pub fn lock(&mut self) {
+        self.0 += 1;
+    }
+
+    pub fn unlock(&mut self) {
+        if self.0 == 0 {
+            warn!("Movement unlocked more times than it was locked");
+            return;
+        }
+        self.0 -= 1;
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.0 > 0
+    }
+}
+
+// Named place the player can be sent to, e.g. just inside each of a room's entrances
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct SpawnPoint {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpawnTarget {
+    Named(String), // A SpawnPoint in the current room
+    Position(Vec2),
+}
+
+// Every move of the player that isn't walking: room transitions, checkpoint restores, debugging
+#[derive(Event)]
+pub struct TeleportPlayerEvent {
+    pub to: SpawnTarget,
+    pub fade: bool, // Fade in from black on arrival
+}
+
+pub fn teleport_player(
+    mut events: EventReader<TeleportPlayerEvent>,
+    mut fade: ResMut<RoomFade>,
+    spawn_points: Query<(&SpawnPoint, &Transform), Without<Player>>,
+    solid_query: AllSolidsQuery,
+    mut player_query: Query<(&mut Player, &mut Transform, &Collider)>,
+) {
+    for event in events.read() {
+        let destination = match &event.to {
+            SpawnTarget::Position(pos) => *pos,
+            SpawnTarget::Named(name) => match spawn_points.iter().find(|(point, _)| &point.name == name) {
+                Some((_, tf)) => tf.translation.truncate(),
+                None => {
+                    warn!("Unknown spawn point {}", name);
+                    continue;
+                }
+            },
+        };
+        let Ok((mut player, mut transform, collider)) = player_query.single_mut() else { continue };
+        let solids: Vec<_> = solid_query.iter().collect();
+        let pos = nearest_free_spot(destination, collider, &solids);
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+        player.facing = Direction::Down;
+        if event.fade && !fade.is_active() {
+            *fade = RoomFade::In { elapsed: 0.0 };
+        }
+    }
+}
+
+// Debug: F6 hops between the current room's spawn points
+fn debug_teleport(
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut query: Query<(&Player, &mut Transform), Without<Solid>>,
-    solid_query: Query<(&Transform, &Sprite), (With<Solid>, Without<Player>)>,
-    ui_state: Res<crate::ui::UiState>,
+    mut next: Local<usize>,
+    spawn_points: Query<&SpawnPoint>,
+    mut teleports: EventWriter<TeleportPlayerEvent>,
 ) {
-    // Don't move if menu is open
-    if ui_state.menu_open || ui_state.dialog_open {
+    if !keyboard.just_pressed(KeyCode::F6) {
+        return;
+    }
+    let mut names: Vec<&str> = spawn_points.iter().map(|p| p.name.as_str()).collect();
+    if names.is_empty() {
+        info!("No spawn points in this room");
         return;
     }
+    names.sort();
+    let name = names[*next % names.len()];
+    *next += 1;
+    info!("Teleporting to {}", name);
+    teleports.write(TeleportPlayerEvent { to: SpawnTarget::Named(name.to_string()), fade: true });
+}
+
+// Everything solid but the player, crates included
+type AllSolidsQuery<'w, 's> =
+    Query<'w, 's, (&'static Transform, &'static Sprite, Option<&'static Collider>), (With<Solid>, Without<Player>)>;
+
+// What the player bumps into; crates, which get pushed, are handled apart
+type SolidsQuery<'w, 's> =
+    Query<'w, 's, (&'static Transform, &'static Sprite, Option<&'static Collider>), (With<Solid>, Without<Player>, Without<Pushable>)>;
+
+type CratesQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Transform, &'static Sprite, Option<&'static Collider>),
+    (With<Pushable>, With<Solid>, Without<Player>),
+>;
+
+type FloorsQuery<'w, 's> =
+    Query<'w, 's, (&'static FloorModifier, &'static Transform, &'static Sprite), (Without<Player>, Without<Pushable>)>;
+
+// The player as it walks: a hidden one stays put
+type WalkerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Player,
+        &'static mut Transform,
+        &'static Collider,
+        Option<&'static mut AnimationState>,
+        Option<&'static mut ExternalImpulse>,
+        Option<&'static MoveTarget>,
+        Has<Sneaking>,
+        Option<&'static mut Dash>,
+    ),
+    (Without<Solid>, Without<Hidden>),
+>;
+
+// Which way the player is being steered: the keys, how they're bound, and whether anything is
+// holding the player still
+#[derive(SystemParam)]
+struct Steering<'w> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    input: Res<'w, InputMap>,
+    gestures: Res<'w, InputGestures>,
+    settings: Res<'w, Settings>,
+    locks: Res<'w, MovementLocks>,
+}
+
+// What's around the player to walk into, push or walk over
+#[derive(SystemParam)]
+struct Surroundings<'w, 's> {
+    solids: SolidsQuery<'w, 's>,
+    grid: Res<'w, SpatialGrid>,
+    crates: CratesQuery<'w, 's>,
+    floors: FloorsQuery<'w, 's>,
+}
+
+fn player_movement(
+    time: Res<Time>,
+    mut commands: Commands,
+    steering: Steering,
+    mut query: WalkerQuery,
+    surroundings: Surroundings,
+    mut dashed: EventWriter<DashedEvent>,
+) {
+    let Steering { keyboard, input, gestures, settings, locks } = steering;
+    let Surroundings { solids: solid_query, grid, crates: mut crate_query, floors: floor_query } = surroundings;
+    // Input is ignored while a menu, dialog, room change, etc. holds the player still,
+    // but impulses like knockback still carry them
+    let locked = locks.is_locked();
 
-    for (player, mut transform) in query.iter_mut() {
-        let mut movement = Vec2::ZERO;
-
-        if keyboard.pressed(KeyCode::KeyW) || keyboard.pressed(KeyCode::ArrowUp) {
-            movement.y += 1.0;
-        }
-        if keyboard.pressed(KeyCode::KeyS) || keyboard.pressed(KeyCode::ArrowDown) {
-            movement.y -= 1.0;
-        }
-        if keyboard.pressed(KeyCode::KeyA) || keyboard.pressed(KeyCode::ArrowLeft) {
-            movement.x -= 1.0;
-        }
-        if keyboard.pressed(KeyCode::KeyD) || keyboard.pressed(KeyCode::ArrowRight) {
-            movement.x += 1.0;
-        }
-
-        if movement.length() > 0.0 {
-            movement = movement.normalize();
-            // Proposed movement
-            let delta = movement * player.speed * time.delta_secs();
-
-            // Player AABB (half extents) — approximate sprite size
-            let half = Vec2::new(8.0, 10.0);
-
-            // Move X then Y, resolving collisions against solids (AABB)
-            // X axis
-            transform.translation.x += delta.x;
-            // Query solids in the world and resolve overlaps
-            for (solid_tf, sprite) in solid_query.iter() {
-                let solid_size = sprite.custom_size.unwrap_or(Vec2::splat(16.0));
-                let s_half = solid_size / 2.0;
-                let s_min_x = solid_tf.translation.x - s_half.x;
-                let s_max_x = solid_tf.translation.x + s_half.x;
-                let s_min_y = solid_tf.translation.y - s_half.y;
-                let s_max_y = solid_tf.translation.y + s_half.y;
-
-                let player_min_x = transform.translation.x - half.x;
-                let player_max_x = transform.translation.x + half.x;
-                let player_min_y = transform.translation.y - half.y;
-                let player_max_y = transform.translation.y + half.y;
-
-                let overlap_x = player_max_x > s_min_x && player_min_x < s_max_x;
-                let overlap_y = player_max_y > s_min_y && player_min_y < s_max_y;
-                if overlap_x && overlap_y {
-                    // Push out along X based on direction
-                    if delta.x > 0.0 {
-                        transform.translation.x = s_min_x - half.x;
-                    } else if delta.x < 0.0 {
-                        transform.translation.x = s_max_x + half.x;
-                    }
+    for (entity, mut player, mut transform, collider, anim, impulse, target, sneaking, dash) in query.iter_mut() {
+        let mut delta = Vec2::ZERO;
+        if let Some(mut impulse) = impulse {
+            match impulse.advance(time.delta_secs()) {
+                Some(push) => delta += push,
+                None => {
+                    commands.entity(entity).remove::<ExternalImpulse>();
                 }
             }
+        }
 
-            // Y axis
-            transform.translation.y += delta.y;
-            for (solid_tf, sprite) in solid_query.iter() {
-                let solid_size = sprite.custom_size.unwrap_or(Vec2::splat(16.0));
-                let s_half = solid_size / 2.0;
-                let s_min_x = solid_tf.translation.x - s_half.x;
-                let s_max_x = solid_tf.translation.x + s_half.x;
-                let s_min_y = solid_tf.translation.y - s_half.y;
-                let s_max_y = solid_tf.translation.y + s_half.y;
-
-                let player_min_x = transform.translation.x - half.x;
-                let player_max_x = transform.translation.x + half.x;
-                let player_min_y = transform.translation.y - half.y;
-                let player_max_y = transform.translation.y + half.y;
-
-                let overlap_x = player_max_x > s_min_x && player_min_x < s_max_x;
-                let overlap_y = player_max_y > s_min_y && player_min_y < s_max_y;
-                if overlap_x && overlap_y {
-                    if delta.y > 0.0 {
-                        transform.translation.y = s_min_y - half.y;
-                    } else if delta.y < 0.0 {
-                        transform.translation.y = s_max_y + half.y;
-                    }
+        let mut walk = Vec2::ZERO;
+        // Read where the feet are now, so stepping off a region is full speed straight away
+        let speed = player.speed * floor_speed(collider.feet(transform.translation.truncate()), floor_query.iter());
+        let heading = if locked { Vec2::ZERO } else { input_direction(&mut player, &keyboard, &input, &settings) };
+        let mut dashing = None;
+        if let Some(mut dash) = dash {
+            // Double-tapping a direction dashes that way too
+            let double_tapped = MOVE_ACTIONS.iter().any(|&action| gestures.double_tapped(action));
+            let pressed = input.just_pressed(Action::Dash, &keyboard) || double_tapped;
+            if !locked && pressed && dash.start(heading) {
+                dashed.write(DashedEvent { direction: dash.direction });
+            }
+            // Lost rather than paused if something locks the player mid-dash
+            let secs = dash.advance(time.delta_secs());
+            if secs > 0.0 {
+                dashing = Some(dash.direction * speed * DASH_SPEED_MULTIPLIER * secs);
+            }
+        }
+        if !locked {
+            let step = speed * player.gait(sneaking) * time.delta_secs();
+            // The keyboard takes over from a click
+            if heading != Vec2::ZERO && target.is_some() {
+                commands.entity(entity).remove::<MoveTarget>();
+            }
+            if let Some(dash_step) = dashing {
+                walk = dash_step;
+            } else if heading != Vec2::ZERO {
+                walk = heading * step;
+            } else if let Some(target) = target {
+                walk = step_toward(transform.translation.truncate(), target.0, step);
+                if walk == Vec2::ZERO {
+                    commands.entity(entity).remove::<MoveTarget>();
                 }
             }
+            if let Some(mut anim) = anim {
+                anim.moving = walk != Vec2::ZERO;
+            }
+        }
+        delta += walk;
+
+        if delta != Vec2::ZERO {
+            let before = transform.translation.truncate();
+            let nearby = grid.solids_near(movement_area(before, collider, delta));
+            let solids: Vec<_> = nearby.into_iter().filter_map(|e| solid_query.get(e).ok()).collect();
+            let mut crates: Vec<_> = crate_query.iter_mut().collect();
+            move_player(&mut transform.translation, collider, delta, &solids, &mut crates);
+            // Walked into something on the way to a clicked spot; give up rather than push forever
+            let moved = transform.translation.truncate() - before;
+            if target.is_some() && walk != Vec2::ZERO && moved.length() < walk.length() * 0.1 {
+                commands.entity(entity).remove::<MoveTarget>();
+            }
         }
     }
 }
 
+// Adds or removes Sneaking as the sneak key is held; not while locked in place or hidden
+fn update_sneaking(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    locks: Res<MovementLocks>,
+    query: Query<(Entity, Has<Sneaking>, Has<Hidden>), With<Player>>,
+) {
+    let held = input.pressed(Action::Sneak, &keyboard) && !locks.is_locked();
+    for (entity, sneaking, hidden) in query.iter() {
+        let held = held && !hidden;
+        if held && !sneaking {
+            commands.entity(entity).insert(Sneaking);
+        } else if !held && sneaking {
+            commands.entity(entity).remove::<Sneaking>();
+        }
+    }
+}
+
+// Up to `step` along the line from `pos` to `target`, or zero once within MOVE_TARGET_REACHED
+pub fn step_toward(pos: Vec2, target: Vec2, step: f32) -> Vec2 {
+    let to = target - pos;
+    let distance = to.length();
+    if distance <= MOVE_TARGET_REACHED {
+        return Vec2::ZERO;
+    }
+    to / distance * step.min(distance)
+}
+
+// Which way the movement keys point (unit length or zero); also updates running
+fn input_direction(
+    player: &mut Player,
+    keyboard: &ButtonInput<KeyCode>,
+    input: &InputMap,
+    settings: &Settings,
+) -> Vec2 {
+    player.running = if settings.toggle_run {
+        player.running != input.just_pressed(Action::Run, keyboard)
+    } else {
+        input.pressed(Action::Run, keyboard)
+    };
+
+    let mut movement = Vec2::ZERO;
+
+    if input.pressed(Action::MoveUp, keyboard) {
+        movement.y += 1.0;
+    }
+    if input.pressed(Action::MoveDown, keyboard) {
+        movement.y -= 1.0;
+    }
+    if input.pressed(Action::MoveLeft, keyboard) {
+        movement.x -= 1.0;
+    }
+    if input.pressed(Action::MoveRight, keyboard) {
+        movement.x += 1.0;
+    }
+    movement.normalize_or_zero()
+}
+
+// A solid's collision box, from its Collider or else its sprite
+pub fn solid_box(tf: &Transform, sprite: &Sprite, collider: Option<&Collider>) -> Aabb {
+    Collider::of(sprite, collider).aabb(tf.translation.truncate())
+}
+
+fn solid_boxes(solids: &[(&Transform, &Sprite, Option<&Collider>)]) -> Vec<Aabb> {
+    solids.iter().map(|(tf, sprite, c)| solid_box(tf, sprite, *c)).collect()
+}
+
+// Puts `translation` where `collider`'s box has its middle at `center`, keeping its depth
+fn place(translation: &mut Vec3, collider: &Collider, center: Vec2) {
+    translation.x = center.x - collider.offset.x;
+    translation.y = center.y - collider.offset.y;
+}
+
+// Whether `collider` at `pos` is clear of every solid
+pub fn fits(pos: Vec2, collider: &Collider, solids: &[(&Transform, &Sprite, Option<&Collider>)]) -> bool {
+    collision::fits(collider.aabb(pos), &solid_boxes(solids))
+}
+
+// Where `collider` at `pos` should go to stop overlapping the solids: out along the shallower
+// axis of each overlap, or the nearest free spot if that lands in something else. None if it's
+// already clear.
+pub fn depenetrate(pos: Vec2, collider: &Collider, solids: &[(&Transform, &Sprite, Option<&Collider>)]) -> Option<Vec2> {
+    if fits(pos, collider, solids) {
+        return None;
+    }
+    let out = solid_boxes(solids)
+        .into_iter()
+        .fold(pos, |out, solid| out + penetration(collider.aabb(out), solid).unwrap_or(Vec2::ZERO));
+    Some(if fits(out, collider, solids) { out } else { nearest_free_spot(pos, collider, solids) })
+}
+
+type UnhiddenPlayerQuery<'w, 's> =
+    Query<'w, 's, (&'static mut Transform, &'static Collider), (With<Player>, Without<Hidden>)>;
+
+fn depenetrate_player(mut player_query: UnhiddenPlayerQuery, solid_query: AllSolidsQuery) {
+    let Ok((mut transform, collider)) = player_query.single_mut() else { return };
+    let solids: Vec<_> = solid_query.iter().collect();
+    if let Some(free) = depenetrate(transform.translation.truncate(), collider, &solids) {
+        info!("Player was stuck in something solid; moved out to {}", free);
+        transform.translation.x = free.x;
+        transform.translation.y = free.y;
+    }
+}
+
+// Spacing and reach of the rings searched for a free spot around a blocked destination
+const FREE_SPOT_STEP: f32 = 4.0;
+const FREE_SPOT_RINGS: u32 = 24;
+
+// The closest spot to `pos` where `collider` fits, checking rings of 16 points outward
+pub fn nearest_free_spot(pos: Vec2, collider: &Collider, solids: &[(&Transform, &Sprite, Option<&Collider>)]) -> Vec2 {
+    let boxes = solid_boxes(solids);
+    let free = |spot: Vec2| collision::fits(collider.aabb(spot), &boxes);
+    if free(pos) {
+        return pos;
+    }
+    for ring in 1..=FREE_SPOT_RINGS {
+        let radius = ring as f32 * FREE_SPOT_STEP;
+        for i in 0..16 {
+            let spot = pos + Vec2::from_angle(i as f32 * std::f32::consts::TAU / 16.0) * radius;
+            if free(spot) {
+                return spot;
+            }
+        }
+    }
+    warn!("No free spot near {}", pos);
+    pos
+}
+
+// Everything a move by `delta` from `pos` could bump into lies within this, corner nudges
+// included
+pub fn movement_area(pos: Vec2, collider: &Collider, delta: Vec2) -> Rect {
+    let center = collider.center(pos) + delta / 2.0;
+    Rect::from_center_half_size(center, collider.half_extents + delta.abs() / 2.0 + Vec2::splat(CORNER_NUDGE))
+}
+
+// Walks the player by `delta`, X then Y in small steps, stopping at solids and shoving crates
+pub fn move_player<T: DerefMut<Target = Transform>>(
+    translation: &mut Vec3,
+    collider: &Collider,
+    delta: Vec2,
+    solids: &[(&Transform, &Sprite, Option<&Collider>)],
+    crates: &mut [(T, &Sprite, Option<&Collider>)],
+) {
+    let mut crate_boxes: Vec<_> = crates.iter().map(|(tf, sprite, c)| solid_box(tf, sprite, *c)).collect();
+    let center = move_and_collide(collider.aabb(translation.truncate()), delta, &solid_boxes(solids), &mut crate_boxes);
+    place(translation, collider, center);
+    for ((crate_tf, sprite, c), moved) in crates.iter_mut().zip(crate_boxes) {
+        let body = Collider::of(sprite, *c);
+        if moved.center != body.center(crate_tf.translation.truncate()) {
+            place(&mut crate_tf.translation, &body, moved.center);
+        }
+    }
+}
+
+// Pushes a box that just moved by `delta` (along one axis) back out of any solid it now overlaps.
+// Shared by everything that walks: the player and critters.
+pub fn push_out_of_solids<'a>(
+    translation: &mut Vec3,
+    collider: &Collider,
+    delta: Vec2,
+    solids: impl IntoIterator<Item = (&'a Transform, &'a Sprite, Option<&'a Collider>)>,
+) {
+    let boxes = solids.into_iter().map(|(tf, sprite, c)| solid_box(tf, sprite, c));
+    let center = push_out(collider.aabb(translation.truncate()), delta, boxes);
+    place(translation, collider, center);
+}
+
+const DIRECTION_ACTIONS: [(Action, Direction); 4] = [
+    (Action::MoveUp, Direction::Up),
+    (Action::MoveDown, Direction::Down),
+    (Action::MoveLeft, Direction::Left),
+    (Action::MoveRight, Direction::Right),
+];
+
+// Movement directions being held, most recently pressed first. Facing follows the newest
+// press, so holding W then adding D turns right, and letting go of D turns back up.
+#[derive(Default, Debug)]
+pub struct HeldDirections(Vec<Direction>);
+
+impl HeldDirections {
+    pub fn update(&mut self, input: &InputMap, keyboard: &ButtonInput<KeyCode>) {
+        for (action, direction) in DIRECTION_ACTIONS {
+            if !input.pressed(action, keyboard) {
+                self.0.retain(|d| *d != direction);
+            } else if input.just_pressed(action, keyboard) || !self.0.contains(&direction) {
+                // Also catches keys that went down while we weren't looking
+                self.0.retain(|d| *d != direction);
+                self.0.insert(0, direction);
+            }
+        }
+    }
+
+    pub fn latest(&self) -> Option<Direction> {
+        self.0.first().copied()
+    }
+}
+
 fn update_player_facing(
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut query: Query<&mut Player>,
-    ui_state: Res<crate::ui::UiState>,
+    input: Res<InputMap>,
+    mut held: Local<HeldDirections>,
+    mut query: Query<(&mut Player, &Transform, Option<&MoveTarget>), Without<Hidden>>,
+    locks: Res<MovementLocks>,
+) {
+    // Tracked even while locked so the order is right when the player can move again
+    held.update(&input, &keyboard);
+    if locks.is_locked() {
+        return;
+    }
+
+    for (mut player, transform, target) in query.iter_mut() {
+        if let Some(direction) = held.latest() {
+            player.facing = direction;
+        } else if let Some(target) = target {
+            let to = target.0 - transform.translation.truncate();
+            if to.length() > MOVE_TARGET_REACHED {
+                player.facing = Direction::toward(to);
+            }
+        }
+    }
+}
+
+// What the ground is made of, for footstep sounds. Set by FloorModifier regions; anywhere else
+// is Concrete.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Reflect)]
+pub enum FloorSurface {
+    #[default]
+    Concrete,
+    Metal,
+    Carpet,
+    Oil,
+}
+
+impl FloorSurface {
+    pub const ALL: [FloorSurface; 4] = [Self::Concrete, Self::Metal, Self::Carpet, Self::Oil];
+
+    fn sample_prefix(self) -> &'static str {
+        match self {
+            Self::Concrete => "concrete",
+            Self::Metal => "metal",
+            Self::Carpet => "carpet",
+            Self::Oil => "oil",
+        }
+    }
+}
+
+// The floor regions whose sprites contain `pos`
+fn floors_at<'a>(
+    pos: Vec2,
+    floors: impl IntoIterator<Item = (&'a FloorModifier, &'a Transform, &'a Sprite)>,
+) -> impl Iterator<Item = (&'a FloorModifier, &'a Transform)> {
+    floors.into_iter().filter_map(move |(floor, tf, sprite)| {
+        Collider::of(sprite, None).contains(tf.translation.truncate(), pos).then_some((floor, tf))
+    })
+}
+
+// The surface of the topmost floor region containing `pos`
+pub fn floor_under<'a>(
+    pos: Vec2,
+    floors: impl IntoIterator<Item = (&'a FloorModifier, &'a Transform, &'a Sprite)>,
+) -> FloorSurface {
+    floors_at(pos, floors)
+        .max_by(|(_, a), (_, b)| a.translation.z.total_cmp(&b.translation.z))
+        .map_or_else(FloorSurface::default, |(floor, _)| floor.surface)
+}
+
+// Speed multiplier for feet at `pos`: every region containing it, multiplied and clamped
+pub fn floor_speed<'a>(
+    pos: Vec2,
+    floors: impl IntoIterator<Item = (&'a FloorModifier, &'a Transform, &'a Sprite)>,
+) -> f32 {
+    floors_at(pos, floors)
+        .map(|(floor, _)| floor.speed_mult)
+        .product::<f32>()
+        .clamp(MIN_FLOOR_SPEED, MAX_FLOOR_SPEED)
+}
+
+// Step samples per surface, from assets/audio/footsteps/<surface>_<n>.ogg. Only files that
+// exist are loaded, so a surface with no samples (or a build without them) is simply silent.
+#[derive(Resource, Default)]
+pub struct FootstepSounds(pub HashMap<FloorSurface, Vec<Handle<AudioSource>>>);
+
+const FOOTSTEP_DIR: &str = "audio/footsteps";
+const FOOTSTEP_SAMPLES: usize = 4; // Looked for per surface, numbered from 1
+const FOOTSTEP_VOLUME: f32 = 0.5;
+const MAX_WALK_PER_FRAME: f32 = 32.0; // Anything further is a teleport, not a step
+
+fn load_footstep_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let base = FileAssetReader::get_base_path().join("assets");
+    let sounds = FloorSurface::ALL
+        .into_iter()
+        .map(|surface| {
+            let handles = (1..=FOOTSTEP_SAMPLES)
+                .map(|n| format!("{}/{}_{}.ogg", FOOTSTEP_DIR, surface.sample_prefix(), n))
+                .filter(|path| base.join(path).exists())
+                .map(|path| asset_server.load(path))
+                .collect();
+            (surface, handles)
+        })
+        .collect();
+    commands.insert_resource(FootstepSounds(sounds));
+}
+
+// A step sounds when a foot comes down in the walk cycle (see AnimClip::Walk), so they keep
+// time with the legs at any speed
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Footsteps {
+    pub volume: f32, // 0.0 to 1.0
+    last_pos: Option<Vec2>,
+}
+
+impl Footsteps {
+    pub fn new(volume: f32) -> Self {
+        Self { volume, last_pos: None }
+    }
+}
+
+// Checks movement after collisions, so walking on the spot into a wall stays quiet
+fn play_footsteps(
+    mut anim_events: EventReader<AnimationEvent>,
+    mut sfx: EventWriter<PlaySfxEvent>,
+    sounds: Option<Res<FootstepSounds>>,
+    loaded: Option<Res<Assets<AudioSource>>>,
+    floors: Query<(&FloorModifier, &Transform, &Sprite), Without<Player>>,
+    mut player_query: Query<(Entity, &Transform, &Collider, &mut Footsteps), With<Player>>,
+    mut rng: ResMut<GameRng>,
 ) {
-    if ui_state.menu_open || ui_state.dialog_open {
+    let Ok((entity, transform, collider, mut steps)) = player_query.single_mut() else { return };
+    let pos = transform.translation.truncate();
+    let last = steps.last_pos.replace(pos);
+    let footfall = anim_events.read().any(|e| e.entity == entity && e.clip == AnimClip::Walk);
+    let Some(last) = last.filter(|_| footfall) else { return };
+    // Pushing against something, or carried further than a step rather than walking
+    let moved = pos.distance(last);
+    if moved <= 0.0 || moved > MAX_WALK_PER_FRAME {
+        return;
+    }
+
+    let surface = floor_under(collider.feet(pos), floors.iter());
+    let Some(samples) = sounds.as_ref().and_then(|s| s.0.get(&surface)) else { return };
+    // Only draw once they're all in, so how fast they load can't change what gets drawn
+    if !loaded.is_some_and(|assets| samples.iter().all(|sample| assets.contains(sample))) {
         return;
     }
+    let Some(sample) = rng.pick(samples) else { return };
+    sfx.write(PlaySfxEvent::at(SfxId::Footstep, pos).with_volume(steps.volume).with_sample(sample.clone()));
+}
+
+type AnimatedPlayerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Player,
+        &'static mut AnimationState,
+        &'static mut Sprite,
+        Has<Sneaking>,
+        Option<&'static mut IdleTimer>,
+        Option<&'static mut AnimationOverride>,
+    ),
+>;
+
+// Steps through the walk cycle while moving; stands in the idle frame otherwise,
+// including whenever movement is locked
+fn animate_player(
+    time: Res<Time>,
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    locks: Res<MovementLocks>,
+    (frame_events, mut anim_events): (Res<FrameEvents>, EventWriter<AnimationEvent>),
+    mut query: AnimatedPlayerQuery,
+) {
+    let frozen = locks.is_locked();
+    let pressed_anything = keyboard.get_just_pressed().next().is_some() || mouse.get_just_pressed().next().is_some();
+    for (entity, player, mut anim, mut sprite, sneaking, idle, mut playing) in query.iter_mut() {
+        anim.facing = player.facing;
+        if anim.moving && !frozen {
+            anim.timer.tick(time.delta().mul_f32(player.gait(sneaking)));
+            if anim.timer.just_finished() {
+                anim.frame = (anim.frame + 1) % WALK_FRAMES;
+                frame_events.entered(&mut anim_events, entity, AnimClip::Walk, anim.frame);
+            }
+        } else {
+            anim.frame = 0;
+            anim.timer.reset();
+        }
+
+        // Fidget after standing around; anything the player does cuts it short
+        let active = (anim.moving && !frozen) || pressed_anything;
+        if let Some(mut idle) = idle {
+            if idle.tick(time.delta_secs(), active || playing.is_some()) {
+                playing = None;
+                commands.entity(entity).insert(AnimationOverride::once(SpriteClip::look_around()));
+            } else if active && playing.as_ref().is_some_and(|p| p.clip == SpriteClip::look_around()) {
+                playing = None;
+                commands.entity(entity).remove::<AnimationOverride>();
+            }
+        }
+
+        // An override wins over the facing every frame until it runs out
+        let index = match playing.as_mut().and_then(|p| p.advance(time.delta_secs())) {
+            Some(frame) => frame,
+            None => {
+                if playing.is_some() {
+                    commands.entity(entity).remove::<AnimationOverride>();
+                }
+                anim.atlas_index()
+            }
+        };
+        // Only touch the sprite when the frame actually changes
+        if sprite.texture_atlas.as_ref().is_some_and(|a| a.index != index)
+            && let Some(atlas) = sprite.texture_atlas.as_mut()
+        {
+            atlas.index = index;
+        }
+    }
+}
+
+const DAMAGE_FLASH_SECS: f32 = 0.15;
+const FLINCH_SECS: f32 = 0.3;
+const DAMAGE_FLASH_COLOR: Color = Color::srgb(1.0, 0.15, 0.15);
 
-    for mut player in query.iter_mut() {
-        if keyboard.pressed(KeyCode::KeyW) || keyboard.pressed(KeyCode::ArrowUp) {
-            player.facing = Direction::Up;
-        } else if keyboard.pressed(KeyCode::KeyS) || keyboard.pressed(KeyCode::ArrowDown) {
-            player.facing = Direction::Down;
-        } else if keyboard.pressed(KeyCode::KeyA) || keyboard.pressed(KeyCode::ArrowLeft) {
-            player.facing = Direction::Left;
-        } else if keyboard.pressed(KeyCode::KeyD) || keyboard.pressed(KeyCode::ArrowRight) {
-            player.facing = Direction::Right;
+// Tints the player red for a moment after a hit, then restores `color`
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct DamageFlash {
+    timer: Timer,
+    color: Color,
+}
+
+type HurtPlayerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static mut PlayerStats, &'static Transform, &'static Sprite, Option<&'static DamageFlash>),
+    With<Player>,
+>;
+
+fn apply_damage(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    (mut heal_events, mut respawns): (EventReader<HealEvent>, EventReader<PlayerRespawnedEvent>),
+    (mut died, mut time_scale): (EventWriter<PlayerDiedEvent>, EventWriter<SetTimeScaleEvent>),
+    sources: Query<(Option<&Name>, Option<&Transform>)>,
+    mut query: HurtPlayerQuery,
+) {
+    let Ok((player, mut stats, player_tf, sprite, flash)) = query.single_mut() else { return };
+    if stats.invulnerable_for > 0.0 {
+        // Not a change anyone watching HP cares about
+        stats.bypass_change_detection().tick(time.delta_secs());
+    }
+    for event in damage_events.read() {
+        if !stats.take_damage(event.amount) {
+            continue;
+        }
+        let (name, source_tf) = event.source.and_then(|e| sources.get(e).ok()).unwrap_or_default();
+        let source = name.map_or("Something", |n| n.as_str());
+        info!("{} dealt {} damage, HP {}/{}", source, event.amount, stats.hp, stats.max_hp);
+        if event.knockback > 0.0 && let Some(source_tf) = source_tf {
+            // Straight up if the player is dead centre on whatever hit them
+            let away = (player_tf.translation - source_tf.translation).truncate();
+            apply_knockback(&mut commands, player, away.try_normalize().unwrap_or(Vec2::Y), event.knockback);
+        }
+        commands.entity(player).insert((
+            DamageFlash {
+                timer: Timer::from_seconds(DAMAGE_FLASH_SECS, TimerMode::Once),
+                color: flash.map_or(sprite.color, |f| f.color),
+            },
+            // Flinch toward the viewer
+            AnimationOverride::new(SpriteClip::surprised(), FLINCH_SECS),
+        ));
+        // A beat of slow motion so the hit lands
+        time_scale.write(SetTimeScaleEvent { scale: HIT_STOP_SCALE, duration: Some(HIT_STOP_SECS) });
+        if stats.is_down() {
+            died.write(PlayerDiedEvent);
         }
     }
+    for event in heal_events.read() {
+        stats.heal(event.amount);
+    }
+    if respawns.read().count() > 0 {
+        stats.revive();
+    }
 }
 
-// Sprite::size() provides the logical size set at spawn for our AABB.
\ No newline at end of file
+fn flash_on_damage(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut DamageFlash, &mut Sprite)>,
+) {
+    for (entity, mut flash, mut sprite) in query.iter_mut() {
+        flash.timer.tick(time.delta());
+        if flash.timer.finished() {
+            sprite.color = flash.color;
+            commands.entity(entity).remove::<DamageFlash>();
+        } else {
+            sprite.color = DAMAGE_FLASH_COLOR;
+        }
+    }
+}
+
+// Sprite::size() provides the logical size set at spawn for our AABB.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::slide_axis;
+    use crate::objects::{Critter, CRITTER_NOTICE_RADIUS, ELECTRIC_KNOCKBACK};
+
+    #[test]
+    fn collider_defaults_to_sprite_box() {
+        let (_, sprite) = wall_at(0.0, 0.0, 32.0, 40.0);
+        assert_eq!(Collider::of(&sprite, None), Collider::new(Vec2::new(16.0, 20.0)));
+        let feet = Collider { half_extents: Vec2::new(8.0, 4.0), offset: Vec2::new(0.0, -6.0) };
+        assert_eq!(Collider::of(&sprite, Some(&feet)), feet);
+    }
+
+    #[test]
+    fn player_stops_flush_against_walls_on_every_side() {
+        // Same results the old fixed 8x10 half extents gave
+        let player = Collider::new(PLAYER_HALF_EXTENTS);
+        let (wall_tf, wall) = wall_at(0.0, 0.0, 20.0, 20.0);
+        let cases = [
+            (Vec3::new(-15.0, 0.0, 0.0), Vec2::X, Vec3::new(-18.0, 0.0, 0.0)),
+            (Vec3::new(15.0, 0.0, 0.0), Vec2::NEG_X, Vec3::new(18.0, 0.0, 0.0)),
+            (Vec3::new(0.0, -15.0, 0.0), Vec2::Y, Vec3::new(0.0, -20.0, 0.0)),
+            (Vec3::new(0.0, 15.0, 0.0), Vec2::NEG_Y, Vec3::new(0.0, 20.0, 0.0)),
+        ];
+        for (start, delta, expected) in cases {
+            let mut pos = start;
+            push_out_of_solids(&mut pos, &player, delta, [(&wall_tf, &wall, None)]);
+            assert_eq!(pos, expected, "moving {delta}");
+        }
+        // Not touching: left alone
+        let mut pos = Vec3::new(-30.0, 0.0, 0.0);
+        push_out_of_solids(&mut pos, &player, Vec2::X, [(&wall_tf, &wall, None)]);
+        assert_eq!(pos.x, -30.0);
+    }
+
+    #[test]
+    fn offset_collider_lets_sprite_overlap_walls() {
+        // Feet box in the bottom of a 16x20 sprite: the head may pass in front of a wall above
+        let feet = Collider { half_extents: Vec2::new(8.0, 4.0), offset: Vec2::new(0.0, -6.0) };
+        let (wall_tf, wall) = wall_at(0.0, 20.0, 40.0, 10.0);
+        let mut pos = Vec3::new(0.0, 8.0, 0.0);
+        push_out_of_solids(&mut pos, &feet, Vec2::Y, [(&wall_tf, &wall, None)]);
+        assert_eq!(pos.y, 8.0);
+        pos.y = 20.0;
+        push_out_of_solids(&mut pos, &feet, Vec2::Y, [(&wall_tf, &wall, None)]);
+        assert_eq!(pos.y, 17.0);
+    }
+
+    // Walks `steps` frames of `velocity`, X then Y, as player_movement does
+    fn walk(start: Vec2, velocity: Vec2, steps: usize, walls: &[(Transform, Sprite)]) -> Vec2 {
+        let player = Collider::new(PLAYER_HALF_EXTENTS);
+        let solids: Vec<_> = walls.iter().map(|(tf, sprite)| solid_box(tf, sprite, None)).collect();
+        let mut pos = start;
+        for _ in 0..steps {
+            pos = slide_axis(player.aabb(pos), Vec2::new(velocity.x, 0.0), &solids);
+            pos = slide_axis(player.aabb(pos), Vec2::new(0.0, velocity.y), &solids);
+        }
+        pos
+    }
+
+    #[test]
+    fn diagonal_into_inside_corner_stops_in_the_corner() {
+        let walls = [wall_at(50.0, 0.0, 20.0, 200.0), wall_at(0.0, 50.0, 200.0, 20.0)];
+        assert_eq!(walk(Vec2::ZERO, Vec2::new(2.0, 2.0), 40, &walls), Vec2::new(32.0, 30.0));
+    }
+
+    #[test]
+    fn slides_over_a_small_step_between_wall_segments() {
+        // Floor of two segments; the second sits 1px higher
+        let walls = [wall_at(-50.0, 0.0, 100.0, 20.0), wall_at(50.0, 1.0, 100.0, 20.0)];
+        assert_eq!(walk(Vec2::new(-40.0, 20.0), Vec2::new(2.0, -2.0), 40, &walls), Vec2::new(40.0, 21.0));
+        // Perfectly abutting segments are no obstacle either
+        let walls = [wall_at(-50.0, 0.0, 100.0, 20.0), wall_at(50.0, 0.0, 100.0, 20.0)];
+        assert_eq!(walk(Vec2::new(-40.0, 20.0), Vec2::new(2.0, -2.0), 40, &walls), Vec2::new(40.0, 20.0));
+    }
+
+    #[test]
+    fn larger_steps_still_block() {
+        let walls = [wall_at(-50.0, 0.0, 100.0, 20.0), wall_at(50.0, CORNER_NUDGE + 2.0, 100.0, 20.0)];
+        assert_eq!(walk(Vec2::new(-40.0, 20.0), Vec2::new(2.0, -2.0), 40, &walls), Vec2::new(-8.0, 20.0));
+    }
+
+    #[test]
+    fn overlapping_wall_segments_cannot_be_slipped_through() {
+        // Vertical wall of two segments overlapping by 1px at y = 0
+        let walls = [wall_at(20.0, -49.5, 10.0, 100.0), wall_at(20.0, 50.0, 10.0, 100.0)];
+        for y in [-2.0, 0.0, 0.5, 2.0] {
+            assert_eq!(walk(Vec2::new(0.0, y), Vec2::new(2.0, 0.0), 20, &walls), Vec2::new(7.0, y));
+        }
+    }
+
+    #[test]
+    fn teleport_destination_is_nudged_out_of_walls() {
+        let player = Collider::new(PLAYER_HALF_EXTENTS);
+        let (wall_tf, wall) = wall_at(0.0, 0.0, 20.0, 20.0);
+        let solids = [(&wall_tf, &wall, None)];
+        assert_eq!(nearest_free_spot(Vec2::new(0.0, 40.0), &player, &solids), Vec2::new(0.0, 40.0));
+        // Buried in the wall: the nearest clear ring is 20px out, first found to the right
+        assert_eq!(nearest_free_spot(Vec2::ZERO, &player, &solids), Vec2::new(20.0, 0.0));
+        // Only just clipping it: a short hop up
+        let hop = nearest_free_spot(Vec2::new(0.0, 16.0), &player, &solids);
+        assert!(hop.abs_diff_eq(Vec2::new(0.0, 20.0), 1e-4), "{hop}");
+    }
+
+    #[test]
+    fn movement_locks_nest() {
+        let mut locks = MovementLocks::default();
+        assert!(!locks.is_locked());
+        locks.lock(); // Dialog opens
+        locks.lock(); // Cutscene starts
+        locks.unlock(); // Dialog closes; the cutscene still holds the player
+        assert!(locks.is_locked());
+        locks.unlock();
+        assert!(!locks.is_locked());
+        // A stray unlock doesn't bank a free pass for the next lock
+        locks.unlock();
+        locks.lock();
+        assert!(locks.is_locked());
+    }
+
+    #[test]
+    fn floor_surface_comes_from_the_topmost_region() {
+        let (plate_tf, plate) = wall_at(0.0, 0.0, 24.0, 24.0);
+        let (mut hall_tf, hall) = wall_at(0.0, 0.0, 200.0, 200.0);
+        hall_tf.translation.z = -1.0;
+        let metal = FloorModifier { speed_mult: 1.0, surface: FloorSurface::Metal };
+        let concrete = FloorModifier { speed_mult: 1.0, surface: FloorSurface::Concrete };
+        let floors = [(&metal, &plate_tf, &plate), (&concrete, &hall_tf, &hall)];
+        assert_eq!(floor_under(Vec2::new(5.0, -5.0), floors), FloorSurface::Metal);
+        assert_eq!(floor_under(Vec2::new(50.0, 0.0), floors), FloorSurface::Concrete);
+        assert_eq!(floor_under(Vec2::new(500.0, 0.0), floors), FloorSurface::Concrete);
+        assert_eq!(floor_under(Vec2::ZERO, []), FloorSurface::Concrete);
+    }
+
+    #[test]
+    fn long_frames_cannot_tunnel_through_thin_walls() {
+        let player = Collider::new(PLAYER_HALF_EXTENTS);
+        let (wall_tf, wall) = wall_at(20.0, 0.0, 12.0, 100.0);
+        let solids = [(&wall_tf, &wall, None)];
+        let mut no_crates: [(&mut Transform, &Sprite, Option<&Collider>); 0] = [];
+        // One 50ms frame at speed 400, then a 200ms hitch at the same speed
+        for delta in [400.0 * 0.05, 400.0 * 0.2] {
+            let mut pos = Vec3::ZERO;
+            move_player(&mut pos, &player, Vec2::new(delta, 0.0), &solids, &mut no_crates);
+            assert_eq!(pos.x, 6.0, "{delta}px frame");
+        }
+    }
+
+    #[test]
+    fn substeps_shove_crates_the_whole_way() {
+        let player = Collider::new(PLAYER_HALF_EXTENTS);
+        let (mut crate_tf, crate_sprite) = wall_at(20.0, 0.0, 20.0, 20.0);
+        let mut pos = Vec3::ZERO;
+        move_player(&mut pos, &player, Vec2::new(10.0, 0.0), &[], &mut [(&mut crate_tf, &crate_sprite, None)]);
+        assert_eq!(pos.x, 10.0);
+        assert_eq!(crate_tf.translation.x, 28.0); // Flush against the player's front
+
+        // A crate that can't go the whole step (1px short of a wall) stops the player instead
+        let (wall_tf, wall) = wall_at(44.0, 0.0, 10.0, 100.0);
+        let crates = &mut [(&mut crate_tf, &crate_sprite, None)];
+        move_player(&mut pos, &player, Vec2::new(10.0, 0.0), &[(&wall_tf, &wall, None)], crates);
+        assert!((pos.x - 10.0).abs() < 1e-4);
+        assert_eq!(crate_tf.translation.x, 28.0);
+    }
+
+    #[test]
+    fn hits_during_i_frames_are_ignored() {
+        let mut stats = PlayerStats::new(20);
+        assert!(stats.take_damage(3));
+        assert!(!stats.take_damage(3)); // Same frame
+        stats.tick(INVULNERABLE_SECS - 0.1);
+        assert!(!stats.take_damage(3));
+        stats.tick(0.2);
+        assert!(stats.take_damage(3));
+        assert_eq!(stats.hp, 14);
+    }
+
+    #[test]
+    fn hp_clamps_at_zero_and_max() {
+        let mut stats = PlayerStats::new(20);
+        assert!(stats.take_damage(50));
+        assert_eq!(stats.hp, 0);
+        assert!(stats.is_down());
+        // Nothing more lands once down, even after the i-frames
+        stats.tick(INVULNERABLE_SECS);
+        assert!(!stats.take_damage(1));
+
+        stats.heal(5);
+        assert_eq!(stats.hp, 5);
+        stats.heal(HealEvent::full().amount);
+        assert_eq!(stats.hp, 20);
+        stats.heal(-4); // Negative heals aren't damage in disguise
+        assert_eq!(stats.hp, 20);
+
+        // Coming back from going down: full HP, and the save point's hazards can't land at once
+        assert!(stats.take_damage(50));
+        stats.tick(INVULNERABLE_SECS);
+        stats.revive();
+        assert_eq!(stats.hp, 20);
+        stats.tick(INVULNERABLE_SECS);
+        assert!(!stats.take_damage(1));
+        stats.tick(RESPAWN_INVULNERABLE_SECS - INVULNERABLE_SECS);
+        assert!(stats.take_damage(1));
+    }
+
+    #[test]
+    fn knockback_dies_down_within_a_third_of_a_second() {
+        let mut impulse = ExternalImpulse { velocity: Vec2::new(ELECTRIC_KNOCKBACK, 0.0), decay: KNOCKBACK_DECAY };
+        let mut travelled = 0.0;
+        let mut frames = 0;
+        while let Some(delta) = impulse.advance(1.0 / 60.0) {
+            assert_eq!(delta.y, 0.0);
+            travelled += delta.x;
+            frames += 1;
+        }
+        assert!(frames <= 18, "still moving after {frames} frames");
+        // Roughly velocity / decay in total, a bit over since each frame moves before it decays
+        let expected = ELECTRIC_KNOCKBACK / KNOCKBACK_DECAY;
+        assert!(travelled > expected && travelled < expected * 1.2, "{travelled}");
+        assert!(impulse.advance(1.0 / 60.0).is_none());
+    }
+
+    #[test]
+    fn knockback_stops_at_walls() {
+        let player = Collider::new(PLAYER_HALF_EXTENTS);
+        let (wall_tf, wall) = wall_at(0.0, 20.0, 100.0, 12.0);
+        let solids = [(&wall_tf, &wall, None)];
+        let mut no_crates: [(&mut Transform, &Sprite, Option<&Collider>); 0] = [];
+        let mut impulse = ExternalImpulse { velocity: Vec2::new(0.0, 600.0), decay: KNOCKBACK_DECAY };
+        let mut pos = Vec3::ZERO;
+        while let Some(delta) = impulse.advance(1.0 / 30.0) {
+            move_player(&mut pos, &player, delta, &solids, &mut no_crates);
+        }
+        assert_eq!(pos.y, 4.0); // Flush with the wall's bottom edge at 14
+    }
+
+    #[test]
+    fn click_walk_stops_on_the_target() {
+        let target = Vec2::new(30.0, 40.0); // 50px away
+        assert_eq!(step_toward(Vec2::ZERO, target, 10.0), Vec2::new(6.0, 8.0));
+        // The last step lands on the target instead of overshooting it
+        assert_eq!(step_toward(Vec2::new(27.0, 36.0), target, 10.0), Vec2::new(3.0, 4.0));
+        assert_eq!(step_toward(target - Vec2::X * MOVE_TARGET_REACHED, target, 10.0), Vec2::ZERO);
+    }
+
+    #[test]
+    fn facing_follows_the_latest_held_direction() {
+        let input = crate::input::InputMap::default();
+        let mut keyboard = ButtonInput::<KeyCode>::default();
+        let mut held = HeldDirections::default();
+        let mut frame = |keyboard: &mut ButtonInput<KeyCode>| {
+            held.update(&input, keyboard);
+            keyboard.clear(); // Next frame; held keys stay down
+            held.latest()
+        };
+
+        keyboard.press(KeyCode::KeyW);
+        assert_eq!(frame(&mut keyboard), Some(Direction::Up));
+        keyboard.press(KeyCode::KeyD);
+        assert_eq!(frame(&mut keyboard), Some(Direction::Right));
+        assert_eq!(frame(&mut keyboard), Some(Direction::Right)); // Still diagonal
+        keyboard.release(KeyCode::KeyD);
+        assert_eq!(frame(&mut keyboard), Some(Direction::Up));
+        keyboard.release(KeyCode::KeyW);
+        assert_eq!(frame(&mut keyboard), None);
+    }
+
+    #[test]
+    fn sneaking_gets_closer_to_critters() {
+        let critter = Critter::new(Vec2::ZERO, 50.0, 20.0);
+        let near = Vec2::new(CRITTER_NOTICE_RADIUS * SNEAK_NOTICE_MULTIPLIER + 4.0, 0.0);
+        assert!(critter.notices(Vec2::ZERO, near, false));
+        assert!(!critter.notices(Vec2::ZERO, near, true));
+        assert!(critter.notices(Vec2::ZERO, near / 2.0, true));
+    }
+
+    #[test]
+    fn dash_bursts_then_cools_down() {
+        let mut dash = Dash::default();
+        assert!(!dash.start(Vec2::ZERO)); // Standing still
+        assert!(dash.start(Vec2::new(3.0, 0.0)));
+        assert_eq!(dash.direction, Vec2::X);
+        assert!(!dash.start(Vec2::Y)); // Already dashing
+
+        // A long frame only dashes for what's left of the burst
+        assert_eq!(dash.advance(0.1), 0.1);
+        assert!((dash.advance(0.1) - (DASH_SECS - 0.1)).abs() < 1e-6);
+        assert_eq!(dash.cooldown_fraction(), 1.0);
+        assert_eq!(dash.advance(0.1), 0.0);
+        assert!(!dash.start(Vec2::X));
+
+        dash.advance(DASH_COOLDOWN_SECS);
+        assert_eq!(dash.cooldown_fraction(), 0.0);
+        assert!(dash.start(Vec2::X));
+    }
+
+    #[test]
+    fn idling_starts_a_fidget_that_plays_once() {
+        let mut idle = IdleTimer::default();
+        assert!(!idle.tick(IDLE_FIDGET_SECS - 1.0, false));
+        assert!(!idle.tick(2.0, true)); // Moved; starts over
+        assert!(!idle.tick(IDLE_FIDGET_SECS - 1.0, false));
+        assert!(idle.tick(1.0, false));
+        assert_eq!(idle.0, 0.0);
+
+        let clip = SpriteClip::look_around();
+        let mut fidget = AnimationOverride::once(clip.clone());
+        let mut shown = Vec::new();
+        while let Some(frame) = fidget.advance(clip.frame_secs) {
+            shown.push(frame);
+        }
+        assert_eq!(shown, clip.frames);
+    }
+
+    #[test]
+    fn player_caught_by_a_closing_door_is_pushed_out() {
+        let player = Collider::new(PLAYER_HALF_EXTENTS);
+        // A 32x40 door at (0, 100); the player's head pokes 4px into its bottom edge
+        let (door_tf, door) = wall_at(0.0, 100.0, 32.0, 40.0);
+        let solids = [(&door_tf, &door, None)];
+        let free = depenetrate(Vec2::new(0.0, 74.0), &player, &solids).unwrap();
+        assert_eq!(free, Vec2::new(0.0, 70.0));
+        // Shallower sideways than vertically: out the side instead
+        let free = depenetrate(Vec2::new(20.0, 100.0), &player, &solids).unwrap();
+        assert_eq!(free, Vec2::new(24.0, 100.0));
+        assert_eq!(depenetrate(free, &player, &solids), None);
+
+        // Pushing out of the door would land in a wall just below it: take the nearest free spot
+        let (wall_tf, wall) = wall_at(0.0, 60.0, 100.0, 12.0);
+        let solids = [(&door_tf, &door, None), (&wall_tf, &wall, None)];
+        let free = depenetrate(Vec2::new(0.0, 74.0), &player, &solids).unwrap();
+        assert!(fits(free, &player, &solids), "{free}");
+    }
+
+    #[test]
+    fn floor_speeds_stack_and_clear_on_leaving() {
+        let carpet = FloorModifier { speed_mult: 0.85, surface: FloorSurface::Carpet };
+        let oil = FloorModifier { speed_mult: 0.5, surface: FloorSurface::Oil };
+        let (carpet_tf, carpet_sprite) = wall_at(0.0, 0.0, 100.0, 100.0);
+        let (oil_tf, oil_sprite) = wall_at(40.0, 0.0, 40.0, 40.0);
+        let floors = [(&carpet, &carpet_tf, &carpet_sprite), (&oil, &oil_tf, &oil_sprite)];
+
+        assert_eq!(floor_speed(Vec2::ZERO, floors), 0.85);
+        assert!((floor_speed(Vec2::new(45.0, 0.0), floors) - 0.425).abs() < 1e-6);
+        assert_eq!(floor_speed(Vec2::new(55.0, 0.0), floors), 0.5);
+        // One step off the edge is full speed again
+        assert_eq!(floor_speed(Vec2::new(60.5, 0.0), floors), 1.0);
+
+        // A pile of slow regions still leaves the player able to move
+        let floors = [(&oil, &oil_tf, &oil_sprite); 4];
+        assert_eq!(floor_speed(Vec2::new(40.0, 0.0), floors), MIN_FLOOR_SPEED);
+    }
+}
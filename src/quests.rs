@@ -0,0 +1,253 @@
+// src/quests.rs
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::GameSet;
+use crate::input::{Action, InputMap};
+
+pub struct QuestsPlugin;
+
+impl Plugin for QuestsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Quests>()
+            .init_resource::<QuestLog>()
+            .add_event::<QuestEvent>()
+            .add_systems(Update, toggle_quest_log.in_set(GameSet::Input))
+            // After the gameplay sets, so an interaction's progress is announced the same frame.
+            // Not gated on GameState, like the flags.
+            .add_systems(Update, emit_quest_events.after(GameSet::Process));
+    }
+}
+
+pub const POWER_GENERATOR: &str = "power_generator";
+
+// The J panel: whether it's up, and which of its tabs it shows
+#[derive(Resource, Default)]
+pub struct QuestLog {
+    pub open: bool,
+    pub tab: LogTab,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LogTab {
+    #[default]
+    Quests,
+    Journal,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, Reflect)]
+pub enum QuestState {
+    #[default]
+    Inactive,
+    Active,
+    Complete,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct Objective {
+    pub text: String,
+    pub done: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct Quest {
+    pub id: String,
+    pub title: String,
+    pub objectives: Vec<Objective>, // In the order they're meant to be done
+    pub state: QuestState,
+}
+
+impl Quest {
+    fn new(id: &str, title: &str, objectives: &[&str]) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+            objectives: objectives.iter().map(|text| Objective { text: text.to_string(), done: false }).collect(),
+            state: QuestState::Inactive,
+        }
+    }
+
+    // The first objective not yet done
+    pub fn current_objective(&self) -> Option<&Objective> {
+        self.objectives.iter().find(|objective| !objective.done)
+    }
+}
+
+#[derive(Event, Clone, Debug, PartialEq)]
+pub enum QuestEvent {
+    Activated(String),
+    ObjectiveCompleted { quest: String, index: usize },
+    Completed(String),
+}
+
+// Every quest in the game and how far along each one is, in the order the log lists them
+#[derive(Resource, Clone, Debug, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+pub struct Quests {
+    quests: Vec<Quest>,
+    // Progress not yet sent as QuestEvents; never saved
+    #[serde(skip)]
+    #[reflect(ignore)]
+    events: Vec<QuestEvent>,
+}
+
+impl Default for Quests {
+    fn default() -> Self {
+        Self {
+            quests: vec![Quest::new(POWER_GENERATOR, "Power the Generator", &[
+                "Find some fuel",
+                "Refuel the generator",
+                "Start the generator",
+            ])],
+            events: Vec::new(),
+        }
+    }
+}
+
+// Pending notifications don't make two sets of quests different
+impl PartialEq for Quests {
+    fn eq(&self, other: &Self) -> bool {
+        self.quests == other.quests
+    }
+}
+
+impl Quests {
+    pub fn get(&self, id: &str) -> Option<&Quest> {
+        self.quests.iter().find(|quest| quest.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Quest> {
+        self.quests.iter()
+    }
+
+    // The objective the HUD reminds the player of: the earliest-listed active quest's next step
+    pub fn current(&self) -> Option<(&Quest, &Objective)> {
+        self.quests
+            .iter()
+            .filter(|quest| quest.state == QuestState::Active)
+            .find_map(|quest| Some((quest, quest.current_objective()?)))
+    }
+
+    // Starts an inactive quest; returns false if it was already started (or finished) or unknown
+    pub fn activate(&mut self, id: &str) -> bool {
+        let Some(quest) = self.quests.iter_mut().find(|quest| quest.id == id) else {
+            warn!("Unknown quest: {}", id);
+            return false;
+        };
+        if quest.state != QuestState::Inactive {
+            return false;
+        }
+        quest.state = QuestState::Active;
+        self.events.push(QuestEvent::Activated(id.to_string()));
+        true
+    }
+
+    // Marks an active quest's objective done, along with any earlier ones that were skipped
+    // past, and completes the quest once nothing is left. Returns whether anything changed.
+    pub fn complete_objective(&mut self, id: &str, index: usize) -> bool {
+        let Some(quest) = self.quests.iter_mut().find(|quest| quest.id == id) else {
+            warn!("Unknown quest: {}", id);
+            return false;
+        };
+        if quest.state != QuestState::Active || index >= quest.objectives.len() {
+            return false;
+        }
+        let mut changed = false;
+        for (i, objective) in quest.objectives.iter_mut().enumerate().take(index + 1) {
+            if !objective.done {
+                objective.done = true;
+                changed = true;
+                self.events.push(QuestEvent::ObjectiveCompleted { quest: id.to_string(), index: i });
+            }
+        }
+        if quest.objectives.iter().all(|objective| objective.done) {
+            quest.state = QuestState::Complete;
+            self.events.push(QuestEvent::Completed(id.to_string()));
+        }
+        changed
+    }
+}
+
+// J opens and closes the log; Tab flips between its quests and the journal while it's up
+fn toggle_quest_log(keyboard: Res<ButtonInput<KeyCode>>, input: Res<InputMap>, mut log: ResMut<QuestLog>) {
+    if input.just_pressed(Action::QuestLog, &keyboard) {
+        log.open = !log.open;
+    }
+    if log.open && input.just_pressed(Action::NextTab, &keyboard) {
+        log.tab = match log.tab {
+            LogTab::Quests => LogTab::Journal,
+            LogTab::Journal => LogTab::Quests,
+        };
+    }
+}
+
+fn emit_quest_events(mut quests: ResMut<Quests>, mut events: EventWriter<QuestEvent>) {
+    // Only touch the resource (and trip change detection) when there's something to send
+    if quests.events.is_empty() {
+        return;
+    }
+    for event in quests.events.drain(..) {
+        info!("Quest: {:?}", event);
+        events.write(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(quests: &mut Quests) -> Vec<QuestEvent> {
+        quests.events.drain(..).collect()
+    }
+
+    #[test]
+    fn quests_go_inactive_active_complete() {
+        let mut quests = Quests::default();
+        let state = |quests: &Quests| quests.get(POWER_GENERATOR).unwrap().state;
+        assert_eq!(state(&quests), QuestState::Inactive);
+        assert!(quests.current().is_none());
+
+        // Nothing counts before the quest starts
+        assert!(!quests.complete_objective(POWER_GENERATOR, 0));
+        assert!(drain(&mut quests).is_empty());
+
+        assert!(quests.activate(POWER_GENERATOR));
+        assert!(!quests.activate(POWER_GENERATOR));
+        assert_eq!(state(&quests), QuestState::Active);
+        assert_eq!(quests.current().unwrap().1.text, "Find some fuel");
+        assert_eq!(drain(&mut quests), vec![QuestEvent::Activated(POWER_GENERATOR.to_string())]);
+
+        assert!(quests.complete_objective(POWER_GENERATOR, 0));
+        assert!(!quests.complete_objective(POWER_GENERATOR, 0));
+        assert!(!quests.complete_objective(POWER_GENERATOR, 3));
+        assert_eq!(quests.current().unwrap().1.text, "Refuel the generator");
+
+        quests.complete_objective(POWER_GENERATOR, 1);
+        quests.complete_objective(POWER_GENERATOR, 2);
+        assert_eq!(state(&quests), QuestState::Complete);
+        assert!(quests.current().is_none());
+        // Finished quests don't start again
+        assert!(!quests.activate(POWER_GENERATOR));
+        assert_eq!(drain(&mut quests), vec![
+            QuestEvent::ObjectiveCompleted { quest: POWER_GENERATOR.to_string(), index: 0 },
+            QuestEvent::ObjectiveCompleted { quest: POWER_GENERATOR.to_string(), index: 1 },
+            QuestEvent::ObjectiveCompleted { quest: POWER_GENERATOR.to_string(), index: 2 },
+            QuestEvent::Completed(POWER_GENERATOR.to_string()),
+        ]);
+    }
+
+    #[test]
+    fn skipping_ahead_completes_the_earlier_objectives() {
+        let mut quests = Quests::default();
+        quests.activate(POWER_GENERATOR);
+        drain(&mut quests);
+
+        // The generator started on the fuel it already had
+        assert!(quests.complete_objective(POWER_GENERATOR, 2));
+        assert_eq!(quests.get(POWER_GENERATOR).unwrap().state, QuestState::Complete);
+        assert_eq!(drain(&mut quests).len(), 4);
+
+        let text = ron::to_string(&quests).unwrap();
+        let back: Quests = ron::from_str(&text).unwrap();
+        assert_eq!(back, quests);
+    }
+}
@@ -0,0 +1,285 @@
+// src/replay.rs
+use bevy::prelude::*;
+use bevy::input::InputSystem;
+use bevy::time::TimeUpdateStrategy;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::input::{hold_consumed_actions, Action, InputMap};
+use crate::rng::GameRng;
+use crate::rooms::RoomDefs;
+use crate::save::NewGameEvent;
+use crate::state::GameState;
+
+// Dev tool for reproducing a session. `--record path.ron` appends every frame's actions to the
+// file as it's played; `--replay path.ron` plays them back in place of the keyboard, frame for
+// frame and at the recorded frame times. Either way the game skips the title screen, starts a new
+// game and seeds its random numbers from the file, so the same replay plays out the same.
+pub struct ReplayPlugin {
+    mode: ReplayMode,
+}
+
+enum ReplayMode {
+    Off,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl ReplayPlugin {
+    // Picks the mode out of the command line (without the program name); other arguments are ignored
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mut mode = ReplayMode::Off;
+        while let Some(arg) = args.next() {
+            let path = match arg.as_str() {
+                "--record" | "--replay" => args.next().map(PathBuf::from),
+                _ => continue,
+            };
+            let Some(path) = path else {
+                warn!("{} needs a file to go with it", arg);
+                continue;
+            };
+            mode = if arg == "--record" { ReplayMode::Record(path) } else { ReplayMode::Replay(path) };
+        }
+        Self { mode }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self.mode, ReplayMode::Off)
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = match &self.mode {
+            ReplayMode::Off => return,
+            ReplayMode::Record(path) => {
+                // --seed's, if one was given
+                let seed = app.world().get_resource::<GameRng>().map_or_else(rand::random, GameRng::seed);
+                match Recorder::create(path, seed) {
+                    Ok(recorder) => {
+                        info!("Recording input to {}", path.display());
+                        app.insert_resource(recorder)
+                            .add_systems(PreUpdate, record_input.after(InputSystem));
+                    }
+                    Err(e) => warn!("Couldn't record to {}: {}", path.display(), e),
+                }
+                seed
+            }
+            ReplayMode::Replay(path) => match Replay::read(path) {
+                Ok(replay) => {
+                    info!("Replaying {} ({} frames)", path.display(), replay.frames.len());
+                    let seed = replay.seed;
+                    add_playback(app, replay);
+                    seed
+                }
+                Err(e) => {
+                    warn!("Couldn't replay {}: {}", path.display(), e);
+                    return;
+                }
+            },
+        };
+        app.insert_resource(GameRng::seeded(seed))
+            .add_systems(Startup, start_new_game);
+    }
+}
+
+// The first line of a replay file; one ReplayFrame per line follows
+#[derive(Serialize, Deserialize)]
+struct ReplayHeader {
+    seed: u64,
+}
+
+// One frame of a recording: how long it took, which actions were held and which had just
+// been pressed (or tapped and let go within the frame)
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReplayFrame {
+    pub secs: f32,
+    pub pressed: Vec<Action>,
+    pub just_pressed: Vec<Action>,
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        Self::parse(&std::fs::read_to_string(path).map_err(|e| e.to_string())?)
+    }
+
+    // Blank lines and // comments between frames are skipped, so a committed replay can say
+    // what it does
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with("//"));
+        let (_, header) = lines.next().ok_or("empty file")?;
+        let header: ReplayHeader = ron::from_str(header).map_err(|e| format!("header: {}", e))?;
+        let frames = lines
+            .map(|(n, line)| ron::from_str(line).map_err(|e| format!("line {}: {}", n + 1, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { seed: header.seed, frames })
+    }
+}
+
+#[derive(Resource)]
+struct Recorder {
+    file: std::fs::File,
+}
+
+impl Recorder {
+    fn create(path: &Path, seed: u64) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::fs::File::create(path)?;
+        writeln!(file, "{}", ron::to_string(&ReplayHeader { seed }).map_err(std::io::Error::other)?)?;
+        Ok(Self { file })
+    }
+}
+
+// Appends this frame as it was played. Straight after the keyboard is read, so it's the same
+// frame every other system sees. Frames while room files load are left out, as playback waits
+// those out rather than counting them.
+fn record_input(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    time: Res<Time<Real>>,
+    mut recorder: ResMut<Recorder>,
+    rooms: Res<RoomDefs>,
+) {
+    if rooms.is_loading() {
+        return;
+    }
+    let held = |action| input.pressed(action, &keyboard);
+    let fresh = |action| input.just_pressed(action, &keyboard);
+    let frame = ReplayFrame {
+        secs: time.delta_secs(),
+        pressed: Action::ALL.into_iter().filter(|&a| held(a)).collect(),
+        just_pressed: Action::ALL.into_iter().filter(|&a| fresh(a)).collect(),
+    };
+    let written = ron::to_string(&frame)
+        .map_err(|e| e.to_string())
+        .and_then(|line| writeln!(recorder.file, "{}", line).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        warn!("Stopped recording: {}", e);
+        commands.remove_resource::<Recorder>();
+    }
+}
+
+// The frames still to play, and where it's up to
+#[derive(Resource)]
+pub struct Playback {
+    frames: Vec<ReplayFrame>,
+    next: usize,
+}
+
+impl Playback {
+    pub fn finished(&self) -> bool {
+        self.next >= self.frames.len()
+    }
+}
+
+// Feeds `replay` to the app in place of the keyboard. Public so tests can play one headless.
+pub fn add_playback(app: &mut App, replay: Replay) {
+    let first = replay.frames.first().map_or(0.0, |frame| frame.secs);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(first)))
+        .insert_resource(Playback { frames: replay.frames, next: 0 })
+        .add_systems(PreUpdate, play_back_input.after(InputSystem).before(hold_consumed_actions));
+}
+
+// Replaces the keyboard with this frame's actions, each pressed on its first key, and sets how
+// long the next frame takes. The keyboard is handed back once the frames run out. Nothing plays
+// while room files are loading, so how quick the disk is can't shift where the frames land.
+fn play_back_input(
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    mut playback: ResMut<Playback>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    rooms: Res<RoomDefs>,
+) {
+    if playback.finished() {
+        return;
+    }
+    if rooms.is_loading() {
+        keyboard.reset_all();
+        return;
+    }
+    let frame = playback.frames[playback.next].clone();
+    playback.next += 1;
+    let key = |action| input.keys(action).first().copied();
+    keyboard.reset_all();
+    for key in frame.pressed.iter().chain(&frame.just_pressed).filter_map(|&action| key(action)) {
+        keyboard.press(key);
+    }
+    // Held since an earlier frame
+    for &action in &frame.pressed {
+        if let Some(key) = key(action).filter(|_| !frame.just_pressed.contains(&action)) {
+            keyboard.clear_just_pressed(key);
+        }
+    }
+    // Tapped and let go again within this frame
+    for &action in &frame.just_pressed {
+        if let Some(key) = key(action).filter(|_| !frame.pressed.contains(&action)) {
+            keyboard.release(key);
+        }
+    }
+    *strategy = match playback.frames.get(playback.next) {
+        Some(next) => TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(next.secs)),
+        None => {
+            info!("Replay finished");
+            TimeUpdateStrategy::Automatic
+        }
+    };
+}
+
+// Straight into a fresh game, whatever saves are around
+fn start_new_game(mut new_games: EventWriter<NewGameEvent>, mut next_state: ResMut<NextState<GameState>>) {
+    new_games.write(NewGameEvent { carry_from: None });
+    next_state.set(GameState::Playing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::Inventory;
+    use crate::objects::{Door, ObjectId};
+    use crate::headless_app;
+
+    #[test]
+    fn the_committed_replay_takes_the_key_and_opens_the_door() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/replays/take_key_open_door.ron");
+        let mut app = headless_app();
+        app.add_plugins(ReplayPlugin::from_args(["--replay".to_string(), path.to_string()]));
+        assert!(app.world().contains_resource::<Playback>(), "{} didn't load", path);
+        while !app.world().resource::<Playback>().finished() {
+            app.update();
+        }
+
+        let world = app.world_mut();
+        assert_eq!(*world.resource::<State<GameState>>().get(), GameState::Playing);
+        let mut doors = world.query::<(&Door, &ObjectId)>();
+        let (door, _) = doors.iter(world).find(|(_, id)| id.0 == "start/metal_door").unwrap();
+        assert!(door.is_open);
+        let mut objects = world.query::<&ObjectId>();
+        assert!(!objects.iter(world).any(|id| id.0 == "start/rusty_key"));
+        assert!(world.resource::<Inventory>().items.iter().any(|item| item.id == "rusty_key"));
+    }
+
+    #[test]
+    fn replay_files_skip_comments_and_name_bad_lines() {
+        let text = "// A note\n(seed: 3)\n\n(secs: 0.5, pressed: [Run], just_pressed: [])\n// More\n";
+        let replay = Replay::parse(text).unwrap();
+        assert_eq!(replay.seed, 3);
+        assert_eq!(replay.frames, [ReplayFrame { secs: 0.5, pressed: vec![Action::Run], just_pressed: vec![] }]);
+        let broken = format!("{}(secs: oops)\n", text);
+        assert!(Replay::parse(&broken).unwrap_err().starts_with("line 6"));
+        assert!(Replay::parse("// Nothing here\n").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+// src/rng.rs
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::ops::Range;
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        // main inserts one seeded from the command line first; tests get a random seed
+        app.init_resource::<GameRng>()
+            .configure_sets(Update, (RngDraw::Critters, RngDraw::Footsteps, RngDraw::Blips).chain())
+            .add_systems(Startup, announce_seed);
+    }
+}
+
+// Every random number the game uses comes from here, so one seed plays a run out the same way
+// again: where critters wander, blip pitches, which footstep sound plays. The seed comes from
+// --seed, a replay file or the OS, is logged at startup and goes into save files, so a bug report
+// can carry it. Draw from it in a fixed order: only from a system in an RngDraw set, entities in
+// query order.
+#[derive(Resource)]
+pub struct GameRng {
+    rng: StdRng,
+    seed: u64,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::seeded(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed), seed }
+    }
+
+    // `--seed N` out of the command line (without the program name), or a random seed without one
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg != "--seed" {
+                continue;
+            }
+            match args.next().map(|seed| seed.parse()) {
+                Some(Ok(seed)) => return Self::seeded(seed),
+                _ => warn!("--seed needs a whole number to go with it"),
+            }
+        }
+        Self::default()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        self.rng.gen_range(range)
+    }
+
+    // One of `items`, or None if there aren't any
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        items.choose(&mut self.rng)
+    }
+
+    // True with probability `p`, clamped to 0..=1
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.rng.gen_bool(p.clamp(0.0, 1.0) as f64)
+    }
+}
+
+// Every system that draws from GameRng goes in one of these, and they run in this order, so which
+// numbers each one gets never comes down to how the scheduler happened to arrange the frame
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum RngDraw {
+    Critters,  // objects.rs wander_critters
+    Footsteps, // player.rs play_footsteps
+    Blips,     // audio.rs speak_dialog_blips
+}
+
+fn announce_seed(rng: Res<GameRng>) {
+    info!("Random seed {} (run with --seed {} to repeat it)", rng.seed, rng.seed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_draws_the_same_numbers() {
+        let draws = |rng: &mut GameRng| {
+            (0..50)
+                .map(|_| (rng.range_f32(-1.0..1.0), *rng.pick(&[1, 2, 3, 4]).unwrap(), rng.chance(0.5)))
+                .collect::<Vec<_>>()
+        };
+        let seeded = || GameRng::from_args(["--replay".to_string(), "x.ron".to_string(), "--seed".to_string(), "430".to_string()]);
+        let (mut first, mut second) = (seeded(), seeded());
+        assert_eq!(first.seed(), 430);
+        assert_eq!(draws(&mut first), draws(&mut second));
+        assert_ne!(draws(&mut first), draws(&mut GameRng::seeded(431)));
+
+        assert_eq!(first.pick::<u8>(&[]), None);
+        assert!(!first.chance(0.0) && first.chance(1.0));
+    }
+}
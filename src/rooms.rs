@@ -0,0 +1,445 @@
+// src/rooms.rs
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoadFailedEvent, AssetLoader, LoadContext};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::GameSet;
+use crate::interaction::PendingInteraction;
+use crate::objects::{room_def_path, spawn_room_def, RoomDef};
+use crate::player::{MoveTarget, Player, SpawnTarget, TeleportPlayerEvent};
+use crate::state::GameState;
+use crate::ui::UiState;
+
+pub struct RoomsPlugin;
+
+impl Plugin for RoomsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LoadRoomEvent>()
+            .init_asset::<RoomDef>()
+            .init_asset_loader::<RoomDefLoader>()
+            .init_resource::<RoomRegistry>()
+            .init_resource::<RoomDefs>()
+            .init_resource::<RoomVisits>()
+            .init_resource::<RoomWatch>()
+            .insert_resource(CurrentRoom(START_ROOM.to_string()))
+            .add_systems(Startup, request_room_files)
+            // After game logic, so a door walked through this frame swaps the room this frame.
+            // Not gated on GameState: Start and Continue load from the title screen.
+            .add_systems(Update, (
+                track_room_files,
+                reload_edited_room.run_if(in_state(GameState::Playing)),
+                load_rooms,
+            ).chain().after(GameSet::Process));
+    }
+}
+
+pub const START_ROOM: &str = "start";
+pub const HALLWAY: &str = "hallway";
+
+// Tags an entity as belonging to a room so it's despawned when the room unloads
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct RoomMember(pub String);
+
+impl RoomMember {
+    pub fn new(room: &str) -> Self {
+        Self(room.to_string())
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CurrentRoom(pub String);
+
+// How many times the player has entered each room, for dialogue that notices them coming back
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+pub struct RoomVisits(HashMap<String, u32>);
+
+impl RoomVisits {
+    pub fn count(&self, room: &str) -> u32 {
+        self.0.get(room).copied().unwrap_or(0)
+    }
+
+    pub fn record(&mut self, room: &str) {
+        *self.0.entry(room.to_string()).or_default() += 1;
+    }
+}
+
+// Rooms built in code, used when assets/rooms/<id>.ron is missing or broken
+#[derive(Resource, Default)]
+pub struct RoomRegistry {
+    builders: HashMap<String, fn(&mut Commands)>,
+}
+
+impl RoomRegistry {
+    pub fn register(&mut self, id: &str, build: fn(&mut Commands)) {
+        self.builders.insert(id.to_string(), build);
+    }
+}
+
+// Unload the current room and spawn `id` in its place. With a spawn point the player is moved
+// there once the new room exists and it counts as a visit; without one, whoever sent the event
+// places them (loading a save, the F9 rewind) and the visit count is left alone.
+#[derive(Event, Clone, Debug)]
+pub struct LoadRoomEvent {
+    pub id: String,
+    pub spawn_point: Option<SpawnTarget>,
+}
+
+impl LoadRoomEvent {
+    pub fn at(id: &str, spawn_point: SpawnTarget) -> Self {
+        Self { id: id.to_string(), spawn_point: Some(spawn_point) }
+    }
+}
+
+// Reads assets/rooms/*.ron into RoomDefs. Asked for by type, so other .ron files aren't its.
+#[derive(Default, TypePath)]
+pub struct RoomDefLoader;
+
+impl AssetLoader for RoomDefLoader {
+    type Asset = RoomDef;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(&self, reader: &mut dyn Reader, _: &(), _: &mut LoadContext<'_>) -> Result<RoomDef, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+// Room files by room id, as the AssetServer has them. The built-in rooms are asked for at startup
+// and the rooms a loaded one leads to as it comes in, so a room's file is normally in well before
+// anything loads it; loading one whose file is still on its way waits for it.
+#[derive(Resource, Default)]
+pub struct RoomDefs(HashMap<String, RoomFile>);
+
+struct RoomFile {
+    handle: Handle<RoomDef>,
+    settled: bool, // Loaded, or failed to
+}
+
+impl RoomDefs {
+    fn request(&mut self, room: &str, asset_server: &AssetServer) {
+        self.0.entry(room.to_string()).or_insert_with(|| RoomFile {
+            handle: asset_server.load(room_def_path(room)),
+            settled: false,
+        });
+    }
+
+    fn settle(&mut self, id: AssetId<RoomDef>) {
+        for file in self.0.values_mut().filter(|file| file.handle.id() == id) {
+            file.settled = true;
+        }
+    }
+
+    fn handle(&self, room: &str) -> Option<&Handle<RoomDef>> {
+        self.0.get(room).map(|file| &file.handle)
+    }
+
+    // Whether any room file asked for hasn't come in (or failed to) yet
+    pub fn is_loading(&self) -> bool {
+        self.0.values().any(|file| !file.settled)
+    }
+}
+
+fn request_room_files(asset_server: Res<AssetServer>, registry: Res<RoomRegistry>, mut defs: ResMut<RoomDefs>) {
+    for room in registry.builders.keys() {
+        defs.request(room, &asset_server);
+    }
+}
+
+// Notes room files coming in or failing to, and asks for any room a loaded one or a load
+// request names that hasn't been asked for yet
+fn track_room_files(
+    mut asset_events: EventReader<AssetEvent<RoomDef>>,
+    mut failures: EventReader<AssetLoadFailedEvent<RoomDef>>,
+    mut loads: EventReader<LoadRoomEvent>,
+    room_assets: Res<Assets<RoomDef>>,
+    asset_server: Res<AssetServer>,
+    mut defs: ResMut<RoomDefs>,
+) {
+    for event in asset_events.read() {
+        let AssetEvent::LoadedWithDependencies { id } = *event else { continue };
+        defs.settle(id);
+        let Some(def) = room_assets.get(id) else { continue };
+        for room in def.entries.iter().filter_map(|entry| entry.target_room.as_deref()) {
+            defs.request(room, &asset_server);
+        }
+    }
+    for failure in failures.read() {
+        defs.settle(failure.id);
+    }
+    for event in loads.read() {
+        defs.request(&event.id, &asset_server);
+    }
+}
+
+// Spawns `room` from its file, falling back to the registered layouts
+fn spawn_room(commands: &mut Commands, registry: &RoomRegistry, room: &str, def: Option<&RoomDef>) {
+    if let Some(def) = def {
+        spawn_room_def(commands, room, def);
+        return;
+    }
+    match registry.builders.get(room) {
+        Some(build) => build(commands),
+        None => warn!("Unknown room: {}", room),
+    }
+}
+
+// Where a room's entities come from: its builder, or its file once that's read
+#[derive(SystemParam)]
+pub struct RoomSources<'w> {
+    registry: Res<'w, RoomRegistry>,
+    defs: Res<'w, RoomDefs>,
+    room_assets: Res<'w, Assets<RoomDef>>,
+}
+
+// What changes when the player arrives: the current room, the visit count, and where they stand
+#[derive(SystemParam)]
+pub struct RoomArrival<'w> {
+    current_room: ResMut<'w, CurrentRoom>,
+    visits: ResMut<'w, RoomVisits>,
+    teleports: EventWriter<'w, TeleportPlayerEvent>,
+}
+
+// Only the last request in a frame is loaded; any before it would be unloaded straight away.
+// Events still aimed at the old room's entities (an InteractionEvent sent this frame, a
+// click-to-move target) find them gone and are dropped by the entity lookups that read them.
+// A request for a room whose file is still loading is held until it's in, the old room staying
+// up meanwhile.
+pub fn load_rooms(
+    mut events: EventReader<LoadRoomEvent>,
+    mut waiting: Local<Option<LoadRoomEvent>>,
+    mut commands: Commands,
+    members: Query<Entity, With<RoomMember>>,
+    player_query: Query<Entity, With<Player>>,
+    sources: RoomSources,
+    arrival: RoomArrival,
+) {
+    let RoomSources { registry, defs, room_assets } = sources;
+    let RoomArrival { mut current_room, mut visits, mut teleports } = arrival;
+    if let Some(event) = events.read().last() {
+        *waiting = Some(event.clone());
+    }
+    let Some(id) = waiting.as_ref().map(|event| event.id.as_str()) else { return };
+    if defs.0.get(id).is_some_and(|file| !file.settled) {
+        return;
+    }
+    let Some(event) = waiting.take() else { return };
+    for entity in members.iter() {
+        commands.entity(entity).despawn();
+    }
+    let def = defs.handle(&event.id).and_then(|handle| room_assets.get(handle));
+    spawn_room(&mut commands, &registry, &event.id, def);
+    current_room.0 = event.id.clone();
+
+    // A walk toward something in the old room has nowhere left to go
+    if let Ok(player) = player_query.single() {
+        commands.entity(player).remove::<(MoveTarget, PendingInteraction)>();
+    }
+    // Placed later this frame, once the new room's spawn points exist
+    if let Some(to) = &event.spawn_point {
+        teleports.write(TeleportPlayerEvent { to: to.clone(), fade: false });
+        visits.record(&event.id);
+    }
+    info!("Loaded room {}", event.id);
+}
+
+// Editing assets/rooms/<room>.ron while the game runs reloads the room. The AssetServer watches
+// the files in debug builds (main.rs) and reads an edited one again; the room respawns from that.
+#[derive(Resource, Default)]
+pub struct RoomWatch {
+    room: String,
+    pending: bool, // Changed, but waiting for a dialog to close
+}
+
+// Respawns the current room when its file has been read again. WorldState is reapplied as the
+// objects spawn, so a taken key stays taken; the player stays put, pulled inside the new bounds.
+// An edit that doesn't parse never comes through, so the room is left as it was.
+fn reload_edited_room(
+    mut edits: EventReader<AssetEvent<RoomDef>>,
+    mut watch: ResMut<RoomWatch>,
+    current_room: Res<CurrentRoom>,
+    ui_state: Res<UiState>,
+    (defs, room_assets): (Res<RoomDefs>, Res<Assets<RoomDef>>),
+    mut player_query: Query<&mut Transform, With<Player>>,
+    mut loads: ParamSet<(EventReader<LoadRoomEvent>, EventWriter<LoadRoomEvent>)>,
+) {
+    let current = defs.handle(&current_room.0).map(Handle::id);
+    if edits.read().filter(|event| current.is_some_and(|id| event.is_modified(id))).count() > 0 {
+        watch.pending = true;
+    }
+    // A room change already on its way goes first (our own reload included, a frame later);
+    // the watch starts over in the new room
+    if loads.p0().read().count() > 0 {
+        return;
+    }
+    if watch.room != current_room.0 {
+        watch.room = current_room.0.clone();
+        watch.pending = false;
+        return;
+    }
+    if !watch.pending || ui_state.dialog_open {
+        return;
+    }
+    watch.pending = false;
+    let Some(def) = current.and_then(|id| room_assets.get(id)) else { return };
+    let bounds = def.bounds();
+    if let Ok(mut tf) = player_query.single_mut() {
+        let pos = tf.translation.truncate().clamp(bounds.min, bounds.max);
+        tf.translation = pos.extend(tf.translation.z);
+    }
+    loads.p1().write(LoadRoomEvent { id: watch.room.clone(), spawn_point: None });
+    info!("Reloaded room {} from {}", watch.room, room_def_path(&watch.room));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::path::Path;
+
+    #[derive(Component)]
+    struct Marker(&'static str);
+
+    fn build_test_room(commands: &mut Commands) {
+        commands.spawn((Marker("new"), RoomMember::new("test_room")));
+    }
+
+    #[test]
+    fn loading_a_room_replaces_the_old_one() {
+        let mut world = World::new();
+        world.init_resource::<Events<LoadRoomEvent>>();
+        world.init_resource::<Events<TeleportPlayerEvent>>();
+        world.insert_resource(CurrentRoom(START_ROOM.to_string()));
+        world.init_resource::<RoomVisits>();
+        world.init_resource::<RoomDefs>();
+        world.init_resource::<Assets<RoomDef>>();
+        let mut registry = RoomRegistry::default();
+        registry.register("test_room", build_test_room);
+        world.insert_resource(registry);
+
+        world.spawn((Marker("old"), RoomMember::new(START_ROOM)));
+        // Not in any room: the player, camera and UI
+        world.spawn(Marker("kept"));
+
+        world.send_event(LoadRoomEvent::at("test_room", SpawnTarget::Named("from_start".to_string())));
+        world.run_system_once(load_rooms).unwrap();
+
+        let mut markers: Vec<_> = world.query::<&Marker>().iter(&world).map(|m| m.0).collect();
+        markers.sort();
+        assert_eq!(markers, ["kept", "new"]);
+        assert_eq!(world.resource::<CurrentRoom>().0, "test_room");
+        let teleports: Vec<_> = world.resource_mut::<Events<TeleportPlayerEvent>>().drain().collect();
+        assert_eq!(teleports.len(), 1);
+        assert!(matches!(&teleports[0].to, SpawnTarget::Named(name) if name == "from_start"));
+        assert_eq!(world.resource::<RoomVisits>().count("test_room"), 1);
+
+        // Walking back in counts again; being placed by a save load doesn't
+        world.send_event(LoadRoomEvent::at("test_room", SpawnTarget::Named("from_start".to_string())));
+        world.run_system_once(load_rooms).unwrap();
+        world.send_event(LoadRoomEvent { id: "test_room".to_string(), spawn_point: None });
+        world.run_system_once(load_rooms).unwrap();
+        assert_eq!(world.resource::<RoomVisits>().count("test_room"), 2);
+        assert_eq!(world.resource::<RoomVisits>().count(START_ROOM), 0);
+    }
+
+    // A room file as shipped, parsed the way RoomDefLoader does
+    fn shipped_room(room: &str) -> RoomDef {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(room_def_path(room));
+        let bytes = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        ron::de::from_bytes(&bytes).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+
+    #[test]
+    fn every_shipped_room_file_parses() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/rooms");
+        let mut rooms: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+            .map(|path| path.file_stem().unwrap().to_string_lossy().into_owned())
+            .collect();
+        rooms.sort();
+        assert_eq!(rooms, [HALLWAY, START_ROOM]);
+        for room in &rooms {
+            assert!(!shipped_room(room).entries.is_empty(), "{} has nothing in it", room);
+        }
+    }
+
+    #[test]
+    fn a_room_waits_for_its_file_then_spawns_from_it() {
+        let mut world = World::new();
+        world.init_resource::<Events<LoadRoomEvent>>();
+        world.init_resource::<Events<TeleportPlayerEvent>>();
+        world.insert_resource(CurrentRoom(START_ROOM.to_string()));
+        world.init_resource::<RoomVisits>();
+        world.init_resource::<RoomRegistry>();
+        world.init_resource::<Assets<RoomDef>>();
+        let def: RoomDef = ron::from_str(r#"(entries: [(kind: "Prop", name: "Chair", size: (8.0, 8.0))])"#).unwrap();
+        let handle = world.resource_mut::<Assets<RoomDef>>().add(def);
+        world.insert_resource(RoomDefs(HashMap::from([("test_room".to_string(), RoomFile { handle, settled: false })])));
+        world.spawn((Marker("old"), RoomMember::new(START_ROOM)));
+        let load = world.register_system(load_rooms);
+
+        // Still loading: the old room stays
+        world.send_event(LoadRoomEvent::at("test_room", SpawnTarget::Named("from_start".to_string())));
+        world.run_system(load).unwrap();
+        assert_eq!(world.resource::<CurrentRoom>().0, START_ROOM);
+        assert_eq!(world.query::<&Marker>().iter(&world).count(), 1);
+
+        // In: swapped for what the file has, without asking again
+        world.resource_mut::<RoomDefs>().0.get_mut("test_room").unwrap().settled = true;
+        world.run_system(load).unwrap();
+        assert_eq!(world.resource::<CurrentRoom>().0, "test_room");
+        assert_eq!(world.query::<&Marker>().iter(&world).count(), 0);
+        let names: Vec<_> = world.query::<&Name>().iter(&world).map(|name| name.as_str().to_string()).collect();
+        assert_eq!(names, ["Chair"]);
+        assert_eq!(world.resource::<RoomVisits>().count("test_room"), 1);
+    }
+
+    #[test]
+    fn edited_room_reloads_once_after_dialog_closes() {
+        let mut world = World::new();
+        world.init_resource::<UiState>();
+        world.init_resource::<Events<LoadRoomEvent>>();
+        world.init_resource::<Events<AssetEvent<RoomDef>>>();
+        world.insert_resource(CurrentRoom(START_ROOM.to_string()));
+        world.insert_resource(RoomWatch { room: START_ROOM.to_string(), ..default() });
+        let mut room_assets = Assets::<RoomDef>::default();
+        let handle = room_assets.add(shipped_room(START_ROOM));
+        let edited = AssetEvent::Modified { id: handle.id() };
+        world.insert_resource(room_assets);
+        world.insert_resource(RoomDefs(HashMap::from([(START_ROOM.to_string(), RoomFile { handle, settled: true })])));
+        let player = world.spawn((Player::default(), Transform::from_xyz(900.0, 0.0, 10.0))).id();
+        world.resource_mut::<UiState>().dialog_open = true;
+        let reload = world.register_system(reload_edited_room);
+        let frame = |world: &mut World| {
+            world.run_system(reload).unwrap();
+            world.resource_mut::<Events<LoadRoomEvent>>().drain().collect::<Vec<_>>()
+        };
+
+        // Read again while someone's talking: held
+        world.send_event(edited);
+        assert!(frame(&mut world).is_empty());
+        world.resource_mut::<UiState>().dialog_open = false;
+        let loads = frame(&mut world);
+        assert_eq!(loads.len(), 1);
+        assert_eq!(loads[0].id, START_ROOM);
+        assert!(loads[0].spawn_point.is_none());
+        // Pulled back inside the start room's bounds
+        assert_eq!(world.get::<Transform>(player).unwrap().translation, Vec3::new(320.0, 0.0, 10.0));
+
+        // Unchanged since: nothing more to do
+        assert!(frame(&mut world).is_empty());
+        assert!(frame(&mut world).is_empty());
+    }
+}
@@ -0,0 +1,560 @@
+// src/save.rs
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::GameSet;
+use crate::autosave::Autosave;
+use crate::flags::{FlagChangedEvent, GameFlags};
+use crate::interaction::InteractionEvent;
+use crate::inventory::{item_by_id, Inventory, Wallet, STARTING_GOLD};
+use crate::objects::{LastCheckpoint, PendingRespawns, WorldState};
+use crate::journal::Journal;
+use crate::ngplus::{NgPlus, GAME_COMPLETED};
+use crate::quests::Quests;
+use crate::rng::GameRng;
+use crate::player::{AnimationState, Direction, HealEvent, Player, SpawnTarget, TeleportPlayerEvent, PLAYER_START};
+use crate::rooms::{load_rooms, CurrentRoom, LoadRoomEvent, RoomVisits, START_ROOM};
+use crate::state::GameState;
+use crate::ui::LogEvent;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LoadGameEvent>()
+            .add_event::<NewGameEvent>()
+            .add_event::<WriteSaveEvent>()
+            .init_resource::<Playtime>()
+            .init_resource::<DirtySinceSave>()
+            .init_resource::<SaveDir>()
+            .add_systems(Update, (
+                tick_playtime.run_if(in_state(GameState::Playing)),
+                // After the gameplay sets, so a save chosen this frame clears the progress that
+                // choosing it marked. Before the room loader, so the room a load or new game asks
+                // for is there before the player is placed in it, on the same frame.
+                (mark_unsaved_progress, write_save_file, load_game, new_game)
+                    .chain()
+                    .after(GameSet::Process)
+                    .before(load_rooms),
+            ))
+            // Whatever wasn't saved is gone once the game is back on the title screen
+            .add_systems(OnEnter(GameState::MainMenu), |mut dirty: ResMut<DirtySinceSave>| dirty.0 = false);
+    }
+}
+
+// Seconds spent playing, not counting menus or the pause screen. Shown on the save files and
+// the pause menu.
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Playtime(pub f32);
+
+// Something has happened since the game was last saved to a file, so quitting would lose it.
+// The autosave doesn't count: it's a safety net, not a save the player chose.
+#[derive(Resource, Default)]
+pub struct DirtySinceSave(pub bool);
+
+// What the slot pickers show for a file. First in the file, so it can be read on its own.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveMeta {
+    pub room: String,
+    pub playtime: f32,
+    pub saved_at: u64, // Unix seconds
+    #[serde(default)] // Saves from before New Game+
+    pub completed: bool, // The game was finished; New Game+ starts from saves like this
+}
+
+// Everything a save file holds
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveData {
+    pub meta: SaveMeta,
+    pub player_position: Vec2,
+    pub facing: Direction,
+    pub items: Vec<String>, // Item ids, rebuilt with item_by_id
+    pub gold: u32,
+    pub world: WorldState,
+    pub flags: GameFlags,
+    #[serde(default)] // Saves from before visits were counted
+    pub visits: RoomVisits,
+    #[serde(default)] // Saves from before quests
+    pub quests: Quests,
+    #[serde(default)] // Saves from before the journal
+    pub journal: Journal,
+    #[serde(default)] // Saves from before New Game+
+    pub ng_plus: NgPlus,
+    // The run's random seed (rng.rs), for bug reports; loading doesn't reseed
+    #[serde(default)]
+    pub seed: u64,
+}
+
+// Just the part of a save file the pickers need; serde skips the rest without building it
+#[derive(Deserialize)]
+struct SaveHeader {
+    meta: SaveMeta,
+}
+
+// Ask to load a slot; a missing or unreadable one starts a new game instead
+#[derive(Event)]
+pub struct LoadGameEvent {
+    pub slot: usize,
+}
+
+// Start over from the first room. With a slot, that save must be a completed one: the new game
+// is its next New Game+ cycle and keeps its carried flags.
+#[derive(Event)]
+pub struct NewGameEvent {
+    pub carry_from: Option<usize>,
+}
+
+// Write the game as it stands to a slot, replacing whatever was there
+#[derive(Event)]
+pub struct WriteSaveEvent {
+    pub slot: usize,
+}
+
+pub const SAVE_SLOTS: usize = 3;
+// Not one of the files on the picker: autosave.rs writes it, the title screen offers it back
+pub const AUTOSAVE_SLOT: usize = SAVE_SLOTS;
+
+// Per-user data folder for the game: %APPDATA% on Windows, Application Support on macOS,
+// $XDG_DATA_HOME (or ~/.local/share) elsewhere. The working directory if none of those exist.
+pub fn data_dir() -> PathBuf {
+    let env = |key: &str| std::env::var_os(key).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = if cfg!(windows) {
+        env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        env("XDG_DATA_HOME").or_else(|| env("HOME").map(|home| home.join(".local/share")))
+    };
+    base.map_or_else(|| PathBuf::from("."), |base| base.join("dissonance"))
+}
+
+// The folder save files live in: data_dir() in the game. Tests and the headless app get a
+// scratch folder of their own instead, so what's on the machine can't change how they go.
+#[derive(Resource, Clone, Debug)]
+pub struct SaveDir(pub PathBuf);
+
+impl Default for SaveDir {
+    fn default() -> Self {
+        Self(data_dir())
+    }
+}
+
+impl SaveDir {
+    // A folder under the temp dir that nothing else uses, not made until something is saved to it
+    pub fn scratch() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        Self(std::env::temp_dir().join(format!("dissonance-saves-{}-{}", std::process::id(), n)))
+    }
+
+    pub fn slot_path(&self, slot: usize) -> PathBuf {
+        if slot == AUTOSAVE_SLOT {
+            return self.0.join("autosave.ron");
+        }
+        self.0.join(format!("save_{}.ron", slot))
+    }
+
+    pub fn slot_meta(&self, slot: usize) -> Option<SaveMeta> {
+        read_meta(&self.slot_path(slot))
+    }
+
+    pub fn any_saves(&self) -> bool {
+        (0..SAVE_SLOTS).any(|slot| self.slot_path(slot).exists())
+    }
+
+    pub fn delete_slot(&self, slot: usize) {
+        let path = self.slot_path(slot);
+        match std::fs::remove_file(&path) {
+            Ok(()) => info!("Deleted {}", path.display()),
+            Err(e) => warn!("Couldn't delete {}: {}", path.display(), e),
+        }
+    }
+}
+
+// The slot's metadata, or None if it's empty or unreadable
+pub fn read_meta(path: &Path) -> Option<SaveMeta> {
+    let text = std::fs::read_to_string(path).ok()?;
+    match ron::from_str::<SaveHeader>(&text) {
+        Ok(header) => Some(header.meta),
+        Err(e) => {
+            warn!("Couldn't parse {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// Whole seconds as "hh:mm:ss"; the hours keep counting past 99
+pub fn format_playtime(secs: f32) -> String {
+    let total = secs.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", total / 3600, total / 60 % 60, total % 60)
+}
+
+// Unix seconds as "YYYY-MM-DD HH:MM" (UTC)
+pub fn format_timestamp(secs: u64) -> String {
+    let (year, month, day, hour, minute, _) = utc_date_time(secs);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, hour, minute)
+}
+
+// Unix seconds as (year, month, day, hour, minute, second), UTC
+pub fn utc_date_time(secs: u64) -> (i64, i64, i64, u64, u64, u64) {
+    let days = (secs / 86_400) as i64;
+    let (hour, minute, second) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day, hour, minute, second)
+}
+
+// One line per slot for the pickers
+pub fn slot_label(slot: usize, meta: Option<&SaveMeta>) -> String {
+    let name = if slot == AUTOSAVE_SLOT { "Autosave".to_string() } else { format!("File {}", slot + 1) };
+    match meta {
+        Some(meta) => format!(
+            "{}  {}  {}  {}",
+            name,
+            room_title(&meta.room),
+            format_playtime(meta.playtime),
+            format_timestamp(meta.saved_at),
+        ),
+        None => format!("{}  Empty", name),
+    }
+}
+
+// "hallway" -> "Hallway"
+fn room_title(room: &str) -> String {
+    let mut chars = room.chars();
+    chars.next().map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+}
+
+pub fn write_save(path: &Path, data: &SaveData) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, text).map_err(|e| e.to_string())
+}
+
+// None (with a warning) if the file is missing or doesn't parse
+pub fn read_save(path: &Path) -> Option<SaveData> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            warn!("Couldn't read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    match ron::from_str(&text) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            warn!("Couldn't parse {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// Any interaction or flag change is progress worth warning about
+fn mark_unsaved_progress(
+    mut interactions: EventReader<InteractionEvent>,
+    mut flag_changes: EventReader<FlagChangedEvent>,
+    mut dirty: ResMut<DirtySinceSave>,
+) {
+    let progressed = interactions.read().count() + flag_changes.read().count() > 0;
+    if progressed && !dirty.0 {
+        dirty.0 = true;
+    }
+}
+
+// Only counted while Playing, so the pause menu doesn't add to it; real time, so reading a dialog
+// (which stops virtual time) does
+fn tick_playtime(time: Res<Time<Real>>, mut playtime: ResMut<Playtime>) {
+    playtime.0 += time.delta_secs();
+}
+
+// Everything a save is built from
+#[derive(SystemParam)]
+pub struct SaveSources<'w, 's> {
+    player_query: Query<'w, 's, (&'static Transform, &'static Player)>,
+    inventory: Res<'w, Inventory>,
+    wallet: Res<'w, Wallet>,
+    world: Res<'w, WorldState>,
+    flags: Res<'w, GameFlags>,
+    current_room: Res<'w, CurrentRoom>,
+    playtime: Res<'w, Playtime>,
+    visits: Res<'w, RoomVisits>,
+    quests: Res<'w, Quests>,
+    journal: Res<'w, Journal>,
+    ng_plus: Res<'w, NgPlus>,
+    rng: Res<'w, GameRng>,
+}
+
+impl SaveSources<'_, '_> {
+    // The game as it stands, stamped with the current time; None without a player
+    pub fn snapshot(&self) -> Option<SaveData> {
+        let (player_tf, player) = self.player_query.single().ok()?;
+        let saved_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Some(SaveData {
+            meta: SaveMeta {
+                room: self.current_room.0.clone(),
+                playtime: self.playtime.0,
+                saved_at,
+                completed: self.flags.get_bool(GAME_COMPLETED),
+            },
+            player_position: player_tf.translation.truncate(),
+            facing: player.facing,
+            items: self.inventory.items.iter().map(|item| item.id.clone()).collect(),
+            gold: self.wallet.gold,
+            world: self.world.clone(),
+            flags: self.flags.clone(),
+            visits: self.visits.clone(),
+            quests: self.quests.clone(),
+            journal: self.journal.clone(),
+            ng_plus: self.ng_plus.clone(),
+            seed: self.rng.seed(),
+        })
+    }
+}
+
+// Everything a save is restored into, SaveSources the other way round. Loading and starting over
+// both go through it, so neither can miss something the other puts back.
+#[derive(SystemParam)]
+pub struct SaveTargets<'w, 's> {
+    player_query: Query<'w, 's, (&'static mut Player, &'static mut AnimationState)>,
+    inventory: ResMut<'w, Inventory>,
+    wallet: ResMut<'w, Wallet>,
+    world: ResMut<'w, WorldState>,
+    pending_respawns: ResMut<'w, PendingRespawns>,
+    flags: ResMut<'w, GameFlags>,
+    playtime: ResMut<'w, Playtime>,
+    autosave: ResMut<'w, Autosave>,
+    visits: ResMut<'w, RoomVisits>,
+    quests: ResMut<'w, Quests>,
+    journal: ResMut<'w, Journal>,
+    ng_plus: ResMut<'w, NgPlus>,
+    dirty: ResMut<'w, DirtySinceSave>,
+}
+
+impl SaveTargets<'_, '_> {
+    // Everything `data` holds but the room and where the player stands, which are for the room
+    // loader and a teleport
+    pub fn restore(&mut self, data: &SaveData) {
+        *self.world = data.world.clone();
+        *self.flags = data.flags.clone();
+        self.pending_respawns.entries.clear();
+        self.inventory.items = data.items.iter().map(|id| item_by_id(id)).collect();
+        self.wallet.gold = data.gold;
+        self.playtime.0 = data.meta.playtime;
+        self.autosave.restart_clock();
+        *self.visits = data.visits.clone();
+        *self.quests = data.quests.clone();
+        *self.journal = data.journal.clone();
+        *self.ng_plus = data.ng_plus.clone();
+        self.dirty.0 = false;
+        if let Ok((mut player, mut anim)) = self.player_query.single_mut() {
+            player.facing = data.facing;
+            anim.facing = data.facing;
+        }
+    }
+
+    // How a fresh game has it, in New Game+ cycle `ng_plus` with its carried `flags`
+    pub fn reset(&mut self, ng_plus: NgPlus, flags: GameFlags) {
+        *self.world = WorldState::default();
+        *self.flags = flags;
+        self.pending_respawns.entries.clear();
+        self.inventory.items.clear();
+        self.wallet.gold = STARTING_GOLD;
+        self.playtime.0 = 0.0;
+        self.autosave.restart_clock();
+        *self.visits = RoomVisits::default();
+        *self.quests = Quests::default();
+        *self.journal = Journal::default();
+        *self.ng_plus = ng_plus;
+        self.dirty.0 = false;
+    }
+}
+
+// Picking a slot at a save point writes the file, and restores HP once it's actually written
+fn write_save_file(
+    mut events: EventReader<WriteSaveEvent>,
+    mut log_writer: EventWriter<LogEvent>,
+    mut heals: EventWriter<HealEvent>,
+    mut dirty: ResMut<DirtySinceSave>,
+    sources: SaveSources,
+    saves: Res<SaveDir>,
+) {
+    let Some(event) = events.read().last() else { return };
+    let Some(data) = sources.snapshot() else { return };
+    let path = saves.slot_path(event.slot);
+    match write_save(&path, &data) {
+        Ok(()) => {
+            info!("Saved to {}", path.display());
+            dirty.0 = false;
+            heals.write(HealEvent::full());
+        }
+        Err(e) => {
+            warn!("Couldn't save to {}: {}", path.display(), e);
+            log_writer.write(LogEvent::new("* ...but the save didn't take.".to_string()));
+        }
+    }
+}
+
+// Rebuilds the world from the save file: the room it was in, object states, inventory, flags
+// and where the player stood
+fn load_game(
+    mut events: EventReader<LoadGameEvent>,
+    mut commands: Commands,
+    mut targets: SaveTargets,
+    (mut loads, mut teleports): (EventWriter<LoadRoomEvent>, EventWriter<TeleportPlayerEvent>),
+    mut next_state: ResMut<NextState<GameState>>,
+    saves: Res<SaveDir>,
+) {
+    let Some(event) = events.read().last() else { return };
+    next_state.set(GameState::Playing);
+    let Some(data) = read_save(&saves.slot_path(event.slot)) else {
+        warn!("No usable save; starting a new game");
+        loads.write(LoadRoomEvent::at(START_ROOM, SpawnTarget::Named(PLAYER_START.to_string())));
+        return;
+    };
+
+    targets.restore(&data);
+    // The room loader replaces whatever is loaded, so nothing spawns twice
+    loads.write(LoadRoomEvent { id: data.meta.room.clone(), spawn_point: None });
+    teleports.write(TeleportPlayerEvent { to: SpawnTarget::Position(data.player_position), fade: true });
+    // Dying goes back to this save, as if its save point had just been used
+    commands.insert_resource(LastCheckpoint {
+        room: data.meta.room.clone(),
+        player_position: data.player_position,
+        items: targets.inventory.items.clone(),
+        world: data.world,
+    });
+    info!("Loaded {} in {}", saves.slot_path(event.slot).display(), data.meta.room);
+}
+
+// Puts everything back how a fresh game has it (bar the carried flags on New Game+) and loads the
+// first room. The title screen moves on to Playing itself.
+fn new_game(
+    mut events: EventReader<NewGameEvent>,
+    mut commands: Commands,
+    mut targets: SaveTargets,
+    (mut loads, mut heals): (EventWriter<LoadRoomEvent>, EventWriter<HealEvent>),
+    saves: Res<SaveDir>,
+) {
+    let Some(event) = events.read().last() else { return };
+    let finished = event.carry_from.and_then(|slot| read_save(&saves.slot_path(slot)));
+    let (next_ng_plus, next_flags) = match &finished {
+        Some(data) => data.ng_plus.next_cycle(&data.flags),
+        None => (NgPlus::default(), GameFlags::default()),
+    };
+
+    targets.reset(next_ng_plus, next_flags);
+    // Dying before the first save point has nowhere to go back to but the start
+    commands.remove_resource::<LastCheckpoint>();
+    heals.write(HealEvent::full());
+    // The room loader replaces whatever is loaded, so nothing from the last game lingers
+    loads.write(LoadRoomEvent::at(START_ROOM, SpawnTarget::Named(PLAYER_START.to_string())));
+    match targets.ng_plus.cycle {
+        0 => info!("Started a new game"),
+        cycle => info!("Started New Game+ cycle {} carrying {:?}", cycle, targets.ng_plus.carried_flags),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::ObjectState;
+
+    fn sample() -> SaveData {
+        let mut world = WorldState::default();
+        world.objects.insert("start/rusty_key".to_string(), ObjectState { taken: true, ..default() });
+        world.containers.insert("start/chest".to_string(), vec!["firewood".to_string()]);
+        let mut flags = GameFlags::default();
+        flags.set("peeked_metal_door", true);
+        let mut visits = RoomVisits::default();
+        visits.record("hallway");
+        let mut quests = Quests::default();
+        quests.activate(crate::quests::POWER_GENERATOR);
+        quests.complete_objective(crate::quests::POWER_GENERATOR, 0);
+        let mut journal = Journal::default();
+        journal.record("Opened the Metal Door", 95.0);
+        SaveData {
+            meta: SaveMeta { room: "hallway".to_string(), playtime: 3721.5, saved_at: 1_792_159_320, completed: true },
+            player_position: Vec2::new(12.0, -80.0),
+            facing: Direction::Left,
+            items: vec!["rusty_key".to_string(), "fuel_can".to_string()],
+            gold: 25,
+            world,
+            flags,
+            visits,
+            quests,
+            journal,
+            ng_plus: NgPlus { cycle: 1, carried_flags: vec!["flirted_with_figure".to_string()] },
+            seed: 430,
+        }
+    }
+
+    #[test]
+    fn save_round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!("dissonance-test-{}/save.ron", std::process::id()));
+        let data = sample();
+        write_save(&path, &data).unwrap();
+        assert_eq!(read_save(&path), Some(data.clone()));
+        // The pickers read only the header
+        assert_eq!(read_meta(&path), Some(data.meta.clone()));
+        assert_eq!(slot_label(0, Some(&data.meta)), "File 1  Hallway  01:02:01  2026-10-16 14:02");
+        assert_eq!(slot_label(2, None), "File 3  Empty");
+        assert_eq!(slot_label(AUTOSAVE_SLOT, Some(&data.meta)), "Autosave  Hallway  01:02:01  2026-10-16 14:02");
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn playtime_formats_as_hh_mm_ss() {
+        assert_eq!(format_playtime(0.0), "00:00:00");
+        assert_eq!(format_playtime(59.9), "00:00:59");
+        assert_eq!(format_playtime(123.0), "00:02:03");
+        assert_eq!(format_playtime(3721.5), "01:02:01");
+        assert_eq!(format_playtime(360_000.0), "100:00:00");
+        assert_eq!(format_playtime(-5.0), "00:00:00");
+    }
+
+    #[test]
+    fn older_saves_load_without_room_visits() {
+        let text = ron::to_string(&sample()).unwrap();
+        let start = text.find(",visits:").unwrap();
+        let old = format!("{})", &text[..start]);
+        let data: SaveData = ron::from_str(&old).unwrap();
+        assert_eq!(data.visits, RoomVisits::default());
+        assert_eq!(data.quests, Quests::default());
+        assert_eq!(data.journal, Journal::default());
+        assert_eq!(data.ng_plus, NgPlus::default());
+        assert_eq!(data.flags, sample().flags);
+        // Nor did they know about finishing the game
+        let old = text.replace(",completed:true", "");
+        assert!(!ron::from_str::<SaveData>(&old).unwrap().meta.completed);
+    }
+
+    #[test]
+    fn missing_or_corrupt_saves_load_as_nothing() {
+        let dir = std::env::temp_dir().join(format!("dissonance-test-corrupt-{}", std::process::id()));
+        assert_eq!(read_save(&dir.join("save.ron")), None);
+
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("save.ron");
+        let text = ron::to_string(&sample()).unwrap();
+        std::fs::write(&path, &text[..text.len() / 2]).unwrap();
+        assert_eq!(read_save(&path), None);
+        std::fs::write(&path, "not a save").unwrap();
+        assert_eq!(read_save(&path), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+// src/scenes.rs
+use bevy::prelude::*;
+use bevy::reflect::TypeRegistry;
+use bevy::scene::SceneFilter;
+use crate::audio::AmbientLoop;
+use crate::camera::CameraTarget;
+use crate::depth::{CastsShadow, YSort};
+use crate::flags::GameFlags;
+use crate::interaction::{
+    ActionRequirements, ConditionalExamine, CustomActionText, Interactable, LastInteracted, NearbyInteractable,
+    PeekText, PendingInteraction,
+};
+use crate::inventory::{Inventory, Wallet};
+use crate::journal::Journal;
+use crate::ngplus::NgPlus;
+use crate::objects::{
+    Container, Critter, Door, DoorAnimation, Elevator, Generator, Hazard, Hideable, Item, Light, Lockable, ObjectId,
+    PressurePlate, Pulse, Pushable, Respawns, RoomTransition, SavePoint, Shop, Solid, Switch, SwitchTarget, Telephone,
+    WorldState, NPC,
+};
+use crate::player::{
+    AnimationOverride, AnimationState, Collider, DamageFlash, Dash, ExternalImpulse, FloorModifier, Footsteps, Hidden,
+    IdleTimer, MoveTarget, Player, PlayerStats, Sneaking, SpawnPoint,
+};
+use crate::quests::Quests;
+use crate::rooms::{CurrentRoom, RoomMember, RoomVisits};
+use crate::save::{data_dir, Playtime};
+
+pub struct ScenesPlugin;
+
+impl Plugin for ScenesPlugin {
+    fn build(&self, app: &mut App) {
+        register_gameplay_types(app);
+        app.add_systems(Update, export_scene_on_key);
+    }
+}
+
+// Every gameplay component and the resources a playthrough is made of, so scene files and
+// inspectors can see them. Types they hold (Direction, InteractionAction, ...) come along.
+pub fn register_gameplay_types(app: &mut App) {
+    app.register_type::<Player>()
+        .register_type::<PlayerStats>()
+        .register_type::<Collider>()
+        .register_type::<Sneaking>()
+        .register_type::<Dash>()
+        .register_type::<FloorModifier>()
+        .register_type::<MoveTarget>()
+        .register_type::<ExternalImpulse>()
+        .register_type::<AnimationState>()
+        .register_type::<AnimationOverride>()
+        .register_type::<IdleTimer>()
+        .register_type::<Hidden>()
+        .register_type::<SpawnPoint>()
+        .register_type::<Footsteps>()
+        .register_type::<DamageFlash>()
+        .register_type::<Interactable>()
+        .register_type::<ConditionalExamine>()
+        .register_type::<ActionRequirements>()
+        .register_type::<PeekText>()
+        .register_type::<CustomActionText>()
+        .register_type::<LastInteracted>()
+        .register_type::<NearbyInteractable>()
+        .register_type::<PendingInteraction>()
+        .register_type::<Item>()
+        .register_type::<Light>()
+        .register_type::<Generator>()
+        .register_type::<NPC>()
+        .register_type::<Solid>()
+        .register_type::<Switch>()
+        .register_type::<SwitchTarget>()
+        .register_type::<Elevator>()
+        .register_type::<Pushable>()
+        .register_type::<PressurePlate>()
+        .register_type::<Lockable>()
+        .register_type::<Container>()
+        .register_type::<ObjectId>()
+        .register_type::<Respawns>()
+        .register_type::<Shop>()
+        .register_type::<Hazard>()
+        .register_type::<Telephone>()
+        .register_type::<Hideable>()
+        .register_type::<SavePoint>()
+        .register_type::<Pulse>()
+        .register_type::<Critter>()
+        .register_type::<Door>()
+        .register_type::<DoorAnimation>()
+        .register_type::<RoomTransition>()
+        .register_type::<RoomMember>()
+        .register_type::<YSort>()
+        .register_type::<CastsShadow>()
+        .register_type::<CameraTarget>()
+        .register_type::<AmbientLoop>()
+        .register_type::<Inventory>()
+        .register_type::<Wallet>()
+        .register_type::<WorldState>()
+        .register_type::<GameFlags>()
+        .register_type::<Quests>()
+        .register_type::<Journal>()
+        .register_type::<Playtime>()
+        .register_type::<RoomVisits>()
+        .register_type::<CurrentRoom>()
+        .register_type::<NgPlus>();
+}
+
+// Whether a registered type is one of the game's own rather than Bevy's
+fn is_game_type(type_path: &str) -> bool {
+    type_path.starts_with(concat!(env!("CARGO_CRATE_NAME"), "::"))
+}
+
+// The game's own registered components (plus where each entity stands), or its resources
+fn gameplay_filter(registry: &TypeRegistry, resources: bool) -> SceneFilter {
+    let base = if resources { SceneFilter::deny_all() } else { SceneFilter::deny_all().allow::<Transform>() };
+    registry
+        .iter()
+        .filter(|registration| is_game_type(registration.type_info().type_path()))
+        .filter(|registration| match resources {
+            true => registration.data::<ReflectResource>().is_some(),
+            false => registration.data::<ReflectComponent>().is_some(),
+        })
+        .fold(base, |filter, registration| filter.allow_by_id(registration.type_id()))
+}
+
+// The player and everything in the loaded room, with the playthrough's resources
+pub fn gameplay_scene(world: &World) -> DynamicScene {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let entities = world
+        .iter_entities()
+        .filter(|entity| entity.contains::<Player>() || entity.contains::<RoomMember>())
+        .map(|entity| entity.id());
+    DynamicSceneBuilder::from_world(world)
+        .with_component_filter(gameplay_filter(&registry, false))
+        .with_resource_filter(gameplay_filter(&registry, true))
+        .extract_entities(entities)
+        .extract_resources()
+        .build()
+}
+
+// Debug: F8 writes the current room's gameplay entities to <data dir>/<room>.scn.ron
+fn export_scene_on_key(world: &World) {
+    if !world.resource::<ButtonInput<KeyCode>>().just_pressed(KeyCode::F8) {
+        return;
+    }
+    let path = data_dir().join(format!("{}.scn.ron", world.resource::<CurrentRoom>().0));
+    let text = gameplay_scene(world).serialize(&world.resource::<AppTypeRegistry>().read());
+    let written = text.map_err(|e| e.to_string()).and_then(|text| {
+        std::fs::create_dir_all(data_dir()).map_err(|e| e.to_string())?;
+        std::fs::write(&path, text).map_err(|e| e.to_string())
+    });
+    match written {
+        Ok(()) => info!("Exported scene to {}", path.display()),
+        Err(e) => warn!("Couldn't export scene to {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::entity::EntityHashMap;
+    use bevy::scene::serde::SceneDeserializer;
+    use serde::de::DeserializeSeed;
+    use crate::inventory::item_by_id;
+    use crate::objects::{HazardKind, KeyRequirement};
+
+    fn app() -> App {
+        let mut app = App::new();
+        register_gameplay_types(&mut app);
+        app.register_type::<Transform>();
+        app
+    }
+
+    fn by_id(world: &mut World, id: &str) -> Entity {
+        let mut query = world.query::<(Entity, &ObjectId)>();
+        query.iter(world).find(|(_, object)| object.0 == id).unwrap().0
+    }
+
+    #[test]
+    fn exported_scenes_load_back_the_same() {
+        let mut source = app();
+        let world = source.world_mut();
+        let generator = world
+            .spawn((
+                Generator { is_running: true, fuel_level: 2.5, max_fuel: 10.0 },
+                ObjectId("start/generator".to_string()),
+                RoomMember::new("start"),
+                Solid,
+                Transform::from_xyz(0.0, -120.0, 1.0),
+            ))
+            .id();
+        world.spawn((
+            Hazard { kind: HazardKind::Electric, magnitude: 4.0, powered_by: Some(generator) },
+            ObjectId("start/wires".to_string()),
+            RoomMember::new("start"),
+        ));
+        world.spawn((
+            Door { is_open: false, auto_close_after: Some(3.0), open_for: 0.0 },
+            Lockable { requires: KeyRequirement::AnyOf(vec!["rusty_key".to_string()]), locked: true, consumes_key: false },
+            ObjectId("start/metal_door".to_string()),
+            RoomMember::new("start"),
+        ));
+        let player = Player {
+            speed: 150.0,
+            run_multiplier: 1.75,
+            running: false,
+            interact_range: 50.0,
+            facing: crate::player::Direction::Up,
+        };
+        world.spawn((player, PlayerStats::new(20), Transform::from_xyz(12.0, -80.0, 10.0)));
+        // Not part of the room: left out
+        world.spawn(ObjectId("hallway/elsewhere".to_string()));
+        let mut inventory = Inventory::new(8);
+        inventory.add_item(item_by_id("fuel_can"));
+        world.insert_resource(inventory);
+        let mut flags = GameFlags::default();
+        flags.set("first_door_opened", true);
+        world.insert_resource(flags.clone());
+
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let text = gameplay_scene(world).serialize(&registry.read()).unwrap();
+
+        let mut loaded = app();
+        let mut deserializer = ron::de::Deserializer::from_str(&text).unwrap();
+        let scene = SceneDeserializer { type_registry: &registry.read() }.deserialize(&mut deserializer).unwrap();
+        let world = loaded.world_mut();
+        scene.write_to_world(world, &mut EntityHashMap::default()).unwrap();
+
+        assert_eq!(world.query::<&ObjectId>().iter(world).count(), 3);
+        let generator = by_id(world, "start/generator");
+        assert_eq!(world.get::<Generator>(generator).unwrap().fuel_level, 2.5);
+        assert!(world.get::<Solid>(generator).is_some());
+        // References between entities point at the loaded copies
+        let wires = by_id(world, "start/wires");
+        assert_eq!(world.get::<Hazard>(wires).unwrap().powered_by, Some(generator));
+        let door = by_id(world, "start/metal_door");
+        let door_state = world.get::<Door>(door).unwrap();
+        assert_eq!((door_state.is_open, door_state.auto_close_after), (false, Some(3.0)));
+        let lock = world.get::<Lockable>(door).unwrap();
+        assert!(lock.locked && matches!(&lock.requires, KeyRequirement::AnyOf(ids) if ids == &["rusty_key"]));
+
+        let (player, stats, tf) = world.query::<(&Player, &PlayerStats, &Transform)>().single(world).unwrap();
+        assert_eq!((player.speed, stats.hp, tf.translation), (150.0, 20, Vec3::new(12.0, -80.0, 10.0)));
+        assert_eq!(world.resource::<Inventory>().items[0].id, "fuel_can");
+        assert_eq!(*world.resource::<GameFlags>(), flags);
+    }
+}
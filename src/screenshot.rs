@@ -0,0 +1,134 @@
+// src/screenshot.rs
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use bevy::window::PrimaryWindow;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::input::{Action, InputMap};
+use crate::save::utc_date_time;
+use crate::settings::Settings;
+use crate::ui::ToastEvent;
+
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingScreenshots>()
+            // Works anywhere, the title screen and pause menu included
+            .add_systems(Update, take_screenshot_on_key);
+    }
+}
+
+// Captures asked for and not yet written, by the file each is going to. While any are waiting
+// with the UI left out, the root UI nodes hidden for them are kept here, with how they were shown.
+#[derive(Resource, Default)]
+struct PendingScreenshots {
+    paths: Vec<PathBuf>,
+    hidden: Vec<(Entity, Visibility)>,
+}
+
+// screenshots/ next to the executable
+fn screenshot_dir() -> Option<PathBuf> {
+    Some(std::env::current_exe().ok()?.parent()?.join("screenshots"))
+}
+
+// dissonance_YYYYMMDD_HHMMSS.png (UTC, like save times), then _2, _3, ... for more in the same
+// second, so no capture overwrites another
+fn screenshot_path(dir: &Path, secs: u64, taken: impl Fn(&Path) -> bool) -> PathBuf {
+    let (year, month, day, hour, minute, second) = utc_date_time(secs);
+    let stem = format!("dissonance_{:04}{:02}{:02}_{:02}{:02}{:02}", year, month, day, hour, minute, second);
+    let mut path = dir.join(format!("{}.png", stem));
+    let mut n = 1;
+    while taken(&path) {
+        n += 1;
+        path = dir.join(format!("{}_{}.png", stem, n));
+    }
+    path
+}
+
+fn screenshot_failed(toasts: &mut EventWriter<ToastEvent>, error: &str) {
+    warn!("Couldn't take a screenshot: {}", error);
+    toasts.write(ToastEvent("Screenshot failed".to_string()));
+}
+
+// Every press gets its own capture and file, however fast they come
+fn take_screenshot_on_key(
+    mut commands: Commands,
+    (keyboard, input): (Res<ButtonInput<KeyCode>>, Res<InputMap>),
+    settings: Res<Settings>,
+    window: Query<(), With<PrimaryWindow>>,
+    mut pending: ResMut<PendingScreenshots>,
+    mut ui_nodes: Query<(Entity, &mut Visibility, Has<ChildOf>), With<Node>>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    if !input.just_pressed(Action::Screenshot, &keyboard) {
+        return;
+    }
+    let dir = match screenshot_dir() {
+        _ if window.is_empty() => Err("there's no window to capture".to_string()),
+        None => Err("couldn't find the game's folder".to_string()),
+        Some(dir) => std::fs::create_dir_all(&dir).map(|_| dir).map_err(|e| e.to_string()),
+    };
+    let dir = match dir {
+        Ok(dir) => dir,
+        Err(e) => return screenshot_failed(&mut toasts, &e),
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let path = screenshot_path(&dir, now, |path| path.exists() || pending.paths.iter().any(|p| p == path));
+    pending.paths.push(path.clone());
+
+    // Hidden for the frame being captured; put back once the last capture waiting is written
+    if settings.clean_screenshots && pending.hidden.is_empty() {
+        for (entity, mut visibility, _) in ui_nodes.iter_mut().filter(|(_, _, is_child)| !is_child) {
+            pending.hidden.push((entity, *visibility));
+            *visibility = Visibility::Hidden;
+        }
+    }
+    commands.spawn(Screenshot::primary_window()).observe(
+        move |captured: Trigger<ScreenshotCaptured>,
+              mut pending: ResMut<PendingScreenshots>,
+              mut visibilities: Query<&mut Visibility>,
+              mut toasts: EventWriter<ToastEvent>| {
+            // Without the alpha channel, which holds brightness rather than transparency with HDR on
+            let written = captured
+                .event()
+                .0
+                .clone()
+                .try_into_dynamic()
+                .map_err(|e| e.to_string())
+                .and_then(|image| image.to_rgb8().save(&path).map_err(|e| e.to_string()));
+            match written {
+                Ok(()) => {
+                    info!("Saved screenshot to {}", path.display());
+                    toasts.write(ToastEvent(format!("Saved {}", path.display())));
+                }
+                Err(e) => screenshot_failed(&mut toasts, &format!("{}: {}", path.display(), e)),
+            }
+            pending.paths.retain(|p| *p != path);
+            if pending.paths.is_empty() {
+                for (entity, shown) in pending.hidden.drain(..) {
+                    if let Ok(mut visibility) = visibilities.get_mut(entity) {
+                        *visibility = shown;
+                    }
+                }
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screenshots_in_the_same_second_get_their_own_files() {
+        let dir = Path::new("screenshots");
+        // 2024-03-05 06:07:08 UTC
+        let secs = 1_709_618_828;
+        let first = dir.join("dissonance_20240305_060708.png");
+        assert_eq!(screenshot_path(dir, secs, |_| false), first);
+        let taken = [first.clone(), dir.join("dissonance_20240305_060708_2.png")];
+        let third = screenshot_path(dir, secs, |path| taken.iter().any(|t| t == path));
+        assert_eq!(third, dir.join("dissonance_20240305_060708_3.png"));
+    }
+}
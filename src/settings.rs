@@ -0,0 +1,239 @@
+// src/settings.rs
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use crate::input::InputMap;
+use crate::save::data_dir;
+
+// How fast dialog lines type themselves out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TextSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+    Instant,
+}
+
+impl TextSpeed {
+    pub const ALL: [Self; 4] = [Self::Slow, Self::Normal, Self::Fast, Self::Instant];
+
+    // Characters revealed per second; None shows whole lines at once
+    pub fn chars_per_sec(self) -> Option<f32> {
+        match self {
+            Self::Slow => Some(20.0),
+            Self::Normal => Some(40.0),
+            Self::Fast => Some(80.0),
+            Self::Instant => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Slow => "Slow",
+            Self::Normal => "Normal",
+            Self::Fast => "Fast",
+            Self::Instant => "Instant",
+        }
+    }
+
+    // The next speed `steps` along, stopping at either end
+    pub fn step(self, steps: i32) -> Self {
+        let index = Self::ALL.iter().position(|&s| s == self).unwrap_or(1) as i32;
+        Self::ALL[(index + steps).clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+}
+
+// Player-facing options, kept in settings.ron next to the saves. Fields missing from an older
+// file take their defaults.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub text_speed: TextSpeed,
+    pub ui_scale: f32,      // On top of the letterbox scale; 1.0 is one UI pixel per game pixel
+    pub master_volume: f32, // 0.0 to 1.0, applied to everything
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub fullscreen: bool,
+    pub toggle_run: bool, // Tap Shift to start/stop running instead of holding it
+    pub swap_confirm_cancel: bool, // Confirm and cancel trade their first keys (Z and X by default)
+    pub zoom: f32,        // Camera projection scale; one of camera::ZOOM_STEPS
+    pub clean_screenshots: bool, // F12 leaves the UI out of the picture
+    pub touch_controls: bool, // Show the on-screen buttons even before a touch is seen
+    pub controls: InputMap, // As rebound on the Controls page, without the confirm/cancel swap
+    #[serde(skip)]
+    pub muted: bool, // M silences everything without touching the volumes; never saved
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            text_speed: TextSpeed::Normal,
+            ui_scale: 1.0,
+            master_volume: 1.0,
+            music_volume: 0.8,
+            sfx_volume: 1.0,
+            fullscreen: false,
+            toggle_run: false,
+            swap_confirm_cancel: false,
+            zoom: 1.0,
+            clean_screenshots: false,
+            touch_controls: false,
+            controls: InputMap::default(),
+            muted: false,
+        }
+    }
+}
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+// Volume sliders move in tenths
+const VOLUME_STEPS: f32 = 10.0;
+
+impl Settings {
+    pub fn path() -> PathBuf {
+        data_dir().join(SETTINGS_FILE)
+    }
+
+    // The saved settings, or the defaults (with a warning) if the file is missing or broken
+    pub fn load_or_default() -> Self {
+        Self::read(&Self::path()).unwrap_or_default()
+    }
+
+    pub fn read(path: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| info!("No settings at {} ({}); using defaults", path.display(), e))
+            .ok()?;
+        ron::from_str(&text)
+            .map_err(|e| warn!("Couldn't parse {}: {}; using defaults", path.display(), e))
+            .ok()
+    }
+
+    pub fn save(&self) {
+        let path = Self::path();
+        if let Err(e) = self.write(&path) {
+            warn!("Couldn't save settings to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(path, text).map_err(|e| e.to_string())
+    }
+
+    // The keys to play with: the saved controls, with the confirm/cancel swap on top. Actions
+    // newer than the file get their default keys.
+    pub fn input_map(&self) -> InputMap {
+        let mut map = self.controls.clone();
+        for (action, keys) in InputMap::default().bindings {
+            map.bindings.entry(action).or_insert(keys);
+        }
+        if self.swap_confirm_cancel {
+            map.swap_confirm_cancel();
+        }
+        map
+    }
+
+    // Keeps `map` (the keys in play) as the saved controls, taking the swap back off
+    pub fn remember_controls(&mut self, map: &InputMap) {
+        self.controls = map.clone();
+        if self.swap_confirm_cancel {
+            self.controls.swap_confirm_cancel();
+        }
+    }
+
+    fn master_gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.master_volume }
+    }
+
+    // Linear gain for a sound effect at `volume`
+    pub fn sfx_gain(&self, volume: f32) -> f32 {
+        volume * self.master_gain() * self.sfx_volume
+    }
+
+    // Linear gain for music at `volume`
+    pub fn music_gain(&self, volume: f32) -> f32 {
+        volume * self.master_gain() * self.music_volume
+    }
+}
+
+// Moves a volume by `steps` tenths, staying within 0..=1 and on whole tenths
+pub fn step_volume(volume: f32, steps: i32) -> f32 {
+    ((volume * VOLUME_STEPS).round() + steps as f32).clamp(0.0, VOLUME_STEPS) / VOLUME_STEPS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_and_fill_in_missing_fields() {
+        let dir = std::env::temp_dir().join(format!("dissonance-settings-{}", std::process::id()));
+        let path = dir.join(SETTINGS_FILE);
+        let settings = Settings {
+            text_speed: TextSpeed::Fast,
+            sfx_volume: 0.3,
+            toggle_run: true,
+            ..default()
+        };
+        settings.write(&path).unwrap();
+        assert_eq!(Settings::read(&path), Some(settings));
+
+        // An older file with only some of the fields
+        std::fs::write(&path, "(music_volume: 0.5)").unwrap();
+        let partial = Settings::read(&path).unwrap();
+        assert_eq!(partial.music_volume, 0.5);
+        assert_eq!(partial.text_speed, TextSpeed::Normal);
+
+        std::fs::write(&path, "not settings").unwrap();
+        assert_eq!(Settings::read(&path), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(Settings::read(&path), None);
+    }
+
+    #[test]
+    fn rebound_controls_are_saved_without_the_swap() {
+        use crate::input::Action;
+        let mut settings = Settings { swap_confirm_cancel: true, ..default() };
+        let mut map = settings.input_map();
+        assert_eq!(map.keys(Action::Confirm)[0], KeyCode::KeyX);
+        map.bind(Action::Dash, KeyCode::KeyQ);
+        settings.remember_controls(&map);
+        assert_eq!(settings.controls.keys(Action::Confirm)[0], KeyCode::KeyZ);
+        assert_eq!(settings.controls.keys(Action::Dash), [KeyCode::KeyC, KeyCode::KeyQ]);
+        assert_eq!(settings.input_map(), map);
+
+        // A file from before an action existed still gets that action's keys
+        settings.controls.bindings.remove(&Action::Screenshot);
+        assert_eq!(settings.input_map().keys(Action::Screenshot), [KeyCode::F12]);
+    }
+
+    #[test]
+    fn volumes_multiply_and_mute_silences_everything() {
+        let mut settings = Settings { master_volume: 0.5, music_volume: 0.8, sfx_volume: 0.4, ..default() };
+        assert_eq!(settings.music_gain(1.0), 0.4);
+        assert_eq!(settings.sfx_gain(0.5), 0.1);
+
+        settings.muted = true;
+        assert_eq!(settings.music_gain(1.0), 0.0);
+        assert_eq!(settings.sfx_gain(0.5), 0.0);
+        // The sliders are kept for unmuting, and muting isn't saved
+        assert_eq!(settings.master_volume, 0.5);
+        let saved: Settings = ron::from_str(&ron::to_string(&settings).unwrap()).unwrap();
+        assert!(!saved.muted);
+    }
+
+    #[test]
+    fn adjustments_stop_at_the_ends() {
+        assert_eq!(step_volume(0.8, 1), 0.9);
+        assert_eq!(step_volume(1.0, 1), 1.0);
+        assert_eq!(step_volume(0.1, -1), 0.0);
+        assert_eq!(step_volume(0.0, -1), 0.0);
+        assert_eq!(TextSpeed::Normal.step(1), TextSpeed::Fast);
+        assert_eq!(TextSpeed::Instant.step(1), TextSpeed::Instant);
+        assert_eq!(TextSpeed::Slow.step(-1), TextSpeed::Slow);
+    }
+}
@@ -0,0 +1,205 @@
+// src/spatial.rs
+use bevy::prelude::*;
+use std::collections::HashMap;
+use crate::interaction::Interactable;
+use crate::objects::Solid;
+use crate::player::Collider;
+use crate::GameSet;
+
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialGrid>()
+            .add_systems(Update, update_spatial_grid.in_set(GameSet::Detect));
+    }
+}
+
+// Side of one grid cell, in world pixels
+pub const GRID_CELL: f32 = 64.0;
+
+// Which solids and interactables are roughly where, so collision and reach checks only look at
+// what's nearby. Queries can return things that turn out not to touch; they never miss one that
+// does. Each half is rebuilt in Detect whenever one of its own moves, changes shape, comes or
+// goes, so a critter wandering about doesn't redo every wall in the room.
+#[derive(Resource, Default)]
+pub struct SpatialGrid {
+    solids: HashMap<IVec2, Vec<Entity>>,
+    interactables: HashMap<IVec2, Vec<Entity>>,
+    reach: f32, // The longest interaction radius of anything in the grid
+}
+
+impl SpatialGrid {
+    pub fn clear_solids(&mut self) {
+        self.solids.clear();
+    }
+
+    pub fn clear_interactables(&mut self) {
+        self.interactables.clear();
+        self.reach = 0.0;
+    }
+
+    // `rect` is the solid's collision box
+    pub fn add_solid(&mut self, entity: Entity, rect: Rect) {
+        for cell in cells(rect) {
+            self.solids.entry(cell).or_default().push(entity);
+        }
+    }
+
+    // `rect` is the object's sprite (what a click lands on), or just where it is if it has none;
+    // `reach` its interaction radius
+    pub fn add_interactable(&mut self, entity: Entity, rect: Rect, reach: f32) {
+        for cell in cells(rect) {
+            self.interactables.entry(cell).or_default().push(entity);
+        }
+        self.reach = self.reach.max(reach);
+    }
+
+    // Solids whose boxes may overlap `area`, in entity order
+    pub fn solids_near(&self, area: Rect) -> Vec<Entity> {
+        near(&self.solids, area)
+    }
+
+    // Interactables whose sprites or positions may be within `radius` of `point`, in entity order
+    pub fn interactables_near(&self, point: Vec2, radius: f32) -> Vec<Entity> {
+        near(&self.interactables, Rect::from_center_half_size(point, Vec2::splat(radius)))
+    }
+
+    // How far away something can still be in reach
+    pub fn reach(&self) -> f32 {
+        self.reach
+    }
+}
+
+fn cells(rect: Rect) -> impl Iterator<Item = IVec2> {
+    let min = (rect.min / GRID_CELL).floor().as_ivec2();
+    let max = (rect.max / GRID_CELL).floor().as_ivec2();
+    (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+}
+
+fn near(grid: &HashMap<IVec2, Vec<Entity>>, area: Rect) -> Vec<Entity> {
+    let mut found: Vec<Entity> = cells(area).filter_map(|cell| grid.get(&cell)).flatten().copied().collect();
+    // Big things sit in several cells
+    found.sort();
+    found.dedup();
+    found
+}
+
+// A solid that moved, changed shape or just became one
+type SolidsChanged = (With<Solid>, Or<(Changed<Transform>, Changed<Sprite>, Changed<Collider>, Changed<Solid>)>);
+
+// The same for interactables; their colliders don't matter here, their sprites do
+type InteractablesChanged = (With<Interactable>, Or<(Changed<Transform>, Changed<Sprite>, Changed<Interactable>)>);
+
+pub fn update_spatial_grid(
+    mut grid: ResMut<SpatialGrid>,
+    solids: Query<(Entity, &Transform, &Sprite, Option<&Collider>), With<Solid>>,
+    interactables: Query<(Entity, &Interactable, &Transform, Option<&Sprite>)>,
+    changed_solids: Query<(), SolidsChanged>,
+    changed_interactables: Query<(), InteractablesChanged>,
+    mut removed_solids: RemovedComponents<Solid>,
+    mut removed_interactables: RemovedComponents<Interactable>,
+) {
+    if !changed_solids.is_empty() || removed_solids.read().count() > 0 {
+        grid.clear_solids();
+        for (entity, tf, sprite, collider) in solids.iter() {
+            let collider = Collider::of(sprite, collider);
+            let center = collider.center(tf.translation.truncate());
+            grid.add_solid(entity, Rect::from_center_half_size(center, collider.half_extents));
+        }
+    }
+    if changed_interactables.is_empty() && removed_interactables.read().count() == 0 {
+        return;
+    }
+    grid.clear_interactables();
+    for (entity, interactable, tf, sprite) in interactables.iter() {
+        // Without a sprite there's nothing to click, just a spot to be in reach of
+        let half = sprite.map_or(Vec2::ZERO, |sprite| Collider::of(sprite, None).half_extents);
+        grid.add_interactable(entity, Rect::from_center_half_size(tf.translation.truncate(), half), interactable.reach());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::{fits, move_player, movement_area, PLAYER_HALF_EXTENTS};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    // 500 solids of mixed sizes scattered over a big map, and a walk through it checked against
+    // every solid and against only the grid's nearby ones
+    #[test]
+    fn the_grid_finds_what_brute_force_finds() {
+        let mut rng = StdRng::seed_from_u64(423);
+        let solids: Vec<(Transform, Sprite)> = (0..500)
+            .map(|_| {
+                let pos = Vec2::new(rng.gen_range(-2000.0..2000.0), rng.gen_range(-2000.0..2000.0));
+                let size = Vec2::new(rng.gen_range(4.0..160.0), rng.gen_range(4.0..160.0));
+                (Transform::from_translation(pos.extend(0.0)), Sprite { custom_size: Some(size), ..default() })
+            })
+            .collect();
+        let boxes: Vec<Rect> = solids
+            .iter()
+            .map(|(tf, sprite)| Rect::from_center_half_size(tf.translation.truncate(), Collider::of(sprite, None).half_extents))
+            .collect();
+        let mut grid = SpatialGrid::default();
+        for (i, rect) in boxes.iter().enumerate() {
+            grid.add_solid(Entity::from_raw(i as u32), *rect);
+        }
+
+        for _ in 0..200 {
+            let center = Vec2::new(rng.gen_range(-2100.0..2100.0), rng.gen_range(-2100.0..2100.0));
+            let area = Rect::from_center_half_size(center, Vec2::new(rng.gen_range(1.0..100.0), rng.gen_range(1.0..100.0)));
+            let touching = |i: &usize| !boxes[*i].intersect(area).is_empty();
+            let brute: Vec<usize> = (0..boxes.len()).filter(touching).collect();
+            let near: Vec<usize> = grid.solids_near(area).iter().map(|e| e.index() as usize).filter(touching).collect();
+            assert_eq!(near, brute);
+        }
+
+        let all: Vec<_> = solids.iter().map(|(tf, sprite)| (tf, sprite, None)).collect();
+        let collider = Collider::new(PLAYER_HALF_EXTENTS);
+        let no_crates: &mut [(&mut Transform, &Sprite, Option<&Collider>)] = &mut [];
+        let mut walks = 0;
+        while walks < 200 {
+            let start = Vec3::new(rng.gen_range(-2000.0..2000.0), rng.gen_range(-2000.0..2000.0), 0.0);
+            // The player is never left inside a solid to start from
+            if !fits(start.truncate(), &collider, &all) {
+                continue;
+            }
+            walks += 1;
+            let delta = Vec2::new(rng.gen_range(-40.0..40.0), rng.gen_range(-40.0..40.0));
+            let mut brute = start;
+            move_player(&mut brute, &collider, delta, &all, no_crates);
+            let area = movement_area(start.truncate(), &collider, delta);
+            let nearby: Vec<_> = grid.solids_near(area).iter().map(|e| all[e.index() as usize]).collect();
+            let mut gridded = start;
+            move_player(&mut gridded, &collider, delta, &nearby, no_crates);
+            assert_eq!(gridded, brute, "from {} by {}", start, delta);
+        }
+    }
+
+    // A critter walking about moves its own entry and leaves the walls' half of the grid alone
+    #[test]
+    fn a_moving_interactable_leaves_the_solids_be() {
+        // One system kept across runs, so it only sees what changed since the last one
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_spatial_grid);
+        let mut world = World::new();
+        world.init_resource::<SpatialGrid>();
+        let sprite = Sprite { custom_size: Some(Vec2::splat(16.0)), ..default() };
+        let wall = world.spawn((Solid, Transform::default(), sprite.clone())).id();
+        let dog = world.spawn((Interactable::default(), Transform::from_xyz(200.0, 0.0, 0.0), sprite)).id();
+        schedule.run(&mut world);
+
+        // Stands in for a solid the next rebuild would drop
+        let marker = world.spawn_empty().id();
+        world.resource_mut::<SpatialGrid>().add_solid(marker, Rect::from_center_half_size(Vec2::ZERO, Vec2::ONE));
+        world.get_mut::<Transform>(dog).unwrap().translation.x = 400.0;
+        schedule.run(&mut world);
+
+        let grid = world.resource::<SpatialGrid>();
+        assert_eq!(grid.solids_near(Rect::from_center_half_size(Vec2::ZERO, Vec2::ONE)), [wall, marker]);
+        assert!(grid.interactables_near(Vec2::new(200.0, 0.0), 1.0).is_empty());
+        assert_eq!(grid.interactables_near(Vec2::new(400.0, 0.0), 1.0), [dog]);
+    }
+}
@@ -0,0 +1,1121 @@
+// src/state.rs
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::color::palettes::basic::{RED, WHITE};
+use bevy::input::InputSystem;
+use bevy::window::WindowCloseRequested;
+use crate::GameSet;
+use crate::autosave::autosave_newer_than_files;
+use crate::input::{key_label, Action, ControlPreset, InputMap};
+use crate::ngplus::newest_completed_slot;
+use crate::settings::{step_volume, Settings};
+use crate::save::{
+    format_playtime, slot_label, DirtySinceSave, LoadGameEvent, NewGameEvent, Playtime, SaveDir, AUTOSAVE_SLOT,
+    SAVE_SLOTS,
+};
+use crate::ui::{option_color, UiState};
+
+// Which screen the game is on. Everything in a GameSet (and the player's own systems) only runs
+// while Playing; the world stays loaded but frozen underneath the other two.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    Playing,
+    Paused,
+}
+
+pub struct StatePlugin;
+
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), despawn_screen_menu)
+            .add_systems(OnEnter(GameState::Paused), (spawn_pause_menu, pause_time))
+            .add_systems(OnExit(GameState::Paused), (despawn_screen_menu, resume_time))
+            .add_systems(Update, (
+                navigate_screen_menu,
+                select_main_menu_option.run_if(in_state(GameState::MainMenu)),
+                cancel_file_picker.run_if(in_state(GameState::MainMenu)),
+                select_pause_menu_option.run_if(in_state(GameState::Paused)),
+                cancel_controls_page.run_if(in_state(GameState::Paused)),
+            ).chain().run_if(in_state(GameState::MainMenu).or(in_state(GameState::Paused))))
+            // Straight after the keyboard is read, so the key it takes can be swallowed before
+            // anything else sees it
+            .add_systems(PreUpdate, capture_binding.after(InputSystem).run_if(in_state(GameState::Paused)))
+            // Ahead of the gameplay sets, so an Escape that closes a context menu doesn't also pause
+            .add_systems(Update, toggle_pause
+                .before(GameSet::Detect)
+                .run_if(in_state(GameState::Playing).or(in_state(GameState::Paused))))
+            .add_systems(Update, (
+                confirm_window_close,
+                fade_title_flash,
+                save_controls.run_if(resource_changed::<InputMap>.and(not(resource_added::<InputMap>))),
+            ));
+    }
+}
+
+// Title screen options, top to bottom
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MainMenuOption {
+    Continue,
+    Start,
+    NewGamePlus,
+    Quit,
+}
+
+impl MainMenuOption {
+    pub const ALL: [Self; 4] = [Self::Continue, Self::Start, Self::NewGamePlus, Self::Quit];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Continue => "Continue",
+            Self::Start => "Start",
+            Self::NewGamePlus => "New Game+",
+            Self::Quit => "Quit",
+        }
+    }
+
+    // The options on screen: New Game+ only shows up once some file has finished the game
+    pub fn shown(ng_plus: bool) -> Vec<Self> {
+        Self::ALL.into_iter().filter(|&o| ng_plus || o != Self::NewGamePlus).collect()
+    }
+}
+
+// Which list the title screen shows: its own options, the save files behind Continue, or the
+// offer to pick up from an autosave newer than any of them
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+enum TitlePage {
+    #[default]
+    Main,
+    Files,
+    Recover,
+}
+
+// Seconds Cancel has to be held on the file picker to delete the highlighted file, or on the
+// Controls page to clear the highlighted action's keys
+const DELETE_HOLD_SECS: f32 = 1.0;
+
+// Pause menu options, top to bottom
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PauseMenuOption {
+    Resume,
+    Settings,
+    QuitToTitle,
+}
+
+impl PauseMenuOption {
+    pub const ALL: [Self; 3] = [Self::Resume, Self::Settings, Self::QuitToTitle];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Resume => "Resume",
+            Self::Settings => "Settings",
+            Self::QuitToTitle => "Quit to Title",
+        }
+    }
+
+}
+
+// Which list the pause menu shows: its own options, the settings behind Settings, the controls
+// behind those, the wait for a key to bind to an action, or the are-you-sure before quitting
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+enum PausePage {
+    #[default]
+    Main,
+    Settings,
+    Controls,
+    Rebind(Action),
+    ConfirmQuit(QuitTarget),
+}
+
+// The Controls page's row after one per action; Back follows it
+const RESTORE_DEFAULTS_ROW: usize = Action::ALL.len();
+
+// How long a refused key (or clear) keeps the title red
+const FLASH_SECS: f32 = 0.6;
+
+// Where confirming a quit goes: Quit to Title, or out of the game from the window's close button
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum QuitTarget {
+    Title,
+    Desktop,
+}
+
+// Settings screen rows, top to bottom. Left and right change the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SettingsOption {
+    TextSpeed,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Run,
+    Screenshots,
+    Touch,
+    Preset,
+    SwapConfirm,
+    Controls,
+    Back,
+}
+
+impl SettingsOption {
+    pub const ALL: [Self; 11] = [
+        Self::TextSpeed,
+        Self::MasterVolume,
+        Self::MusicVolume,
+        Self::SfxVolume,
+        Self::Run,
+        Self::Screenshots,
+        Self::Touch,
+        Self::Preset,
+        Self::SwapConfirm,
+        Self::Controls,
+        Self::Back,
+    ];
+
+    pub fn label(self, settings: &Settings) -> String {
+        let percent = |volume: f32| format!("{}%", (volume * 100.0).round());
+        let (name, value) = match self {
+            Self::TextSpeed => ("Text Speed", settings.text_speed.label().to_string()),
+            Self::MasterVolume => ("Master Volume", percent(settings.master_volume)),
+            Self::MusicVolume => ("Music Volume", percent(settings.music_volume)),
+            Self::SfxVolume => ("Sound Volume", percent(settings.sfx_volume)),
+            Self::Run => ("Run", if settings.toggle_run { "Toggle" } else { "Hold" }.to_string()),
+            Self::Screenshots => ("Screenshots", if settings.clean_screenshots { "No UI" } else { "With UI" }.to_string()),
+            Self::Touch => ("Touch Controls", if settings.touch_controls { "On" } else { "Auto" }.to_string()),
+            Self::Preset => {
+                let preset = ControlPreset::matching(&settings.controls);
+                ("Controls Preset", preset.map_or("Custom", ControlPreset::label).to_string())
+            }
+            // Whichever keys confirm and cancel right now
+            Self::SwapConfirm => {
+                let input = settings.input_map();
+                let key = |action| input.key_display_name(action);
+                ("Confirm / Cancel", format!("{} / {}", key(Action::Confirm), key(Action::Cancel)))
+            }
+            Self::Controls => return "Controls".to_string(),
+            Self::Back => return "Back".to_string(),
+        };
+        format!("{}  < {} >", name, value)
+    }
+
+    // Moves this row's value `steps` along. False if it was already at the end (or has no value).
+    pub fn adjust(self, settings: &mut Settings, steps: i32) -> bool {
+        let before = settings.clone();
+        match self {
+            Self::TextSpeed => settings.text_speed = settings.text_speed.step(steps),
+            Self::MasterVolume => settings.master_volume = step_volume(settings.master_volume, steps),
+            Self::MusicVolume => settings.music_volume = step_volume(settings.music_volume, steps),
+            Self::SfxVolume => settings.sfx_volume = step_volume(settings.sfx_volume, steps),
+            Self::Run => settings.toggle_run = !settings.toggle_run,
+            Self::Screenshots => settings.clean_screenshots = !settings.clean_screenshots,
+            Self::Touch => settings.touch_controls = !settings.touch_controls,
+            Self::Preset => {
+                let preset = ControlPreset::matching(&settings.controls);
+                settings.controls = ControlPreset::step(preset, steps).map();
+            }
+            Self::SwapConfirm => settings.swap_confirm_cancel = !settings.swap_confirm_cancel,
+            Self::Controls | Self::Back => {}
+        }
+        *settings != before
+    }
+}
+
+// The title screen or pause menu, whichever is up; there's never more than one
+#[derive(Component)]
+struct ScreenMenuRoot;
+
+#[derive(Component)]
+struct ScreenMenuTitle;
+
+type MenuTitles<'w, 's> = Query<'w, 's, (Entity, &'static mut Text), With<ScreenMenuTitle>>;
+
+// Fades a screen menu title from red back to white
+#[derive(Component)]
+struct TitleFlash {
+    secs: f32,
+}
+
+#[derive(Component)]
+struct ScreenMenuEntry {
+    index: usize,
+    disabled: bool,
+}
+
+// Highlighted option on the screen menu
+#[derive(Resource, Default)]
+struct ScreenMenuSelection(usize);
+
+// The screen menu that's up, for the systems that act on its options: which is highlighted,
+// which are greyed out, and the root to despawn when the page changes
+#[derive(SystemParam)]
+struct ScreenMenu<'w, 's> {
+    selection: Option<Res<'w, ScreenMenuSelection>>,
+    entries: Query<'w, 's, &'static ScreenMenuEntry>,
+    roots: Query<'w, 's, Entity, With<ScreenMenuRoot>>,
+}
+
+// Which title page is up, and the folder of save files it offers
+#[derive(SystemParam)]
+struct TitleScreen<'w> {
+    page: Res<'w, TitlePage>,
+    saves: Res<'w, SaveDir>,
+}
+
+// Spawns a centered list of `options` (label, disabled) under `title`, with an optional `footer`
+// of key hints below. `backdrop` fills the whole game area behind it; `boxed` frames the list
+// like the context menu.
+fn spawn_screen_menu(
+    commands: &mut Commands,
+    backdrop: Color,
+    boxed: bool,
+    title: (&str, f32),
+    options: &[(&str, bool)],
+    selected: Option<usize>,
+    footer: Option<&str>,
+) {
+    // Start on the first option that can be picked, unless told otherwise
+    let first = selected.unwrap_or_else(|| options.iter().position(|&(_, disabled)| !disabled).unwrap_or(0));
+    commands.insert_resource(ScreenMenuSelection(first));
+    // Long lists (the Controls page) get tighter rows so they still fit the screen
+    let (font_size, row_padding) = if options.len() > 12 { (12.0, 1.0) } else { (16.0, 5.0) };
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(backdrop),
+        // Over everything, the fade overlay and context menu included
+        GlobalZIndex(1001),
+        ScreenMenuRoot,
+    ))
+    .with_children(|parent| {
+        let mut list = parent.spawn(Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            padding: UiRect::all(Val::Px(15.0)),
+            border: UiRect::all(Val::Px(if boxed { 4.0 } else { 0.0 })),
+            min_width: Val::Px(200.0),
+            ..default()
+        });
+        if boxed {
+            list.insert((BackgroundColor(Color::srgb(0.1, 0.1, 0.15)), BorderColor(WHITE.into())));
+        }
+        list.with_children(|parent| {
+            let (title, title_size) = title;
+            parent.spawn((
+                Text::new(title),
+                TextFont {
+                    font_size: title_size,
+                    ..default()
+                },
+                TextColor(WHITE.into()),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    margin: UiRect::bottom(Val::Px(if boxed { 10.0 } else { 24.0 })),
+                    ..default()
+                },
+                ScreenMenuTitle,
+            ));
+            for (index, &(label, disabled)) in options.iter().enumerate() {
+                parent.spawn((
+                    Text::new(label),
+                    TextFont {
+                        font_size,
+                        ..default()
+                    },
+                    TextColor(option_color(index == first, disabled)),
+                    Node {
+                        padding: UiRect::all(Val::Px(row_padding)),
+                        ..default()
+                    },
+                    ScreenMenuEntry { index, disabled },
+                ));
+            }
+            if let Some(footer) = footer {
+                parent.spawn((
+                    Text::new(footer),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                    Node {
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                ));
+            }
+        });
+    });
+}
+
+fn spawn_main_menu(mut commands: Commands, input: Res<InputMap>, saves: Res<SaveDir>) {
+    let page = if autosave_newer_than_files(&saves) { TitlePage::Recover } else { TitlePage::Main };
+    spawn_title_page(&mut commands, page, &input, &saves);
+}
+
+// Replaces whatever the title screen shows with `page`. The caller despawns the old one.
+fn spawn_title_page(commands: &mut Commands, page: TitlePage, input: &InputMap, saves: &SaveDir) {
+    commands.insert_resource(page);
+    // Opaque, so the frozen room behind it doesn't show
+    let backdrop = Color::srgb(0.05, 0.05, 0.05);
+    match page {
+        TitlePage::Main => {
+            // Continue is greyed out until there's a save file to continue from
+            let has_save = saves.any_saves();
+            let options: Vec<_> = MainMenuOption::shown(newest_completed_slot(saves).is_some())
+                .iter()
+                .map(|&o| (o.label(), o == MainMenuOption::Continue && !has_save))
+                .collect();
+            spawn_screen_menu(commands, backdrop, false, ("DISSONANCE", 32.0), &options, None, None);
+        }
+        TitlePage::Files => {
+            // One line per slot, empty ones greyed out, then Back
+            let labels: Vec<_> = (0..SAVE_SLOTS)
+                .map(|slot| {
+                    let meta = saves.slot_meta(slot);
+                    (slot_label(slot, meta.as_ref()), meta.is_none())
+                })
+                .collect();
+            let mut options: Vec<_> = labels.iter().map(|(label, empty)| (label.as_str(), *empty)).collect();
+            options.push(("Back", false));
+            let title = format!("Hold {} on a file to delete it", input.key_display_name(Action::Cancel));
+            spawn_screen_menu(commands, backdrop, false, (&title, 16.0), &options, None, None);
+        }
+        TitlePage::Recover => {
+            let label = slot_label(AUTOSAVE_SLOT, saves.slot_meta(AUTOSAVE_SLOT).as_ref());
+            let options = [(label.as_str(), false), ("Not now", false)];
+            spawn_screen_menu(commands, backdrop, false, ("Recover autosave?", 16.0), &options, None, None);
+        }
+    }
+}
+
+// Opens on the main page, unless something (the window's close button) asked for another
+fn spawn_pause_menu(
+    mut commands: Commands,
+    page: Option<Res<PausePage>>,
+    (settings, input): (Res<Settings>, Res<InputMap>),
+    playtime: Res<Playtime>,
+    dirty: Res<DirtySinceSave>,
+) {
+    let page = page.map_or(PausePage::Main, |page| *page);
+    spawn_pause_page(&mut commands, page, &settings, &input, (&playtime, dirty.0), None);
+}
+
+// Replaces whatever the pause menu shows with `page`. The caller despawns the old one.
+// `progress` is the playtime so far and whether any of it is unsaved
+fn spawn_pause_page(
+    commands: &mut Commands,
+    page: PausePage,
+    settings: &Settings,
+    input: &InputMap,
+    (playtime, unsaved): (&Playtime, bool),
+    selected: Option<usize>,
+) {
+    commands.insert_resource(page);
+    // Dims the room, which stays visible underneath
+    let backdrop = Color::BLACK.with_alpha(0.5);
+    let key = |action| input.key_display_name(action);
+    match page {
+        PausePage::Main => {
+            let options: Vec<_> = PauseMenuOption::ALL.iter().map(|o| (o.label(), false)).collect();
+            // The clock is stopped while paused, so the time shown stays right
+            let title = format!("[ Paused ]  {}", format_playtime(playtime.0));
+            let footer = format!("[{}] Select   [{}] Resume", key(Action::Confirm), key(Action::Menu));
+            spawn_screen_menu(commands, backdrop, true, (&title, 20.0), &options, selected, Some(&footer));
+        }
+        PausePage::Settings => {
+            let labels: Vec<_> = SettingsOption::ALL.iter().map(|o| o.label(settings)).collect();
+            let options: Vec<_> = labels.iter().map(|label| (label.as_str(), false)).collect();
+            let change = format!("{}/{}", key(Action::MoveLeft), key(Action::MoveRight));
+            let footer = format!("[{}] Change   [{}] Back", change, key(Action::Cancel));
+            spawn_screen_menu(commands, backdrop, true, ("[ Settings ]", 20.0), &options, selected, Some(&footer));
+        }
+        PausePage::Controls => {
+            // One row per action with its keys, then Restore Defaults and Back
+            let labels: Vec<_> = Action::ALL
+                .iter()
+                .map(|&action| format!("{}: {}", action.label(), input.describe(action)))
+                .collect();
+            let mut options: Vec<_> = labels.iter().map(|label| (label.as_str(), false)).collect();
+            options.extend([("Restore Defaults", false), ("Back", false)]);
+            let title = format!("[ Controls ]\nHold {} on one to clear it", key(Action::Cancel));
+            let footer = format!("[{}] Rebind   [{}] Back", key(Action::Confirm), key(Action::Cancel));
+            spawn_screen_menu(commands, backdrop, true, (&title, 16.0), &options, selected, Some(&footer));
+        }
+        PausePage::Rebind(action) => {
+            let title = format!("Press a key for {}...\nEsc to stop", action.label());
+            spawn_screen_menu(commands, backdrop, true, (&title, 16.0), &[], None, None);
+        }
+        PausePage::ConfirmQuit(target) => {
+            let question = match target {
+                QuitTarget::Title => "Quit to the title?",
+                QuitTarget::Desktop => "Quit the game?",
+            };
+            let title = if unsaved { format!("{}\nUnsaved progress will be lost.", question) } else { question.to_string() };
+            // Starts on Cancel, so a stray confirm press doesn't throw anything away
+            let options = [("Quit", false), ("Cancel", false)];
+            spawn_screen_menu(commands, backdrop, true, (&title, 16.0), &options, selected.or(Some(1)), None);
+        }
+    }
+}
+
+fn switch_pause_page(
+    commands: &mut Commands,
+    roots: &Query<Entity, With<ScreenMenuRoot>>,
+    page: PausePage,
+    settings: &Settings,
+    input: &InputMap,
+    progress: (&Playtime, bool),
+    selected: Option<usize>,
+) {
+    for root in roots.iter() {
+        commands.entity(root).despawn();
+    }
+    spawn_pause_page(commands, page, settings, input, progress, selected);
+}
+
+fn despawn_screen_menu(mut commands: Commands, roots: Query<Entity, With<ScreenMenuRoot>>) {
+    for root in roots.iter() {
+        commands.entity(root).despawn();
+    }
+    commands.remove_resource::<ScreenMenuSelection>();
+    commands.remove_resource::<TitlePage>();
+    commands.remove_resource::<PausePage>();
+}
+
+// Stops every Time-driven timer (fuel burn, door auto-close, respawns) while the pause menu is up.
+// Back in Playing, an open dialog or menu keeps it stopped (ui.rs).
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+// Up/down move the highlight, wrapping like the context menu
+fn navigate_screen_menu(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    selection: Option<ResMut<ScreenMenuSelection>>,
+    mut entries: Query<(&ScreenMenuEntry, &mut TextColor)>,
+) {
+    let Some(mut selection) = selection else { return };
+    let count = entries.iter().count();
+    if count == 0 {
+        return;
+    }
+    if input.just_pressed(Action::MoveUp, &keyboard) {
+        selection.0 = (selection.0 + count - 1) % count;
+    } else if input.just_pressed(Action::MoveDown, &keyboard) {
+        selection.0 = (selection.0 + 1) % count;
+    } else {
+        return;
+    }
+    for (entry, mut color) in entries.iter_mut() {
+        color.0 = option_color(entry.index == selection.0, entry.disabled);
+    }
+}
+
+fn select_main_menu_option(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    menu: ScreenMenu,
+    title: TitleScreen,
+    mut next_state: ResMut<NextState<GameState>>,
+    (mut loads, mut new_games, mut exit): (EventWriter<LoadGameEvent>, EventWriter<NewGameEvent>, EventWriter<AppExit>),
+) {
+    let ScreenMenu { selection, entries, roots } = menu;
+    let TitleScreen { page, saves } = title;
+    let Some(selection) = selection else { return };
+    // Turning down the autosave is the same as "Not now"
+    if *page == TitlePage::Recover && input.just_pressed(Action::Cancel, &keyboard) {
+        switch_title_page(&mut commands, &roots, &input, &saves, TitlePage::Main);
+        return;
+    }
+    if !input.just_pressed(Action::Confirm, &keyboard) {
+        return;
+    }
+    if entries.iter().any(|e| e.index == selection.0 && e.disabled) {
+        return;
+    }
+    if *page == TitlePage::Recover {
+        if selection.0 == 0 {
+            loads.write(LoadGameEvent { slot: AUTOSAVE_SLOT });
+        } else {
+            switch_title_page(&mut commands, &roots, &input, &saves, TitlePage::Main);
+        }
+        return;
+    }
+    if *page == TitlePage::Files {
+        if selection.0 < SAVE_SLOTS {
+            // load_game moves on to Playing itself, once the save is applied
+            loads.write(LoadGameEvent { slot: selection.0 });
+        } else {
+            switch_title_page(&mut commands, &roots, &input, &saves, TitlePage::Main);
+        }
+        return;
+    }
+    let completed = newest_completed_slot(&saves);
+    match MainMenuOption::shown(completed.is_some())[selection.0] {
+        MainMenuOption::Continue => switch_title_page(&mut commands, &roots, &input, &saves, TitlePage::Files),
+        MainMenuOption::Start => {
+            new_games.write(NewGameEvent { carry_from: None });
+            next_state.set(GameState::Playing);
+        }
+        // From the most recent finished file
+        MainMenuOption::NewGamePlus => {
+            new_games.write(NewGameEvent { carry_from: completed });
+            next_state.set(GameState::Playing);
+        }
+        MainMenuOption::Quit => {
+            exit.write(AppExit::Success);
+        }
+    }
+}
+
+fn switch_title_page(
+    commands: &mut Commands,
+    roots: &Query<Entity, With<ScreenMenuRoot>>,
+    input: &InputMap,
+    saves: &SaveDir,
+    page: TitlePage,
+) {
+    for root in roots.iter() {
+        commands.entity(root).despawn();
+    }
+    spawn_title_page(commands, page, input, saves);
+}
+
+#[derive(Default)]
+struct CancelHold {
+    held: bool,
+    secs: f32,
+    fired: bool,
+}
+
+// On the file picker, tapping Cancel goes back to the title options; holding it deletes the
+// highlighted file. The release after a delete is ignored.
+fn cancel_file_picker(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    time: Res<Time<Real>>,
+    title: TitleScreen,
+    menu: ScreenMenu,
+    mut hold: Local<CancelHold>,
+) {
+    let ScreenMenu { selection, entries, roots } = menu;
+    let TitleScreen { page, saves } = title;
+    if *page != TitlePage::Files {
+        *hold = CancelHold::default();
+        return;
+    }
+    if input.pressed(Action::Cancel, &keyboard) {
+        if !hold.held {
+            *hold = CancelHold { held: true, ..default() };
+            return;
+        }
+        hold.secs += time.delta_secs();
+        if hold.fired || hold.secs < DELETE_HOLD_SECS {
+            return;
+        }
+        hold.fired = true;
+        let Some(selection) = selection else { return };
+        let is_file = selection.0 < SAVE_SLOTS && entries.iter().any(|e| e.index == selection.0 && !e.disabled);
+        if is_file {
+            saves.delete_slot(selection.0);
+            // Nothing left to continue from goes straight back to the title options
+            let page = if saves.any_saves() { TitlePage::Files } else { TitlePage::Main };
+            switch_title_page(&mut commands, &roots, &input, &saves, page);
+        }
+    } else if hold.held {
+        if !hold.fired {
+            switch_title_page(&mut commands, &roots, &input, &saves, TitlePage::Main);
+        }
+        *hold = CancelHold::default();
+    }
+}
+
+fn select_pause_menu_option(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    (mut settings, mut input): (ResMut<Settings>, ResMut<InputMap>),
+    menu: ScreenMenu,
+    page: Res<PausePage>,
+    (playtime, dirty): (Res<Playtime>, Res<DirtySinceSave>),
+    (mut next_state, mut exit): (ResMut<NextState<GameState>>, EventWriter<AppExit>),
+) {
+    let ScreenMenu { selection, roots, .. } = menu;
+    let Some(selection) = selection else { return };
+    let confirm = input.just_pressed(Action::Confirm, &keyboard);
+    let progress = (playtime.as_ref(), dirty.0);
+    if let PausePage::ConfirmQuit(target) = *page {
+        let quit = confirm && selection.0 == 0;
+        if quit && target == QuitTarget::Title {
+            next_state.set(GameState::MainMenu);
+        } else if quit {
+            info!("Quitting");
+            exit.write(AppExit::Success);
+        } else if confirm || input.just_pressed(Action::Cancel, &keyboard) {
+            let back_on = PauseMenuOption::ALL.iter().position(|&o| o == PauseMenuOption::QuitToTitle);
+            let back_on = if target == QuitTarget::Title { back_on } else { None };
+            switch_pause_page(&mut commands, &roots, PausePage::Main, &settings, &input, progress, back_on);
+        }
+        return;
+    }
+    if *page == PausePage::Settings {
+        let option = SettingsOption::ALL[selection.0];
+        if input.just_pressed(Action::Cancel, &keyboard) || (confirm && option == SettingsOption::Back) {
+            let back_on = PauseMenuOption::ALL.iter().position(|&o| o == PauseMenuOption::Settings);
+            switch_pause_page(&mut commands, &roots, PausePage::Main, &settings, &input, progress, back_on);
+            return;
+        }
+        if confirm && option == SettingsOption::Controls {
+            switch_pause_page(&mut commands, &roots, PausePage::Controls, &settings, &input, progress, None);
+            return;
+        }
+        let steps = input.just_pressed(Action::MoveRight, &keyboard) as i32
+            - input.just_pressed(Action::MoveLeft, &keyboard) as i32;
+        // Confirm flips the on/off rows too
+        let flips = matches!(
+            option,
+            SettingsOption::Run | SettingsOption::Screenshots | SettingsOption::Touch | SettingsOption::SwapConfirm
+        );
+        let steps = if steps == 0 && confirm && flips { 1 } else { steps };
+        if steps != 0 && option.adjust(&mut settings, steps) {
+            settings.save();
+            // A new layout takes effect straight away
+            if matches!(option, SettingsOption::Preset | SettingsOption::SwapConfirm) {
+                *input = settings.input_map();
+            }
+            let page = PausePage::Settings;
+            switch_pause_page(&mut commands, &roots, page, &settings, &input, progress, Some(selection.0));
+        }
+        return;
+    }
+    // Cancel on the Controls page is cancel_controls_page's, and the key being waited for is
+    // capture_binding's
+    if *page == PausePage::Controls && confirm {
+        let page = match selection.0 {
+            row if row < RESTORE_DEFAULTS_ROW => PausePage::Rebind(Action::ALL[row]),
+            RESTORE_DEFAULTS_ROW => {
+                settings.controls = InputMap::default();
+                *input = settings.input_map();
+                PausePage::Controls
+            }
+            _ => {
+                let back_on = SettingsOption::ALL.iter().position(|&o| o == SettingsOption::Controls);
+                switch_pause_page(&mut commands, &roots, PausePage::Settings, &settings, &input, progress, back_on);
+                return;
+            }
+        };
+        switch_pause_page(&mut commands, &roots, page, &settings, &input, progress, Some(selection.0));
+        return;
+    }
+    if *page != PausePage::Main || !confirm {
+        return;
+    }
+    match PauseMenuOption::ALL[selection.0] {
+        PauseMenuOption::Resume => next_state.set(GameState::Playing),
+        PauseMenuOption::Settings => {
+            switch_pause_page(&mut commands, &roots, PausePage::Settings, &settings, &input, progress, None);
+        }
+        PauseMenuOption::QuitToTitle => {
+            let page = PausePage::ConfirmQuit(QuitTarget::Title);
+            switch_pause_page(&mut commands, &roots, page, &settings, &input, progress, None);
+        }
+    }
+}
+
+// On the Controls page, tapping Cancel goes back to the settings; holding it clears the
+// highlighted action's keys. Only a fresh press starts the hold, so the Escape that ended a
+// rebind doesn't also leave the page when it's let go.
+fn cancel_controls_page(
+    mut commands: Commands,
+    (keyboard, mut input): (Res<ButtonInput<KeyCode>>, ResMut<InputMap>),
+    time: Res<Time<Real>>,
+    (page, selection): (Res<PausePage>, Option<Res<ScreenMenuSelection>>),
+    (roots, mut titles): (Query<Entity, With<ScreenMenuRoot>>, MenuTitles),
+    (settings, playtime, dirty): (Res<Settings>, Res<Playtime>, Res<DirtySinceSave>),
+    mut hold: Local<CancelHold>,
+) {
+    if *page != PausePage::Controls {
+        *hold = CancelHold::default();
+        return;
+    }
+    let progress = (playtime.as_ref(), dirty.0);
+    if input.pressed(Action::Cancel, &keyboard) {
+        if !hold.held {
+            if input.just_pressed(Action::Cancel, &keyboard) {
+                *hold = CancelHold { held: true, ..default() };
+            }
+            return;
+        }
+        hold.secs += time.delta_secs();
+        if hold.fired || hold.secs < DELETE_HOLD_SECS {
+            return;
+        }
+        hold.fired = true;
+        let Some(selection) = selection else { return };
+        let Some(&action) = Action::ALL.get(selection.0) else { return };
+        if action.drives_menus() {
+            let text = format!("{} needs a key\nto work the menus", action.label());
+            flash_title(&mut commands, &mut titles, text);
+        } else {
+            input.clear(action);
+            let page = PausePage::Controls;
+            switch_pause_page(&mut commands, &roots, page, &settings, &input, progress, Some(selection.0));
+        }
+    } else if hold.held {
+        if !hold.fired {
+            let back_on = SettingsOption::ALL.iter().position(|&o| o == SettingsOption::Controls);
+            switch_pause_page(&mut commands, &roots, PausePage::Settings, &settings, &input, progress, back_on);
+        }
+        *hold = CancelHold::default();
+    }
+}
+
+// While a Rebind page waits, the first key pressed goes to its action, unless another action
+// already has it; Escape gives up. Whichever it is, nothing else sees that press.
+fn capture_binding(
+    mut commands: Commands,
+    (mut keyboard, mut input): (ResMut<ButtonInput<KeyCode>>, ResMut<InputMap>),
+    page: Option<Res<PausePage>>,
+    (roots, mut titles): (Query<Entity, With<ScreenMenuRoot>>, MenuTitles),
+    (settings, playtime, dirty): (Res<Settings>, Res<Playtime>, Res<DirtySinceSave>),
+) {
+    let Some(&PausePage::Rebind(action)) = page.as_deref() else { return };
+    let Some(&key) = keyboard.get_just_pressed().next() else { return };
+    keyboard.clear_just_pressed(key);
+    if key != KeyCode::Escape {
+        if let Some(other) = input.conflict(action, key) {
+            let (key, other, action) = (key_label(key), other.label(), action.label());
+            let text = format!("{} is already {}\nPress another key for {}", key, other, action);
+            flash_title(&mut commands, &mut titles, text);
+            return;
+        }
+        input.bind(action, key);
+    }
+    let back_on = Action::ALL.iter().position(|&a| a == action);
+    let progress = (playtime.as_ref(), dirty.0);
+    switch_pause_page(&mut commands, &roots, PausePage::Controls, &settings, &input, progress, back_on);
+}
+
+// Turns the screen menu's title red and says `text` instead
+fn flash_title(commands: &mut Commands, titles: &mut MenuTitles, text: String) {
+    for (entity, mut title) in titles.iter_mut() {
+        title.0 = text.clone();
+        commands.entity(entity).insert((TextColor(RED.into()), TitleFlash { secs: FLASH_SECS }));
+    }
+}
+
+// Real time, since the pause menu stops the virtual clock
+fn fade_title_flash(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut flashes: Query<(Entity, &mut TitleFlash, &mut TextColor)>,
+) {
+    for (entity, mut flash, mut color) in flashes.iter_mut() {
+        flash.secs -= time.delta_secs();
+        if flash.secs <= 0.0 {
+            color.0 = WHITE.into();
+            commands.entity(entity).remove::<TitleFlash>();
+        } else {
+            color.0 = WHITE.mix(&RED, flash.secs / FLASH_SECS).into();
+        }
+    }
+}
+
+// Rebinds and restored defaults go straight into settings.ron
+fn save_controls(input: Res<InputMap>, mut settings: ResMut<Settings>) {
+    settings.remember_controls(&input);
+    settings.save();
+}
+
+// The window's close button asks first during a game, through the pause menu's quit page. On the
+// title screen there's nothing to lose, so it just closes.
+fn confirm_window_close(
+    mut commands: Commands,
+    mut close_requests: EventReader<WindowCloseRequested>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    roots: Query<Entity, With<ScreenMenuRoot>>,
+    (settings, input, playtime, dirty): (Res<Settings>, Res<InputMap>, Res<Playtime>, Res<DirtySinceSave>),
+    mut exit: EventWriter<AppExit>,
+) {
+    if close_requests.read().count() == 0 {
+        return;
+    }
+    let page = PausePage::ConfirmQuit(QuitTarget::Desktop);
+    match state.get() {
+        GameState::MainMenu => {
+            exit.write(AppExit::Success);
+        }
+        GameState::Playing => {
+            // The pause menu opens on this page
+            commands.insert_resource(page);
+            next_state.set(GameState::Paused);
+        }
+        GameState::Paused => {
+            switch_pause_page(&mut commands, &roots, page, &settings, &input, (&playtime, dirty.0), None);
+        }
+    }
+}
+
+// The Menu key (Escape) pauses, and unpauses again. A context menu or dialog keeps Escape for itself.
+fn toggle_pause(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
+    ui_state: Res<UiState>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(Action::Menu, &keyboard) {
+        return;
+    }
+    match state.get() {
+        GameState::Playing if !ui_state.menu_open && !ui_state.dialog_open => {
+            info!("Paused");
+            next_state.set(GameState::Paused);
+        }
+        GameState::Paused => {
+            info!("Resumed");
+            next_state.set(GameState::Playing);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::state::app::StatesPlugin;
+
+    fn state_app() -> App {
+        state_app_with(SaveDir::scratch())
+    }
+
+    fn state_app_with(saves: SaveDir) -> App {
+        let mut app = App::new();
+        app.add_plugins((StatesPlugin, StatePlugin))
+            .insert_resource(saves)
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<InputMap>()
+            .init_resource::<UiState>()
+            .init_resource::<Time>()
+            .init_resource::<Time<Virtual>>()
+            .init_resource::<Time<Real>>()
+            .init_resource::<Settings>()
+            .init_resource::<Playtime>()
+            .init_resource::<DirtySinceSave>()
+            .add_event::<WindowCloseRequested>()
+            .add_event::<LoadGameEvent>()
+            .add_event::<NewGameEvent>();
+        app.update();
+        app
+    }
+
+    // Highlights `option` on the title screen directly. The app's save folder starts empty, so
+    // there's no New Game+ and Continue is greyed out.
+    fn highlight(app: &mut App, option: MainMenuOption) {
+        let index = MainMenuOption::shown(false).iter().position(|&o| o == option).unwrap();
+        app.world_mut().resource_mut::<ScreenMenuSelection>().0 = index;
+    }
+
+    // Presses `key` for one frame, then lets the state change land
+    fn tap(app: &mut App, key: KeyCode) {
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(key);
+        app.update();
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().release(key);
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().clear();
+        app.update();
+    }
+
+    // The screen menu's options in order, each with whether it's greyed out
+    fn options(app: &mut App) -> Vec<(String, bool)> {
+        let world = app.world_mut();
+        let mut entries: Vec<_> = world.query::<(&Text, &ScreenMenuEntry)>().iter(world).collect();
+        entries.sort_by_key(|(_, entry)| entry.index);
+        entries.into_iter().map(|(text, entry)| (text.0.clone(), entry.disabled)).collect()
+    }
+
+    fn menus(app: &mut App) -> usize {
+        app.world_mut().query_filtered::<(), With<ScreenMenuRoot>>().iter(app.world()).count()
+    }
+
+    fn state(app: &App) -> GameState {
+        *app.world().resource::<State<GameState>>().get()
+    }
+
+    #[test]
+    fn start_pause_and_resume() {
+        let mut app = state_app();
+        assert_eq!(state(&app), GameState::MainMenu);
+        assert_eq!(*app.world().resource::<TitlePage>(), TitlePage::Main);
+        let nothing_saved = [("Continue".to_string(), true), ("Start".to_string(), false), ("Quit".to_string(), false)];
+        assert_eq!(options(&mut app), nothing_saved);
+        // Escape does nothing on the title screen
+        tap(&mut app, KeyCode::Escape);
+        assert_eq!(state(&app), GameState::MainMenu);
+
+        highlight(&mut app, MainMenuOption::Start);
+        tap(&mut app, KeyCode::KeyZ);
+        assert_eq!(state(&app), GameState::Playing);
+        assert_eq!(menus(&mut app), 0);
+
+        tap(&mut app, KeyCode::Escape);
+        assert_eq!(state(&app), GameState::Paused);
+        assert_eq!(menus(&mut app), 1);
+        assert!(app.world().resource::<Time<Virtual>>().is_paused());
+        tap(&mut app, KeyCode::Escape);
+        assert_eq!(state(&app), GameState::Playing);
+        assert_eq!(menus(&mut app), 0);
+        assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+
+        // With a context menu open, Escape is the menu's
+        app.world_mut().resource_mut::<UiState>().menu_open = true;
+        tap(&mut app, KeyCode::Escape);
+        assert_eq!(state(&app), GameState::Playing);
+    }
+
+    #[test]
+    fn new_game_plus_needs_a_finished_file() {
+        use MainMenuOption::*;
+        assert_eq!(MainMenuOption::shown(false), [Continue, Start, Quit]);
+        assert_eq!(MainMenuOption::shown(true), [Continue, Start, NewGamePlus, Quit]);
+    }
+
+    #[test]
+    fn a_newer_autosave_is_offered_back_first() {
+        let saves = SaveDir::scratch();
+        std::fs::create_dir_all(&saves.0).unwrap();
+        let header = |saved_at| format!("(meta: (room: \"start\", playtime: 0.0, saved_at: {}, completed: false))", saved_at);
+        std::fs::write(saves.slot_path(0), header(10)).unwrap();
+        std::fs::write(saves.slot_path(AUTOSAVE_SLOT), header(20)).unwrap();
+        let mut app = state_app_with(saves.clone());
+        assert_eq!(*app.world().resource::<TitlePage>(), TitlePage::Recover);
+        let shown = options(&mut app);
+        assert!(shown[0].0.starts_with("Autosave") && !shown[0].1);
+        assert_eq!(shown[1], ("Not now".to_string(), false));
+
+        // Turned down, there's still File 1 to continue from
+        tap(&mut app, KeyCode::KeyX);
+        assert_eq!(*app.world().resource::<TitlePage>(), TitlePage::Main);
+        assert_eq!(options(&mut app), [("Continue".to_string(), false), ("Start".to_string(), false), ("Quit".to_string(), false)]);
+        std::fs::remove_dir_all(&saves.0).unwrap();
+    }
+
+    #[test]
+    fn quit_from_the_title_screen() {
+        let mut app = state_app();
+        highlight(&mut app, MainMenuOption::Quit);
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyZ);
+        app.update();
+        assert_eq!(app.should_exit(), Some(AppExit::Success));
+        assert_eq!(state(&app), GameState::MainMenu);
+    }
+
+    #[test]
+    fn pause_menu_resumes_or_quits_to_title() {
+        let mut app = state_app();
+        highlight(&mut app, MainMenuOption::Start);
+        tap(&mut app, KeyCode::KeyZ);
+        tap(&mut app, KeyCode::Escape);
+        // Resume is highlighted first
+        tap(&mut app, KeyCode::KeyZ);
+        assert_eq!(state(&app), GameState::Playing);
+
+        tap(&mut app, KeyCode::Escape);
+        // Settings opens its own page, and X comes back with Settings still highlighted
+        tap(&mut app, KeyCode::ArrowDown);
+        tap(&mut app, KeyCode::KeyZ);
+        assert_eq!(state(&app), GameState::Paused);
+        assert_eq!(*app.world().resource::<PausePage>(), PausePage::Settings);
+        assert_eq!(menus(&mut app), 1);
+        tap(&mut app, KeyCode::KeyX);
+        assert_eq!(*app.world().resource::<PausePage>(), PausePage::Main);
+        // Quit to Title asks first, starting on Cancel
+        tap(&mut app, KeyCode::ArrowDown);
+        tap(&mut app, KeyCode::KeyZ);
+        assert_eq!(*app.world().resource::<PausePage>(), PausePage::ConfirmQuit(QuitTarget::Title));
+        tap(&mut app, KeyCode::KeyZ);
+        assert_eq!(*app.world().resource::<PausePage>(), PausePage::Main);
+        tap(&mut app, KeyCode::KeyZ);
+        tap(&mut app, KeyCode::ArrowUp);
+        tap(&mut app, KeyCode::KeyZ);
+        assert_eq!(state(&app), GameState::MainMenu);
+        assert_eq!(menus(&mut app), 1);
+        assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+    }
+
+    #[test]
+    fn rebinding_takes_the_next_free_key_and_swallows_it() {
+        use bevy::ecs::system::RunSystemOnce;
+        let mut world = World::new();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<InputMap>();
+        world.init_resource::<Settings>();
+        world.init_resource::<Playtime>();
+        world.init_resource::<DirtySinceSave>();
+        let title = world.spawn((Text::new("Press a key"), TextColor(WHITE.into()), ScreenMenuTitle)).id();
+        let press = |world: &mut World, page: PausePage, key: KeyCode| {
+            world.insert_resource(page);
+            let mut keyboard = world.resource_mut::<ButtonInput<KeyCode>>();
+            keyboard.release_all();
+            keyboard.clear();
+            keyboard.press(key);
+            world.run_system_once(capture_binding).unwrap();
+        };
+
+        // Z already confirms, so Dash can't have it: the title flashes and it keeps waiting
+        press(&mut world, PausePage::Rebind(Action::Dash), KeyCode::KeyZ);
+        assert_eq!(world.resource::<InputMap>().keys(Action::Dash), [KeyCode::KeyC]);
+        assert_eq!(*world.resource::<PausePage>(), PausePage::Rebind(Action::Dash));
+        assert!(world.get::<TitleFlash>(title).is_some());
+        assert!(world.get::<Text>(title).unwrap().0.starts_with("Z is already Interact"));
+        // Still held, but used up: neither Confirm nor anything else gets this press
+        let keyboard = world.resource::<ButtonInput<KeyCode>>();
+        assert!(keyboard.pressed(KeyCode::KeyZ) && !keyboard.just_pressed(KeyCode::KeyZ));
+
+        press(&mut world, PausePage::Rebind(Action::Dash), KeyCode::KeyQ);
+        assert_eq!(world.resource::<InputMap>().keys(Action::Dash), [KeyCode::KeyC, KeyCode::KeyQ]);
+        assert_eq!(*world.resource::<PausePage>(), PausePage::Controls);
+        assert!(!world.resource::<ButtonInput<KeyCode>>().just_pressed(KeyCode::KeyQ));
+
+        // Escape gives up without pausing or binding anything
+        press(&mut world, PausePage::Rebind(Action::Run), KeyCode::Escape);
+        assert_eq!(world.resource::<InputMap>().keys(Action::Run), [KeyCode::ShiftLeft]);
+        assert_eq!(*world.resource::<PausePage>(), PausePage::Controls);
+        assert!(!world.resource::<InputMap>().just_pressed(Action::Menu, world.resource::<ButtonInput<KeyCode>>()));
+    }
+
+    #[test]
+    fn closing_the_window_mid_game_asks_first() {
+        let mut app = state_app();
+        highlight(&mut app, MainMenuOption::Start);
+        tap(&mut app, KeyCode::KeyZ);
+        app.world_mut().resource_mut::<DirtySinceSave>().0 = true;
+
+        let window = app.world_mut().spawn_empty().id();
+        app.world_mut().send_event(WindowCloseRequested { window });
+        app.update();
+        app.update();
+        assert_eq!(state(&app), GameState::Paused);
+        assert_eq!(*app.world().resource::<PausePage>(), PausePage::ConfirmQuit(QuitTarget::Desktop));
+        assert_eq!(app.should_exit(), None);
+
+        tap(&mut app, KeyCode::ArrowUp);
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyZ);
+        app.update();
+        assert_eq!(app.should_exit(), Some(AppExit::Success));
+    }
+}
@@ -0,0 +1,149 @@
+// src/timescale.rs
+use bevy::prelude::*;
+use crate::GameSet;
+
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeScale>()
+            .init_resource::<TimeScaleControl>()
+            .add_event::<SetTimeScaleEvent>()
+            // Ahead of the gameplay sets, so a hit this frame slows the next one. Not gated on
+            // GameState: a slow-mo that was running when the game paused picks up where it was.
+            .add_systems(Update, ease_time_scale.before(GameSet::Detect));
+    }
+}
+
+// How fast game time runs, 1.0 being normal. Applied to Time<Virtual>, so every gameplay
+// system that reads Time slows with it; UI that has to stay readable reads Time<Real>.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+// Ease to `scale`. With a duration it's temporary (a hit-stop, a freeze) and the scale goes back
+// to whatever it was after that many real seconds; without one it's the new normal.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SetTimeScaleEvent {
+    pub scale: f32,
+    pub duration: Option<f32>,
+}
+
+// Scale change per real second while easing; 1.0 to a standstill takes an eighth of a second
+const TIME_SCALE_EASE_PER_SEC: f32 = 8.0;
+
+// Player hit: a short slow-motion beat
+pub const HIT_STOP_SCALE: f32 = 0.3;
+pub const HIT_STOP_SECS: f32 = 0.2;
+
+#[derive(Resource, Debug)]
+struct TimeScaleControl {
+    target: f32,
+    base: f32,                  // Where temporary changes return to
+    restore_after: Option<f32>, // Real seconds left on a temporary change
+}
+
+impl Default for TimeScaleControl {
+    fn default() -> Self {
+        Self { target: 1.0, base: 1.0, restore_after: None }
+    }
+}
+
+impl TimeScaleControl {
+    fn request(&mut self, event: &SetTimeScaleEvent) {
+        let scale = event.scale.max(0.0);
+        self.target = scale;
+        match event.duration {
+            Some(secs) => self.restore_after = Some(secs),
+            None => {
+                self.base = scale;
+                self.restore_after = None;
+            }
+        }
+    }
+
+    // Counts down a temporary change and returns the scale `current` should be after `dt`
+    // real seconds
+    fn step(&mut self, current: f32, dt: f32) -> f32 {
+        if let Some(left) = self.restore_after.as_mut() {
+            *left -= dt;
+            if *left <= 0.0 {
+                self.restore_after = None;
+                self.target = self.base;
+            }
+        }
+        let max_step = TIME_SCALE_EASE_PER_SEC * dt;
+        if (self.target - current).abs() <= max_step {
+            self.target
+        } else {
+            current + max_step.copysign(self.target - current)
+        }
+    }
+}
+
+fn ease_time_scale(
+    real: Res<Time<Real>>,
+    mut events: EventReader<SetTimeScaleEvent>,
+    mut control: ResMut<TimeScaleControl>,
+    mut scale: ResMut<TimeScale>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    for event in events.read() {
+        control.request(event);
+    }
+    // The pause menu and open dialogs stop the clock; a temporary change waits with it
+    if virtual_time.is_paused() {
+        return;
+    }
+    let next = control.step(scale.0, real.delta_secs());
+    if next != scale.0 {
+        scale.0 = next;
+        virtual_time.set_relative_speed(next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporary_changes_ease_in_and_restore() {
+        let mut control = TimeScaleControl::default();
+        control.request(&SetTimeScaleEvent { scale: HIT_STOP_SCALE, duration: Some(0.5) });
+
+        // Eases down rather than snapping
+        let mut scale = control.step(1.0, 0.05);
+        assert!((scale - 0.6).abs() < 1e-5);
+        scale = control.step(scale, 0.05);
+        assert_eq!(scale, HIT_STOP_SCALE);
+        scale = control.step(scale, 0.3);
+        assert_eq!(scale, HIT_STOP_SCALE);
+
+        // Past the duration it heads back to normal
+        scale = control.step(scale, 0.2);
+        assert!(scale > HIT_STOP_SCALE);
+        scale = control.step(scale, 1.0);
+        assert_eq!(scale, 1.0);
+    }
+
+    #[test]
+    fn lasting_changes_become_the_new_normal() {
+        let mut control = TimeScaleControl::default();
+        control.request(&SetTimeScaleEvent { scale: 0.5, duration: None });
+        let mut scale = control.step(1.0, 1.0);
+        assert_eq!(scale, 0.5);
+
+        // A freeze on top of it returns to 0.5, not 1.0
+        control.request(&SetTimeScaleEvent { scale: 0.0, duration: Some(0.5) });
+        scale = control.step(scale, 0.1);
+        assert_eq!(scale, 0.0);
+        scale = control.step(scale, 0.01);
+        scale = control.step(scale, 1.0);
+        assert_eq!(scale, 0.5);
+    }
+}
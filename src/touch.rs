@@ -0,0 +1,222 @@
+// src/touch.rs
+use bevy::prelude::*;
+use bevy::input::touch::Touches;
+use bevy::input::InputSystem;
+use bevy::window::PrimaryWindow;
+use std::collections::HashSet;
+use crate::camera::CameraFollow;
+use crate::input::{hold_consumed_actions, Action, InputMap};
+use crate::settings::Settings;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchControls>()
+            // Straight after the keyboard is read, so the buttons' presses land the same frame
+            .add_systems(PreUpdate, press_touch_buttons.after(InputSystem).before(hold_consumed_actions))
+            .add_systems(Update, (show_touch_controls, highlight_touch_buttons));
+    }
+}
+
+// The on-screen buttons' side of things. They come up once a touch is seen (or the setting asks
+// for them) and feed their actions in as presses of each action's first key, so everything that
+// reads the InputMap works the same either way.
+#[derive(Resource, Default)]
+pub struct TouchControls {
+    detected: bool,
+    held: HashSet<Action>,
+    // Touches (by id) that landed on a button, so they aren't also taps on the world
+    pub on_buttons: HashSet<u64>,
+}
+
+#[derive(Component)]
+struct TouchControlsRoot;
+
+// An on-screen button and the actions it holds down while touched
+#[derive(Component)]
+struct TouchButton(&'static [Action]);
+
+// The confirm button also opens whatever's in reach, like Z does
+const CONFIRM_ACTIONS: &[Action] = &[Action::Interact, Action::Confirm];
+
+const DPAD_BUTTON: f32 = 36.0;
+const CONFIRM_BUTTON: f32 = 52.0;
+const EDGE_MARGIN: f32 = 16.0;
+const BUTTON_ALPHA: f32 = 0.25;
+const HELD_ALPHA: f32 = 0.5;
+
+fn show_touch_controls(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    touch: Res<TouchControls>,
+    roots: Query<Entity, With<TouchControlsRoot>>,
+) {
+    let wanted = settings.touch_controls || touch.detected;
+    match (wanted, roots.iter().next()) {
+        (true, None) => spawn_touch_controls(&mut commands),
+        (false, Some(root)) => commands.entity(root).despawn(),
+        _ => {}
+    }
+}
+
+fn spawn_touch_controls(commands: &mut Commands) {
+    let button = |actions: &'static [Action], label: &str, size: f32, position: Node| {
+        (
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(size),
+                height: Val::Px(size),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..position
+            },
+            BackgroundColor(Color::WHITE.with_alpha(BUTTON_ALPHA)),
+            BorderRadius::all(Val::Px(size / 4.0)),
+            TouchButton(actions),
+            children![(
+                Text::new(label),
+                TextFont { font_size: size / 2.0, ..default() },
+                TextColor(Color::WHITE.with_alpha(0.8)),
+            )],
+        )
+    };
+    let at = |left: f32, top: f32| Node { left: Val::Px(left), top: Val::Px(top), ..default() };
+    let dpad = DPAD_BUTTON;
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        // Over the pause menu too, whose lists the d-pad and confirm button also work
+        GlobalZIndex(1002),
+        TouchControlsRoot,
+        children![
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(EDGE_MARGIN),
+                    bottom: Val::Px(EDGE_MARGIN),
+                    width: Val::Px(dpad * 3.0),
+                    height: Val::Px(dpad * 3.0),
+                    ..default()
+                },
+                children![
+                    button(&[Action::MoveUp], "^", dpad, at(dpad, 0.0)),
+                    button(&[Action::MoveLeft], "<", dpad, at(0.0, dpad)),
+                    button(&[Action::MoveRight], ">", dpad, at(dpad * 2.0, dpad)),
+                    button(&[Action::MoveDown], "v", dpad, at(dpad, dpad * 2.0)),
+                ],
+            ),
+            button(CONFIRM_ACTIONS, "A", CONFIRM_BUTTON, Node {
+                right: Val::Px(EDGE_MARGIN * 2.0),
+                bottom: Val::Px(EDGE_MARGIN * 2.0),
+                ..default()
+            }),
+        ],
+    ));
+}
+
+// Every touch counts, so moving with one thumb while confirming with the other works. With the
+// buttons hidden nothing is held and no touch is kept from the world.
+fn press_touch_buttons(
+    touches: Res<Touches>,
+    mut touch: ResMut<TouchControls>,
+    (mut keyboard, input): (ResMut<ButtonInput<KeyCode>>, Res<InputMap>),
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<&Camera, With<CameraFollow>>,
+    buttons: Query<(&TouchButton, &ComputedNode, &GlobalTransform)>,
+) {
+    if touches.iter_just_pressed().next().is_some() {
+        touch.detected = true;
+    }
+    // UI nodes are laid out in physical pixels from the corner of the camera's viewport
+    let scale = windows.single().map_or(1.0, |window| window.scale_factor());
+    let corner = cameras.single().ok().and_then(|camera| camera.viewport.as_ref()).map_or(Vec2::ZERO, |v| {
+        v.physical_position.as_vec2()
+    });
+    let points: Vec<_> = touches.iter().map(|t| (t.id(), t.position() * scale - corner)).collect();
+    let rects: Vec<_> = buttons
+        .iter()
+        .map(|(button, node, tf)| (Rect::from_center_size(tf.translation().truncate(), node.size()), button.0))
+        .collect();
+    let (held, on_buttons) = touched_actions(&points, &rects);
+    touch.on_buttons = on_buttons;
+    touch.hold(held, &input, &mut keyboard);
+}
+
+// The actions held down by touches at `points` (id, position) over `buttons`, and which of
+// the touches that was
+fn touched_actions(points: &[(u64, Vec2)], buttons: &[(Rect, &[Action])]) -> (HashSet<Action>, HashSet<u64>) {
+    let mut held = HashSet::new();
+    let mut on_buttons = HashSet::new();
+    for &(id, point) in points {
+        for (_, actions) in buttons.iter().filter(|(rect, _)| rect.contains(point)) {
+            held.extend(actions.iter().copied());
+            on_buttons.insert(id);
+        }
+    }
+    (held, on_buttons)
+}
+
+impl TouchControls {
+    // Presses the first key of every action in `now` that wasn't held, and lets go of those
+    // that no longer are
+    fn hold(&mut self, now: HashSet<Action>, input: &InputMap, keyboard: &mut ButtonInput<KeyCode>) {
+        let key = |action: &Action| input.keys(*action).first().copied();
+        for released in self.held.difference(&now).filter_map(key) {
+            keyboard.release(released);
+        }
+        for pressed in now.difference(&self.held).filter_map(key) {
+            keyboard.press(pressed);
+        }
+        self.held = now;
+    }
+}
+
+fn highlight_touch_buttons(touch: Res<TouchControls>, mut buttons: Query<(&TouchButton, &mut BackgroundColor)>) {
+    if !touch.is_changed() {
+        return;
+    }
+    for (button, mut color) in buttons.iter_mut() {
+        let alpha = if button.0.iter().any(|action| touch.held.contains(action)) { HELD_ALPHA } else { BUTTON_ALPHA };
+        color.0 = Color::WHITE.with_alpha(alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_thumbs_hold_two_buttons_at_once() {
+        let right = Rect::from_center_size(Vec2::new(100.0, 400.0), Vec2::splat(36.0));
+        let confirm = Rect::from_center_size(Vec2::new(560.0, 400.0), Vec2::splat(52.0));
+        let buttons = [(right, &[Action::MoveRight][..]), (confirm, CONFIRM_ACTIONS)];
+        let input = InputMap::default();
+        let mut keyboard = ButtonInput::<KeyCode>::default();
+        let mut touch = TouchControls::default();
+
+        // One thumb on Right, the other on confirm, a third finger on the world
+        let points = [(1, Vec2::new(95.0, 405.0)), (2, Vec2::new(560.0, 390.0)), (3, Vec2::new(300.0, 200.0))];
+        let (held, on_buttons) = touched_actions(&points, &buttons);
+        assert_eq!(on_buttons, HashSet::from([1, 2]));
+        touch.hold(held, &input, &mut keyboard);
+        assert!(input.just_pressed(Action::MoveRight, &keyboard));
+        assert!(input.just_pressed(Action::Confirm, &keyboard));
+        assert!(input.just_pressed(Action::Interact, &keyboard));
+
+        // The confirm thumb lifts; Right stays held without pressing again
+        keyboard.clear();
+        let (held, _) = touched_actions(&points[..1], &buttons);
+        touch.hold(held, &input, &mut keyboard);
+        assert!(input.pressed(Action::MoveRight, &keyboard) && !input.just_pressed(Action::MoveRight, &keyboard));
+        assert!(!input.pressed(Action::Confirm, &keyboard));
+
+        // Hidden buttons hold nothing
+        touch.hold(HashSet::new(), &input, &mut keyboard);
+        assert!(!input.pressed(Action::MoveRight, &keyboard));
+    }
+}
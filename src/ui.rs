@@ -1,9 +1,17 @@
 // src/ui.rs
 use bevy::prelude::*;
-use bevy::color::palettes::basic::{WHITE, YELLOW};
-use crate::interaction::{InteractionAction, InteractionEvent};
-use crate::GameSet;
+use bevy::color::palettes::basic::{GRAY, WHITE, YELLOW};
+use crate::audio::{PlaySfxEvent, SfxId};
+use crate::interaction::{Interactable, InteractionAction, InteractionEvent};
+use crate::{GameSet, PendingDespawn};
+use crate::input::{Action, ConsumedActions, InputGestures, InputMap};
 use crate::inventory::Inventory;
+use crate::objects::RoomFade;
+use crate::player::{Dash, MovementLocks, PlayerStats, Sneaking};
+use crate::journal::Journal;
+use crate::quests::{LogTab, QuestEvent, QuestLog, QuestState, Quests};
+use crate::save::format_playtime;
+use crate::settings::Settings;
 
 #[derive(Component)]
 struct ContinueChevron;
@@ -21,22 +29,35 @@ impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ContextMenuEvent>()
             .add_event::<LogEvent>()
+            .add_event::<ToastEvent>()
             .insert_resource(UiState::default())
             .add_systems(Startup, setup_ui)
             .add_systems(Update, (
                 // Order matters here for consistent feel
                 show_context_menu,
+                close_menu_without_target,
                 handle_menu_navigation,
                 handle_menu_selection,
                 handle_menu_cancel,
             ).chain().in_set(GameSet::Ui))
             .add_systems(Update, (
-                // Dialog open/update happens before input so the same-frame key press doesn't skip
+                // Dialog open/update happens before input so the same-frame key press doesn't skip.
+                // UI timing reads Time<Real>, so text and blinks keep their pace in slow motion.
                 update_log_display,
                 handle_dialog_input,
+                typewrite_dialog.after(update_log_display).after(handle_dialog_input),
                 blink_continue_chevron,
                 update_inventory_ui,
-            ).in_set(GameSet::Process));
+                update_quest_reminder,
+                update_quest_log,
+                update_fade_overlay,
+                update_hp_bar,
+                update_sneak_indicator,
+                update_dash_meter,
+            ).in_set(GameSet::Process))
+            .add_systems(Update, pause_time_while_reading.in_set(GameSet::Cleanup))
+            // Toasts can come from outside gameplay (a save finishing on the title screen)
+            .add_systems(Update, show_toasts);
     }
 }
 
@@ -51,16 +72,42 @@ pub struct UiState {
     pub menu_opened_at: f64,
     // Modal dialog state (Undertale-style): a queue of lines, shown one per press
     pub dialog_open: bool,
-    pub dialog_queue: Vec<String>,
+    pub dialog_queue: Vec<DialogLine>,
     pub dialog_index: usize,
     pub dialog_opened_at: f64,
+    pub dialog_revealed: f32, // Characters of the current line typed out so far
+}
+
+impl UiState {
+    // Forgets the menu's object along with the menu, so nothing acts on it afterwards
+    pub fn close_menu(&mut self) {
+        self.menu_open = false;
+        self.current_entity = None;
+    }
+
+    // Whether the current dialog line has finished typing out
+    pub fn line_revealed(&self) -> bool {
+        self.dialog_queue
+            .get(self.dialog_index)
+            .is_none_or(|line| self.dialog_revealed >= line.text.chars().count() as f32)
+    }
+}
+
+// One line of the dialog box and who says it (their Voice types it out), None for narration
+#[derive(Clone, Debug, PartialEq)]
+pub struct DialogLine {
+    pub text: String,
+    pub speaker: Option<Entity>,
 }
 
 #[derive(Event)]
 pub struct ContextMenuEvent {
     pub entity: Entity,
     pub actions: Vec<InteractionAction>,
+    pub labels: Vec<String>, // Display text per action, same order as `actions`
     pub object_name: String,
+    pub disabled: Vec<bool>, // Greyed out but still selectable, same order; empty means none
+    pub suffixes: Vec<Option<(String, Color)>>, // Colored text after a label, e.g. a price
 }
 
 #[derive(Component)]
@@ -72,6 +119,7 @@ struct ContextMenuBox;
 #[derive(Component)]
 struct MenuOption {
     index: usize,
+    disabled: bool,
 }
 
 #[derive(Component)]
@@ -81,7 +129,30 @@ struct MessageLogRoot;
 struct MessageText;
 
 #[derive(Event)]
-pub struct LogEvent(pub String);
+pub struct LogEvent {
+    pub text: String,
+    pub speaker: Option<Entity>,
+}
+
+impl LogEvent {
+    // Narration
+    pub fn new(text: String) -> Self {
+        Self { text, speaker: None }
+    }
+
+    pub fn said(speaker: Entity, text: String) -> Self {
+        Self { text, speaker: Some(speaker) }
+    }
+}
+
+// A short notice in the corner that fades on its own, for things that shouldn't open a dialog
+#[derive(Event)]
+pub struct ToastEvent(pub String);
+
+#[derive(Component)]
+struct Toast {
+    secs: f32, // Left on screen
+}
 
 #[derive(Component)]
 struct InventoryRoot;
@@ -89,6 +160,54 @@ struct InventoryRoot;
 #[derive(Component)]
 struct InventoryList;
 
+// The current objective in the top-right corner; `flash` is how long it stays highlighted after
+// the quest moves on
+#[derive(Component)]
+struct QuestReminder {
+    flash: f32,
+}
+
+#[derive(Component)]
+struct QuestLogRoot;
+
+#[derive(Component)]
+struct QuestLogList;
+
+// A tab name at the top of the quest log, lit while its tab is showing
+#[derive(Component)]
+struct QuestLogTabLabel(LogTab);
+
+#[derive(Component)]
+struct FadeOverlay;
+
+#[derive(Component)]
+struct HpBarFill;
+
+#[derive(Component)]
+struct HpText;
+
+#[derive(Component)]
+struct SneakIndicator;
+
+#[derive(Component)]
+struct DashMeterFill;
+
+const DASH_METER_WIDTH: f32 = 24.0;
+
+const HP_BAR_WIDTH: f32 = 60.0;
+
+const QUEST_FLASH_SECS: f32 = 2.0;
+
+// Journal entries the log has room for, newest first
+const JOURNAL_SHOWN: usize = 10;
+
+const TOAST_SECS: f32 = 2.0;
+// The last half-second of a toast fades out
+const TOAST_FADE_SECS: f32 = 0.5;
+
+// Holding Interact this long on an object's menu takes its first action
+const HOLD_TO_INTERACT_SECS: f32 = 0.5;
+
 fn setup_ui(mut commands: Commands) {
     // Create the root UI container that will hold our menu
     // This stays spawned but hidden until we need it
@@ -136,7 +255,8 @@ fn setup_ui(mut commands: Commands) {
         },
         BackgroundColor(Color::srgb(0.07, 0.07, 0.1)),
         BorderColor(WHITE.into()),
-        GlobalZIndex(900),
+        // Over the fade overlay, so a line can be read on a black screen ("* Stay determined.")
+        GlobalZIndex(1000),
         Visibility::Hidden,
         MessageLogRoot,
     ))
@@ -223,6 +343,220 @@ fn setup_ui(mut commands: Commands) {
             InventoryList,
         ));
     });
+
+    // Quest log panel (J), styled like the inventory but down the left side. Tab switches between
+    // the quests and the journal.
+    commands.spawn((
+        Node {
+            width: Val::Px(260.0),
+            min_height: Val::Px(140.0),
+            position_type: PositionType::Absolute,
+            top: Val::Px(56.0),
+            left: Val::Px(24.0),
+            padding: UiRect::all(Val::Px(12.0)),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.08, 0.08, 0.12)),
+        BorderColor(WHITE.into()),
+        GlobalZIndex(925),
+        Visibility::Hidden,
+        QuestLogRoot,
+    ))
+    .with_children(|parent| {
+        parent.spawn(Node { column_gap: Val::Px(16.0), ..default() }).with_children(|tabs| {
+            for (tab, name) in [(LogTab::Quests, "Quests"), (LogTab::Journal, "Journal")] {
+                tabs.spawn((
+                    Text::new(name),
+                    TextFont { font_size: 22.0, ..default() },
+                    TextColor(GRAY.into()),
+                    QuestLogTabLabel(tab),
+                ));
+            }
+        });
+        parent.spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+            QuestLogList,
+        ));
+    });
+
+    // Current objective in the top-right corner, hidden while the quest log is up
+    commands.spawn((
+        Text::new(""),
+        TextFont { font_size: 14.0, ..default() },
+        TextColor(WHITE.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..default()
+        },
+        GlobalZIndex(800),
+        Visibility::Hidden,
+        QuestReminder { flash: 0.0 },
+    ));
+
+    // HP readout in the top-left corner: a yellow bar over red, like Undertale's
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            ..default()
+        },
+        GlobalZIndex(800),
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::new("HP"),
+            TextFont { font_size: 16.0, ..default() },
+            TextColor(WHITE.into()),
+        ));
+        parent
+            .spawn((
+                Node {
+                    width: Val::Px(HP_BAR_WIDTH),
+                    height: Val::Px(12.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.8, 0.1, 0.1)),
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(YELLOW.into()),
+                    HpBarFill,
+                ));
+            });
+        parent.spawn((
+            Text::new(""),
+            TextFont { font_size: 16.0, ..default() },
+            TextColor(WHITE.into()),
+            HpText,
+        ));
+        // Dash cooldown: refills left to right, white once ready
+        parent
+            .spawn((
+                Node {
+                    width: Val::Px(DASH_METER_WIDTH),
+                    height: Val::Px(4.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.2, 0.2, 0.25)),
+            ))
+            .with_children(|meter| {
+                meter.spawn((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(WHITE.into()),
+                    DashMeterFill,
+                ));
+            });
+    });
+
+    // "SNEAK" under the HP bar while the sneak key is held
+    commands.spawn((
+        Text::new("SNEAK"),
+        TextFont { font_size: 14.0, ..default() },
+        TextColor(GRAY.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(32.0),
+            left: Val::Px(12.0),
+            ..default()
+        },
+        GlobalZIndex(800),
+        Visibility::Hidden,
+        SneakIndicator,
+    ));
+
+    // Toasts in the bottom-right, just above the message log
+    commands.spawn((
+        Text::new(""),
+        TextFont { font_size: 14.0, ..default() },
+        TextColor(WHITE.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(104.0),
+            right: Val::Px(12.0),
+            ..default()
+        },
+        GlobalZIndex(800),
+        Visibility::Hidden,
+        Toast { secs: 0.0 },
+    ));
+
+    // Fullscreen black overlay for room transitions; alpha driven by RoomFade
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.0)),
+        GlobalZIndex(950),
+        FadeOverlay,
+    ));
+}
+
+fn update_fade_overlay(
+    fade: Res<RoomFade>,
+    mut overlay_query: Query<&mut BackgroundColor, With<FadeOverlay>>,
+) {
+    if !fade.is_changed() {
+        return;
+    }
+    if let Ok(mut background) = overlay_query.single_mut() {
+        background.0 = Color::BLACK.with_alpha(fade.overlay_alpha());
+    }
+}
+
+fn update_hp_bar(
+    stats_query: Query<&PlayerStats, Changed<PlayerStats>>,
+    mut fill_query: Query<&mut Node, With<HpBarFill>>,
+    mut text_query: Query<&mut Text, With<HpText>>,
+) {
+    let Ok(stats) = stats_query.single() else { return };
+    if let Ok(mut fill) = fill_query.single_mut() {
+        fill.width = Val::Percent(100.0 * stats.hp as f32 / stats.max_hp.max(1) as f32);
+    }
+    if let Ok(mut text) = text_query.single_mut() {
+        *text = Text::new(format!("{} / {}", stats.hp, stats.max_hp));
+    }
+}
+
+fn update_dash_meter(
+    dash_query: Query<&Dash, Changed<Dash>>,
+    mut fill_query: Query<(&mut Node, &mut BackgroundColor), With<DashMeterFill>>,
+) {
+    let Ok(dash) = dash_query.single() else { return };
+    let Ok((mut fill, mut color)) = fill_query.single_mut() else { return };
+    let charged = 1.0 - dash.cooldown_fraction();
+    fill.width = Val::Percent(100.0 * charged);
+    color.0 = if charged >= 1.0 { WHITE.into() } else { GRAY.into() };
+}
+
+fn update_sneak_indicator(
+    sneaking: Query<(), With<Sneaking>>,
+    mut indicator_query: Query<&mut Visibility, With<SneakIndicator>>,
+) {
+    let Ok(mut visibility) = indicator_query.single_mut() else { return };
+    visibility.set_if_neq(if sneaking.is_empty() { Visibility::Hidden } else { Visibility::Visible });
 }
 
 fn show_context_menu(
@@ -231,12 +565,16 @@ fn show_context_menu(
     mut menu_root_query: Query<(Entity, &mut Visibility, &Children), With<ContextMenuRoot>>,
     menu_box_query: Query<(Entity, Option<&Children>), With<ContextMenuBox>>,
     mut ui_state: ResMut<UiState>,
-    time: Res<Time>,
+    mut locks: ResMut<MovementLocks>,
+    time: Res<Time<Real>>,
 ) {
     for event in events.read() {
         if let Ok((_root_entity, mut visibility, children)) = menu_root_query.single_mut() {
             // Show the menu
             *visibility = Visibility::Visible;
+            if !ui_state.menu_open {
+                locks.lock();
+            }
             ui_state.menu_open = true;
             ui_state.selected_index = 0;
             ui_state.current_entity = Some(event.entity);
@@ -270,25 +608,32 @@ fn show_context_menu(
                         ));
                         
                         // Add each menu option
-                        for (index, action) in event.actions.iter().enumerate() {
+                        for (index, label) in event.labels.iter().enumerate() {
                             let is_selected = index == 0;
-                            parent.spawn((
-                                Text::new(action.to_string()),
+                            let disabled = event.disabled.get(index).copied().unwrap_or(false);
+                            let mut option = parent.spawn((
+                                Text::new(label.clone()),
                                 TextFont {
                                     font_size: 16.0,
                                     ..default()
                                 },
-                                TextColor(if is_selected { 
-                                    YELLOW.into() 
-                                } else { 
-                                    WHITE.into() 
-                                }),
+                                TextColor(option_color(is_selected, disabled)),
                                 Node {
                                     padding: UiRect::all(Val::Px(5.0)),
                                     ..default()
                                 },
-                                MenuOption { index },
+                                MenuOption { index, disabled },
                             ));
+                            if let Some(Some((suffix, color))) = event.suffixes.get(index) {
+                                option.with_child((
+                                    TextSpan::new(suffix.clone()),
+                                    TextFont {
+                                        font_size: 16.0,
+                                        ..default()
+                                    },
+                                    TextColor(*color),
+                                ));
+                            }
                         }
                     });
                     
@@ -301,8 +646,10 @@ fn show_context_menu(
 
 fn handle_menu_navigation(
     keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
     mut ui_state: ResMut<UiState>,
     mut option_query: Query<(&MenuOption, &mut TextColor)>,
+    mut sfx: EventWriter<PlaySfxEvent>,
 ) {
     if !ui_state.menu_open {
         return;
@@ -313,34 +660,63 @@ fn handle_menu_navigation(
         return;
     }
     
-    if keyboard.just_pressed(KeyCode::ArrowUp) || keyboard.just_pressed(KeyCode::KeyW) {
+    if input.just_pressed(Action::MoveUp, &keyboard) {
         if ui_state.selected_index > 0 {
             ui_state.selected_index -= 1;
         } else {
             ui_state.selected_index = option_count - 1;
         }
-    } else if keyboard.just_pressed(KeyCode::ArrowDown) || keyboard.just_pressed(KeyCode::KeyS) {
+    } else if input.just_pressed(Action::MoveDown, &keyboard) {
         ui_state.selected_index = (ui_state.selected_index + 1) % option_count;
     } else {
         return;
     }
+    sfx.write(PlaySfxEvent::ui(SfxId::MenuMove));
     
     // Update colors
     for (option, mut text_color) in option_query.iter_mut() {
-        text_color.0 = if option.index == ui_state.selected_index {
-            YELLOW.into()
-        } else {
-            WHITE.into()
-        };
+        text_color.0 = option_color(option.index == ui_state.selected_index, option.disabled);
+    }
+}
+
+pub fn option_color(is_selected: bool, disabled: bool) -> Color {
+    if is_selected {
+        YELLOW.into()
+    } else if disabled {
+        GRAY.into()
+    } else {
+        WHITE.into()
     }
 }
 
+// The object whose menu is up can go while it's open (taken, its room unloaded, despawned by a
+// script) or stop being interactable. The menu closes rather than offer actions on nothing.
+fn close_menu_without_target(
+    mut ui_state: ResMut<UiState>,
+    targets: Query<(), (With<Interactable>, Without<PendingDespawn>)>,
+    mut menu_root_query: Query<&mut Visibility, With<ContextMenuRoot>>,
+    mut locks: ResMut<MovementLocks>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    if !ui_state.menu_open || ui_state.current_entity.is_some_and(|entity| targets.contains(entity)) {
+        return;
+    }
+    if let Ok(mut visibility) = menu_root_query.single_mut() {
+        *visibility = Visibility::Hidden;
+    }
+    ui_state.close_menu();
+    locks.unlock();
+    toasts.write(ToastEvent("It's gone".to_string()));
+}
+
 fn handle_menu_selection(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut interaction_events: EventWriter<InteractionEvent>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    (input, gestures): (Res<InputMap>, Res<InputGestures>),
+    (mut interaction_events, mut sfx): (EventWriter<InteractionEvent>, EventWriter<PlaySfxEvent>),
     mut menu_root_query: Query<&mut Visibility, With<ContextMenuRoot>>,
-    mut ui_state: ResMut<UiState>,
-    time: Res<Time>,
+    (mut ui_state, mut consumed): (ResMut<UiState>, ResMut<ConsumedActions>),
+    mut locks: ResMut<MovementLocks>,
+    time: Res<Time<Real>>,
 ) {
     if !ui_state.menu_open {
         return;
@@ -353,14 +729,18 @@ fn handle_menu_selection(
         return;
     }
 
-    let select = keyboard.just_pressed(KeyCode::KeyZ)
-        || keyboard.just_pressed(KeyCode::Space)
-        || keyboard.just_pressed(KeyCode::Enter);
+    // Keeping Interact held from opening the menu picks the highlighted (first) action
+    let select = input.just_pressed(Action::Confirm, &keyboard)
+        || gestures.long_pressed(Action::Interact, HOLD_TO_INTERACT_SECS);
     
     if select {
         if let Some(entity) = ui_state.current_entity {
             if let Some(action) = ui_state.current_actions.get(ui_state.selected_index) {
+                sfx.write(PlaySfxEvent::ui(SfxId::MenuSelect));
                 info!("Executing action {:?} on entity {:?}", action, entity);
+                // The press that picked this is spent: it mustn't also page the dialog the
+                // action opens, or reach the world again next frame
+                consumed.consume(Action::Confirm, &input, &mut keyboard);
                 interaction_events.write(InteractionEvent {
                     entity,
                     action: action.clone(),
@@ -370,7 +750,8 @@ fn handle_menu_selection(
                 if let Ok(mut visibility) = menu_root_query.single_mut() {
                     *visibility = Visibility::Hidden;
                 }
-                ui_state.menu_open = false;
+                ui_state.close_menu();
+                locks.unlock();
             }
         }
     }
@@ -378,22 +759,25 @@ fn handle_menu_selection(
 
 fn handle_menu_cancel(
     keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
     mut menu_root_query: Query<&mut Visibility, With<ContextMenuRoot>>,
     mut ui_state: ResMut<UiState>,
+    mut locks: ResMut<MovementLocks>,
+    mut sfx: EventWriter<PlaySfxEvent>,
 ) {
     if !ui_state.menu_open {
         return;
     }
     
-    let cancel = keyboard.just_pressed(KeyCode::KeyX)
-        || keyboard.just_pressed(KeyCode::Escape)
-        || keyboard.just_pressed(KeyCode::ShiftLeft);
+    let cancel = input.just_pressed(Action::Cancel, &keyboard);
     
     if cancel {
         if let Ok(mut visibility) = menu_root_query.single_mut() {
             *visibility = Visibility::Hidden;
         }
-        ui_state.menu_open = false;
+        ui_state.close_menu();
+        locks.unlock();
+        sfx.write(PlaySfxEvent::ui(SfxId::MenuCancel));
         info!("Menu cancelled");
     }
 }
@@ -401,13 +785,14 @@ fn handle_menu_cancel(
 fn update_log_display(
     mut events: EventReader<LogEvent>,
     mut ui_state: ResMut<UiState>,
-    mut text_query: Query<&mut Text, With<MessageText>>,
+    mut locks: ResMut<MovementLocks>,
     mut root_vis_query: Query<&mut Visibility, With<MessageLogRoot>>,
-    time: Res<Time>,
+    time: Res<Time<Real>>,
+    mut sfx: EventWriter<PlaySfxEvent>,
 ) {
     let mut received_any = false;
     for e in events.read() {
-        ui_state.dialog_queue.push(e.0.clone());
+        ui_state.dialog_queue.push(DialogLine { text: e.text.clone(), speaker: e.speaker });
         received_any = true;
     }
 
@@ -415,32 +800,27 @@ fn update_log_display(
         // If dialog is not open, open it and show the first line
         if !ui_state.dialog_open && !ui_state.dialog_queue.is_empty() {
             ui_state.dialog_open = true;
+            locks.lock();
             ui_state.dialog_index = 0;
             ui_state.dialog_opened_at = time.elapsed().as_secs_f64();
+            // typewrite_dialog fills the text in from here
+            ui_state.dialog_revealed = 0.0;
+            sfx.write(PlaySfxEvent::ui(SfxId::DialogOpen));
             if let Ok(mut vis) = root_vis_query.single_mut() {
                 *vis = Visibility::Visible;
             }
-            if let Ok(mut text) = text_query.single_mut() {
-                // Show cumulative lines up to current index (first line here)
-                let shown = ui_state
-                    .dialog_queue
-                    .iter()
-                    .take(ui_state.dialog_index + 1)
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                *text = Text::new(shown);
-            }
         }
     }
 }
 
 fn handle_dialog_input(
     keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<InputMap>,
     mut ui_state: ResMut<UiState>,
+    mut locks: ResMut<MovementLocks>,
     mut text_query: Query<&mut Text, With<MessageText>>,
     mut root_vis_query: Query<&mut Visibility, With<MessageLogRoot>>,
-    time: Res<Time>,
+    time: Res<Time<Real>>,
 ) {
     if !ui_state.dialog_open {
         return;
@@ -453,14 +833,18 @@ fn handle_dialog_input(
         return;
     }
 
-    let advance = keyboard.just_pressed(KeyCode::KeyZ)
-        || keyboard.just_pressed(KeyCode::Space)
-        || keyboard.just_pressed(KeyCode::Enter);
+    let advance = input.just_pressed(Action::Confirm, &keyboard);
 
     if !advance {
         return;
     }
 
+    // The first press finishes a line that's still typing out
+    if !ui_state.line_revealed() {
+        ui_state.dialog_revealed = f32::INFINITY;
+        return;
+    }
+
     ui_state.dialog_index += 1;
     if ui_state.dialog_index >= ui_state.dialog_queue.len() {
         // Close dialog
@@ -471,31 +855,73 @@ fn handle_dialog_input(
             *text = Text::new(String::new());
         }
         ui_state.dialog_open = false;
+        locks.unlock();
         ui_state.dialog_queue.clear();
         ui_state.dialog_index = 0;
         return;
     }
+    ui_state.dialog_revealed = 0.0;
+}
 
-    // Show cumulative lines up to current index
-    if let Ok(mut text) = text_query.single_mut() {
-        let shown = ui_state
-            .dialog_queue
-            .iter()
-            .take(ui_state.dialog_index + 1)
-            .cloned()
-            .collect::<Vec<_>>()
-            .join("\n");
-        *text = Text::new(shown);
+// The world waits while the player reads or picks: gameplay (fuel burn, door auto-close,
+// respawns, hazards) runs on Time<Virtual>, which stands still whenever a dialog or menu is up,
+// as it does on the pause menu (see state.rs). Everything drawn over the world reads Time<Real>.
+fn pause_time_while_reading(ui_state: Res<UiState>, mut time: ResMut<Time<Virtual>>) {
+    let reading = ui_state.dialog_open || ui_state.menu_open;
+    if reading == time.is_paused() {
+        return;
+    }
+    if reading {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+}
+
+// Shows the lines so far, the current one typed out at the player's text speed
+fn typewrite_dialog(
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    mut ui_state: ResMut<UiState>,
+    mut text_query: Query<&mut Text, With<MessageText>>,
+) {
+    if !ui_state.dialog_open {
+        return;
+    }
+    let Some(line) = ui_state.dialog_queue.get(ui_state.dialog_index) else { return };
+    let len = line.text.chars().count() as f32;
+    let revealed = match settings.text_speed.chars_per_sec() {
+        Some(speed) => (ui_state.dialog_revealed + speed * time.delta_secs()).min(len),
+        None => len,
+    };
+
+    let mut shown = ui_state.dialog_queue[..ui_state.dialog_index]
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if ui_state.dialog_index > 0 {
+        shown.push('\n');
+    }
+    shown.extend(line.text.chars().take(revealed as usize));
+    if let Ok(mut text) = text_query.single_mut()
+        && text.0 != shown
+    {
+        text.0 = shown;
+    }
+    if ui_state.dialog_revealed != revealed {
+        ui_state.dialog_revealed = revealed;
     }
 }
 
 fn blink_continue_chevron(
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     ui_state: Res<UiState>,
     mut cont_query: Query<(&mut Visibility, &mut ChevronBlink), (With<ContinueChevron>, Without<CloseChevron>)>,
     mut close_query: Query<(&mut Visibility, &mut ChevronBlink), (With<CloseChevron>, Without<ContinueChevron>)>,
 ) {
-    let dialog_active = ui_state.dialog_open && !ui_state.dialog_queue.is_empty();
+    // Nothing to press on to until the line has typed out
+    let dialog_active = ui_state.dialog_open && !ui_state.dialog_queue.is_empty() && ui_state.line_revealed();
     let has_more_after = dialog_active && (ui_state.dialog_index + 1 < ui_state.dialog_queue.len());
     let on_last = dialog_active && (ui_state.dialog_index + 1 == ui_state.dialog_queue.len());
 
@@ -575,4 +1001,146 @@ fn update_inventory_ui(
             }
         }
     }
-}
\ No newline at end of file
+}
+// Keeps the corner reminder on the current objective. Quest progress flashes it yellow for a
+// moment; finishing a quest shows it as complete until the flash ends.
+fn update_quest_reminder(
+    time: Res<Time<Real>>,
+    quests: Res<Quests>,
+    log: Res<QuestLog>,
+    mut events: EventReader<QuestEvent>,
+    mut reminder_query: Query<(&mut Text, &mut TextColor, &mut Visibility, &mut QuestReminder)>,
+) {
+    let Ok((mut text, mut color, mut visibility, mut reminder)) = reminder_query.single_mut() else { return };
+    let mut completed = None;
+    for event in events.read() {
+        reminder.flash = QUEST_FLASH_SECS;
+        if let QuestEvent::Completed(id) = event {
+            completed = quests.get(id);
+        }
+    }
+    reminder.flash = (reminder.flash - time.delta_secs()).max(0.0);
+
+    if let Some(quest) = completed {
+        text.0 = format!("Complete: {}", quest.title);
+    } else if quests.is_changed() && let Some((quest, objective)) = quests.current() {
+        text.0 = format!("{}: {}", quest.title, objective.text);
+    }
+    // A finished quest's line stays up only while it's flashing
+    let showing = quests.current().is_some() || (reminder.flash > 0.0 && !text.0.is_empty());
+    visibility.set_if_neq(if showing && !log.open { Visibility::Visible } else { Visibility::Hidden });
+    color.0 = if reminder.flash > 0.0 { YELLOW.into() } else { WHITE.into() };
+}
+
+fn update_quest_log(
+    (quests, journal, log): (Res<Quests>, Res<Journal>, Res<QuestLog>),
+    mut root_query: Query<&mut Visibility, With<QuestLogRoot>>,
+    mut tab_query: Query<(&QuestLogTabLabel, &mut TextColor)>,
+    list_query: Query<(Entity, Option<&Children>), With<QuestLogList>>,
+    mut commands: Commands,
+) {
+    if !quests.is_changed() && !journal.is_changed() && !log.is_changed() {
+        return;
+    }
+    if let Ok(mut visibility) = root_query.single_mut() {
+        *visibility = if log.open { Visibility::Visible } else { Visibility::Hidden };
+    }
+    if !log.open {
+        return;
+    }
+    for (label, mut color) in tab_query.iter_mut() {
+        color.0 = if label.0 == log.tab { YELLOW.into() } else { GRAY.into() };
+    }
+    let Ok((list, lines)) = list_query.single() else { return };
+    for line in lines.into_iter().flatten() {
+        commands.entity(*line).despawn();
+    }
+    let line = |text: String, color: Color| (Text::new(text), TextFont { font_size: 18.0, ..default() }, TextColor(color));
+    if log.tab == LogTab::Journal {
+        // Long entries wrap at the panel's width
+        commands.entity(list).with_children(|parent| {
+            let mut entries = journal.newest_first().take(JOURNAL_SHOWN).peekable();
+            if entries.peek().is_none() {
+                parent.spawn(line("(Nothing yet)".to_string(), WHITE.into()));
+            }
+            for entry in entries {
+                parent.spawn(line(format!("{}  {}", format_playtime(entry.playtime), entry.text), WHITE.into()));
+            }
+        });
+        return;
+    }
+    commands.entity(list).with_children(|parent| {
+        let mut started = quests.iter().filter(|quest| quest.state != QuestState::Inactive).peekable();
+        if started.peek().is_none() {
+            parent.spawn(line("(Nothing yet)".to_string(), WHITE.into()));
+        }
+        for quest in started {
+            let done = quest.state == QuestState::Complete;
+            parent.spawn(line(format!("* {}", quest.title), if done { GRAY.into() } else { YELLOW.into() }));
+            // Finished objectives and the one being worked on; the rest stay a surprise
+            let shown = quest.objectives.iter().filter(|o| o.done).count() + usize::from(!done);
+            for objective in quest.objectives.iter().take(shown) {
+                let color = if objective.done { GRAY.into() } else { WHITE.into() };
+                parent.spawn(line(format!("  - {}", objective.text), color));
+            }
+        }
+    });
+}
+
+// Shows the newest toast and fades it out; real time, so one raised on pause still goes away
+fn show_toasts(
+    time: Res<Time<Real>>,
+    mut events: EventReader<ToastEvent>,
+    mut toast_query: Query<(&mut Text, &mut TextColor, &mut Visibility, &mut Toast)>,
+    mut sfx: EventWriter<PlaySfxEvent>,
+) {
+    let Ok((mut text, mut color, mut visibility, mut toast)) = toast_query.single_mut() else { return };
+    if let Some(ToastEvent(message)) = events.read().last() {
+        text.0 = message.clone();
+        toast.secs = TOAST_SECS;
+        sfx.write(PlaySfxEvent::ui(SfxId::Toast));
+    }
+    if toast.secs <= 0.0 {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    toast.secs -= time.delta_secs();
+    *visibility = Visibility::Visible;
+    color.0 = Color::WHITE.with_alpha((toast.secs / TOAST_FADE_SECS).clamp(0.0, 1.0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn a_menu_whose_object_goes_closes() {
+        let mut world = World::new();
+        world.init_resource::<Events<ToastEvent>>();
+        let target = world.spawn(Interactable::default()).id();
+        let menu = world.spawn((ContextMenuRoot, Visibility::Visible)).id();
+        let mut locks = MovementLocks::default();
+        locks.lock();
+        world.insert_resource(locks);
+        world.insert_resource(UiState { menu_open: true, current_entity: Some(target), ..default() });
+
+        // Still there: nothing happens
+        world.run_system_once(close_menu_without_target).unwrap();
+        assert!(world.resource::<UiState>().menu_open);
+
+        world.despawn(target);
+        world.run_system_once(close_menu_without_target).unwrap();
+        let ui_state = world.resource::<UiState>();
+        assert!(!ui_state.menu_open);
+        assert_eq!(ui_state.current_entity, None);
+        assert!(!world.resource::<MovementLocks>().is_locked());
+        assert_eq!(world.get::<Visibility>(menu), Some(&Visibility::Hidden));
+        assert_eq!(world.resource::<Events<ToastEvent>>().len(), 1);
+
+        // Already closed, so once is enough
+        world.run_system_once(close_menu_without_target).unwrap();
+        assert!(!world.resource::<MovementLocks>().is_locked());
+        assert_eq!(world.resource::<Events<ToastEvent>>().len(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+// The game run headless for scenario tests: a new game started as if from the title screen,
+// driven one key and one frame at a time, at a steady 60 frames per second
+use bevy::input::keyboard::{Key, KeyboardInput, NativeKey};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use dissonance::input::{Action, InputMap};
+use dissonance::interaction::{Interactable, InteractionAction};
+use dissonance::player::Player;
+use dissonance::save::NewGameEvent;
+use dissonance::state::GameState;
+use dissonance::ui::UiState;
+use std::time::Duration;
+
+// Frames left after a key goes down or up, for whatever it set off to happen
+const SETTLE_FRAMES: usize = 6;
+
+pub struct Harness {
+    pub app: App,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let mut app = dissonance::headless_app();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / 60.0)));
+        // Startup and the title screen (until the room files are in) first, then into a new game
+        app.update();
+        dissonance::wait_for_room_files(&mut app);
+        app.world_mut().send_event(NewGameEvent { carry_from: None });
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        let mut harness = Self { app };
+        harness.frames(2);
+        harness
+    }
+
+    pub fn frames(&mut self, n: usize) {
+        for _ in 0..n {
+            self.app.update();
+        }
+    }
+
+    // Keys go in as the keyboard's own events, which InputPlugin writes into
+    // ButtonInput<KeyCode> at the start of the next frame; a direct write would be cleared by then
+    pub fn press(&mut self, key: KeyCode) {
+        self.key(key, ButtonState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.key(key, ButtonState::Released);
+    }
+
+    fn key(&mut self, key_code: KeyCode, state: ButtonState) {
+        self.app.world_mut().send_event(KeyboardInput {
+            key_code,
+            logical_key: Key::Unidentified(NativeKey::Unidentified),
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+        self.frames(1);
+    }
+
+    // Down for a frame, then up, then a few frames for it to take effect
+    pub fn tap(&mut self, key: KeyCode) {
+        self.press(key);
+        self.release(key);
+        self.frames(SETTLE_FRAMES);
+    }
+
+    // Taps the first key bound to `action`
+    pub fn tap_action(&mut self, action: Action) {
+        let key = self.resource::<InputMap>().keys(action)[0];
+        self.tap(key);
+    }
+
+    pub fn resource<R: Resource>(&self) -> &R {
+        self.app.world().resource::<R>()
+    }
+
+    pub fn resource_mut<R: Resource>(&mut self) -> Mut<'_, R> {
+        self.app.world_mut().resource_mut::<R>()
+    }
+
+    pub fn get<C: Component>(&self, entity: Entity) -> Option<&C> {
+        self.app.world().get::<C>(entity)
+    }
+
+    pub fn ui(&self) -> &UiState {
+        self.resource::<UiState>()
+    }
+
+    // The interactable called `name`, if it's still around
+    pub fn find(&mut self, name: &str) -> Option<Entity> {
+        let world = self.app.world_mut();
+        let mut query = world.query::<(Entity, &Interactable)>();
+        query.iter(world).find(|(_, i)| i.name == name).map(|(entity, _)| entity)
+    }
+
+    pub fn player_to(&mut self, pos: Vec2) {
+        let world = self.app.world_mut();
+        let mut query = world.query_filtered::<&mut Transform, With<Player>>();
+        let mut tf = query.single_mut(world).unwrap();
+        tf.translation = pos.extend(tf.translation.z);
+    }
+
+    // Stands just below the object called `name`, in its reach, and presses Interact
+    pub fn interact_with(&mut self, name: &str) {
+        let entity = self.find(name).unwrap_or_else(|| panic!("no {} in the room", name));
+        let world = self.app.world();
+        let pos = world.get::<Transform>(entity).unwrap().translation.truncate();
+        let half_height = world.get::<Sprite>(entity).and_then(|s| s.custom_size).map_or(0.0, |size| size.y / 2.0);
+        self.player_to(pos - Vec2::new(0.0, half_height + 12.0));
+        self.tap_action(Action::Interact);
+    }
+
+    // Moves the open menu's highlight onto `action` and picks it
+    pub fn choose(&mut self, action: InteractionAction) {
+        let ui = self.ui();
+        assert!(ui.menu_open, "no menu to choose {:?} from", action);
+        let index = ui.current_actions.iter().position(|a| *a == action);
+        let index = index.unwrap_or_else(|| panic!("{:?} isn't on the menu: {:?}", action, ui.current_actions));
+        let steps = index as isize - ui.selected_index as isize;
+        for _ in 0..steps.unsigned_abs() {
+            self.tap_action(if steps > 0 { Action::MoveDown } else { Action::MoveUp });
+        }
+        self.tap_action(Action::Confirm);
+    }
+
+    // The open dialog's lines, read to the end with Confirm
+    pub fn read_dialog(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for _ in 0..40 {
+            if !self.ui().dialog_open {
+                return lines;
+            }
+            for line in &self.ui().dialog_queue[lines.len()..] {
+                lines.push(line.text.clone());
+            }
+            self.tap_action(Action::Confirm);
+        }
+        panic!("the dialog never closed: {:?}", lines);
+    }
+}
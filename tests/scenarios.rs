@@ -0,0 +1,110 @@
+// Whole interactions played through the start room with the keyboard, as a player would
+mod common;
+
+use common::Harness;
+use dissonance::input::Action;
+use dissonance::interaction::InteractionAction;
+use dissonance::inventory::{item_by_id, Inventory};
+use dissonance::objects::{Door, Light};
+
+#[test]
+fn taking_the_key() {
+    let mut h = Harness::new();
+    let key = h.find("Rusty Key").unwrap();
+    h.interact_with("Rusty Key");
+    assert_eq!(h.ui().current_actions, [InteractionAction::Examine, InteractionAction::Take]);
+    h.choose(InteractionAction::Take);
+    assert_eq!(h.read_dialog(), ["* You obtained the rusty key!"]);
+
+    assert!(h.resource::<Inventory>().has_item_id("rusty_key"));
+    assert!(h.app.world().get_entity(key).is_err());
+}
+
+#[test]
+fn the_metal_door_needs_the_rusty_key() {
+    let mut h = Harness::new();
+    let door = h.find("Metal Door").unwrap();
+    h.interact_with("Metal Door");
+    h.choose(InteractionAction::Open);
+    assert_eq!(h.read_dialog(), ["* The metal door is locked."]);
+    assert!(!h.get::<Door>(door).unwrap().is_open);
+
+    h.resource_mut::<Inventory>().add_item(item_by_id("rusty_key"));
+    h.interact_with("Metal Door");
+    h.choose(InteractionAction::Open);
+    assert_eq!(
+        h.read_dialog(),
+        ["* You unlock the metal door with the rusty key.", "* You pull the metal door open."]
+    );
+    assert!(h.get::<Door>(door).unwrap().is_open);
+    // It's kept for the next lock
+    assert!(h.resource::<Inventory>().has_item_id("rusty_key"));
+}
+
+#[test]
+fn the_lamp_turns_on_once_the_generator_runs() {
+    let mut h = Harness::new();
+    let lamp = h.find("Old Lamp").unwrap();
+    h.interact_with("Old Lamp");
+    h.choose(InteractionAction::TurnOn);
+    assert_eq!(h.read_dialog(), ["* You flip the switch on the old lamp.", "* Nothing happens. There's no power."]);
+    assert!(!h.get::<Light>(lamp).unwrap().is_on);
+
+    h.interact_with("Generator");
+    h.choose(InteractionAction::Use);
+    h.read_dialog();
+    // Past the lamp's cooldown, which only counts down while no dialog is up
+    h.frames(40);
+    h.interact_with("Old Lamp");
+    h.choose(InteractionAction::TurnOn);
+    assert_eq!(h.read_dialog(), ["* You flip the switch on the old lamp.", "* It hums to life."]);
+    assert!(h.get::<Light>(lamp).unwrap().is_on);
+
+    // Lit, it only offers to go off again
+    h.frames(40);
+    h.interact_with("Old Lamp");
+    assert_eq!(h.ui().current_actions, [InteractionAction::Examine, InteractionAction::TurnOff]);
+    h.choose(InteractionAction::TurnOff);
+    assert_eq!(h.read_dialog(), ["* You flip the switch on the old lamp.", "* It clicks off."]);
+    assert!(!h.get::<Light>(lamp).unwrap().is_on);
+}
+
+#[test]
+fn dialog_pages_one_line_at_a_time() {
+    let mut h = Harness::new();
+    h.interact_with("Strange Figure");
+    h.choose(InteractionAction::Talk);
+    assert!(h.ui().dialog_open);
+    assert_eq!(h.ui().dialog_index, 0);
+
+    // Let the first line finish typing out, then page on
+    h.frames(60);
+    h.tap_action(Action::Confirm);
+    assert!(h.ui().dialog_open);
+    assert_eq!(h.ui().dialog_index, 1);
+    assert_eq!(h.ui().dialog_queue[1].text, "* The figure stares at you silently.");
+
+    // The last line closes it
+    h.frames(60);
+    h.tap_action(Action::Confirm);
+    assert!(!h.ui().dialog_open);
+    assert!(h.ui().dialog_queue.is_empty());
+}
+
+#[test]
+fn a_full_inventory_leaves_the_key_where_it_lies() {
+    let mut h = Harness::new();
+    {
+        let mut inventory = h.resource_mut::<Inventory>();
+        while inventory.add_item(item_by_id("firewood")) {}
+    }
+    let held = h.resource::<Inventory>().items.len();
+    let key = h.find("Rusty Key").unwrap();
+    h.interact_with("Rusty Key");
+    h.choose(InteractionAction::Take);
+    assert_eq!(h.read_dialog(), ["* Your inventory is full!"]);
+
+    assert_eq!(h.resource::<Inventory>().items.len(), held);
+    assert!(!h.resource::<Inventory>().has_item_id("rusty_key"));
+    assert!(h.app.world().get_entity(key).is_ok());
+}